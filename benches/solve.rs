@@ -0,0 +1,53 @@
+//! Whole-solve benchmark, with and without the BMI2 bit packing fast path
+//!
+//! Run with `cargo bench --bench solve`. Solves the double-dummy tables of a
+//! few deals, best of several runs, then the same with BMI2 disabled.
+//!
+//! AMD CPUs before Zen 3 report BMI2 but run PEXT and PDEP in microcode, so
+//! the solver keeps the portable path there and both runs time the same code.
+
+use bridge_solver::{set_no_bmi2, solve_dd_table, Hands};
+use std::time::{Duration, Instant};
+
+const DEALS: [&str; 3] = [
+    "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72",
+    "N:KQ2.AT54.Q83.K72 J98.K2.AJT76.QJ5 A6543.Q3.K5.AT84 T7.J9876.942.963",
+    "N:A874.K92.QJ3.AT5 KJ5.QJ8.K9842.73 Q92.AT654.A7.K82 T63.73.T65.QJ964",
+];
+
+const RUNS: usize = 5;
+
+/// Best time to solve the tables of all deals
+fn best_time(deals: &[Hands]) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            for deal in deals {
+                std::hint::black_box(solve_dd_table(deal));
+            }
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let deals: Vec<Hands> = DEALS.iter().map(|d| Hands::from_pbn(d).unwrap()).collect();
+
+    // Detected, not necessarily used: see the note on AMD above
+    #[cfg(target_arch = "x86_64")]
+    println!("bmi2 detected: {}", std::is_x86_feature_detected!("bmi2"));
+
+    let fast = best_time(&deals);
+    set_no_bmi2(true);
+    let portable = best_time(&deals);
+    set_no_bmi2(false);
+
+    println!("dd tables, {} deals (best of {} runs)", deals.len(), RUNS);
+    println!("  default:  {:>8.1} ms", fast.as_secs_f64() * 1000.0);
+    println!("  portable: {:>8.1} ms", portable.as_secs_f64() * 1000.0);
+    println!(
+        "  speedup:  {:>8.2}x",
+        portable.as_secs_f64() / fast.as_secs_f64()
+    );
+}
@@ -0,0 +1,18 @@
+//! The PBN annotator on arbitrary files; blocks without a valid deal must
+//! come through unchanged
+
+#![no_main]
+
+use bridge_solver::annotate_pbn;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let annotated = annotate_pbn(text).pbn;
+    if !annotated.contains("[DoubleDummyTricks ") {
+        let lines: String = text.lines().map(|line| format!("{}\n", line)).collect();
+        assert_eq!(annotated, lines);
+    }
+});
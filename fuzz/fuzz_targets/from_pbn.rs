@@ -0,0 +1,17 @@
+//! `Hands::from_pbn` and `parse_card` on arbitrary text; whatever parses must
+//! survive a round trip through `to_pbn`
+
+#![no_main]
+
+use bridge_solver::{parse_card, Hands};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let _ = parse_card(text);
+    if let Some(hands) = Hands::from_pbn(text) {
+        assert_eq!(Hands::from_pbn(&hands.to_pbn()), Some(hands));
+    }
+});
@@ -0,0 +1,81 @@
+//! `Solver` on small positions built from arbitrary bytes, at the start of
+//! a trick or part way through one, checked against the brute-force
+//! reference solver
+//!
+//! Byte 0 picks the strain, byte 1 the leader, byte 2 how many cards are
+//! already played to the trick; the rest deal cards round the table.
+
+#![no_main]
+
+use bridge_solver::{
+    reference_solve, reference_solve_mid_trick, Card, CutoffCache, Hands, PartialTrick,
+    PatternCache, Seat, Solver, Strain, NUM_SEATS, NUM_STRAINS, TOTAL_CARDS,
+};
+use libfuzzer_sys::fuzz_target;
+
+/// Largest ending to try, to keep the reference solver fast
+const MAX_TRICKS: usize = 4;
+
+fuzz_target!(|data: &[u8]| {
+    let [trump, leader, played, deal @ ..] = data else {
+        return;
+    };
+    let trump = *trump as usize % NUM_STRAINS;
+    let leader = *leader as usize % NUM_SEATS;
+    let played = *played as usize % NUM_SEATS;
+
+    let mut hands = Hands::new();
+    let mut seat = 0;
+    for &byte in deal.iter().take(MAX_TRICKS * NUM_SEATS) {
+        let card = byte as usize % TOTAL_CARDS;
+        if !hands.all_cards().have_index(card) {
+            hands[seat].add_index(card);
+            seat = (seat + 1) % NUM_SEATS;
+        }
+    }
+    if !hands.is_valid() {
+        return;
+    }
+
+    // Play the lowest legal card of each seat to the trick
+    let mut partial = PartialTrick::new();
+    for i in 0..played {
+        let seat = (leader + i) % NUM_SEATS;
+        let hand = hands[seat];
+        let playable = match partial.lead_suit() {
+            Some(suit) if !hand.suit(suit).is_empty() => hand.suit(suit),
+            _ => hand,
+        };
+        let Some(card) = playable.iter().last() else {
+            return;
+        };
+        hands[seat].remove_index(card);
+        partial.add(Card::from_index(card).unwrap(), Seat::ALL[seat]);
+    }
+
+    let mut cutoff = CutoffCache::new(8);
+    let mut pattern = PatternCache::new(8);
+    let (solver, reference) = if partial.is_empty() {
+        (
+            Solver::new(hands, Strain::ALL[trump], Seat::ALL[leader])
+                .solve_with_caches(&mut cutoff, &mut pattern),
+            reference_solve(hands, trump, leader),
+        )
+    } else {
+        let Some(solver) = Solver::new_mid_trick(hands, Strain::ALL[trump], &partial) else {
+            return;
+        };
+        (
+            solver.solve_mid_trick(&mut cutoff, &mut pattern, &partial),
+            reference_solve_mid_trick(hands, trump, &partial),
+        )
+    };
+    assert_eq!(
+        solver,
+        reference,
+        "{} trump {} leader {}",
+        hands.to_pbn(),
+        trump,
+        leader
+    );
+});
@@ -0,0 +1,19 @@
+//! `Hands::from_solver_lines` on the first three lines of arbitrary text, as
+//! solver-diag reads its input files
+
+#![no_main]
+
+use bridge_solver::Hands;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let mut lines = text.lines();
+    let mut line = || lines.next().unwrap_or("");
+    let (north, west_east, south) = (line(), line(), line());
+    if let Some(hands) = Hands::from_solver_lines(north, west_east, south) {
+        assert_eq!(Hands::from_pbn(&hands.to_pbn()), Some(hands));
+    }
+});
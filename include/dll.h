@@ -0,0 +1,152 @@
+/*
+ * DDS-compatible C interface to bridge-solver.
+ *
+ * Build with `cargo build --release --features dds` and link against the
+ * resulting cdylib (libbridge_solver.so / bridge_solver.dll / .dylib).
+ *
+ * Struct layouts, entry point signatures and return codes follow Bo Haglund's
+ * DDS dll.h, so programs written against DDS can use this library instead.
+ * Only the entry points below are provided. Differences from DDS:
+ *   - SolveBoard: `mode` and `threadIndex` are validated but otherwise
+ *     ignored, `nodes` is always 0, and `solutions` 3 ignores `target`.
+ *   - All entry points are thread-safe; no SetMaxThreads call is needed.
+ */
+
+#ifndef BRIDGE_SOLVER_DLL_H
+#define BRIDGE_SOLVER_DLL_H
+
+#if defined(_WIN32)
+#define STDCALL __stdcall
+#else
+#define STDCALL
+#endif
+
+#ifdef __cplusplus
+#define EXTERN_C extern "C"
+#else
+#define EXTERN_C
+#endif
+
+#define DLLEXPORT EXTERN_C
+
+/* Hands: 0 = North, 1 = East, 2 = South, 3 = West */
+#define DDS_HANDS 4
+/* Suits: 0 = Spades, 1 = Hearts, 2 = Diamonds, 3 = Clubs */
+#define DDS_SUITS 4
+/* Strains: suits as above, 4 = No trump */
+#define DDS_STRAINS 5
+
+#define MAXNOOFTABLES 40
+
+#define RETURN_NO_FAULT 1
+#define RETURN_UNKNOWN_FAULT -1
+#define RETURN_ZERO_CARDS -2
+#define RETURN_TARGET_TOO_HIGH -3
+#define RETURN_DUPLICATE_CARDS -4
+#define RETURN_TARGET_WRONG_LO -5
+#define RETURN_TARGET_WRONG_HI -7
+#define RETURN_SOLNS_WRONG_LO -8
+#define RETURN_SOLNS_WRONG_HI -9
+#define RETURN_TOO_MANY_CARDS -10
+#define RETURN_SUIT_OR_RANK -12
+#define RETURN_PLAYED_CARD -13
+#define RETURN_CARD_COUNT -14
+#define RETURN_THREAD_INDEX -15
+#define RETURN_MODE_WRONG_LO -16
+#define RETURN_MODE_WRONG_HI -17
+#define RETURN_TRUMP_WRONG -18
+#define RETURN_FIRST_WRONG -19
+#define RETURN_NO_SUIT -201
+#define RETURN_TOO_MANY_TABLES -202
+
+/* Holdings are rank bitmasks: bit 2 = deuce ... bit 14 = ace */
+
+struct deal
+{
+  int trump;
+  int first;
+  int currentTrickSuit[3];
+  int currentTrickRank[3];
+  unsigned int remainCards[DDS_HANDS][DDS_SUITS];
+};
+
+struct futureTricks
+{
+  int nodes;
+  int cards;
+  int suit[13];
+  int rank[13];
+  int equals[13];
+  int score[13];
+};
+
+struct ddTableDeal
+{
+  unsigned int cards[DDS_HANDS][DDS_SUITS];
+};
+
+struct ddTableDeals
+{
+  int noOfTables;
+  struct ddTableDeal deals[MAXNOOFTABLES * DDS_STRAINS];
+};
+
+struct ddTableResults
+{
+  int resTable[DDS_STRAINS][DDS_HANDS];
+};
+
+struct ddTablesRes
+{
+  int noOfBoards;
+  struct ddTableResults results[MAXNOOFTABLES * DDS_STRAINS];
+};
+
+struct parResults
+{
+  /* Index 0 is from NS's side, index 1 from EW's, e.g. "NS 620" / "EW -620" */
+  char parScore[2][16];
+  /* e.g. "NS:NS 4S" or "EW:NS 5Hx" */
+  char parContractsString[2][128];
+};
+
+struct allParResults
+{
+  struct parResults presults[MAXNOOFTABLES];
+};
+
+/*
+ * target: -1 = find the maximum, 0-13 = tricks to reach
+ * solutions: 1 = one best card, 2 = all best cards, 3 = all cards
+ * mode: 0-2
+ */
+DLLEXPORT int STDCALL SolveBoard(
+  struct deal dl,
+  int target,
+  int solutions,
+  int mode,
+  struct futureTricks * futp,
+  int threadIndex);
+
+DLLEXPORT int STDCALL CalcDDtable(
+  struct ddTableDeal tableDeal,
+  struct ddTableResults * tablep);
+
+/*
+ * mode: -1 = no par, otherwise the vulnerability as for Par
+ * trumpFilter: non-zero entries skip that strain (par needs all five)
+ */
+DLLEXPORT int STDCALL CalcAllTables(
+  struct ddTableDeals * dealsp,
+  int mode,
+  int trumpFilter[DDS_STRAINS],
+  struct ddTablesRes * resp,
+  struct allParResults * presp);
+
+/* vulnerable: 0 = None, 1 = Both, 2 = NS, 3 = EW */
+DLLEXPORT int STDCALL Par(
+  struct ddTableResults * tablep,
+  struct parResults * presp,
+  int vulnerable);
+
+#endif
@@ -0,0 +1,193 @@
+//! Per-card analysis
+//!
+//! Solves the position after each legal play of the seat to move, giving the
+//! double-dummy value of every card (as shown by the "analyse play" view of
+//! most bridge programs).
+
+use super::bridge_solver::{PartialTrick, SolveLimit, Solver};
+use super::hands::Hands;
+use super::pattern::PatternCache;
+use super::play::get_playable_cards;
+use super::search::CutoffCache;
+use super::types::*;
+
+/// The double-dummy result of playing one card
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CardAnalysis {
+    /// The card played (0-51)
+    pub card: usize,
+    /// NS tricks from this position on (current trick included) after the card is played
+    pub ns_tricks: u8,
+}
+
+/// Why a per-card analysis gave no result
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnalysisError {
+    /// The partial trick cannot be continued (e.g. it already holds four cards)
+    InvalidPosition,
+    /// A solve was abandoned on reaching the limit
+    LimitReached,
+}
+
+impl std::fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnalysisError::InvalidPosition => write!(f, "position cannot be played from"),
+            AnalysisError::LimitReached => write!(f, "solve abandoned on reaching the limit"),
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
+
+/// Analyse every legal play of the seat to move
+///
+/// `hands` are the cards not yet played. With an empty `partial_trick`, `leader`
+/// is on lead; otherwise the seat after the last played card is to move.
+/// Results are in card order (spades first, high to low). Fails only with
+/// [`AnalysisError::InvalidPosition`].
+pub fn analyze_plays(
+    hands: Hands,
+    trump: usize,
+    leader: SeatIndex,
+    partial_trick: &PartialTrick,
+    cutoff_cache: &mut CutoffCache,
+    pattern_cache: &mut PatternCache,
+) -> Result<Vec<CardAnalysis>, AnalysisError> {
+    analyze_plays_with_limit(
+        hands,
+        trump,
+        leader,
+        partial_trick,
+        cutoff_cache,
+        pattern_cache,
+        &SolveLimit::unlimited(),
+    )
+}
+
+/// Like [`analyze_plays`], giving up when `limit` is reached by any one solve
+///
+/// Fails with [`AnalysisError::LimitReached`] if a solve was abandoned.
+pub fn analyze_plays_with_limit(
+    hands: Hands,
+    trump: usize,
+    leader: SeatIndex,
+    partial_trick: &PartialTrick,
+    cutoff_cache: &mut CutoffCache,
+    pattern_cache: &mut PatternCache,
+    limit: &SolveLimit,
+) -> Result<Vec<CardAnalysis>, AnalysisError> {
+    if partial_trick.len() >= NUM_SEATS {
+        return Err(AnalysisError::InvalidPosition);
+    }
+    let seat = partial_trick.next_to_play().unwrap_or(leader);
+    let playable = get_playable_cards(&hands, seat, partial_trick.lead_suit());
+
+    let mut results = Vec::with_capacity(playable.size());
+    for card in playable.iter() {
+        let mut remaining = hands;
+        remaining[seat].remove_index(card);
+        let mut trick = partial_trick.clone();
+        trick.add_index(card, seat);
+        let ns_tricks =
+            solve_after_play(remaining, trump, &trick, cutoff_cache, pattern_cache, limit)?;
+        results.push(CardAnalysis { card, ns_tricks });
+    }
+    Ok(results)
+}
+
+/// Solve a position where `trick` has just received its latest card
+///
+/// Completed tricks are scored and the solve continues from the winner's lead.
+fn solve_after_play(
+    hands: Hands,
+    trump: usize,
+    trick: &PartialTrick,
+    cutoff_cache: &mut CutoffCache,
+    pattern_cache: &mut PatternCache,
+    limit: &SolveLimit,
+) -> Result<u8, AnalysisError> {
+    if trick.len() < NUM_SEATS {
+        let solver = Solver::mid_trick_with_indices(hands, trump, trick)
+            .ok_or(AnalysisError::InvalidPosition)?;
+        return solver
+            .solve_mid_trick_with_limit(cutoff_cache, pattern_cache, trick, limit)
+            .ok_or(AnalysisError::LimitReached);
+    }
+
+    let winner = trick.winner_index(trump).unwrap().seat;
+    let won = is_ns(winner) as u8;
+    if hands.num_tricks() == 0 {
+        return Ok(won);
+    }
+    let rest = Solver::with_indices(hands, trump, winner)
+        .solve_with_limit(cutoff_cache, pattern_cache, limit)
+        .ok_or(AnalysisError::LimitReached)?;
+    Ok(won + rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::card_of;
+
+    #[test]
+    fn test_analyze_leads() {
+        // North's aces take both tricks whatever West leads
+        // N: SA,HA  E: SK,HK  S: S2,H2  W: S3,HQ
+        let hands = Hands::from_pbn("N:A.A.. K.K.. 2.2.. 3.Q..").unwrap();
+        let mut cutoff = CutoffCache::new(16);
+        let mut pattern = PatternCache::new(16);
+
+        let results = analyze_plays(
+            hands,
+            NOTRUMP,
+            WEST,
+            &PartialTrick::new(),
+            &mut cutoff,
+            &mut pattern,
+        )
+        .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].card, card_of(SPADE, THREE));
+        assert_eq!(results[1].card, card_of(HEART, QUEEN));
+        assert!(results.iter().all(|r| r.ns_tricks == 2));
+    }
+
+    #[test]
+    fn test_analyze_completes_trick() {
+        // South plays last to a trick won by North's ace
+        // W: H3  N: HA  E: HK  S: S2,H2 after W S3, N SA, E SK
+        let hands = Hands::from_pbn("N:.A.. .K.. 2.2.. .3..").unwrap();
+        let mut partial = PartialTrick::new();
+        partial.add_index(card_of(SPADE, THREE), WEST);
+        partial.add_index(card_of(SPADE, ACE), NORTH);
+        partial.add_index(card_of(SPADE, KING), EAST);
+        let mut cutoff = CutoffCache::new(16);
+        let mut pattern = PatternCache::new(16);
+
+        let results =
+            analyze_plays(hands, NOTRUMP, WEST, &partial, &mut cutoff, &mut pattern).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].card, card_of(SPADE, TWO));
+        assert_eq!(results[0].ns_tricks, 2);
+    }
+
+    #[test]
+    fn test_analyze_complete_trick_is_invalid() {
+        // A trick already holding four cards has no seat to move
+        let hands = Hands::from_pbn("N:.A.. .K.. .2.. .3..").unwrap();
+        let mut partial = PartialTrick::new();
+        partial.add_index(card_of(SPADE, THREE), WEST);
+        partial.add_index(card_of(SPADE, ACE), NORTH);
+        partial.add_index(card_of(SPADE, KING), EAST);
+        partial.add_index(card_of(SPADE, TWO), SOUTH);
+        let mut cutoff = CutoffCache::new(16);
+        let mut pattern = PatternCache::new(16);
+
+        assert_eq!(
+            analyze_plays(hands, NOTRUMP, WEST, &partial, &mut cutoff, &mut pattern),
+            Err(AnalysisError::InvalidPosition)
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! dd-dataset - Generate random deals with their double-dummy tables
+//!
+//! Deals random hands from a seed, optionally limited by each seat's high card
+//! points, solves the full table of each on all cores and writes them to a
+//! dataset file that `DatasetReader` reads. Run the same command again to
+//! resume an interrupted run, or with a larger `--deals` to extend a dataset.
+//!
+//! Usage: dd-dataset --output <file> --deals <n> [--seed <n>] [--threads <n>]
+//!                   [--chunk <deals>] [--hcp <seat>:<min>-<max>]... [--cache-memory <MB>]
+
+use bridge_solver::types::char_to_seat;
+use bridge_solver::{
+    generate_dataset, set_cache_memory_limit, DatasetHeader, DealConstraints, DEFAULT_CHUNK_DEALS,
+    MAX_CHUNK_DEALS,
+};
+use clap::Parser;
+use std::time::Instant;
+
+#[derive(Parser)]
+#[command(name = "dd-dataset")]
+#[command(about = "Generate random deals with their double-dummy tables")]
+#[command(version)]
+struct Args {
+    /// Output file, resumed if it exists
+    #[arg(short = 'o', long = "output")]
+    output: String,
+
+    /// Number of deals the dataset should hold
+    #[arg(short = 'n', long = "deals")]
+    deals: u64,
+
+    /// Random seed
+    #[arg(short = 's', long = "seed", default_value_t = 1)]
+    seed: u64,
+
+    /// Solver threads (default: one per core)
+    #[arg(short = 't', long = "threads")]
+    threads: Option<usize>,
+
+    /// Deals per chunk, the unit of work and of resuming
+    #[arg(long = "chunk", default_value_t = DEFAULT_CHUNK_DEALS,
+          value_parser = clap::value_parser!(u32).range(1..=MAX_CHUNK_DEALS as i64))]
+    chunk: u32,
+
+    /// High card points of a seat, e.g. N:15-17 (repeatable)
+    #[arg(long = "hcp", value_name = "SEAT:MIN-MAX", value_parser = parse_hcp)]
+    hcp: Vec<(usize, u8, u8)>,
+
+    /// Bound each thread's solver caches to this many megabytes (default: unbounded)
+    #[arg(long = "cache-memory", value_name = "MB")]
+    cache_memory: Option<usize>,
+}
+
+/// Parse "N:15-17" into a seat and its range
+fn parse_hcp(s: &str) -> Result<(usize, u8, u8), String> {
+    let error = || format!("expected SEAT:MIN-MAX such as N:15-17, got '{}'", s);
+    let (seat, range) = s.split_once(':').ok_or_else(error)?;
+    let mut seat_chars = seat.trim().chars();
+    let seat = match (seat_chars.next().and_then(char_to_seat), seat_chars.next()) {
+        (Some(seat), None) => seat,
+        _ => return Err(error()),
+    };
+    let (min, max) = range.split_once('-').ok_or_else(error)?;
+    let min = min.trim().parse().map_err(|_| error())?;
+    let max = max.trim().parse().map_err(|_| error())?;
+    Ok((seat, min, max))
+}
+
+fn main() {
+    let args = Args::parse();
+    set_cache_memory_limit(args.cache_memory);
+
+    let mut constraints = DealConstraints::default();
+    for &(seat, min, max) in &args.hcp {
+        constraints.hcp[seat] = (min, max);
+    }
+    if !constraints.is_satisfiable() {
+        eprintln!("Error: no deal meets the --hcp ranges");
+        std::process::exit(1);
+    }
+    let header = DatasetHeader {
+        chunk_deals: args.chunk,
+        ..DatasetHeader::new(args.seed, constraints)
+    };
+    let threads = args
+        .threads
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+
+    let start = Instant::now();
+    let mut first = None;
+    let result = generate_dataset(&args.output, &header, args.deals, threads, |done| {
+        // Rate since the first chunk of this run, leaving out resumed deals
+        let &mut (first_done, first_time) = first.get_or_insert((done, Instant::now()));
+        let seconds = first_time.elapsed().as_secs_f64();
+        eprint!(
+            "\r{} of {} deals, {:.1} deals/s",
+            done,
+            args.deals,
+            (done - first_done) as f64 / seconds.max(1e-9)
+        );
+    });
+    match result {
+        Ok(done) => eprintln!(
+            "\n{} deals in '{}' ({:.1} s on {} threads)",
+            done,
+            args.output,
+            start.elapsed().as_secs_f64(),
+            threads
+        ),
+        Err(e) => {
+            eprintln!("\nError writing dataset '{}': {}", args.output, e);
+            std::process::exit(1);
+        }
+    }
+}
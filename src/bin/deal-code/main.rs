@@ -0,0 +1,165 @@
+//! deal-code - Convert deals, positions and DD tables to and from codes
+//!
+//! Encodes a full deal, a position in play (a deal with trump, opening leader
+//! and the cards played) or a DD table as the compact codes of the library's
+//! `Code`, in hex or base64, and decodes them back to readable form.
+//!
+//! Usage: deal-code encode --deal <pbn> [--base64]
+//!        deal-code encode --deal <pbn> --trump <S|H|D|C|N> --leader <seat>
+//!                         [--played "<card> <card> ..."] [--base64]
+//!        deal-code encode --table <DoubleDummyTricks> [--base64]
+//!        deal-code decode <code> [--base64]
+
+use bridge_solver::cards::name_of;
+use bridge_solver::types::{seat_letter, strain_name};
+use bridge_solver::{Card, Code, DdTable, Hands, PlaySession, PlayedCard, Seat, Strain};
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[command(name = "deal-code")]
+#[command(about = "Convert deals, positions and DD tables to and from compact codes")]
+#[command(version)]
+struct Args {
+    /// Read and write base64 instead of hex
+    #[arg(long = "base64", global = true)]
+    base64: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the code of a deal, position or table
+    Encode {
+        /// PBN deal; with --trump and --leader, the start of a position
+        #[arg(short = 'd', long = "deal", required_unless_present = "table")]
+        deal: Option<String>,
+
+        /// Trump suit or N for notrump
+        #[arg(short = 't', long = "trump", requires_all = ["deal", "leader"])]
+        trump: Option<char>,
+
+        /// Seat on lead to the first trick
+        #[arg(short = 'l', long = "leader", requires = "trump")]
+        leader: Option<char>,
+
+        /// Cards played since, in order, e.g. "HK HA H2"
+        #[arg(short = 'p', long = "played", requires = "leader")]
+        played: Option<String>,
+
+        /// DD table as a PBN DoubleDummyTricks value
+        #[arg(long = "table", conflicts_with = "deal")]
+        table: Option<String>,
+    },
+
+    /// Print what a code holds
+    Decode { code: String },
+}
+
+fn main() {
+    let args = Args::parse();
+    let result = match args.command {
+        Command::Encode {
+            deal,
+            trump,
+            leader,
+            played,
+            table,
+        } => encode(deal, trump, leader, played, table).map(|code| {
+            if args.base64 {
+                code.to_base64()
+            } else {
+                code.to_hex()
+            }
+        }),
+        Command::Decode { code } => {
+            let code = if args.base64 {
+                Code::from_base64(&code)
+            } else {
+                Code::from_hex(&code)
+            };
+            code.map(|code| describe(&code))
+                .map_err(|e| format!("cannot decode: {}", e))
+        }
+    };
+    match result {
+        Ok(output) => println!("{}", output),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn encode(
+    deal: Option<String>,
+    trump: Option<char>,
+    leader: Option<char>,
+    played: Option<String>,
+    table: Option<String>,
+) -> Result<Code, String> {
+    if let Some(table) = table {
+        return DdTable::from_dd_tricks(&table)
+            .map(Code::Table)
+            .ok_or_else(|| format!("invalid DoubleDummyTricks value '{}'", table));
+    }
+    let deal = deal.unwrap();
+    let hands = Hands::from_pbn(&deal)
+        .filter(Hands::is_valid)
+        .ok_or_else(|| format!("invalid deal '{}'", deal))?;
+    let (Some(trump), Some(leader)) = (trump, leader) else {
+        return Code::deal(hands)
+            .ok_or_else(|| "only full deals have a deal code: give --trump and --leader".into());
+    };
+
+    let trump = Strain::from_char(trump).ok_or_else(|| format!("invalid trump '{}'", trump))?;
+    let leader = Seat::from_char(leader).ok_or_else(|| format!("invalid leader '{}'", leader))?;
+    let mut session = PlaySession::new(hands, trump, leader).unwrap();
+    for name in played.iter().flat_map(|played| played.split_whitespace()) {
+        let card = Card::from_name(name).ok_or_else(|| format!("invalid card '{}'", name))?;
+        session
+            .play(card)
+            .map_err(|e| format!("cannot play {}: {}", name, e))?;
+    }
+    Ok(Code::Position(session))
+}
+
+/// Readable form of a decoded code
+fn describe(code: &Code) -> String {
+    match code {
+        Code::Deal(deal) => format!("Deal {}", deal.hands().to_pbn()),
+        Code::Table(table) => format!("DoubleDummyTricks {}", table.to_dd_tricks()),
+        Code::Position(session) => {
+            let mut lines = vec![
+                format!("Remaining {}", session.hands().to_pbn()),
+                format!("Trump {}", strain_name(session.trump())),
+            ];
+            for (i, trick) in session.tricks().iter().enumerate() {
+                lines.push(format!("Trick {}: {}", i + 1, trick_cards(&trick.plays)));
+            }
+            if !session.current_trick().is_empty() {
+                lines.push(format!(
+                    "Current trick: {}",
+                    trick_cards(&session.current_trick().plays)
+                ));
+            }
+            lines.push(format!(
+                "To play {} (NS {}, EW {})",
+                seat_letter(session.seat_to_play()),
+                session.ns_tricks_won(),
+                session.ew_tricks_won()
+            ));
+            lines.join("\n")
+        }
+    }
+}
+
+/// Cards of a trick with their seats, e.g. "W:HK N:HA E:H2 S:H3"
+fn trick_cards(plays: &[PlayedCard]) -> String {
+    plays
+        .iter()
+        .map(|play| format!("{}:{}", seat_letter(play.seat), name_of(play.card)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,130 @@
+//! results-db - Maintain a results database of solved deals
+//!
+//! `bridge-solver --db <file>` looks deals up in the database and adds the
+//! ones it solves. This tool shows what a database holds, moves results in and
+//! out as text (one deal per line: PBN deal, DoubleDummyTricks and solver
+//! version, separated by tabs), and compacts the file.
+//!
+//! Usage: results-db <file> stats
+//!        results-db <file> export [--output <file>]
+//!        results-db <file> import <file>...
+//!        results-db <file> compact [--current-only]
+
+use bridge_solver::{ResultsDb, SOLVER_VERSION};
+use clap::{Parser, Subcommand};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+
+#[derive(Parser)]
+#[command(name = "results-db")]
+#[command(about = "Maintain a results database of solved deals")]
+#[command(version)]
+struct Args {
+    /// Results database file (created if missing)
+    database: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show the number of deals and records, by solver version
+    Stats,
+
+    /// Write every deal as a text line
+    Export {
+        /// Output file (if not specified, writes to stdout)
+        #[arg(short = 'o', long = "output")]
+        output: Option<String>,
+    },
+
+    /// Add the deals of exported text files
+    Import {
+        /// Files written by export
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+
+    /// Rewrite the file with one record per deal
+    Compact {
+        /// Drop deals solved by other solver versions
+        #[arg(long = "current-only")]
+        current_only: bool,
+    },
+}
+
+fn main() {
+    let args = Args::parse();
+    let mut db = match ResultsDb::open(&args.database) {
+        Ok(db) => db,
+        Err(e) => {
+            eprintln!("Error opening results database '{}': {}", args.database, e);
+            std::process::exit(1);
+        }
+    };
+
+    match args.command {
+        Command::Stats => stats(&db),
+        Command::Export { output } => {
+            let written = match &output {
+                Some(path) => File::create(path).and_then(|file| {
+                    let mut writer = BufWriter::new(file);
+                    let written = db.export(&mut writer)?;
+                    writer.flush()?;
+                    Ok(written)
+                }),
+                None => db.export(&mut io::stdout().lock()),
+            };
+            match written {
+                Ok(written) => eprintln!("Exported {} deal(s)", written),
+                Err(e) => {
+                    eprintln!("Error exporting: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Command::Import { files } => {
+            for path in &files {
+                let imported = File::open(path).and_then(|file| db.import(BufReader::new(file)));
+                match imported {
+                    Ok(imported) => eprintln!("Imported {} deal(s) from {}", imported, path),
+                    Err(e) => {
+                        eprintln!("Error importing '{}': {}", path, e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        Command::Compact { current_only } => {
+            let records = db.records();
+            if let Err(e) = db.compact(current_only) {
+                eprintln!("Error compacting '{}': {}", args.database, e);
+                std::process::exit(1);
+            }
+            eprintln!(
+                "Compacted {} record(s) to {} deal(s)",
+                records,
+                db.records()
+            );
+        }
+    }
+}
+
+/// Print the number of deals and records, and deals by solver version
+fn stats(db: &ResultsDb) {
+    println!("{} deal(s) in {} record(s)", db.len(), db.records());
+    let mut versions = BTreeMap::new();
+    for entry in db.entries() {
+        *versions.entry(entry.solver_version.as_str()).or_insert(0) += 1;
+    }
+    for (version, count) in &versions {
+        let current = if *version == SOLVER_VERSION {
+            " (current)"
+        } else {
+            ""
+        };
+        println!("  {}{}: {} deal(s)", version, current, count);
+    }
+}
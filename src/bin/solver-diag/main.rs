@@ -1,6 +1,17 @@
 //! Rust double-dummy solver CLI with C++ solver-compatible file format and output
 //!
-//! Usage: solver -f <file> [-X <iterations>] [-P] [-T] [-R] [-V]
+//! Usage: solver -f <file> [-X <iterations>] [-P] [-T] [-R] [-B] [-V] [-J] [-G <tree file>] [-M <nodes>] [-E <endgame file>]
+//!
+//! -V prints a table of search and cache statistics to stderr after each
+//! solve; -J prints them as one line of JSON instead.
+//!
+//! -G writes the search tree of each solve as Graphviz DOT, or as JSON if the
+//! file name ends in `.json`, recording at most -M nodes (default 1000). When
+//! the file has several solves, the strain and leader are added to the name
+//! (`tree.dot` becomes `tree-NT-W.dot`). -G takes precedence over -V and -J.
+//!
+//! -E loads an endgame table built by endgame-gen, so the search looks up the
+//! last few tricks instead of playing them out.
 //!
 //! File format (same as C++ solver):
 //!   Line 1: North hand (spades hearts diamonds clubs, space-separated)
@@ -11,13 +22,17 @@
 
 use bridge_solver::cards::card_of;
 use bridge_solver::types::rank_name;
+use bridge_solver::types::{seat_letter, strain_name};
+use bridge_solver::types::{CLUB, DIAMOND, EAST, HEART, NORTH, NOTRUMP, SOUTH, SPADE, WEST};
 use bridge_solver::{
-    set_no_pruning, set_no_rank_skip, set_no_tt, set_show_perf, set_xray_limit, Cards, CutoffCache,
-    Hands, PatternCache, Solver, CLUB, DIAMOND, EAST, HEART, NORTH, NOTRUMP, NUM_RANKS, SOUTH,
-    SPADE, WEST,
+    set_endgame_table, set_no_bmi2, set_no_pruning, set_no_rank_skip, set_no_tt, set_xray_limit,
+    Cards, CutoffCache, EndgameTable, Hands, PatternCache, SearchTree, Seat, Solver, Strain,
+    NUM_RANKS,
 };
 use std::env;
 use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Instant;
 
 fn main() {
@@ -29,7 +44,11 @@ fn main() {
     let mut no_pruning = false;
     let mut no_tt = false;
     let mut no_rank_skip = false;
-    let mut show_perf = false;
+    let mut no_bmi2 = false;
+    let mut report = Report::None;
+    let mut tree_path = None;
+    let mut tree_nodes = 1000;
+    let mut endgame_path = None;
     let mut i = 1;
     while i < args.len() {
         if args[i] == "-f" && i + 1 < args.len() {
@@ -47,9 +66,24 @@ fn main() {
         } else if args[i] == "-R" {
             no_rank_skip = true;
             i += 1;
+        } else if args[i] == "-B" {
+            no_bmi2 = true;
+            i += 1;
         } else if args[i] == "-V" {
-            show_perf = true;
+            report = Report::Table;
+            i += 1;
+        } else if args[i] == "-J" {
+            report = Report::Json;
             i += 1;
+        } else if args[i] == "-G" && i + 1 < args.len() {
+            tree_path = Some(&args[i + 1]);
+            i += 2;
+        } else if args[i] == "-M" && i + 1 < args.len() {
+            tree_nodes = args[i + 1].parse().unwrap_or(tree_nodes);
+            i += 2;
+        } else if args[i] == "-E" && i + 1 < args.len() {
+            endgame_path = Some(&args[i + 1]);
+            i += 2;
         } else {
             i += 1;
         }
@@ -58,7 +92,7 @@ fn main() {
     let file_path = match file_path {
         Some(p) => p,
         None => {
-            eprintln!("Usage: solver -f <file> [-X <iterations>] [-P] [-T] [-R] [-V]");
+            eprintln!("Usage: solver -f <file> [-X <iterations>] [-P] [-T] [-R] [-B] [-V] [-J] [-G <tree file>] [-M <nodes>] [-E <endgame file>]");
             std::process::exit(1);
         }
     };
@@ -78,16 +112,27 @@ fn main() {
         set_no_tt(true);
     }
 
-    // Set show-perf mode if specified
-    if show_perf {
-        set_show_perf(true);
-    }
-
     // Set no-rank-skip mode if specified
     if no_rank_skip {
         set_no_rank_skip(true);
     }
 
+    // Set no-BMI2 mode if specified
+    if no_bmi2 {
+        set_no_bmi2(true);
+    }
+
+    // Load the endgame table if specified
+    if let Some(path) = endgame_path {
+        match EndgameTable::load(path) {
+            Ok(table) => set_endgame_table(Some(Arc::new(table))),
+            Err(e) => {
+                eprintln!("Failed to read endgame file {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
     // Read and parse the file
     let content = fs::read_to_string(file_path).expect("Failed to read file");
     let lines: Vec<&str> = content.lines().collect();
@@ -98,21 +143,27 @@ fn main() {
     }
 
     // Parse hands
-    let north_str = lines[0].trim();
-    let west_east_str = lines[1];
-    let south_str = lines[2].trim();
-
-    // Split west and east from line 2 (they're separated by multiple spaces)
-    let (west_str, east_str) = parse_west_east(west_east_str);
-
-    let hands = Hands::from_solver_format(north_str, &west_str, &east_str, south_str)
-        .expect("Failed to parse hands");
+    let Some(hands) = Hands::from_solver_lines(lines[0], lines[1], lines[2]) else {
+        eprintln!("Error: Failed to parse hands");
+        std::process::exit(1);
+    };
+    if !hands.is_valid() {
+        eprintln!(
+            "Error: Each card must appear once and all hands must hold the same number of cards"
+        );
+        std::process::exit(1);
+    }
 
     // Parse optional trump (line 4)
     let trump: Option<usize> = if lines.len() > 3 {
         let trump_str = lines[3].trim();
         if !trump_str.is_empty() {
-            Some(parse_trump(trump_str.chars().next().unwrap()))
+            Some(
+                parse_trump(trump_str.chars().next().unwrap()).unwrap_or_else(|| {
+                    eprintln!("Error: Invalid trump: {}", trump_str);
+                    std::process::exit(1);
+                }),
+            )
         } else {
             None
         }
@@ -124,7 +175,12 @@ fn main() {
     let leader: Option<usize> = if lines.len() > 4 {
         let leader_str = lines[4].trim();
         if !leader_str.is_empty() {
-            Some(parse_seat(leader_str.chars().next().unwrap()))
+            Some(
+                parse_seat(leader_str.chars().next().unwrap()).unwrap_or_else(|| {
+                    eprintln!("Error: Invalid leader: {}", leader_str);
+                    std::process::exit(1);
+                }),
+            )
         } else {
             None
         }
@@ -147,6 +203,11 @@ fn main() {
     };
 
     let num_tricks = hands.num_tricks();
+    let tree = tree_path.map(|path| TreeExport {
+        path: PathBuf::from(path),
+        max_nodes: tree_nodes,
+        several: trumps.len() * leaders.len() > 1,
+    });
 
     // Solve for each trump/leader combination
     // Caches are shared across leaders for the same trump (matching C++ behavior)
@@ -161,8 +222,15 @@ fn main() {
             // Single leader - simple output
             let l = leaders[0];
             let start = Instant::now();
-            let solver = Solver::new(hands, t, l);
-            let ns_tricks = solver.solve_with_caches(&mut cutoff_cache, &mut pattern_cache);
+            let ns_tricks = solve(
+                hands,
+                t,
+                l,
+                &mut cutoff_cache,
+                &mut pattern_cache,
+                report,
+                tree.as_ref(),
+            );
             let elapsed = start.elapsed();
             // Match C++ output: when N/S leads, show total - ns_tricks
             let result = if l == NORTH || l == SOUTH {
@@ -183,8 +251,15 @@ fn main() {
 
             for &l in &leaders {
                 let start = Instant::now();
-                let solver = Solver::new(hands, t, l);
-                let ns_tricks = solver.solve_with_caches(&mut cutoff_cache, &mut pattern_cache);
+                let ns_tricks = solve(
+                    hands,
+                    t,
+                    l,
+                    &mut cutoff_cache,
+                    &mut pattern_cache,
+                    report,
+                    tree.as_ref(),
+                );
                 let elapsed = start.elapsed();
                 // Match C++ output: when N/S leads, show total - ns_tricks
                 let result = if l == NORTH || l == SOUTH {
@@ -204,62 +279,104 @@ fn main() {
     }
 }
 
-/// Parse the West/East line which has both hands separated by 2+ spaces
-fn parse_west_east(line: &str) -> (String, String) {
-    // Find the first run of 2+ spaces (the separator between West and East)
-    let bytes = line.as_bytes();
-    let mut i = 0;
-    while i < bytes.len() {
-        if bytes[i] == b' ' {
-            let start = i;
-            while i < bytes.len() && bytes[i] == b' ' {
-                i += 1;
-            }
-            // If we found 2+ spaces, split here
-            if i - start >= 2 {
-                let west = line[..start].trim().to_string();
-                let east = line[i..].trim().to_string();
-                return (west, east);
-            }
+/// Statistics to print after each solve
+#[derive(Clone, Copy)]
+enum Report {
+    None,
+    Table,
+    Json,
+}
+
+/// Where and how much of the search trees to write
+struct TreeExport {
+    path: PathBuf,
+    max_nodes: usize,
+    /// The file has several solves, each writing its own tree
+    several: bool,
+}
+
+impl TreeExport {
+    fn path(&self, trump: usize, leader: usize) -> PathBuf {
+        if !self.several {
+            return self.path.clone();
+        }
+        let stem = self.path.file_stem().unwrap_or_default().to_string_lossy();
+        let mut name = format!("{}-{}-{}", stem, strain_name(trump), seat_letter(leader));
+        if let Some(extension) = self.path.extension() {
+            name = format!("{}.{}", name, extension.to_string_lossy());
+        }
+        self.path.with_file_name(name)
+    }
+
+    fn write(&self, tree: &SearchTree, trump: usize, leader: usize) {
+        let path = self.path(trump, leader);
+        let text = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            format!("{:#}\n", tree.to_json())
         } else {
-            i += 1;
+            tree.to_dot()
+        };
+        if let Err(e) = fs::write(&path, text) {
+            eprintln!("Failed to write {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+        if tree.is_truncated() {
+            eprintln!(
+                "{}: search tree truncated at {} nodes",
+                path.display(),
+                tree.len()
+            );
         }
     }
+}
 
-    // Fallback: try splitting by tab
-    let trimmed = line.trim();
-    if let Some(pos) = trimmed.find('\t') {
-        let west = trimmed[..pos].trim().to_string();
-        let east = trimmed[pos..].trim().to_string();
-        (west, east)
-    } else {
-        // Last resort: split by whitespace and divide in half
-        let tokens: Vec<&str> = trimmed.split_whitespace().collect();
-        let mid = tokens.len() / 2;
-        let west = tokens[0..mid].join(" ");
-        let east = tokens[mid..].join(" ");
-        (west, east)
+fn solve(
+    hands: Hands,
+    trump: usize,
+    leader: usize,
+    cutoff_cache: &mut CutoffCache,
+    pattern_cache: &mut PatternCache,
+    report: Report,
+    tree: Option<&TreeExport>,
+) -> u8 {
+    let solver = Solver::new(hands, Strain::ALL[trump], Seat::ALL[leader]);
+    if let Some(export) = tree {
+        let mut search_tree = SearchTree::new(export.max_nodes);
+        let ns_tricks = solver.solve_with_observer(cutoff_cache, pattern_cache, &mut search_tree);
+        export.write(&search_tree, trump, leader);
+        return ns_tricks;
+    }
+    if let Report::None = report {
+        return solver.solve_with_caches(cutoff_cache, pattern_cache);
+    }
+    let (ns_tricks, solve_report) = solver.solve_with_report(cutoff_cache, pattern_cache);
+    match report {
+        Report::Table => eprint!("{}", solve_report),
+        _ => eprintln!("{}", solve_report.to_json()),
     }
+    ns_tricks
 }
 
-fn parse_trump(c: char) -> usize {
+fn parse_trump(c: char) -> Option<usize> {
     match c.to_ascii_uppercase() {
-        'N' => NOTRUMP,
-        'S' => SPADE,
-        'H' => HEART,
-        'D' => DIAMOND,
-        'C' => CLUB,
-        _ => panic!("Invalid trump: {}", c),
+        'N' => Some(NOTRUMP),
+        'S' => Some(SPADE),
+        'H' => Some(HEART),
+        'D' => Some(DIAMOND),
+        'C' => Some(CLUB),
+        _ => None,
     }
 }
 
-fn parse_seat(c: char) -> usize {
+fn parse_seat(c: char) -> Option<usize> {
     match c.to_ascii_uppercase() {
-        'W' => WEST,
-        'N' => NORTH,
-        'E' => EAST,
-        'S' => SOUTH,
-        _ => panic!("Invalid seat: {}", c),
+        'W' => Some(WEST),
+        'N' => Some(NORTH),
+        'E' => Some(EAST),
+        'S' => Some(SOUTH),
+        _ => None,
     }
 }
 
@@ -308,7 +425,7 @@ fn format_suit_cards(cards: Cards, suit: usize) -> String {
     let mut s = String::new();
     // Iterate from Ace down to 2
     for rank in (0..NUM_RANKS).rev() {
-        if cards.have(card_of(suit, rank)) {
+        if cards.have_index(card_of(suit, rank)) {
             s.push(rank_name(rank));
         }
     }
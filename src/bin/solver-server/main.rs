@@ -0,0 +1,374 @@
+//! solver-server - Double-dummy solver over HTTP/JSON on localhost
+//!
+//! Endpoints (all POST, JSON body, same request/response schema as
+//! `bridge-solver --serve` without the `method` field):
+//! - /solve  - NS/EW tricks for a deal, trump and leader (optionally mid-trick)
+//! - /table  - full double-dummy table
+//! - /par    - par score and contract, with the table
+//! - /leads  - value of every opening lead
+//!
+//! Requests are answered by a pool of worker threads, each keeping its own
+//! caches between requests. Solves that run past the timeout or node limit
+//! fail with status 503.
+//!
+//! Usage: solver-server [--port 7878] [--workers N] [--timeout-ms 10000] [--max-nodes N]
+
+use bridge_solver::json::Service;
+use clap::Parser;
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Largest request body accepted
+const MAX_BODY: usize = 64 * 1024;
+
+/// How long to wait for a client to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Parser)]
+#[command(name = "solver-server")]
+#[command(about = "Double-dummy solver HTTP/JSON server (localhost only)")]
+#[command(version)]
+struct Args {
+    /// Port to listen on (127.0.0.1 only)
+    #[arg(short = 'p', long = "port", default_value_t = 7878)]
+    port: u16,
+
+    /// Number of worker threads (default: available parallelism)
+    #[arg(short = 'w', long = "workers")]
+    workers: Option<usize>,
+
+    /// Per-request solve timeout in milliseconds (0 = none)
+    #[arg(short = 't', long = "timeout-ms", default_value_t = 10_000)]
+    timeout_ms: u64,
+
+    /// Per-solve search node limit
+    #[arg(long = "max-nodes")]
+    max_nodes: Option<u64>,
+}
+
+/// Settings shared by all workers
+#[derive(Clone, Copy)]
+struct Config {
+    workers: usize,
+    timeout: Option<Duration>,
+    max_nodes: Option<u64>,
+}
+
+impl Config {
+    fn service(&self) -> Service {
+        let mut service = Service::new();
+        if let Some(timeout) = self.timeout {
+            service = service.with_timeout(timeout);
+        }
+        if let Some(max_nodes) = self.max_nodes {
+            service = service.with_max_nodes(max_nodes);
+        }
+        service
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+    let config = Config {
+        workers: args
+            .workers
+            .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()))
+            .max(1),
+        timeout: (args.timeout_ms > 0).then(|| Duration::from_millis(args.timeout_ms)),
+        max_nodes: args.max_nodes,
+    };
+
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, args.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Error binding to port {}: {}", args.port, e);
+            std::process::exit(1);
+        }
+    };
+    eprintln!(
+        "Listening on http://{} with {} workers",
+        listener.local_addr().unwrap(),
+        config.workers
+    );
+    serve(listener, config);
+}
+
+/// Accept connections and hand them to a pool of workers
+fn serve(listener: TcpListener, config: Config) {
+    let (sender, receiver) = mpsc::channel::<TcpStream>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    let workers: Vec<_> = (0..config.workers)
+        .map(|_| {
+            let receiver = Arc::clone(&receiver);
+            thread::spawn(move || {
+                let mut service = config.service();
+                loop {
+                    let stream = match receiver.lock().unwrap().recv() {
+                        Ok(stream) => stream,
+                        Err(_) => return,
+                    };
+                    if let Err(e) = handle_connection(stream, &mut service) {
+                        eprintln!("Connection error: {}", e);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if sender.send(stream).is_err() {
+                    break;
+                }
+            }
+            Err(e) => eprintln!("Accept error: {}", e),
+        }
+    }
+
+    drop(sender);
+    for worker in workers {
+        let _ = worker.join();
+    }
+}
+
+/// An HTTP response: status code, reason and JSON body
+struct Response {
+    status: u16,
+    reason: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn error(status: u16, reason: &'static str, code: &str, message: &str) -> Self {
+        let body = serde_json::json!({
+            "id": Value::Null,
+            "error": { "code": code, "message": message },
+        });
+        Response {
+            status,
+            reason,
+            body: body.to_string(),
+        }
+    }
+}
+
+/// Read one request from the stream, answer it and close the connection
+fn handle_connection(mut stream: TcpStream, service: &mut Service) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let response = match read_request(&mut stream) {
+        Ok((method, path, body)) => respond(service, &method, &path, &body),
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            Response::error(400, "Bad Request", "invalid_request", &e.to_string())
+        }
+        Err(e) => return Err(e),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+/// Read the request line, headers and body of an HTTP/1.1 request
+fn read_request(stream: &mut TcpStream) -> io::Result<(String, String, Vec<u8>)> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = match (parts.next(), parts.next()) {
+        (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+        _ => return Err(invalid("malformed request line")),
+    };
+
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid("unexpected end of headers"));
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid("invalid Content-Length"))?;
+            }
+        }
+    }
+    if content_length > MAX_BODY {
+        return Err(invalid("request body too large"));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok((method, path, body))
+}
+
+/// Route a request to the service
+fn respond(service: &mut Service, method: &str, path: &str, body: &[u8]) -> Response {
+    let service_method = match path {
+        "/solve" => "solve",
+        "/table" => "table",
+        "/par" => "par",
+        "/leads" => "leads",
+        _ => return Response::error(404, "Not Found", "not_found", "unknown endpoint"),
+    };
+    if method != "POST" {
+        return Response::error(
+            405,
+            "Method Not Allowed",
+            "invalid_request",
+            "use POST with a JSON body",
+        );
+    }
+
+    let mut request = match serde_json::from_slice::<Value>(body) {
+        Ok(Value::Object(request)) => request,
+        Ok(_) => {
+            return Response::error(
+                400,
+                "Bad Request",
+                "invalid_request",
+                "body must be a JSON object",
+            )
+        }
+        Err(e) => {
+            return Response::error(
+                400,
+                "Bad Request",
+                "parse_error",
+                &format!("invalid JSON: {}", e),
+            )
+        }
+    };
+    request.insert("method".to_string(), Value::from(service_method));
+
+    let response = service.handle(&Value::Object(request));
+    let (status, reason) = match response["error"]["code"].as_str() {
+        None => (200, "OK"),
+        Some("limit_exceeded") => (503, "Service Unavailable"),
+        Some(_) => (400, "Bad Request"),
+    };
+    Response {
+        status,
+        reason,
+        body: response.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+
+    const DEAL: &str = "N:A.A.. K.K.. 2.2.. 3.3..";
+
+    /// Start a server on an ephemeral local port
+    fn start(config: Config) -> SocketAddr {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, config));
+        addr
+    }
+
+    /// Send a request and return the status code and parsed body
+    fn post(addr: SocketAddr, path: &str, body: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response.split_once("\r\n\r\n").unwrap().1;
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    fn config() -> Config {
+        Config {
+            workers: 2,
+            timeout: Some(Duration::from_secs(10)),
+            max_nodes: None,
+        }
+    }
+
+    #[test]
+    fn test_endpoints() {
+        let addr = start(config());
+
+        let body = format!(
+            r#"{{"id": 1, "deal": "{}", "trump": "NT", "leader": "W"}}"#,
+            DEAL
+        );
+        let (status, response) = post(addr, "/solve", &body);
+        assert_eq!(status, 200);
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["ns_tricks"], 2);
+
+        let (status, response) = post(addr, "/leads", &body);
+        assert_eq!(status, 200);
+        assert_eq!(response["result"]["seat"], "W");
+        assert_eq!(response["result"]["cards"].as_array().unwrap().len(), 2);
+
+        let body = format!(r#"{{"deal": "{}"}}"#, DEAL);
+        let (status, response) = post(addr, "/table", &body);
+        assert_eq!(status, 200);
+        assert_eq!(response["result"]["table"]["S"]["H"], 2);
+
+        let (status, response) = post(addr, "/par", &body);
+        assert_eq!(status, 200);
+        // Two tricks are not enough for any contract
+        assert_eq!(response["result"]["ns_score"], 0);
+        assert_eq!(response["result"]["contract"], Value::Null);
+    }
+
+    #[test]
+    fn test_errors() {
+        let addr = start(config());
+
+        let (status, response) = post(addr, "/nowhere", "{}");
+        assert_eq!(status, 404);
+        assert_eq!(response["error"]["code"], "not_found");
+
+        let (status, response) = post(addr, "/solve", "[1, 2]");
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["code"], "invalid_request");
+
+        let (status, response) = post(addr, "/table", r#"{"deal": "N:A... K... 2..."}"#);
+        assert_eq!(status, 400);
+        assert_eq!(response["error"]["code"], "invalid_deal");
+    }
+
+    #[test]
+    fn test_limit_exceeded() {
+        let addr = start(Config {
+            max_nodes: Some(100),
+            ..config()
+        });
+        let body = r#"{"deal": "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72", "trump": "N", "leader": "W"}"#;
+        let (status, response) = post(addr, "/solve", body);
+        assert_eq!(status, 503);
+        assert_eq!(response["error"]["code"], "limit_exceeded");
+    }
+}
@@ -2,9 +2,14 @@
 //!
 //! Uses alpha-beta search with MTD(f) driver
 
+use super::analysis::{self, CardAnalysis};
 use super::cards::*;
+use super::endgame::EndgameTable;
 use super::hands::Hands;
 use super::search;
+use super::stats::{SearchStats, SolveReport};
+use super::trace::{NoObserver, SearchObserver, XrayTracer};
+use super::typed::{Card, Seat, Strain};
 use super::types::*;
 
 /// A card played to the current trick, with the seat that played it
@@ -13,13 +18,16 @@ pub struct PlayedCard {
     /// The card played (0-51)
     pub card: usize,
     /// The seat that played the card
-    pub seat: Seat,
+    pub seat: SeatIndex,
 }
 
 impl PlayedCard {
     /// Create a new played card
-    pub fn new(card: usize, seat: Seat) -> Self {
-        PlayedCard { card, seat }
+    pub fn new(card: Card, seat: Seat) -> Self {
+        PlayedCard {
+            card: card.index(),
+            seat: seat.index(),
+        }
     }
 }
 
@@ -37,11 +45,17 @@ impl PartialTrick {
     }
 
     /// Add a card to the partial trick
-    pub fn add(&mut self, card: usize, seat: Seat) -> &mut Self {
+    pub fn add(&mut self, card: Card, seat: Seat) -> &mut Self {
         self.plays.push(PlayedCard::new(card, seat));
         self
     }
 
+    /// Add a card to the partial trick by card and seat index
+    pub(crate) fn add_index(&mut self, card: usize, seat: SeatIndex) -> &mut Self {
+        self.plays.push(PlayedCard { card, seat });
+        self
+    }
+
     /// Get the number of cards played
     pub fn len(&self) -> usize {
         self.plays.len()
@@ -53,19 +67,41 @@ impl PartialTrick {
     }
 
     /// Get the lead suit (suit of the first card played)
-    pub fn lead_suit(&self) -> Option<Suit> {
+    pub fn lead_suit(&self) -> Option<SuitIndex> {
         self.plays.first().map(|p| suit_of(p.card))
     }
 
     /// Get the seat that led to this trick
-    pub fn leader(&self) -> Option<Seat> {
+    pub fn leader(&self) -> Option<SeatIndex> {
         self.plays.first().map(|p| p.seat)
     }
 
     /// Get the next seat to play
-    pub fn next_to_play(&self) -> Option<Seat> {
+    pub fn next_to_play(&self) -> Option<SeatIndex> {
         self.plays.last().map(|p| next_seat(p.seat))
     }
+
+    /// Get the play currently winning the trick
+    ///
+    /// `trump` is a [`Strain`], or a trump [`Suit`](crate::Suit).
+    pub fn winner(&self, trump: impl Into<Strain>) -> Option<PlayedCard> {
+        self.winner_index(trump.into().index())
+    }
+
+    /// Get the play currently winning the trick, with a strain index
+    pub(crate) fn winner_index(&self, trump: usize) -> Option<PlayedCard> {
+        let mut plays = self.plays.iter();
+        let mut winner = *plays.next()?;
+        for play in plays {
+            let same_suit = suit_of(play.card) == suit_of(winner.card);
+            if (same_suit && higher_rank(play.card, winner.card))
+                || (!same_suit && suit_of(play.card) == trump)
+            {
+                winner = *play;
+            }
+        }
+        Some(winner)
+    }
 }
 
 /// Ordered cards container for move ordering
@@ -106,7 +142,7 @@ impl OrderedCards {
         while !remaining.is_empty() {
             let card = remaining.bottom();
             self.add(card);
-            remaining.remove(card);
+            remaining.remove_index(card);
         }
     }
 
@@ -136,7 +172,7 @@ impl OrderedCards {
 pub fn order_leads(
     playable: Cards,
     hands: &Hands,
-    seat: Seat,
+    seat: SeatIndex,
     trump: usize,
     all_cards: Cards,
 ) -> OrderedCards {
@@ -165,9 +201,9 @@ pub fn order_leads(
 
         // Handle trump suit specially in suit contracts
         if is_suit_contract && suit == trump {
-            trump_leads.add(my_suit.top());
+            trump_leads.add_index(my_suit.top());
             if my_suit.size() > 1 {
-                trump_leads.add(my_suit.bottom());
+                trump_leads.add_index(my_suit.bottom());
             }
             continue;
         }
@@ -224,23 +260,23 @@ pub fn order_leads(
         // Partner has K and LHO has A, etc.
         if pd_suit.size() >= 2 && lho_suit.size() >= 2 {
             let mut qj = Cards::new();
-            qj.add(q);
-            qj.add(j);
+            qj.add_index(q);
+            qj.add_index(j);
             let mut jt = Cards::new();
-            jt.add(j);
-            jt.add(t);
-
-            if (pd_suit.have(k) && lho_suit.have(a))
-                || (pd_suit.have(a)
-                    && lho_suit.have(k)
-                    && (pd_suit.have(q) || our_suits.include(qj)))
-                || (pd_suit.have(k)
-                    && lho_suit.have(q)
-                    && (pd_suit.have(j) || our_suits.include(jt)))
+            jt.add_index(j);
+            jt.add_index(t);
+
+            if (pd_suit.have_index(k) && lho_suit.have_index(a))
+                || (pd_suit.have_index(a)
+                    && lho_suit.have_index(k)
+                    && (pd_suit.have_index(q) || our_suits.include(qj)))
+                || (pd_suit.have_index(k)
+                    && lho_suit.have_index(q)
+                    && (pd_suit.have_index(j) || our_suits.include(jt)))
             {
-                good_leads.add(my_suit.top());
+                good_leads.add_index(my_suit.top());
                 if my_suit.size() > 1 {
-                    good_leads.add(my_suit.bottom());
+                    good_leads.add_index(my_suit.bottom());
                 }
                 continue;
             }
@@ -249,13 +285,15 @@ pub fn order_leads(
         // Check for bad leads (high card in front of RHO's higher card)
         if my_suit.size() >= 2
             && rho_suit.size() >= 2
-            && ((my_suit.have(a) && rho_suit.have(k))
-                || (my_suit.have(k) && rho_suit.have(a) && !partnership_cards.have(q)))
+            && ((my_suit.have_index(a) && rho_suit.have_index(k))
+                || (my_suit.have_index(k)
+                    && rho_suit.have_index(a)
+                    && !partnership_cards.have_index(q)))
         {
             if is_suit_contract {
-                bad_leads.add(my_suit.top());
+                bad_leads.add_index(my_suit.top());
                 if my_suit.size() > 1 {
-                    bad_leads.add(my_suit.bottom());
+                    bad_leads.add_index(my_suit.bottom());
                 }
             }
             continue;
@@ -263,16 +301,16 @@ pub fn order_leads(
 
         // Check for high leads (both sides have A/K/Q)
         let mut akq = Cards::new();
-        akq.add(a);
-        akq.add(k);
-        akq.add(q);
+        akq.add_index(a);
+        akq.add_index(k);
+        akq.add_index(q);
         if !lho_suit.is_empty()
             && !rho_suit.is_empty()
             && partnership_cards.intersect(akq).size() >= 2
         {
-            high_leads.add(my_suit.top());
+            high_leads.add_index(my_suit.top());
             if my_suit.size() > 1 {
-                high_leads.add(my_suit.bottom());
+                high_leads.add_index(my_suit.bottom());
             }
             continue;
         }
@@ -286,14 +324,14 @@ pub fn order_leads(
             && pd_hand.suit(trump).size() <= playable.suit(trump).size()
             && my_suit.bottom() != a
         {
-            ruff_leads.add(my_suit.bottom());
+            ruff_leads.add_index(my_suit.bottom());
             continue;
         }
 
         // Normal leads (top and bottom)
-        normal_leads.add(my_suit.top());
+        normal_leads.add_index(my_suit.top());
         if my_suit.size() > 1 {
-            normal_leads.add(my_suit.bottom());
+            normal_leads.add_index(my_suit.bottom());
         }
     }
 
@@ -326,10 +364,10 @@ pub fn order_leads(
 pub fn order_follows(
     playable: Cards,
     hands: &Hands,
-    seat: Seat,
+    seat: SeatIndex,
     trump: usize,
-    lead_suit: Suit,
-    winning_seat: Seat,
+    lead_suit: SuitIndex,
+    winning_seat: SeatIndex,
     winning_card: usize,
     card_in_trick: usize,
     wins_over: impl Fn(usize, usize) -> bool,
@@ -478,12 +516,12 @@ fn add_discards(ordered: &mut OrderedCards, mut playable: Cards, trump: usize) {
             let remaining_in_suit = playable.suit(suit).size();
             discards[num_discards] = (bottom, remaining_in_suit);
             num_discards += 1;
-            playable.remove(bottom);
+            playable.remove_index(bottom);
         }
     }
 
     // Sort discards by suit length (longer suits first) - stable sort to preserve suit order for ties
-    discards[..num_discards].sort_by(|a, b| b.1.cmp(&a.1));
+    discards[..num_discards].sort_by_key(|b| std::cmp::Reverse(b.1));
 
     // Add sorted discards
     for discard in discards.iter().take(num_discards) {
@@ -498,18 +536,26 @@ fn add_discards(ordered: &mut OrderedCards, mut playable: Cards, trump: usize) {
 pub struct Solver {
     hands: Hands,
     trump: usize,
-    initial_leader: Seat,
+    initial_leader: SeatIndex,
     num_tricks: usize,
 }
 
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 pub(crate) static NODE_COUNT: AtomicU64 = AtomicU64::new(0);
-pub(crate) static XRAY_COUNT: AtomicUsize = AtomicUsize::new(0);
 pub(crate) static XRAY_LIMIT: AtomicUsize = AtomicUsize::new(0);
 pub(crate) static NO_PRUNING: AtomicBool = AtomicBool::new(false);
 pub(crate) static NO_TT: AtomicBool = AtomicBool::new(false);
 pub(crate) static NO_RANK_SKIP: AtomicBool = AtomicBool::new(false);
+pub(crate) static NO_BMI2: AtomicBool = AtomicBool::new(false);
 pub(crate) static SHOW_PERF: AtomicBool = AtomicBool::new(false);
+pub(crate) static CACHE_BITS: AtomicUsize = AtomicUsize::new(DEFAULT_CACHE_BITS);
+pub(crate) static CACHE_MEMORY: AtomicUsize = AtomicUsize::new(0);
+static ENDGAME_TABLE: RwLock<Option<Arc<EndgameTable>>> = RwLock::new(None);
+
+/// Default size of the caches created by the solver (2^16 entries each)
+pub const DEFAULT_CACHE_BITS: usize = 16;
 
 /// Get the node count from the last solve (for profiling)
 pub fn get_node_count() -> u64 {
@@ -517,15 +563,11 @@ pub fn get_node_count() -> u64 {
 }
 
 /// Set xray tracing limit (0 = disabled)
+///
+/// Solves that do not take an observer then trace the first `limit` nodes of
+/// each solve to stderr with an `XrayTracer`.
 pub fn set_xray_limit(limit: usize) {
     XRAY_LIMIT.store(limit, Ordering::Relaxed);
-    XRAY_COUNT.store(0, Ordering::Relaxed);
-}
-
-/// Check if xray logging should occur (enabled and under limit)
-pub(crate) fn xray_should_log() -> bool {
-    let limit = XRAY_LIMIT.load(Ordering::Relaxed);
-    limit > 0 && XRAY_COUNT.load(Ordering::Relaxed) <= limit
 }
 
 /// Set no-pruning mode (disables fast/slow tricks pruning for debugging)
@@ -543,14 +585,150 @@ pub fn set_no_rank_skip(enabled: bool) {
     NO_RANK_SKIP.store(enabled, Ordering::Relaxed);
 }
 
-/// Set show-perf mode (outputs [PERF] lines to stderr after each solve)
+/// Set no-BMI2 mode (uses portable bit packing even where the CPU has BMI2,
+/// for benchmarking)
+pub fn set_no_bmi2(enabled: bool) {
+    NO_BMI2.store(enabled, Ordering::Relaxed);
+}
+
+/// Set show-perf mode (prints a `SolveReport` table to stderr after each solve)
 pub fn set_show_perf(enabled: bool) {
     SHOW_PERF.store(enabled, Ordering::Relaxed);
 }
 
+/// Set the size of the caches the solver creates itself, as 2^bits entries
+///
+/// Applies to `Solver::solve`, `solve_dd_table` and the other entry points
+/// that do not take caches. Clamped to 8..=24; lower it to save memory (e.g.
+/// in a browser), raise it for long solves.
+pub fn set_cache_bits(bits: usize) {
+    CACHE_BITS.store(bits.clamp(8, 24), Ordering::Relaxed);
+}
+
+/// Size of the caches the solver creates itself, as 2^bits entries
+pub fn cache_bits() -> usize {
+    CACHE_BITS.load(Ordering::Relaxed)
+}
+
+/// Bound the memory of the caches the solver creates itself, in megabytes
+/// per pair of caches (None = sized by `set_cache_bits` and unbounded)
+///
+/// Bounded caches replace old entries rather than grow, so batch runs use
+/// predictable memory: roughly this much per thread.
+pub fn set_cache_memory_limit(megabytes: Option<usize>) {
+    CACHE_MEMORY.store(megabytes.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// Memory bound of the caches the solver creates itself, in megabytes
+pub fn cache_memory_limit() -> Option<usize> {
+    Some(CACHE_MEMORY.load(Ordering::Relaxed)).filter(|&megabytes| megabytes > 0)
+}
+
+/// Install an endgame table for all later solves (None = search every
+/// ending, the default)
+///
+/// The search then looks up positions with few enough cards per hand at each
+/// trick start instead of playing them out.
+pub fn set_endgame_table(table: Option<Arc<EndgameTable>>) {
+    *ENDGAME_TABLE.write().unwrap() = table;
+}
+
+/// The endgame table installed by `set_endgame_table`
+pub fn endgame_table() -> Option<Arc<EndgameTable>> {
+    ENDGAME_TABLE.read().unwrap().clone()
+}
+
+/// Create the caches for a solve, as configured by `set_cache_bits` and
+/// `set_cache_memory_limit`
+pub fn new_caches() -> (search::CutoffCache, crate::PatternCache) {
+    match cache_memory_limit() {
+        // The cutoff cache is small next to pattern trees: give it an eighth
+        Some(megabytes) => (
+            search::CutoffCache::with_memory_limit(megabytes.div_ceil(8)),
+            crate::PatternCache::with_memory_limit(megabytes - megabytes / 8),
+        ),
+        None => (
+            search::CutoffCache::new(cache_bits()),
+            crate::PatternCache::new(cache_bits()),
+        ),
+    }
+}
+
+/// Current time, or None where there is no clock (wasm32-unknown-unknown,
+/// where `Instant::now` panics)
+fn now() -> Option<Instant> {
+    if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+        None
+    } else {
+        Some(Instant::now())
+    }
+}
+
+/// Limits on the work done by one solve
+///
+/// A solve that hits a limit is abandoned and reports no result. Nodes are
+/// counted per solve, so limits are independent of other threads' solves.
+/// Caches used by an abandoned solve remain valid: bounds are only stored for
+/// subtrees that were searched completely.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SolveLimit {
+    /// Maximum number of search nodes (None = unlimited)
+    pub max_nodes: Option<u64>,
+    /// Time at which the solve is abandoned (None = no deadline)
+    pub deadline: Option<Instant>,
+}
+
+impl SolveLimit {
+    /// The deadline is checked once every 1024 nodes
+    const DEADLINE_CHECK_MASK: u64 = 1023;
+
+    /// No limits
+    pub fn unlimited() -> Self {
+        SolveLimit::default()
+    }
+
+    /// Abandon the solve after `max_nodes` search nodes
+    pub fn with_max_nodes(mut self, max_nodes: u64) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    /// Abandon the solve once `timeout` has elapsed from now
+    ///
+    /// Has no effect on targets without a clock (wasm32-unknown-unknown).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = now().map(|now| now + timeout);
+        self
+    }
+
+    /// Check if a solve that has searched `nodes` nodes must stop
+    pub(crate) fn exceeded(&self, nodes: u64) -> bool {
+        if self.max_nodes.is_some_and(|max| nodes > max) {
+            return true;
+        }
+        match self.deadline {
+            Some(deadline) if nodes & Self::DEADLINE_CHECK_MASK == 0 => Instant::now() >= deadline,
+            _ => false,
+        }
+    }
+}
+
 impl Solver {
     /// Create a new solver
-    pub fn new(hands: Hands, trump: usize, initial_leader: Seat) -> Self {
+    ///
+    /// `trump` is a [`Strain`], or a trump [`Suit`](crate::Suit).
+    pub fn new(hands: Hands, trump: impl Into<Strain>, initial_leader: Seat) -> Self {
+        Self::with_indices(hands, trump.into().index(), initial_leader.index())
+    }
+
+    /// Create a new solver from a strain index and a seat index
+    #[deprecated(note = "use `Solver::new` with a `Strain` and a `Seat`")]
+    pub fn from_indices(hands: Hands, trump: usize, initial_leader: usize) -> Self {
+        Self::with_indices(hands, trump, initial_leader)
+    }
+
+    /// Create a new solver from the solver's own indices
+    pub(crate) fn with_indices(hands: Hands, trump: usize, initial_leader: SeatIndex) -> Self {
         let num_tricks = hands.num_tricks();
         Solver {
             hands,
@@ -567,12 +745,25 @@ impl Solver {
     ///
     /// # Arguments
     /// * `hands` - The remaining cards in each hand (excluding cards in partial_trick)
-    /// * `trump` - Trump suit (0-3) or NOTRUMP (4)
+    /// * `trump` - Trump strain, or a trump suit
     /// * `partial_trick` - Cards already played to the current trick
     ///
     /// # Returns
     /// The solver, or None if the partial trick is invalid
-    pub fn new_mid_trick(hands: Hands, trump: usize, partial_trick: &PartialTrick) -> Option<Self> {
+    pub fn new_mid_trick(
+        hands: Hands,
+        trump: impl Into<Strain>,
+        partial_trick: &PartialTrick,
+    ) -> Option<Self> {
+        Self::mid_trick_with_indices(hands, trump.into().index(), partial_trick)
+    }
+
+    /// Create a solver for a mid-trick position from a strain index
+    pub(crate) fn mid_trick_with_indices(
+        hands: Hands,
+        trump: usize,
+        partial_trick: &PartialTrick,
+    ) -> Option<Self> {
         if partial_trick.is_empty() || partial_trick.len() > 3 {
             return None;
         }
@@ -596,8 +787,7 @@ impl Solver {
 
     /// Solve and return NS tricks
     pub fn solve(&self) -> u8 {
-        let mut cutoff_cache = search::CutoffCache::new(16);
-        let mut pattern_cache = crate::PatternCache::new(16);
+        let (mut cutoff_cache, mut pattern_cache) = new_caches();
         self.solve_with_caches(&mut cutoff_cache, &mut pattern_cache)
     }
 
@@ -607,7 +797,25 @@ impl Solver {
         cutoff_cache: &mut search::CutoffCache,
         pattern_cache: &mut super::pattern::PatternCache,
     ) -> u8 {
-        self.solve_with_caches_and_partial(cutoff_cache, pattern_cache, None)
+        self.solve_with_caches_and_partial(
+            cutoff_cache,
+            pattern_cache,
+            None,
+            &SolveLimit::unlimited(),
+        )
+        .unwrap()
+    }
+
+    /// Solve with external caches, giving up when `limit` is reached
+    ///
+    /// Returns None if the solve was abandoned.
+    pub fn solve_with_limit(
+        &self,
+        cutoff_cache: &mut search::CutoffCache,
+        pattern_cache: &mut super::pattern::PatternCache,
+        limit: &SolveLimit,
+    ) -> Option<u8> {
+        self.solve_with_caches_and_partial(cutoff_cache, pattern_cache, None, limit)
     }
 
     /// Solve from a mid-trick position with external caches
@@ -628,7 +836,103 @@ impl Solver {
         pattern_cache: &mut super::pattern::PatternCache,
         partial_trick: &PartialTrick,
     ) -> u8 {
-        self.solve_with_caches_and_partial(cutoff_cache, pattern_cache, Some(partial_trick))
+        self.solve_with_caches_and_partial(
+            cutoff_cache,
+            pattern_cache,
+            Some(partial_trick),
+            &SolveLimit::unlimited(),
+        )
+        .unwrap()
+    }
+
+    /// Solve from a mid-trick position, giving up when `limit` is reached
+    ///
+    /// Returns None if the solve was abandoned.
+    pub fn solve_mid_trick_with_limit(
+        &self,
+        cutoff_cache: &mut search::CutoffCache,
+        pattern_cache: &mut super::pattern::PatternCache,
+        partial_trick: &PartialTrick,
+        limit: &SolveLimit,
+    ) -> Option<u8> {
+        self.solve_with_caches_and_partial(cutoff_cache, pattern_cache, Some(partial_trick), limit)
+    }
+
+    /// Analyse every legal lead, returning the NS tricks after each card
+    pub fn analyze_plays(
+        &self,
+        cutoff_cache: &mut search::CutoffCache,
+        pattern_cache: &mut super::pattern::PatternCache,
+    ) -> Vec<CardAnalysis> {
+        analysis::analyze_plays(
+            self.hands,
+            self.trump,
+            self.initial_leader,
+            &PartialTrick::new(),
+            cutoff_cache,
+            pattern_cache,
+        )
+        // A lead is always valid
+        .unwrap()
+    }
+
+    /// Analyse every legal play to a partially played trick
+    ///
+    /// The solver must have been created with `new_mid_trick` for the same trick.
+    pub fn analyze_plays_mid_trick(
+        &self,
+        cutoff_cache: &mut search::CutoffCache,
+        pattern_cache: &mut super::pattern::PatternCache,
+        partial_trick: &PartialTrick,
+    ) -> Vec<CardAnalysis> {
+        analysis::analyze_plays(
+            self.hands,
+            self.trump,
+            self.initial_leader,
+            partial_trick,
+            cutoff_cache,
+            pattern_cache,
+        )
+        // new_mid_trick accepted a trick of one to three cards
+        .unwrap()
+    }
+
+    /// Solve with external caches, measuring the search and the caches
+    ///
+    /// The caches' counters are reset first, so the report covers this solve
+    /// only.
+    pub fn solve_with_report(
+        &self,
+        cutoff_cache: &mut search::CutoffCache,
+        pattern_cache: &mut super::pattern::PatternCache,
+    ) -> (u8, SolveReport) {
+        let (result, report) = self.solve_and_report(
+            cutoff_cache,
+            pattern_cache,
+            None,
+            &SolveLimit::unlimited(),
+            &mut NoObserver,
+        );
+        (result.unwrap(), report)
+    }
+
+    /// Solve with external caches, reporting the search's events to `observer`
+    ///
+    /// See the `trace` module for the events and the built-in tracers.
+    pub fn solve_with_observer(
+        &self,
+        cutoff_cache: &mut search::CutoffCache,
+        pattern_cache: &mut super::pattern::PatternCache,
+        observer: &mut impl SearchObserver,
+    ) -> u8 {
+        self.solve_observed(
+            cutoff_cache,
+            pattern_cache,
+            None,
+            &SolveLimit::unlimited(),
+            observer,
+        )
+        .unwrap()
     }
 
     /// Internal solve implementation that handles both normal and mid-trick positions
@@ -637,38 +941,110 @@ impl Solver {
         cutoff_cache: &mut search::CutoffCache,
         pattern_cache: &mut super::pattern::PatternCache,
         partial_trick: Option<&PartialTrick>,
-    ) -> u8 {
+        limit: &SolveLimit,
+    ) -> Option<u8> {
+        let xray_limit = XRAY_LIMIT.load(Ordering::Relaxed);
+        if xray_limit > 0 {
+            let mut tracer = XrayTracer::new(std::io::stderr(), xray_limit);
+            return self.solve_observed(
+                cutoff_cache,
+                pattern_cache,
+                partial_trick,
+                limit,
+                &mut tracer,
+            );
+        }
+        self.solve_observed(
+            cutoff_cache,
+            pattern_cache,
+            partial_trick,
+            limit,
+            &mut NoObserver,
+        )
+    }
+
+    fn solve_observed(
+        &self,
+        cutoff_cache: &mut search::CutoffCache,
+        pattern_cache: &mut super::pattern::PatternCache,
+        partial_trick: Option<&PartialTrick>,
+        limit: &SolveLimit,
+        observer: &mut impl SearchObserver,
+    ) -> Option<u8> {
+        if SHOW_PERF.load(Ordering::Relaxed) {
+            let (result, report) =
+                self.solve_and_report(cutoff_cache, pattern_cache, partial_trick, limit, observer);
+            eprint!("{}", report);
+            return result;
+        }
+        self.run_solve(
+            cutoff_cache,
+            pattern_cache,
+            partial_trick,
+            limit,
+            &mut SearchStats::default(),
+            observer,
+        )
+    }
+
+    fn solve_and_report(
+        &self,
+        cutoff_cache: &mut search::CutoffCache,
+        pattern_cache: &mut super::pattern::PatternCache,
+        partial_trick: Option<&PartialTrick>,
+        limit: &SolveLimit,
+        observer: &mut impl SearchObserver,
+    ) -> (Option<u8>, SolveReport) {
+        cutoff_cache.reset_stats();
+        pattern_cache.reset_stats();
+        let start = now();
+        let mut search = SearchStats::default();
+        let result = self.run_solve(
+            cutoff_cache,
+            pattern_cache,
+            partial_trick,
+            limit,
+            &mut search,
+            observer,
+        );
+        let report = SolveReport {
+            elapsed: start.map(|start| start.elapsed()),
+            search,
+            cutoff_cache: cutoff_cache.stats(),
+            pattern_cache: pattern_cache.stats(),
+            pattern_trees: pattern_cache.tree_stats(),
+            cutoff_cache_bytes: cutoff_cache.memory_used(),
+            pattern_cache_bytes: pattern_cache.memory_used(),
+        };
+        (result, report)
+    }
+
+    fn run_solve(
+        &self,
+        cutoff_cache: &mut search::CutoffCache,
+        pattern_cache: &mut super::pattern::PatternCache,
+        partial_trick: Option<&PartialTrick>,
+        limit: &SolveLimit,
+        stats: &mut SearchStats,
+        observer: &mut impl SearchObserver,
+    ) -> Option<u8> {
         NODE_COUNT.store(0, Ordering::Relaxed);
-        XRAY_COUNT.store(0, Ordering::Relaxed);
-        let start = std::time::Instant::now();
         let num_tricks = self.num_tricks;
         let guess = self.guess_tricks();
-        let result = self.mtdf_search_with_caches_and_partial(
+        self.mtdf_search_with_caches_and_partial(
             num_tricks,
             guess,
             cutoff_cache,
             pattern_cache,
             partial_trick,
-        );
-        if SHOW_PERF.load(Ordering::Relaxed) {
-            let elapsed = start.elapsed();
-            let iterations = NODE_COUNT.load(Ordering::Relaxed);
-            let ns_per_iter = if iterations > 0 {
-                elapsed.as_nanos() as f64 / iterations as f64
-            } else {
-                0.0
-            };
-            eprintln!(
-                "[PERF] iterations={}, time={:.3}s, ns/iter={:.1}",
-                iterations,
-                elapsed.as_secs_f64(),
-                ns_per_iter
-            );
-        }
-        result
+            limit,
+            stats,
+            observer,
+        )
     }
 
     /// MTD(f) search driver that handles mid-trick positions
+    #[allow(clippy::too_many_arguments)]
     fn mtdf_search_with_caches_and_partial(
         &self,
         num_tricks: usize,
@@ -676,8 +1052,12 @@ impl Solver {
         cutoff_cache: &mut search::CutoffCache,
         pattern_cache: &mut super::pattern::PatternCache,
         partial_trick: Option<&PartialTrick>,
-    ) -> u8 {
+        limit: &SolveLimit,
+        stats: &mut SearchStats,
+        observer: &mut impl SearchObserver,
+    ) -> Option<u8> {
         let mut hands = self.hands;
+        let mut nodes = 0;
 
         let mut lower = 0i8;
         let mut upper = num_tricks as i8;
@@ -697,8 +1077,16 @@ impl Solver {
                 cutoff_cache,
                 pattern_cache,
                 partial_trick,
-            );
+            )
+            .with_limit(*limit, nodes)
+            .with_observer(&mut *observer);
             ns_tricks = searcher.search(beta) as i8;
+            stats.iterations += 1;
+            stats.add(searcher.stats());
+            if searcher.aborted() {
+                return None;
+            }
+            nodes = searcher.nodes();
 
             if ns_tricks < beta {
                 upper = ns_tricks;
@@ -707,7 +1095,7 @@ impl Solver {
             }
         }
 
-        lower as u8
+        Some(lower as u8)
     }
 
     /// Estimate starting tricks for MTD(f)
@@ -763,7 +1151,7 @@ mod tests {
         let hands = Hands::from_pbn("N:A... K... 2... 3...").unwrap();
 
         // West leads - EW has the lead but NS has the ace
-        let solver = Solver::new(hands, NOTRUMP, WEST);
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
         let ns_tricks = solver.solve();
         assert_eq!(ns_tricks, 1); // NS wins with the ace
     }
@@ -776,7 +1164,7 @@ mod tests {
         let hands = Hands::from_pbn("N:K... A... 2... 3...").unwrap();
 
         // West leads
-        let solver = Solver::new(hands, NOTRUMP, WEST);
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
         let ns_tricks = solver.solve();
         assert_eq!(ns_tricks, 0); // EW wins with the ace
     }
@@ -789,7 +1177,7 @@ mod tests {
         let hands = Hands::from_pbn("N:A.A.. K.K.. 2.2.. 3.3..").unwrap();
 
         // West leads
-        let solver = Solver::new(hands, NOTRUMP, WEST);
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
         let ns_tricks = solver.solve();
         assert_eq!(ns_tricks, 2); // NS wins both tricks
     }
@@ -801,7 +1189,7 @@ mod tests {
         let hands = Hands::from_pbn("N:A.A.A.A K.K.K.K 2.2.2.2 3.3.3.3").unwrap();
 
         // West leads
-        let solver = Solver::new(hands, NOTRUMP, WEST);
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
         let ns_tricks = solver.solve();
         assert_eq!(ns_tricks, 4); // NS wins all 4 tricks
     }
@@ -814,7 +1202,7 @@ mod tests {
 
         // West leads
         let start = std::time::Instant::now();
-        let solver = Solver::new(hands, NOTRUMP, WEST);
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
         let ns_tricks = solver.solve();
         eprintln!("8-trick test took {:?}", start.elapsed());
         assert_eq!(ns_tricks, 8); // NS wins 8 tricks
@@ -830,7 +1218,7 @@ mod tests {
         .unwrap();
 
         eprintln!("Hands parsed, starting solve...");
-        let solver = Solver::new(hands, NOTRUMP, WEST);
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
         let start = std::time::Instant::now();
         let ns_tricks = solver.solve();
         eprintln!("Solve took {:?}", start.elapsed());
@@ -846,7 +1234,7 @@ mod tests {
         )
         .unwrap();
 
-        let solver = Solver::new(hands, NOTRUMP, WEST);
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
         let ns_tricks = solver.solve();
         assert_eq!(ns_tricks, 0);
     }
@@ -861,7 +1249,7 @@ mod tests {
         .unwrap();
 
         let start = std::time::Instant::now();
-        let solver = Solver::new(hands, NOTRUMP, WEST);
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
         let ns_tricks = solver.solve();
         eprintln!("9-trick test took {:?}", start.elapsed());
         assert_eq!(ns_tricks, 9);
@@ -877,7 +1265,7 @@ mod tests {
         .unwrap();
 
         let start = std::time::Instant::now();
-        let solver = Solver::new(hands, NOTRUMP, NORTH);
+        let solver = Solver::with_indices(hands, NOTRUMP, NORTH);
         let ns_tricks = solver.solve();
         let nodes = get_node_count();
         eprintln!(
@@ -903,9 +1291,9 @@ mod tests {
 
         // Create partial trick with West's S3 already played
         let mut partial = PartialTrick::new();
-        partial.add(card_of(SPADE, THREE), WEST);
+        partial.add_index(card_of(SPADE, THREE), WEST);
 
-        let solver = Solver::new_mid_trick(hands, NOTRUMP, &partial).unwrap();
+        let solver = Solver::mid_trick_with_indices(hands, NOTRUMP, &partial).unwrap();
         let mut cutoff_cache = search::CutoffCache::new(16);
         let mut pattern_cache = crate::PatternCache::new(16);
 
@@ -925,10 +1313,10 @@ mod tests {
         let hands = Hands::from_pbn("N:.A.. K.K.. 2.2.. .3..").unwrap();
 
         let mut partial = PartialTrick::new();
-        partial.add(card_of(SPADE, THREE), WEST);
-        partial.add(card_of(SPADE, ACE), NORTH);
+        partial.add_index(card_of(SPADE, THREE), WEST);
+        partial.add_index(card_of(SPADE, ACE), NORTH);
 
-        let solver = Solver::new_mid_trick(hands, NOTRUMP, &partial).unwrap();
+        let solver = Solver::mid_trick_with_indices(hands, NOTRUMP, &partial).unwrap();
         let mut cutoff_cache = search::CutoffCache::new(16);
         let mut pattern_cache = crate::PatternCache::new(16);
 
@@ -949,11 +1337,11 @@ mod tests {
         let hands = Hands::from_pbn("N:.A.. .K.. 2.2.. .3..").unwrap();
 
         let mut partial = PartialTrick::new();
-        partial.add(card_of(SPADE, THREE), WEST);
-        partial.add(card_of(SPADE, ACE), NORTH);
-        partial.add(card_of(SPADE, KING), EAST);
+        partial.add_index(card_of(SPADE, THREE), WEST);
+        partial.add_index(card_of(SPADE, ACE), NORTH);
+        partial.add_index(card_of(SPADE, KING), EAST);
 
-        let solver = Solver::new_mid_trick(hands, NOTRUMP, &partial).unwrap();
+        let solver = Solver::mid_trick_with_indices(hands, NOTRUMP, &partial).unwrap();
         let mut cutoff_cache = search::CutoffCache::new(16);
         let mut pattern_cache = crate::PatternCache::new(16);
 
@@ -974,9 +1362,9 @@ mod tests {
         let hands = Hands::from_pbn("N:A.A.. ..K.K 2.2.. .3..").unwrap();
 
         let mut partial = PartialTrick::new();
-        partial.add(card_of(DIAMOND, ACE), WEST);
+        partial.add_index(card_of(DIAMOND, ACE), WEST);
 
-        let solver = Solver::new_mid_trick(hands, SPADE, &partial).unwrap();
+        let solver = Solver::mid_trick_with_indices(hands, SPADE, &partial).unwrap();
         let mut cutoff_cache = search::CutoffCache::new(16);
         let mut pattern_cache = crate::PatternCache::new(16);
 
@@ -987,43 +1375,95 @@ mod tests {
         assert_eq!(ns_tricks, 2);
     }
 
+    #[test]
+    fn test_new_caches() {
+        // Default settings: caches of cache_bits() entries, without a memory bound
+        let (mut cutoff_cache, mut pattern_cache) = new_caches();
+        assert_eq!(pattern_cache.memory_limit(), None);
+
+        // North's aces take both tricks whatever West leads
+        let hands = Hands::from_pbn("N:A.A.. K.K.. 2.2.. 3.3..").unwrap();
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
+        assert_eq!(
+            solver.solve_with_caches(&mut cutoff_cache, &mut pattern_cache),
+            2
+        );
+    }
+
     #[test]
     fn test_partial_trick_builder() {
         let mut partial = PartialTrick::new();
         assert!(partial.is_empty());
         assert_eq!(partial.len(), 0);
 
-        partial.add(card_of(SPADE, THREE), WEST);
+        partial.add_index(card_of(SPADE, THREE), WEST);
         assert!(!partial.is_empty());
         assert_eq!(partial.len(), 1);
         assert_eq!(partial.lead_suit(), Some(SPADE));
         assert_eq!(partial.leader(), Some(WEST));
         assert_eq!(partial.next_to_play(), Some(NORTH));
 
-        partial.add(card_of(SPADE, ACE), NORTH);
+        partial.add_index(card_of(SPADE, ACE), NORTH);
         assert_eq!(partial.len(), 2);
         assert_eq!(partial.next_to_play(), Some(EAST));
     }
 
+    #[test]
+    fn test_partial_trick_winner() {
+        let mut partial = PartialTrick::new();
+        assert!(partial.winner(Strain::Spades).is_none());
+
+        partial.add_index(card_of(HEART, KING), WEST);
+        partial.add_index(card_of(HEART, ACE), NORTH);
+        partial.add_index(card_of(SPADE, TWO), EAST);
+        partial.add_index(card_of(CLUB, ACE), SOUTH);
+
+        // Spades are trumps: East's ruff wins
+        assert_eq!(partial.winner(Strain::Spades).unwrap().seat, EAST);
+        // No trumps: the highest heart wins, discards never do
+        assert_eq!(partial.winner(Strain::NoTrump).unwrap().seat, NORTH);
+    }
+
     #[test]
     fn test_new_mid_trick_validation() {
         let hands = Hands::from_pbn("N:A... K... 2... 3...").unwrap();
 
         // Empty partial trick should fail
         let empty = PartialTrick::new();
-        assert!(Solver::new_mid_trick(hands, NOTRUMP, &empty).is_none());
+        assert!(Solver::mid_trick_with_indices(hands, NOTRUMP, &empty).is_none());
 
         // 4 cards (complete trick) should fail
         let mut full = PartialTrick::new();
-        full.add(card_of(SPADE, THREE), WEST);
-        full.add(card_of(SPADE, ACE), NORTH);
-        full.add(card_of(SPADE, KING), EAST);
-        full.add(card_of(SPADE, TWO), SOUTH);
-        assert!(Solver::new_mid_trick(hands, NOTRUMP, &full).is_none());
+        full.add_index(card_of(SPADE, THREE), WEST);
+        full.add_index(card_of(SPADE, ACE), NORTH);
+        full.add_index(card_of(SPADE, KING), EAST);
+        full.add_index(card_of(SPADE, TWO), SOUTH);
+        assert!(Solver::mid_trick_with_indices(hands, NOTRUMP, &full).is_none());
 
         // 1-3 cards should work
         let mut one = PartialTrick::new();
-        one.add(card_of(SPADE, THREE), WEST);
-        assert!(Solver::new_mid_trick(hands, NOTRUMP, &one).is_some());
+        one.add_index(card_of(SPADE, THREE), WEST);
+        assert!(Solver::mid_trick_with_indices(hands, NOTRUMP, &one).is_some());
+    }
+
+    #[test]
+    fn test_solve_limit() {
+        // A full deal needs far more than 100 nodes
+        let hands = Hands::from_pbn(
+            "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72",
+        )
+        .unwrap();
+        let mut cutoff = search::CutoffCache::new(16);
+        let mut pattern = crate::PatternCache::new(16);
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
+        let limit = SolveLimit::unlimited().with_max_nodes(100);
+        assert_eq!(
+            solver.solve_with_limit(&mut cutoff, &mut pattern, &limit),
+            None
+        );
+
+        let expired = SolveLimit::unlimited().with_timeout(Duration::ZERO);
+        assert!(expired.exceeded(1024));
+        assert!(!SolveLimit::unlimited().exceeded(u64::MAX));
     }
 }
@@ -1 +1,403 @@
-//\! Transposition table and caching
+//! Transposition table and caching
+//!
+//! Saving and loading the pattern and cutoff caches, so work done on a deal in
+//! one process can be reused by the next.
+//!
+//! # File format
+//!
+//! All integers are little-endian.
+//!
+//! ```text
+//! magic     8 bytes  "BSCACHE\0"
+//! version   u32      CACHE_FILE_VERSION
+//! length    u64      payload length in bytes
+//! payload            context, cutoff cache, pattern cache
+//! checksum  u64      FNV-1a hash of the payload
+//! ```
+//!
+//! The context records the deal (four hands as card bitsets, WEST..SOUTH),
+//! trump and leader the caches were built for. Caches are only valid for the
+//! same deal family (the deal and positions reached from it) and trump, so
+//! check the context before using loaded caches.
+//!
+//! Only the tables are saved: a loaded cutoff cache may grow without limit and
+//! a loaded pattern cache has no memory budget, whatever the saved caches were
+//! created with.
+
+use super::cards::Cards;
+use super::hands::Hands;
+use super::pattern::PatternCache;
+use super::search::CutoffCache;
+use super::types::*;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Version of the cache file format, bumped on any incompatible change
+pub const CACHE_FILE_VERSION: u32 = 2;
+
+const MAGIC: &[u8; 8] = b"BSCACHE\0";
+
+/// Largest cache (as 2^bits entries) accepted when reading a file, the most
+/// `set_cache_bits` allows
+pub(crate) const MAX_CACHE_FILE_BITS: usize = 24;
+
+/// What a set of caches was built for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheContext {
+    /// The deal at the start of the first solve
+    pub deal: Hands,
+    /// Trump suit or NOTRUMP
+    pub trump: usize,
+    /// Leader of the first solve
+    pub leader: SeatIndex,
+}
+
+impl CacheContext {
+    pub fn new(deal: Hands, trump: usize, leader: SeatIndex) -> Self {
+        CacheContext {
+            deal,
+            trump,
+            leader,
+        }
+    }
+
+    /// Check if caches built for this context can be used for `deal` and `trump`
+    ///
+    /// The leader does not matter: the caches hold positions, whoever led to them.
+    pub fn is_compatible(&self, deal: &Hands, trump: usize) -> bool {
+        self.deal == *deal && self.trump == trump
+    }
+}
+
+/// Write caches with their context
+///
+/// Fails with `InvalidInput`, writing nothing, if either cache has grown past
+/// 2^24 entries, the most [`read_caches`] accepts.
+pub fn write_caches(
+    writer: &mut impl Write,
+    context: &CacheContext,
+    cutoff_cache: &CutoffCache,
+    pattern_cache: &PatternCache,
+) -> io::Result<()> {
+    check_cache_sizes(cutoff_cache, pattern_cache)?;
+    let mut payload = Vec::new();
+    for seat in 0..NUM_SEATS {
+        put_u64(&mut payload, context.deal[seat].value());
+    }
+    payload.push(context.trump as u8);
+    payload.push(context.leader as u8);
+    cutoff_cache.write_to(&mut payload);
+    pattern_cache.write_to(&mut payload);
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&CACHE_FILE_VERSION.to_le_bytes())?;
+    writer.write_all(&(payload.len() as u64).to_le_bytes())?;
+    writer.write_all(&payload)?;
+    writer.write_all(&checksum(&payload).to_le_bytes())
+}
+
+/// Read caches written by [`write_caches`]
+///
+/// Fails with `InvalidData` if the data is not a cache file, was written by
+/// another format version, is truncated or corrupt, or holds a cache of more
+/// than 2^24 entries.
+pub fn read_caches(
+    reader: &mut impl Read,
+) -> io::Result<(CacheContext, CutoffCache, PatternCache)> {
+    let mut header = [0u8; 20];
+    reader.read_exact(&mut header)?;
+    if &header[..8] != MAGIC {
+        return Err(invalid_data("not a cache file"));
+    }
+    let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
+    if version != CACHE_FILE_VERSION {
+        return Err(invalid_data(format!(
+            "cache file version {} (expected {})",
+            version, CACHE_FILE_VERSION
+        )));
+    }
+    let length = u64::from_le_bytes(header[12..20].try_into().unwrap());
+
+    let mut payload = Vec::new();
+    reader.take(length).read_to_end(&mut payload)?;
+    let mut stored_checksum = [0u8; 8];
+    if payload.len() as u64 != length || reader.read_exact(&mut stored_checksum).is_err() {
+        return Err(invalid_data("cache file is truncated"));
+    }
+    if u64::from_le_bytes(stored_checksum) != checksum(&payload) {
+        return Err(invalid_data("cache file checksum mismatch"));
+    }
+
+    let mut input = ByteReader::new(&payload);
+    let mut deal = Hands::new();
+    for seat in 0..NUM_SEATS {
+        deal[seat] = Cards::from_bits(input.u64()?);
+    }
+    let trump = input.u8()? as usize;
+    let leader = input.u8()? as usize;
+    if deal.all_cards().value() >> TOTAL_CARDS != 0 || trump > NOTRUMP || leader >= NUM_SEATS {
+        return Err(invalid_data("invalid cache context"));
+    }
+    let cutoff_cache = CutoffCache::read_from(&mut input)?;
+    let pattern_cache = PatternCache::read_from(&mut input)?;
+    if !input.is_empty() {
+        return Err(invalid_data("unexpected data after caches"));
+    }
+    Ok((
+        CacheContext::new(deal, trump, leader),
+        cutoff_cache,
+        pattern_cache,
+    ))
+}
+
+/// Save caches to a file
+pub fn save_caches(
+    path: impl AsRef<Path>,
+    context: &CacheContext,
+    cutoff_cache: &CutoffCache,
+    pattern_cache: &PatternCache,
+) -> io::Result<()> {
+    // Leave an existing file alone if the caches cannot be saved
+    check_cache_sizes(cutoff_cache, pattern_cache)?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_caches(&mut writer, context, cutoff_cache, pattern_cache)?;
+    writer.flush()
+}
+
+/// Load caches saved by [`save_caches`]
+pub fn load_caches(
+    path: impl AsRef<Path>,
+) -> io::Result<(CacheContext, CutoffCache, PatternCache)> {
+    read_caches(&mut BufReader::new(File::open(path)?))
+}
+
+fn check_cache_sizes(cutoff_cache: &CutoffCache, pattern_cache: &PatternCache) -> io::Result<()> {
+    if cutoff_cache.bits() > MAX_CACHE_FILE_BITS || pattern_cache.bits() > MAX_CACHE_FILE_BITS {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "caches of more than 2^{} entries cannot be saved",
+                MAX_CACHE_FILE_BITS
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// 64-bit FNV-1a hash
+pub(crate) fn checksum(data: &[u8]) -> u64 {
+    let mut hash = Checksum::new();
+    hash.update(data);
+    hash.value()
+}
+
+/// 64-bit FNV-1a hash of data fed in pieces, for payloads too large to hold
+/// in memory twice
+pub(crate) struct Checksum(u64);
+
+impl Checksum {
+    pub(crate) fn new() -> Self {
+        Checksum(0xcbf29ce484222325)
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        self.0 = data.iter().fold(self.0, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    }
+
+    pub(crate) fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+pub(crate) fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+pub(crate) fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+pub(crate) fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Reads little-endian values from a payload, failing at its end
+pub(crate) struct ByteReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        ByteReader { data }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Bytes left to read
+    pub(crate) fn remaining(&self) -> usize {
+        self.data.len()
+    }
+
+    fn take<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        if self.data.len() < N {
+            return Err(invalid_data("cache data ends unexpectedly"));
+        }
+        let (bytes, rest) = self.data.split_at(N);
+        self.data = rest;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    pub(crate) fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    pub(crate) fn i8(&mut self) -> io::Result<i8> {
+        Ok(self.take::<1>()?[0] as i8)
+    }
+
+    pub(crate) fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    pub(crate) fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    pub(crate) fn u128(&mut self) -> io::Result<u128> {
+        Ok(u128::from_le_bytes(self.take()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Solver;
+
+    const DEAL: &str = "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72";
+
+    /// Solve a 5-card ending and return its hands and serialized caches
+    fn solved_caches() -> (Hands, Vec<u8>) {
+        let hands = Hands::from_pbn("N:AKQ.J6.. 65.A.T4. J7..J.A8 98..96.Q").unwrap();
+        let mut cutoff = CutoffCache::new(10);
+        let mut pattern = PatternCache::new(10);
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
+        solver.solve_with_caches(&mut cutoff, &mut pattern);
+        let mut bytes = Vec::new();
+        let context = CacheContext::new(hands, NOTRUMP, WEST);
+        write_caches(&mut bytes, &context, &cutoff, &pattern).unwrap();
+        (hands, bytes)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (hands, bytes) = solved_caches();
+        let (context, mut cutoff, mut pattern) = read_caches(&mut bytes.as_slice()).unwrap();
+        assert_eq!(context, CacheContext::new(hands, NOTRUMP, WEST));
+        assert!(context.is_compatible(&hands, NOTRUMP));
+        assert!(!context.is_compatible(&hands, SPADE));
+
+        // Writing the loaded caches gives the same bytes
+        let mut rewritten = Vec::new();
+        write_caches(&mut rewritten, &context, &cutoff, &pattern).unwrap();
+        assert_eq!(rewritten, bytes);
+
+        // And solving with them gives the same results as fresh caches
+        for leader in 0..NUM_SEATS {
+            let solver = Solver::with_indices(hands, NOTRUMP, leader);
+            assert_eq!(
+                solver.solve_with_caches(&mut cutoff, &mut pattern),
+                solver.solve()
+            );
+        }
+    }
+
+    #[test]
+    fn test_full_deal_file() {
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        let context = CacheContext::new(hands, SPADE, WEST);
+        let path = std::env::temp_dir().join(format!("bridge-solver-{}.cache", std::process::id()));
+        save_caches(&path, &context, &CutoffCache::new(8), &PatternCache::new(8)).unwrap();
+        let (loaded, _, _) = load_caches(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, context);
+    }
+
+    #[test]
+    fn test_rejects_bad_files() {
+        let (_, bytes) = solved_caches();
+        let error = |bytes: &[u8]| read_caches(&mut &bytes[..]).err().unwrap().to_string();
+
+        let mut corrupt = bytes.clone();
+        corrupt[40] ^= 1;
+        assert!(error(&corrupt).contains("checksum"));
+
+        let mut version = bytes.clone();
+        version[8] = 99;
+        assert!(error(&version).contains("version"));
+
+        assert!(error(&bytes[..bytes.len() - 4]).contains("truncated"));
+        assert!(error(b"not a cache file at all").contains("not a cache file"));
+    }
+
+    #[test]
+    fn test_grown_cache_round_trip() {
+        // 1000 entries grow a 2^4 cutoff cache to 2^11
+        let mut cutoff = CutoffCache::new(4);
+        for i in 1..=1000u64 {
+            cutoff.store(i.wrapping_mul(0x9e3779b97f4a7c15), WEST, (i % 52) as usize);
+        }
+        assert_eq!(cutoff.bits(), 11);
+        let pattern = PatternCache::new(8);
+        let context = CacheContext::new(Hands::from_pbn(DEAL).unwrap(), SPADE, WEST);
+        let mut bytes = Vec::new();
+        write_caches(&mut bytes, &context, &cutoff, &pattern).unwrap();
+
+        let (_, loaded, _) = read_caches(&mut bytes.as_slice()).unwrap();
+        assert_eq!(loaded.bits(), 11);
+        assert_eq!(loaded.len(), 1000);
+        let mut rewritten = Vec::new();
+        write_caches(&mut rewritten, &context, &loaded, &pattern).unwrap();
+        assert_eq!(rewritten, bytes);
+    }
+
+    #[test]
+    fn test_rejects_oversized_caches() {
+        let (_, bytes) = solved_caches();
+        let error = |bytes: &[u8]| read_caches(&mut &bytes[..]).err().unwrap().to_string();
+        // Edit the payload (after the 20-byte header), keeping the checksum valid
+        let edited = |offset: usize, value: &[u8]| {
+            let mut bytes = bytes.clone();
+            let end = bytes.len() - 8;
+            bytes[20 + offset..20 + offset + value.len()].copy_from_slice(value);
+            let sum = checksum(&bytes[20..end]);
+            bytes[end..].copy_from_slice(&sum.to_le_bytes());
+            bytes
+        };
+
+        // The cutoff cache follows the 34-byte context: bits, probe distance, load count
+        let cutoff = 34;
+        let bits = (MAX_CACHE_FILE_BITS as u32 + 1).to_le_bytes();
+        assert!(error(&edited(cutoff, &bits)).contains("invalid cutoff cache size"));
+        // A full table of the largest size claims more entries than the payload holds
+        let full: Vec<u8> = [
+            &24u32.to_le_bytes()[..],
+            &[0u8; 4],
+            &(1u64 << 24).to_le_bytes(),
+        ]
+        .concat();
+        assert!(error(&edited(cutoff, &full)).contains("invalid cutoff cache size"));
+
+        // The pattern cache follows the cutoff entries: bits, number of entries
+        let payload = &bytes[20..];
+        let num_cutoff = u64::from_le_bytes(payload[cutoff + 8..cutoff + 16].try_into().unwrap());
+        let pattern = cutoff + 16 + 16 * num_cutoff as usize;
+        assert!(error(&edited(pattern, &bits)).contains("invalid pattern cache size"));
+        let full: Vec<u8> = [&24u32.to_le_bytes()[..], &(1u64 << 24).to_le_bytes()].concat();
+        assert!(error(&edited(pattern, &full)).contains("invalid pattern cache size"));
+    }
+}
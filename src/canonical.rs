@@ -0,0 +1,260 @@
+//! Canonical deals: one form for every position with the same result
+//!
+//! Positions that differ only by symmetries of the game share a canonical
+//! key, so a result solved for one answers them all:
+//! - rotation: the hands are turned so the seat on lead is West, which swaps
+//!   NS and EW when North or South leads
+//! - suits: in a trump contract the trump suit becomes spades and the other
+//!   three are sorted; in notrump all four are sorted
+//! - ranks: each suit's cards are renumbered from the ace down, as the pattern
+//!   cache does with `RelativeHands`, so endings that differ only by spot cards
+//!   already played are the same
+//!
+//! Canonical NS tricks are those of the side not on lead; [`Canonical`] maps
+//! them to and from the original orientation.
+
+use super::cards::Cards;
+use super::hands::Hands;
+use super::pattern::RelativeHands;
+use super::types::*;
+use std::collections::HashMap;
+
+/// Canonical form of hands, strain and leader: West is on lead
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CanonicalKey {
+    /// Relative hands, rotated and with suits reordered
+    pub hands: Hands,
+    /// SPADE for any trump contract, or NOTRUMP
+    pub strain: usize,
+}
+
+/// A position's canonical key, and how to map its results back
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Canonical {
+    pub key: CanonicalKey,
+    /// True when the rotation swapped NS and EW
+    swapped: bool,
+    num_tricks: u8,
+}
+
+impl Canonical {
+    pub fn new(hands: &Hands, strain: usize, leader: SeatIndex) -> Self {
+        let mut relative = RelativeHands::default();
+        relative.compute(hands, hands.all_cards());
+
+        // Each suit's holdings by seat, counted from the leader
+        let holdings: [[u64; NUM_SEATS]; NUM_SUITS] = std::array::from_fn(|suit| {
+            std::array::from_fn(|seat| {
+                relative.hands[(seat + leader) % NUM_SEATS]
+                    .suit(suit)
+                    .value()
+                    >> (suit * NUM_RANKS)
+            })
+        });
+
+        // order[suit] is the original suit shown as `suit`
+        let mut order: [SuitIndex; NUM_SUITS] = std::array::from_fn(|suit| suit);
+        let (fixed, canonical_strain) = if strain == NOTRUMP {
+            (0, NOTRUMP)
+        } else {
+            order.swap(SPADE, strain);
+            (1, SPADE)
+        };
+        order[fixed..].sort_by(|&a, &b| holdings[b].cmp(&holdings[a]));
+
+        let mut canonical = Hands::new();
+        for (suit, &original) in order.iter().enumerate() {
+            for seat in 0..NUM_SEATS {
+                canonical[seat].add_cards(Cards::from_bits(
+                    holdings[original][seat] << (suit * NUM_RANKS),
+                ));
+            }
+        }
+
+        Canonical {
+            key: CanonicalKey {
+                hands: canonical,
+                strain: canonical_strain,
+            },
+            swapped: is_ns(leader),
+            num_tricks: hands.num_tricks() as u8,
+        }
+    }
+
+    /// NS tricks in the original orientation from canonical NS tricks
+    pub fn to_original(&self, canonical_ns_tricks: u8) -> u8 {
+        if self.swapped {
+            self.num_tricks - canonical_ns_tricks
+        } else {
+            canonical_ns_tricks
+        }
+    }
+
+    /// Canonical NS tricks from NS tricks in the original orientation
+    pub fn to_canonical(&self, ns_tricks: u8) -> u8 {
+        // Swapping the sides is its own inverse
+        self.to_original(ns_tricks)
+    }
+}
+
+/// Solved NS tricks by canonical key, shared by every equivalent position
+#[derive(Clone, Debug, Default)]
+pub struct ResultCache {
+    results: HashMap<CanonicalKey, u8>,
+    hits: u64,
+    misses: u64,
+}
+
+impl ResultCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// NS tricks of `hands` with `leader` on lead, if an equivalent position
+    /// has been solved
+    pub fn get(&mut self, hands: &Hands, strain: usize, leader: SeatIndex) -> Option<u8> {
+        let canonical = Canonical::new(hands, strain, leader);
+        match self.results.get(&canonical.key) {
+            Some(&tricks) => {
+                self.hits += 1;
+                Some(canonical.to_original(tricks))
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Record the NS tricks of `hands` with `leader` on lead
+    pub fn insert(&mut self, hands: &Hands, strain: usize, leader: SeatIndex, ns_tricks: u8) {
+        let canonical = Canonical::new(hands, strain, leader);
+        self.results
+            .insert(canonical.key, canonical.to_canonical(ns_tricks));
+    }
+
+    /// Number of distinct canonical positions stored
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Lookups answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Lookups that found nothing
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::{card_of, rank_of, suit_of};
+    use crate::table::solve_dd_table;
+
+    const DEAL: &str = "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72";
+
+    /// `hands` with every hand moved `by` seats clockwise
+    fn rotate(hands: &Hands, by: usize) -> Hands {
+        let mut rotated = Hands::new();
+        for seat in 0..NUM_SEATS {
+            rotated[(seat + by) % NUM_SEATS] = hands[seat];
+        }
+        rotated
+    }
+
+    /// `hands` with suits `a` and `b` exchanged
+    fn swap_suits(hands: &Hands, a: SuitIndex, b: SuitIndex) -> Hands {
+        let mut swapped = Hands::new();
+        for seat in 0..NUM_SEATS {
+            for card in hands[seat].iter() {
+                let suit = suit_of(card);
+                let suit = if suit == a {
+                    b
+                } else if suit == b {
+                    a
+                } else {
+                    suit
+                };
+                swapped[seat].add_index(card_of(suit, rank_of(card)));
+            }
+        }
+        swapped
+    }
+
+    #[test]
+    fn test_rotation() {
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        let canonical = Canonical::new(&hands, HEART, WEST);
+        for by in 1..NUM_SEATS {
+            let rotated = Canonical::new(&rotate(&hands, by), HEART, by);
+            assert_eq!(rotated.key, canonical.key);
+            assert_eq!(rotated.to_original(4), if by % 2 == 1 { 9 } else { 4 });
+        }
+        assert_ne!(Canonical::new(&hands, HEART, NORTH).key, canonical.key);
+    }
+
+    #[test]
+    fn test_suit_permutations() {
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        let swapped = swap_suits(&hands, HEART, CLUB);
+        for leader in 0..NUM_SEATS {
+            // Side suits, and any suits in notrump
+            for strain in [SPADE, DIAMOND, NOTRUMP] {
+                assert_eq!(
+                    Canonical::new(&swapped, strain, leader).key,
+                    Canonical::new(&hands, strain, leader).key
+                );
+            }
+            // The trump suit moves with the swap
+            assert_eq!(
+                Canonical::new(&swapped, CLUB, leader).key,
+                Canonical::new(&hands, HEART, leader).key
+            );
+            assert_ne!(
+                Canonical::new(&swapped, HEART, leader).key,
+                Canonical::new(&hands, HEART, leader).key
+            );
+        }
+    }
+
+    #[test]
+    fn test_rank_equivalence() {
+        // The same ending with different spot cards
+        let a = Hands::from_pbn("N:A2... K3... Q4... J5...").unwrap();
+        let b = Hands::from_pbn("N:K6... Q7... J8... T9...").unwrap();
+        assert_eq!(
+            Canonical::new(&a, NOTRUMP, WEST).key,
+            Canonical::new(&b, NOTRUMP, WEST).key
+        );
+    }
+
+    #[test]
+    fn test_result_cache() {
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        let table = solve_dd_table(&hands);
+        let mut cache = ResultCache::new();
+        for strain in 0..NUM_STRAINS {
+            let leader = left_hand_opp(NORTH);
+            let ns_tricks = table.get_index(NORTH, strain);
+            cache.insert(&hands, strain, leader, ns_tricks);
+        }
+        assert_eq!(cache.len(), NUM_STRAINS);
+
+        // North's hand is East's in the rotated deal, with South on lead
+        let rotated = rotate(&hands, 1);
+        for strain in 0..NUM_STRAINS {
+            let ns_tricks = cache.get(&rotated, strain, SOUTH).unwrap();
+            assert_eq!(ns_tricks, 13 - table.get_index(NORTH, strain));
+        }
+        assert_eq!(cache.get(&hands, NOTRUMP, NORTH), None);
+        assert_eq!((cache.hits(), cache.misses()), (NUM_STRAINS as u64, 1));
+    }
+}
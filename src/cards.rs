@@ -4,6 +4,7 @@
 //! Cards are ordered by suit (SHDC) then rank (Ace high).
 //! Bit 0 = Spade Ace, Bit 12 = Spade 2, Bit 13 = Heart Ace, etc.
 
+use super::typed::Card;
 use super::types::*;
 
 /// Card index lookup tables (initialized at compile time via const fn or lazy)
@@ -12,25 +13,25 @@ use super::types::*;
 ///
 /// Get suit of a card (0-51 -> 0-3)
 #[inline]
-pub fn suit_of(card: usize) -> Suit {
+pub fn suit_of(card: usize) -> SuitIndex {
     card / NUM_RANKS
 }
 
 /// Get rank of a card (0-51 -> 0-12, where 12=Ace)
 #[inline]
-pub fn rank_of(card: usize) -> Rank {
+pub fn rank_of(card: usize) -> RankIndex {
     NUM_RANKS - 1 - (card % NUM_RANKS)
 }
 
 /// Get card index from suit and rank
 #[inline]
-pub fn card_of(suit: Suit, rank: Rank) -> usize {
+pub fn card_of(suit: SuitIndex, rank: RankIndex) -> usize {
     suit * NUM_RANKS + (NUM_RANKS - 1 - rank)
 }
 
 /// Get mask for a suit (13 bits)
 #[inline]
-pub fn mask_of(suit: Suit) -> u64 {
+pub fn mask_of(suit: SuitIndex) -> u64 {
     0x1FFF << (suit * NUM_RANKS)
 }
 
@@ -43,6 +44,17 @@ pub fn name_of(card: usize) -> String {
     )
 }
 
+/// Parse a card name such as "SA" or "h9" (inverse of `name_of`)
+pub fn parse_card(name: &str) -> Option<usize> {
+    let mut chars = name.chars();
+    let suit = char_to_suit(chars.next()?)?;
+    let rank = char_to_rank(chars.next()?)?;
+    if suit >= NUM_SUITS || chars.next().is_some() {
+        return None;
+    }
+    Some(card_of(suit, rank))
+}
+
 /// Compare ranks: returns true if card1 has lower rank than card2
 #[inline]
 pub fn lower_rank(card1: usize, card2: usize) -> bool {
@@ -56,7 +68,7 @@ pub fn higher_rank(card1: usize, card2: usize) -> bool {
 }
 
 /// Cards represented as a 52-bit bitboard
-#[derive(Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct Cards {
     bits: u64,
 }
@@ -88,7 +100,20 @@ impl Cards {
 
     /// Check if a card is present
     #[inline]
+    pub fn has_card(&self, card: Card) -> bool {
+        self.have_index(card.index())
+    }
+
+    /// Check if the card with this index (0-51) is present
+    #[deprecated(note = "use `has_card` with a `Card`, or `have_index`")]
+    #[inline]
     pub fn have(&self, card: usize) -> bool {
+        self.have_index(card)
+    }
+
+    /// Check if the card with this index (0-51) is present
+    #[inline]
+    pub fn have_index(&self, card: usize) -> bool {
         self.bits & (1u64 << card) != 0
     }
 
@@ -100,7 +125,7 @@ impl Cards {
 
     /// Get cards in a specific suit
     #[inline]
-    pub fn suit(&self, suit: Suit) -> Cards {
+    pub fn suit(&self, suit: SuitIndex) -> Cards {
         Cards::from_bits(self.bits & mask_of(suit))
     }
 
@@ -165,14 +190,40 @@ impl Cards {
 
     /// Add a single card
     #[inline]
+    pub fn add_card(&mut self, card: Card) -> &mut Self {
+        self.add_index(card.index())
+    }
+
+    /// Add the card with this index (0-51)
+    #[deprecated(note = "use `add_card` with a `Card`, or `add_index`")]
+    #[inline]
     pub fn add(&mut self, card: usize) -> &mut Self {
+        self.add_index(card)
+    }
+
+    /// Add the card with this index (0-51)
+    #[inline]
+    pub fn add_index(&mut self, card: usize) -> &mut Self {
         self.bits |= 1u64 << card;
         self
     }
 
     /// Remove a single card
     #[inline]
+    pub fn remove_card(&mut self, card: Card) -> &mut Self {
+        self.remove_index(card.index())
+    }
+
+    /// Remove the card with this index (0-51)
+    #[deprecated(note = "use `remove_card` with a `Card`, or `remove_index`")]
+    #[inline]
     pub fn remove(&mut self, card: usize) -> &mut Self {
+        self.remove_index(card)
+    }
+
+    /// Remove the card with this index (0-51)
+    #[inline]
+    pub fn remove_index(&mut self, card: usize) -> &mut Self {
         self.bits &= !(1u64 << card);
         self
     }
@@ -193,14 +244,14 @@ impl Cards {
 
     /// Clear all cards in a suit (mutating)
     #[inline]
-    pub fn clear_suit_mut(&mut self, suit: Suit) -> &mut Self {
+    pub fn clear_suit_mut(&mut self, suit: SuitIndex) -> &mut Self {
         self.bits &= !mask_of(suit);
         self
     }
 
     /// Clear all cards in a suit (returns new Cards)
     #[inline]
-    pub fn clear_suit(&self, suit: Suit) -> Cards {
+    pub fn clear_suit(&self, suit: SuitIndex) -> Cards {
         Cards::from_bits(self.bits & !mask_of(suit))
     }
 
@@ -220,6 +271,11 @@ impl Cards {
     pub fn iter(&self) -> CardsIterator {
         CardsIterator { bits: self.bits }
     }
+
+    /// Iterate over cards as typed [`Card`]s, in the same order as `iter`
+    pub fn cards(&self) -> impl Iterator<Item = Card> {
+        self.iter().map(|card| Card::from_index(card).unwrap())
+    }
 }
 
 impl std::fmt::Debug for Cards {
@@ -327,41 +383,41 @@ mod tests {
         assert_eq!(cards.size(), 0);
 
         // Add spade ace
-        cards.add(card_of(SPADE, ACE));
+        cards.add_index(card_of(SPADE, ACE));
         assert!(!cards.is_empty());
         assert_eq!(cards.size(), 1);
-        assert!(cards.have(card_of(SPADE, ACE)));
+        assert!(cards.have_index(card_of(SPADE, ACE)));
 
         // Add heart king
-        cards.add(card_of(HEART, KING));
+        cards.add_index(card_of(HEART, KING));
         assert_eq!(cards.size(), 2);
 
         // Remove spade ace
-        cards.remove(card_of(SPADE, ACE));
+        cards.remove_index(card_of(SPADE, ACE));
         assert_eq!(cards.size(), 1);
-        assert!(!cards.have(card_of(SPADE, ACE)));
+        assert!(!cards.have_index(card_of(SPADE, ACE)));
     }
 
     #[test]
     fn test_cards_suit() {
         let mut cards = Cards::new();
-        cards.add(card_of(SPADE, ACE));
-        cards.add(card_of(SPADE, KING));
-        cards.add(card_of(HEART, ACE));
+        cards.add_index(card_of(SPADE, ACE));
+        cards.add_index(card_of(SPADE, KING));
+        cards.add_index(card_of(HEART, ACE));
 
         let spades = cards.suit(SPADE);
         assert_eq!(spades.size(), 2);
-        assert!(spades.have(card_of(SPADE, ACE)));
-        assert!(spades.have(card_of(SPADE, KING)));
-        assert!(!spades.have(card_of(HEART, ACE)));
+        assert!(spades.have_index(card_of(SPADE, ACE)));
+        assert!(spades.have_index(card_of(SPADE, KING)));
+        assert!(!spades.have_index(card_of(HEART, ACE)));
     }
 
     #[test]
     fn test_cards_top_bottom() {
         let mut cards = Cards::new();
-        cards.add(card_of(SPADE, ACE)); // card 0
-        cards.add(card_of(SPADE, TWO)); // card 12
-        cards.add(card_of(HEART, KING)); // card 14
+        cards.add_index(card_of(SPADE, ACE)); // card 0
+        cards.add_index(card_of(SPADE, TWO)); // card 12
+        cards.add_index(card_of(HEART, KING)); // card 14
 
         assert_eq!(cards.top(), card_of(SPADE, ACE));
         assert_eq!(cards.bottom(), card_of(HEART, KING));
@@ -370,9 +426,9 @@ mod tests {
     #[test]
     fn test_cards_iteration() {
         let mut cards = Cards::new();
-        cards.add(card_of(SPADE, ACE));
-        cards.add(card_of(SPADE, KING));
-        cards.add(card_of(HEART, ACE));
+        cards.add_index(card_of(SPADE, ACE));
+        cards.add_index(card_of(SPADE, KING));
+        cards.add_index(card_of(HEART, ACE));
 
         let collected: Vec<_> = cards.iter().collect();
         assert_eq!(collected.len(), 3);
@@ -385,11 +441,11 @@ mod tests {
     #[test]
     fn test_cards_points() {
         let mut cards = Cards::new();
-        cards.add(card_of(SPADE, ACE)); // 4 HCP
-        cards.add(card_of(SPADE, KING)); // 3 HCP
-        cards.add(card_of(HEART, QUEEN)); // 2 HCP
-        cards.add(card_of(HEART, JACK)); // 1 HCP
-        cards.add(card_of(HEART, TEN)); // 0 HCP
+        cards.add_index(card_of(SPADE, ACE)); // 4 HCP
+        cards.add_index(card_of(SPADE, KING)); // 3 HCP
+        cards.add_index(card_of(HEART, QUEEN)); // 2 HCP
+        cards.add_index(card_of(HEART, JACK)); // 1 HCP
+        cards.add_index(card_of(HEART, TEN)); // 0 HCP
 
         assert_eq!(cards.points(), 10);
     }
@@ -397,19 +453,33 @@ mod tests {
     #[test]
     fn test_cards_union_intersect() {
         let mut a = Cards::new();
-        a.add(card_of(SPADE, ACE));
-        a.add(card_of(SPADE, KING));
+        a.add_index(card_of(SPADE, ACE));
+        a.add_index(card_of(SPADE, KING));
 
         let mut b = Cards::new();
-        b.add(card_of(SPADE, KING));
-        b.add(card_of(HEART, ACE));
+        b.add_index(card_of(SPADE, KING));
+        b.add_index(card_of(HEART, ACE));
 
         let union = a.union(b);
         assert_eq!(union.size(), 3);
 
         let intersect = a.intersect(b);
         assert_eq!(intersect.size(), 1);
-        assert!(intersect.have(card_of(SPADE, KING)));
+        assert!(intersect.have_index(card_of(SPADE, KING)));
+    }
+
+    #[test]
+    fn test_parse_card() {
+        assert_eq!(parse_card("SA"), Some(card_of(SPADE, ACE)));
+        assert_eq!(parse_card("h9"), Some(card_of(HEART, NINE)));
+        assert_eq!(parse_card("CT"), Some(card_of(CLUB, TEN)));
+        assert_eq!(
+            parse_card(&name_of(card_of(DIAMOND, TWO))),
+            Some(card_of(DIAMOND, TWO))
+        );
+        assert_eq!(parse_card("NA"), None);
+        assert_eq!(parse_card("S"), None);
+        assert_eq!(parse_card("SAK"), None);
     }
 
     #[test]
@@ -0,0 +1,504 @@
+//! Compact binary codes for deals, positions and DD tables
+//!
+//! The packed forms are small enough to store in bulk or send between
+//! processes:
+//! - a full deal in 13 bytes: 2 bits per card (bit 0 the ace of spades, low
+//!   bits first) holding the seat it was dealt to
+//! - a DD table in 10 bytes: 20 nibbles (low nibble first) of declarer tricks,
+//!   `[declarer][strain]` in solver order
+//! - a position in play as its deal, trump, opening leader and the cards
+//!   played, in order; the trick history and seat to play follow from those
+//!
+//! A position's packed form is:
+//!
+//! ```text
+//! flags    u8       trump (bits 0-2), opening leader (bits 3-4), bit 5 set
+//!                   when all 52 cards are dealt
+//! dealt    7 bytes  bitset of the cards dealt, only when some are not
+//! owners            2 bits per dealt card, in card order, padded to a byte
+//! played   u8       number of cards played
+//! plays             6 bits per card played, in play order, padded to a byte
+//! ```
+//!
+//! [`Code`] adds a two-byte header, the kind (`D`, `P` or `T`) and
+//! [`CODE_VERSION`], and converts to and from hex and base64 (RFC 4648, with
+//! padding) text.
+
+use super::bridge_solver::PlayedCard;
+use super::cards::Cards;
+use super::hands::Hands;
+use super::session::PlaySession;
+use super::table::DdTable;
+use super::types::*;
+use std::fmt;
+
+/// Version of the code formats, bumped on any incompatible change
+pub const CODE_VERSION: u8 = 1;
+
+const DEAL_KIND: u8 = b'D';
+const POSITION_KIND: u8 = b'P';
+const TABLE_KIND: u8 = b'T';
+
+/// Bytes of a packed full deal
+pub const PACKED_DEAL_LEN: usize = 13;
+
+/// Bytes of a packed DD table
+pub const PACKED_TABLE_LEN: usize = 10;
+
+const ALL_DEALT: u8 = 1 << 5;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Why a code could not be decoded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodeError {
+    /// Not valid hex or base64 text
+    InvalidText,
+    /// The header names no known kind of code
+    UnknownKind(u8),
+    /// Written by another version of the formats
+    Version(u8),
+    /// The packed data is cut short, too long or describes no valid value
+    Invalid,
+}
+
+impl fmt::Display for CodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodeError::InvalidText => write!(f, "not valid hex or base64 text"),
+            CodeError::UnknownKind(kind) => write!(f, "unknown code kind {:#04x}", kind),
+            CodeError::Version(version) => {
+                write!(f, "code version {} (expected {})", version, CODE_VERSION)
+            }
+            CodeError::Invalid => write!(f, "invalid code data"),
+        }
+    }
+}
+
+impl std::error::Error for CodeError {}
+
+/// A full deal: valid hands of 13 cards each, the deals with a deal code
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FullDeal(Hands);
+
+impl FullDeal {
+    /// The deal of `hands`, None unless every seat holds 13 cards
+    pub fn new(hands: Hands) -> Option<Self> {
+        (hands.is_valid() && hands.num_tricks() == TOTAL_TRICKS).then_some(FullDeal(hands))
+    }
+
+    /// The hands of the deal
+    pub fn hands(&self) -> &Hands {
+        &self.0
+    }
+
+    fn pack(&self) -> [u8; PACKED_DEAL_LEN] {
+        let mut bytes = [0u8; PACKED_DEAL_LEN];
+        for seat in 0..NUM_SEATS {
+            for card in self.0[seat].iter() {
+                bytes[card / 4] |= (seat as u8) << (2 * (card % 4));
+            }
+        }
+        bytes
+    }
+}
+
+/// Pack a full deal, None unless every seat holds 13 cards
+pub fn pack_deal(hands: &Hands) -> Option<[u8; PACKED_DEAL_LEN]> {
+    FullDeal::new(*hands).map(|deal| deal.pack())
+}
+
+/// Unpack a full deal (inverse of [`pack_deal`]), None if the hands are not
+/// 13 cards each
+pub fn unpack_deal(bytes: &[u8; PACKED_DEAL_LEN]) -> Option<Hands> {
+    let mut hands = Hands::new();
+    for card in 0..TOTAL_CARDS {
+        let seat = (bytes[card / 4] >> (2 * (card % 4))) as usize & 3;
+        hands[seat].add_index(card);
+    }
+    FullDeal::new(hands).map(|deal| deal.0)
+}
+
+pub fn pack_table(table: &DdTable) -> [u8; PACKED_TABLE_LEN] {
+    let mut bytes = [0u8; PACKED_TABLE_LEN];
+    for (i, &tricks) in table.tricks().iter().flatten().enumerate() {
+        bytes[i / 2] |= (tricks & 0xf) << (4 * (i % 2));
+    }
+    bytes
+}
+
+/// Unpack a DD table (inverse of [`pack_table`]), None if any entry is over 13
+pub fn unpack_table(bytes: &[u8; PACKED_TABLE_LEN]) -> Option<DdTable> {
+    let mut tricks = [[0; NUM_STRAINS]; NUM_SEATS];
+    for (i, value) in tricks.iter_mut().flatten().enumerate() {
+        *value = (bytes[i / 2] >> (4 * (i % 2))) & 0xf;
+        if *value as usize > TOTAL_TRICKS {
+            return None;
+        }
+    }
+    Some(DdTable::from_tricks(tricks))
+}
+
+/// Pack a position in play
+pub fn pack_position(session: &PlaySession) -> Vec<u8> {
+    let played: Vec<PlayedCard> = session
+        .tricks()
+        .iter()
+        .chain(std::iter::once(session.current_trick()))
+        .flat_map(|trick| trick.plays.iter().copied())
+        .collect();
+    let mut dealt = *session.hands();
+    for play in &played {
+        dealt[play.seat].add_index(play.card);
+    }
+    let leader = played
+        .first()
+        .map_or_else(|| session.seat_to_play(), |play| play.seat);
+    let all_cards = dealt.all_cards();
+    let all_dealt = all_cards.size() == TOTAL_CARDS;
+
+    let mut bytes = vec![session.trump() as u8 | ((leader as u8) << 3)];
+    if all_dealt {
+        bytes[0] |= ALL_DEALT;
+    } else {
+        bytes.extend_from_slice(&all_cards.value().to_le_bytes()[..7]);
+    }
+    let mut owners = BitWriter::default();
+    for card in 0..TOTAL_CARDS {
+        if let Some(seat) = (0..NUM_SEATS).find(|&seat| dealt[seat].have_index(card)) {
+            owners.push(seat as u8, 2);
+        }
+    }
+    bytes.extend(owners.finish());
+    bytes.push(played.len() as u8);
+    let mut plays = BitWriter::default();
+    for play in &played {
+        plays.push(play.card as u8, 6);
+    }
+    bytes.extend(plays.finish());
+    bytes
+}
+
+/// Unpack a position (inverse of [`pack_position`]) by replaying its cards,
+/// None if the data is malformed or a play is illegal
+pub fn unpack_position(bytes: &[u8]) -> Option<PlaySession> {
+    let (&flags, mut rest) = bytes.split_first()?;
+    let trump = (flags & 7) as usize;
+    let leader = ((flags >> 3) & 3) as usize;
+    if trump > NOTRUMP || flags >> 6 != 0 {
+        return None;
+    }
+    let all_cards = if flags & ALL_DEALT != 0 {
+        Cards::from_bits((1 << TOTAL_CARDS) - 1)
+    } else {
+        let (mask, after) = rest.split_at_checked(7)?;
+        rest = after;
+        let mut value = [0u8; 8];
+        value[..7].copy_from_slice(mask);
+        let value = u64::from_le_bytes(value);
+        if value >> TOTAL_CARDS != 0 || value.count_ones() as usize == TOTAL_CARDS {
+            return None;
+        }
+        Cards::from_bits(value)
+    };
+
+    let (owner_bytes, after) = rest.split_at_checked((all_cards.size() * 2).div_ceil(8))?;
+    rest = after;
+    let mut owners = BitReader::new(owner_bytes);
+    let mut hands = Hands::new();
+    for card in 0..TOTAL_CARDS {
+        if all_cards.have_index(card) {
+            hands[owners.take(2) as usize].add_index(card);
+        }
+    }
+
+    let (&num_played, play_bytes) = rest.split_first()?;
+    if play_bytes.len() != (num_played as usize * 6).div_ceil(8) {
+        return None;
+    }
+    let mut session = PlaySession::with_indices(hands, trump, leader)?;
+    let mut plays = BitReader::new(play_bytes);
+    for _ in 0..num_played {
+        session.play_index(plays.take(6) as usize).ok()?;
+    }
+    Some(session)
+}
+
+/// A deal, position or DD table, with the header that tells them apart
+#[derive(Clone, Debug)]
+pub enum Code {
+    /// A full deal; pack other deals as positions
+    Deal(FullDeal),
+    Position(PlaySession),
+    Table(DdTable),
+}
+
+impl Code {
+    /// The code of a full deal, None unless every seat holds 13 cards
+    pub fn deal(hands: Hands) -> Option<Self> {
+        FullDeal::new(hands).map(Code::Deal)
+    }
+
+    /// Header followed by the packed value
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (kind, packed) = match self {
+            Code::Deal(deal) => (DEAL_KIND, deal.pack().to_vec()),
+            Code::Position(session) => (POSITION_KIND, pack_position(session)),
+            Code::Table(table) => (TABLE_KIND, pack_table(table).to_vec()),
+        };
+        let mut bytes = vec![kind, CODE_VERSION];
+        bytes.extend(packed);
+        bytes
+    }
+
+    /// Decode bytes written by [`Code::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CodeError> {
+        let [kind, version, packed @ ..] = bytes else {
+            return Err(CodeError::Invalid);
+        };
+        if !matches!(*kind, DEAL_KIND | POSITION_KIND | TABLE_KIND) {
+            return Err(CodeError::UnknownKind(*kind));
+        }
+        if *version != CODE_VERSION {
+            return Err(CodeError::Version(*version));
+        }
+        let code = match *kind {
+            DEAL_KIND => packed
+                .try_into()
+                .ok()
+                .and_then(unpack_deal)
+                .and_then(Code::deal),
+            POSITION_KIND => unpack_position(packed).map(Code::Position),
+            _ => packed
+                .try_into()
+                .ok()
+                .and_then(unpack_table)
+                .map(Code::Table),
+        };
+        code.ok_or(CodeError::Invalid)
+    }
+
+    /// Lowercase hex of [`Code::to_bytes`]
+    pub fn to_hex(&self) -> String {
+        self.to_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Decode hex text (either case) written by [`Code::to_hex`]
+    pub fn from_hex(text: &str) -> Result<Self, CodeError> {
+        let text = text.trim().as_bytes();
+        if !text.len().is_multiple_of(2) {
+            return Err(CodeError::InvalidText);
+        }
+        // Checked digit by digit: from_str_radix would accept a leading '+'
+        let bytes = text
+            .chunks(2)
+            .map(|pair| {
+                let digit = |ch: u8| (ch as char).to_digit(16);
+                Some(((digit(pair[0])? << 4) | digit(pair[1])?) as u8)
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or(CodeError::InvalidText)?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Base64 of [`Code::to_bytes`]
+    pub fn to_base64(&self) -> String {
+        let bytes = self.to_bytes();
+        let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+        for group in bytes.chunks(3) {
+            let value = group.iter().enumerate().fold(0u32, |value, (i, &byte)| {
+                value | ((byte as u32) << (16 - 8 * i))
+            });
+            for i in 0..4 {
+                if i <= group.len() {
+                    text.push(BASE64[((value >> (18 - 6 * i)) & 63) as usize] as char);
+                } else {
+                    text.push('=');
+                }
+            }
+        }
+        text
+    }
+
+    /// Decode base64 text written by [`Code::to_base64`], with or without
+    /// padding
+    pub fn from_base64(text: &str) -> Result<Self, CodeError> {
+        let text = text.trim().trim_end_matches('=').as_bytes();
+        if text.len() % 4 == 1 {
+            return Err(CodeError::InvalidText);
+        }
+        let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+        for group in text.chunks(4) {
+            let mut value = 0u32;
+            for (i, &ch) in group.iter().enumerate() {
+                let digit = BASE64
+                    .iter()
+                    .position(|&b| b == ch)
+                    .ok_or(CodeError::InvalidText)?;
+                value |= (digit as u32) << (18 - 6 * i);
+            }
+            for i in 0..group.len() - 1 {
+                bytes.push((value >> (16 - 8 * i)) as u8);
+            }
+        }
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// Packs values of a few bits, low bits first
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn push(&mut self, value: u8, width: usize) {
+        for bit in 0..width {
+            if self.bits.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            self.bytes[self.bits / 8] |= ((value >> bit) & 1) << (self.bits % 8);
+            self.bits += 1;
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Reads values written by [`BitWriter`]; the caller checks the length
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    bits: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        BitReader { bytes, bits: 0 }
+    }
+
+    fn take(&mut self, width: usize) -> u8 {
+        let mut value = 0;
+        for bit in 0..width {
+            value |= ((self.bytes[self.bits / 8] >> (self.bits % 8)) & 1) << bit;
+            self.bits += 1;
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cards::parse_card;
+
+    const DEAL: &str = "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72";
+
+    fn cards_played(session: &PlaySession) -> Vec<usize> {
+        session
+            .tricks()
+            .iter()
+            .chain(std::iter::once(session.current_trick()))
+            .flat_map(|trick| trick.plays.iter().map(|play| play.card))
+            .collect()
+    }
+
+    #[test]
+    fn test_deal_and_table() {
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        let packed = pack_deal(&hands).unwrap();
+        assert_eq!(unpack_deal(&packed), Some(hands));
+        let partial = Hands::from_pbn("N:A... K... Q... J...").unwrap();
+        assert_eq!(pack_deal(&partial), None);
+        assert!(Code::deal(partial).is_none());
+        // Only 52 North cards: not 13 each
+        assert_eq!(unpack_deal(&[0x55; PACKED_DEAL_LEN]), None);
+
+        let mut table = DdTable::default();
+        table.set_index(NORTH, SPADE, 13);
+        table.set_index(WEST, NOTRUMP, 4);
+        table.set_index(SOUTH, CLUB, 9);
+        assert_eq!(unpack_table(&pack_table(&table)), Some(table));
+        assert_eq!(unpack_table(&[0xff; PACKED_TABLE_LEN]), None);
+    }
+
+    #[test]
+    fn test_positions() {
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        let mut session = PlaySession::with_indices(hands, HEART, EAST).unwrap();
+        for card in ["C4", "CA", "CQ", "C5", "CK", "C2", "C9", "CT", "S4"] {
+            session.play_index(parse_card(card).unwrap()).unwrap();
+        }
+        let packed = pack_position(&session);
+        // Flags, owners, count and 9 six-bit plays
+        assert_eq!(packed.len(), 1 + 13 + 1 + 7);
+        let unpacked = unpack_position(&packed).unwrap();
+        assert_eq!(unpacked.hands(), session.hands());
+        assert_eq!(cards_played(&unpacked), cards_played(&session));
+        assert_eq!(unpacked.seat_to_play(), session.seat_to_play());
+        assert_eq!(unpacked.ns_tricks_won(), session.ns_tricks_won());
+
+        // An ending that has not started, with some cards never dealt
+        let ending = Hands::from_pbn("N:AK.2.. Q3.5.. J4.7.. 98.T..").unwrap();
+        let session = PlaySession::with_indices(ending, NOTRUMP, SOUTH).unwrap();
+        let unpacked = unpack_position(&pack_position(&session)).unwrap();
+        assert_eq!(unpacked.hands(), &ending);
+        assert_eq!(unpacked.seat_to_play(), SOUTH);
+
+        // A card the seat to play does not hold
+        let mut bad = pack_position(&session);
+        *bad.last_mut().unwrap() = 1;
+        bad.push(parse_card("SA").unwrap() as u8);
+        assert!(unpack_position(&bad).is_none());
+    }
+
+    #[test]
+    fn test_text_forms() {
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        let mut session = PlaySession::with_indices(hands, SPADE, WEST).unwrap();
+        session.play_index(parse_card("H8").unwrap()).unwrap();
+        let mut table = DdTable::default();
+        table.set_index(EAST, DIAMOND, 11);
+
+        for code in [
+            Code::deal(hands).unwrap(),
+            Code::Position(session),
+            Code::Table(table),
+        ] {
+            let bytes = code.to_bytes();
+            let hex = code.to_hex().to_uppercase();
+            assert_eq!(Code::from_hex(&hex).unwrap().to_bytes(), bytes);
+            assert_eq!(
+                Code::from_base64(&code.to_base64()).unwrap().to_bytes(),
+                bytes
+            );
+            let unpadded = code.to_base64().trim_end_matches('=').to_string();
+            assert_eq!(Code::from_base64(&unpadded).unwrap().to_bytes(), bytes);
+        }
+
+        let hex = Code::Table(table).to_hex();
+        assert!(hex.starts_with("5401"));
+        assert_eq!(Code::from_hex("5402").unwrap_err(), CodeError::Version(2));
+        assert_eq!(
+            Code::from_hex("5801").unwrap_err(),
+            CodeError::UnknownKind(b'X')
+        );
+        assert_eq!(Code::from_hex("54").unwrap_err(), CodeError::Invalid);
+        assert_eq!(
+            Code::from_hex("5401zz").unwrap_err(),
+            CodeError::InvalidText
+        );
+        assert_eq!(Code::from_hex("+f01").unwrap_err(), CodeError::InvalidText);
+        assert_eq!(Code::from_hex("54+1").unwrap_err(), CodeError::InvalidText);
+        assert_eq!(Code::from_base64("V*").unwrap_err(), CodeError::InvalidText);
+        assert_eq!(
+            Code::from_hex(&hex[..hex.len() - 2]).unwrap_err(),
+            CodeError::Invalid
+        );
+    }
+}
@@ -0,0 +1,811 @@
+//! Single-suit combinations: how many tricks declarer can take in one suit
+//!
+//! Declarer (South) and dummy (North) hold some cards of a suit and the
+//! defenders the rest, split between West and East in any of the ways 13
+//! cards to a hand allow. Each layout is solved double dummy by a small
+//! search of the suit alone: declarer may lead from either hand at every
+//! trick (entries elsewhere are taken for granted) and the defenders never
+//! lead the suit, so the suit ends when North and South have no cards left.
+//!
+//! [`SuitCombination::best_line`] plays single dummy instead: declarer sees
+//! only the cards played, while the defenders see everything and choose
+//! their cards in each layout to beat declarer's line. The line is found by
+//! vector minimax over the layouts consistent with the cards seen, and its
+//! probability is exact for that line.
+
+use super::types::*;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The cards of one suit in one hand, a bit per rank
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Holding(u16);
+
+const ALL_RANKS: u16 = (1 << NUM_RANKS) - 1;
+
+impl Holding {
+    pub const fn from_bits(bits: u16) -> Self {
+        Holding(bits & ALL_RANKS)
+    }
+
+    /// Bit `rank` is set for each rank held
+    pub fn bits(self) -> u16 {
+        self.0
+    }
+
+    /// Parse ranks such as "AQ42" ("-" or "" for a void)
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if s == "-" {
+            return Some(Holding(0));
+        }
+        let mut bits = 0u16;
+        for c in s.chars() {
+            let bit = 1 << char_to_rank(c)?;
+            if bits & bit != 0 {
+                return None;
+            }
+            bits |= bit;
+        }
+        Some(Holding(bits))
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, rank: RankIndex) -> bool {
+        self.0 & (1 << rank) != 0
+    }
+
+    /// Ranks held, highest first
+    pub fn ranks(self) -> impl Iterator<Item = RankIndex> {
+        (0..NUM_RANKS)
+            .rev()
+            .filter(move |&rank| self.contains(rank))
+    }
+}
+
+impl fmt::Display for Holding {
+    /// Formats as e.g. "AQ42", or "-" for a void
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "-");
+        }
+        for rank in self.ranks() {
+            write!(f, "{}", rank_name(rank))?;
+        }
+        Ok(())
+    }
+}
+
+/// Declarer's and dummy's holdings in a suit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SuitCombination {
+    declarer: Holding,
+    dummy: Holding,
+}
+
+/// One way the missing cards may lie
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Layout {
+    pub west: Holding,
+    pub east: Holding,
+    /// A priori probability, with 13 cards to each hand
+    pub probability: f64,
+    /// Tricks declarer takes double dummy
+    pub tricks: u8,
+}
+
+/// A single-dummy line for a trick target
+#[derive(Clone, Debug, PartialEq)]
+pub struct SuitLine {
+    pub target: usize,
+    /// A priori probability that the line makes the target
+    pub probability: f64,
+    /// Declarer's first play (None if there is nothing to decide: the target
+    /// is reached or out of reach)
+    pub first: Option<LineStep>,
+}
+
+/// A play by declarer or dummy, and what follows it
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineStep {
+    /// NORTH or SOUTH
+    pub seat: SeatIndex,
+    /// True for the first card of a trick
+    pub leads: bool,
+    /// The card played, or None to discard from another suit
+    pub rank: Option<RankIndex>,
+    /// The next play, by the card the next defender plays (None when void);
+    /// the line stops once the target is reached or out of reach
+    pub replies: Vec<(Option<RankIndex>, Option<LineStep>)>,
+}
+
+impl SuitCombination {
+    /// None if the two hands share a card
+    pub fn new(declarer: Holding, dummy: Holding) -> Option<Self> {
+        if declarer.bits() & dummy.bits() != 0 {
+            return None;
+        }
+        Some(SuitCombination { declarer, dummy })
+    }
+
+    /// Parse both holdings, as in [`Holding::parse`]
+    pub fn parse(declarer: &str, dummy: &str) -> Option<Self> {
+        Self::new(Holding::parse(declarer)?, Holding::parse(dummy)?)
+    }
+
+    pub fn declarer(&self) -> Holding {
+        self.declarer
+    }
+
+    pub fn dummy(&self) -> Holding {
+        self.dummy
+    }
+
+    /// The defenders' cards
+    pub fn missing(&self) -> Holding {
+        Holding(ALL_RANKS & !(self.declarer.bits() | self.dummy.bits()))
+    }
+
+    /// Most tricks declarer can take: a trick per card of the longer hand
+    pub fn max_tricks(&self) -> usize {
+        self.declarer.len().max(self.dummy.len())
+    }
+
+    /// Every layout of the missing cards, with its double-dummy tricks
+    pub fn layouts(&self) -> Vec<Layout> {
+        let missing = self.missing().bits();
+        let mut solver = DoubleDummy::default();
+        layout_wests(missing)
+            .map(|west| {
+                let hands = self.hands(west, missing & !west);
+                Layout {
+                    west: Holding(west),
+                    east: Holding(missing & !west),
+                    probability: layout_probability(missing, west),
+                    tricks: solver.tricks(hands),
+                }
+            })
+            .collect()
+    }
+
+    /// Probability of declarer taking each number of tricks double dummy
+    /// (indexed by tricks, up to `max_tricks`)
+    pub fn trick_probabilities(&self) -> Vec<f64> {
+        let mut probabilities = vec![0.0; self.max_tricks() + 1];
+        for layout in self.layouts() {
+            probabilities[layout.tricks as usize] += layout.probability;
+        }
+        probabilities
+    }
+
+    /// The single-dummy line most likely to take `target` tricks (see the
+    /// module documentation for the assumptions)
+    pub fn best_line(&self, target: usize) -> SuitLine {
+        let missing = self.missing().bits();
+        let wests: Vec<u16> = layout_wests(missing).collect();
+        let probabilities = wests
+            .iter()
+            .map(|&west| layout_probability(missing, west))
+            .collect();
+        let mut search = LineSearch {
+            wests,
+            probabilities,
+            memo: HashMap::new(),
+        };
+        let state = State {
+            north: self.dummy.bits(),
+            south: self.declarer.bits(),
+            missing,
+            need: target,
+        };
+        let worlds: Vec<u16> = (0..search.wests.len() as u16).collect();
+        let made = search.lead(&state, &worlds);
+        SuitLine {
+            target,
+            probability: search.weight(&worlds, &made),
+            first: search.plan_lead(&state, &worlds),
+        }
+    }
+
+    fn hands(&self, west: u16, east: u16) -> [u16; NUM_SEATS] {
+        let mut hands = [0; NUM_SEATS];
+        hands[WEST] = west;
+        hands[NORTH] = self.dummy.bits();
+        hands[EAST] = east;
+        hands[SOUTH] = self.declarer.bits();
+        hands
+    }
+}
+
+/// West's share of each layout of `missing`, in increasing order
+fn layout_wests(missing: u16) -> impl Iterator<Item = u16> {
+    let mut next = Some(0u16);
+    std::iter::from_fn(move || {
+        let west = next?;
+        next = (west != missing).then(|| west.wrapping_sub(missing) & missing);
+        Some(west)
+    })
+}
+
+/// Probability that West holds exactly `west` of the `missing` cards, with
+/// the defenders' other cards dealt at random
+fn layout_probability(missing: u16, west: u16) -> f64 {
+    let others = 2 * NUM_RANKS - missing.count_ones() as usize;
+    let west_others = NUM_RANKS - west.count_ones() as usize;
+    binomial(others, west_others) / binomial(2 * NUM_RANKS, NUM_RANKS)
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    (0..k).fold(1.0, |product, i| product * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// Ranks of `hand` worth trying: the top of each run of cards no other
+/// card of `all` separates
+fn choices(hand: u16, all: u16) -> impl Iterator<Item = RankIndex> {
+    Holding(hand).ranks().filter(move |&rank| {
+        let above = all & !((2u16 << rank) - 1);
+        above == 0 || hand & (above & above.wrapping_neg()) == 0
+    })
+}
+
+/// The seat (by order in the trick) playing the highest card
+fn trick_winner(trick: &[Option<RankIndex>; NUM_SEATS]) -> usize {
+    (0..NUM_SEATS).max_by_key(|&i| trick[i]).unwrap()
+}
+
+/// Double-dummy tricks of the suit alone, memoized by the four holdings
+#[derive(Default)]
+struct DoubleDummy {
+    memo: HashMap<[u16; NUM_SEATS], u8>,
+}
+
+impl DoubleDummy {
+    fn tricks(&mut self, hands: [u16; NUM_SEATS]) -> u8 {
+        if hands[NORTH] | hands[SOUTH] == 0 {
+            return 0;
+        }
+        if let Some(&tricks) = self.memo.get(&hands) {
+            return tricks;
+        }
+        let all = hands.iter().fold(0, |all, hand| all | hand);
+        let mut best = 0;
+        for leader in [SOUTH, NORTH] {
+            for lead in choices(hands[leader], all) {
+                let mut trick = [None; NUM_SEATS];
+                trick[0] = Some(lead);
+                let mut rest = hands;
+                rest[leader] &= !(1 << lead);
+                best = best.max(self.follow(rest, all, leader, &mut trick, 1));
+            }
+        }
+        self.memo.insert(hands, best);
+        best
+    }
+
+    fn follow(
+        &mut self,
+        hands: [u16; NUM_SEATS],
+        all: u16,
+        leader: SeatIndex,
+        trick: &mut [Option<RankIndex>; NUM_SEATS],
+        played: usize,
+    ) -> u8 {
+        if played == NUM_SEATS {
+            let won = is_ns((leader + trick_winner(trick)) % NUM_SEATS) as u8;
+            return won + self.tricks(hands);
+        }
+        let seat = (leader + played) % NUM_SEATS;
+        let maximize = is_ns(seat);
+        let options: Vec<Option<RankIndex>> = if hands[seat] == 0 {
+            vec![None]
+        } else {
+            choices(hands[seat], all).map(Some).collect()
+        };
+        let mut best = None;
+        for option in options {
+            let mut rest = hands;
+            if let Some(rank) = option {
+                rest[seat] &= !(1 << rank);
+            }
+            trick[played] = option;
+            let tricks = self.follow(rest, all, leader, trick, played + 1);
+            best = Some(match best {
+                None => tricks,
+                Some(best) if maximize => tricks.max(best),
+                Some(best) => tricks.min(best),
+            });
+        }
+        best.unwrap()
+    }
+}
+
+/// What declarer knows: the cards left and the tricks still needed
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct State {
+    north: u16,
+    south: u16,
+    /// The defenders' cards still out
+    missing: u16,
+    need: usize,
+}
+
+impl State {
+    fn hand(&self, seat: SeatIndex) -> u16 {
+        if seat == NORTH {
+            self.north
+        } else {
+            self.south
+        }
+    }
+
+    fn play(&self, seat: SeatIndex, rank: Option<RankIndex>) -> State {
+        let bit = rank.map_or(0, |rank| 1 << rank);
+        let mut state = *self;
+        match seat {
+            NORTH => state.north &= !bit,
+            SOUTH => state.south &= !bit,
+            _ => state.missing &= !bit,
+        }
+        state
+    }
+
+    /// True if the target is reached or out of reach
+    fn decided(&self) -> Option<bool> {
+        let rounds = self.north.count_ones().max(self.south.count_ones()) as usize;
+        if self.need == 0 {
+            Some(true)
+        } else if self.need > rounds {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+/// Vector minimax over layouts ("worlds", by index): each function returns
+/// whether the target is made in each world of `worlds`
+struct LineSearch {
+    /// West's original holding in each world
+    wests: Vec<u16>,
+    probabilities: Vec<f64>,
+    memo: HashMap<(State, Vec<u16>), Vec<bool>>,
+}
+
+impl LineSearch {
+    fn weight(&self, worlds: &[u16], made: &[bool]) -> f64 {
+        worlds
+            .iter()
+            .zip(made)
+            .filter(|(_, &made)| made)
+            .map(|(&world, _)| self.probabilities[world as usize])
+            .sum()
+    }
+
+    /// A defender's cards in `world`
+    fn defender(&self, state: &State, world: u16, seat: SeatIndex) -> u16 {
+        let west = self.wests[world as usize] & state.missing;
+        if seat == WEST {
+            west
+        } else {
+            state.missing & !west
+        }
+    }
+
+    /// Declarer's leads worth trying
+    fn leads(state: &State) -> Vec<(SeatIndex, RankIndex)> {
+        let all = state.north | state.south | state.missing;
+        [SOUTH, NORTH]
+            .into_iter()
+            .flat_map(|seat| choices(state.hand(seat), all).map(move |rank| (seat, rank)))
+            .collect()
+    }
+
+    /// The best of declarer's `options`, by probability (the first of equals)
+    fn best<T: Copy>(
+        &mut self,
+        worlds: &[u16],
+        options: &[T],
+        mut outcome: impl FnMut(&mut Self, T) -> Vec<bool>,
+    ) -> (T, Vec<bool>) {
+        let mut best: Option<(T, Vec<bool>, f64)> = None;
+        for &option in options {
+            let made = outcome(self, option);
+            let weight = self.weight(worlds, &made);
+            if best.as_ref().is_none_or(|(_, _, best)| weight > *best) {
+                best = Some((option, made, weight));
+            }
+        }
+        let (option, made, _) = best.unwrap();
+        (option, made)
+    }
+
+    /// The worlds of `worlds` by the card `seat` may play in each (None when
+    /// void), as positions in `worlds`
+    fn by_defender_card(
+        &self,
+        state: &State,
+        worlds: &[u16],
+        seat: SeatIndex,
+    ) -> Vec<(Option<RankIndex>, Vec<usize>)> {
+        let mut groups: Vec<(Option<RankIndex>, Vec<usize>)> = Vec::new();
+        for (position, &world) in worlds.iter().enumerate() {
+            let hand = self.defender(state, world, seat);
+            let options: Vec<Option<RankIndex>> = if hand == 0 {
+                vec![None]
+            } else {
+                Holding(hand).ranks().map(Some).collect()
+            };
+            for option in options {
+                match groups.iter_mut().find(|(card, _)| *card == option) {
+                    Some((_, positions)) => positions.push(position),
+                    None => groups.push((option, vec![position])),
+                }
+            }
+        }
+        groups.sort_by_key(|group| std::cmp::Reverse(group.0));
+        groups
+    }
+
+    /// A defender plays to `trick` in each world, choosing the card worst for
+    /// declarer there
+    fn defend(
+        &mut self,
+        state: &State,
+        worlds: &[u16],
+        trick: &Trick,
+        next: fn(&mut Self, &State, &[u16], &Trick) -> Vec<bool>,
+    ) -> Vec<bool> {
+        let seat = trick.next_seat();
+        let mut made = vec![true; worlds.len()];
+        for (card, positions) in self.by_defender_card(state, worlds, seat) {
+            let sub: Vec<u16> = positions.iter().map(|&i| worlds[i]).collect();
+            let child = next(self, &state.play(seat, card), &sub, &trick.with(card));
+            for (&position, child_made) in positions.iter().zip(child) {
+                made[position] &= child_made;
+            }
+        }
+        made
+    }
+
+    /// Declarer to lead
+    fn lead(&mut self, state: &State, worlds: &[u16]) -> Vec<bool> {
+        if let Some(made) = state.decided() {
+            return vec![made; worlds.len()];
+        }
+        let key = (*state, worlds.to_vec());
+        if let Some(made) = self.memo.get(&key) {
+            return made.clone();
+        }
+        let leads = Self::leads(state);
+        let (_, made) = self.best(worlds, &leads, |search, lead| {
+            search.lead_outcome(state, worlds, lead)
+        });
+        self.memo.insert(key, made.clone());
+        made
+    }
+
+    fn lead_outcome(
+        &mut self,
+        state: &State,
+        worlds: &[u16],
+        (seat, rank): (SeatIndex, RankIndex),
+    ) -> Vec<bool> {
+        let trick = Trick::lead(seat, rank);
+        self.defend(&state.play(seat, Some(rank)), worlds, &trick, Self::third)
+    }
+
+    /// Declarer's options in third seat
+    fn thirds(state: &State, seat: SeatIndex) -> Vec<Option<RankIndex>> {
+        let hand = state.hand(seat);
+        if hand == 0 {
+            return vec![None];
+        }
+        choices(hand, state.north | state.south | state.missing)
+            .map(Some)
+            .collect()
+    }
+
+    /// Declarer to play third
+    fn third(&mut self, state: &State, worlds: &[u16], trick: &Trick) -> Vec<bool> {
+        let seat = trick.next_seat();
+        let options = Self::thirds(state, seat);
+        let (_, made) = self.best(worlds, &options, |search, card| {
+            search.third_outcome(state, worlds, trick, card)
+        });
+        made
+    }
+
+    fn third_outcome(
+        &mut self,
+        state: &State,
+        worlds: &[u16],
+        trick: &Trick,
+        card: Option<RankIndex>,
+    ) -> Vec<bool> {
+        let seat = trick.next_seat();
+        self.defend(
+            &state.play(seat, card),
+            worlds,
+            &trick.with(card),
+            Self::end_trick,
+        )
+    }
+
+    /// The trick is complete: on to the next lead
+    fn end_trick(&mut self, state: &State, worlds: &[u16], trick: &Trick) -> Vec<bool> {
+        self.lead(&trick.score(state), worlds)
+    }
+
+    /// The line behind the values: declarer's best lead and its replies
+    fn plan_lead(&mut self, state: &State, worlds: &[u16]) -> Option<LineStep> {
+        if state.decided().is_some() {
+            return None;
+        }
+        let leads = Self::leads(state);
+        let ((seat, rank), _) = self.best(worlds, &leads, |search, lead| {
+            search.lead_outcome(state, worlds, lead)
+        });
+        let after = state.play(seat, Some(rank));
+        let trick = Trick::lead(seat, rank);
+        let defender = trick.next_seat();
+        let replies = self
+            .by_defender_card(&after, worlds, defender)
+            .into_iter()
+            .map(|(card, positions)| {
+                let sub: Vec<u16> = positions.iter().map(|&i| worlds[i]).collect();
+                let step = self.plan_third(&after.play(defender, card), &sub, &trick.with(card));
+                (card, Some(step))
+            })
+            .collect();
+        Some(LineStep {
+            seat,
+            leads: true,
+            rank: Some(rank),
+            replies,
+        })
+    }
+
+    fn plan_third(&mut self, state: &State, worlds: &[u16], trick: &Trick) -> LineStep {
+        let seat = trick.next_seat();
+        let options = Self::thirds(state, seat);
+        let (card, _) = self.best(worlds, &options, |search, card| {
+            search.third_outcome(state, worlds, trick, card)
+        });
+        let after = state.play(seat, card);
+        let trick = trick.with(card);
+        let defender = trick.next_seat();
+        let replies = self
+            .by_defender_card(&after, worlds, defender)
+            .into_iter()
+            .map(|(reply, positions)| {
+                let sub: Vec<u16> = positions.iter().map(|&i| worlds[i]).collect();
+                let complete = trick.with(reply);
+                let next = complete.score(&after.play(defender, reply));
+                (reply, self.plan_lead(&next, &sub))
+            })
+            .collect();
+        LineStep {
+            seat,
+            leads: false,
+            rank: card,
+            replies,
+        }
+    }
+}
+
+/// The cards of a trick so far, in order of play
+#[derive(Clone, Copy)]
+struct Trick {
+    leader: SeatIndex,
+    cards: [Option<RankIndex>; NUM_SEATS],
+    played: usize,
+}
+
+impl Trick {
+    fn lead(leader: SeatIndex, rank: RankIndex) -> Self {
+        let mut cards = [None; NUM_SEATS];
+        cards[0] = Some(rank);
+        Trick {
+            leader,
+            cards,
+            played: 1,
+        }
+    }
+
+    fn next_seat(&self) -> SeatIndex {
+        (self.leader + self.played) % NUM_SEATS
+    }
+
+    fn with(&self, card: Option<RankIndex>) -> Self {
+        let mut trick = *self;
+        trick.cards[trick.played] = card;
+        trick.played += 1;
+        trick
+    }
+
+    /// `state` after the complete trick, with a trick less needed if
+    /// declarer won it
+    fn score(&self, state: &State) -> State {
+        let winner = (self.leader + trick_winner(&self.cards)) % NUM_SEATS;
+        let mut state = *state;
+        if is_ns(winner) {
+            state.need -= 1;
+        }
+        state
+    }
+}
+
+impl fmt::Display for SuitLine {
+    /// The probability, then the line as an indented tree, e.g.
+    /// "South leads 2" / "  West plays 5:" / "    North plays Q"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} tricks: {:.2}%",
+            self.target,
+            100.0 * self.probability
+        )?;
+        if let Some(step) = &self.first {
+            step.write(f, 0)?;
+        }
+        Ok(())
+    }
+}
+
+impl LineStep {
+    fn write(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        let verb = if self.leads { "leads" } else { "plays" };
+        match self.rank {
+            Some(rank) => writeln!(
+                f,
+                "{}{} {} {}",
+                indent,
+                seat_name(self.seat),
+                verb,
+                rank_name(rank)
+            )?,
+            None => writeln!(f, "{}{} discards", indent, seat_name(self.seat))?,
+        }
+        let defender = left_hand_opp(self.seat);
+        for (card, next) in &self.replies {
+            match card {
+                Some(rank) => writeln!(
+                    f,
+                    "{}  {} plays {}:",
+                    indent,
+                    seat_name(defender),
+                    rank_name(*rank)
+                )?,
+                None => writeln!(f, "{}  {} shows out:", indent, seat_name(defender))?,
+            }
+            if let Some(next) = next {
+                next.write(f, depth + 2)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(declarer: &str, dummy: &str) -> SuitCombination {
+        SuitCombination::parse(declarer, dummy).unwrap()
+    }
+
+    #[test]
+    fn test_holding_parse() {
+        let holding = Holding::parse("AQ42").unwrap();
+        assert_eq!(holding.len(), 4);
+        assert!(holding.contains(QUEEN));
+        assert_eq!(holding.to_string(), "AQ42");
+        assert_eq!(Holding::parse("-").unwrap(), Holding::default());
+        assert_eq!(Holding::parse("").unwrap().to_string(), "-");
+        assert_eq!(Holding::parse("AA"), None);
+        assert_eq!(Holding::parse("AX"), None);
+        assert_eq!(SuitCombination::parse("AK", "KQ"), None);
+    }
+
+    #[test]
+    fn test_layout_probabilities() {
+        let combination = parse("AKQ2", "543");
+        let layouts = combination.layouts();
+        assert_eq!(layouts.len(), 64);
+        let total: f64 = layouts.iter().map(|layout| layout.probability).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        // Four tricks when the six missing cards split 3-3
+        let probabilities = combination.trick_probabilities();
+        assert_eq!(probabilities.len(), 5);
+        assert!((probabilities[4] - 0.35528).abs() < 1e-5);
+        assert!((probabilities[3] - 0.64472).abs() < 1e-5);
+
+        // A 2-2 split of four cards
+        let layouts = parse("AK832", "7654").layouts();
+        let two_two: f64 = layouts
+            .iter()
+            .filter(|layout| layout.west.len() == 2)
+            .map(|layout| layout.probability)
+            .sum();
+        assert!((two_two - 0.40696).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_finesse() {
+        // Dummy's AQ over West's king, or East's singleton king drops
+        let combination = parse("32", "AQ");
+        for layout in combination.layouts() {
+            let onside = layout.west.contains(KING) || layout.east.len() == 1;
+            assert_eq!(layout.tricks, 1 + onside as u8, "West {}", layout.west);
+        }
+        let probabilities = combination.trick_probabilities();
+        assert!((probabilities[2] - 0.50059).abs() < 1e-5);
+
+        let line = combination.best_line(2);
+        assert!((line.probability - 0.5).abs() < 1e-9);
+        let first = line.first.unwrap();
+        assert_eq!((first.seat, first.leads), (SOUTH, true));
+        // Finesse the queen when West plays low, take the ace over the king
+        let third = |card| {
+            let (_, next) = first.replies.iter().find(|(c, _)| *c == card).unwrap();
+            let next = next.as_ref().unwrap();
+            (next.seat, next.rank)
+        };
+        assert_eq!(third(Some(FOUR)), (NORTH, Some(QUEEN)));
+        assert_eq!(third(Some(KING)), (NORTH, Some(ACE)));
+
+        assert!((combination.best_line(1).probability - 1.0).abs() < 1e-9);
+        assert_eq!(combination.best_line(3).probability, 0.0);
+        assert_eq!(combination.best_line(0).first, None);
+    }
+
+    #[test]
+    fn test_single_dummy_below_double_dummy() {
+        for (declarer, dummy) in [
+            ("AK32", "654"),
+            ("AJ32", "K54"),
+            ("KJ3", "Q42"),
+            ("A98", "Q762"),
+        ] {
+            let combination = parse(declarer, dummy);
+            let probabilities = combination.trick_probabilities();
+            for target in 1..=combination.max_tricks() {
+                let at_least: f64 = probabilities[target..].iter().sum();
+                let line = combination.best_line(target);
+                assert!(
+                    line.probability <= at_least + 1e-9,
+                    "{} {} target {}",
+                    declarer,
+                    dummy,
+                    target
+                );
+            }
+        }
+
+        // Double dummy always finesses the right way; single dummy must guess
+        let combination = parse("AJ3", "KT2");
+        let dd = combination.trick_probabilities()[3];
+        let line = combination.best_line(3);
+        assert!(line.probability < dd);
+        assert!(line.probability > 0.0);
+    }
+
+    #[test]
+    fn test_line_display() {
+        let text = parse("32", "AQ").best_line(2).to_string();
+        assert!(text.starts_with("2 tricks: 50.00%\nSouth leads 3\n"));
+        assert!(text.contains("West plays K:\n    North plays A"));
+    }
+}
@@ -1,10 +1,16 @@
 //! Conversion between bridge-types and internal solver types.
 //!
 //! The solver uses optimized internal representations (bitboards) for performance.
-//! This module provides conversion at the API boundary.
+//! This module provides conversion at the API boundary, both ways, and solves
+//! bridge-types deals with results in bridge-types terms. Contracts are given
+//! as a declarer `Direction` and a trump `Suit`, None for notrump.
 
+use super::analysis::CardAnalysis;
+use super::bridge_solver::{PlayedCard, Solver};
 use super::cards::*;
 use super::hands::Hands;
+use super::table::{solve_dd_table, DdTable};
+use super::typed;
 use super::types::*;
 
 /// Convert a bridge_types::Deal to solver Hands
@@ -27,12 +33,19 @@ impl Hands {
             let hand = deal.hand(dir);
             for card in hand.cards() {
                 let solver_card = convert_card(card);
-                hands[seat].add(solver_card);
+                hands[seat].add_index(solver_card);
             }
         }
 
         hands
     }
+
+    /// Create a bridge_types::Deal from Hands (inverse of `from_deal`)
+    ///
+    /// Returns None if bridge-types does not accept the hands as a deal.
+    pub fn to_deal(&self) -> Option<bridge_types::Deal> {
+        bridge_types::Deal::from_pbn(&self.to_pbn())
+    }
 }
 
 /// Convert a bridge_types::Card to solver card index
@@ -42,10 +55,23 @@ fn convert_card(card: &bridge_types::Card) -> usize {
     card_of(suit, rank)
 }
 
+/// Convert a bridge_types::Card to a solver card index (0-51)
+pub fn card_to_index(card: &bridge_types::Card) -> usize {
+    convert_card(card)
+}
+
+/// Convert a solver card index (0-51) to a bridge_types::Card
+pub fn index_to_card(card: usize) -> bridge_types::Card {
+    bridge_types::Card {
+        suit: bridge_types::Suit::from(typed::Suit::ALL[suit_of(card)]),
+        rank: bridge_types::Rank::from(typed::Rank::ALL[rank_of(card)]),
+    }
+}
+
 /// Convert bridge_types::Suit to solver Suit
 /// bridge_types: Clubs=0, Diamonds=1, Hearts=2, Spades=3
 /// solver: SPADE=0, HEART=1, DIAMOND=2, CLUB=3
-fn convert_suit(suit: bridge_types::Suit) -> Suit {
+fn convert_suit(suit: bridge_types::Suit) -> SuitIndex {
     match suit {
         bridge_types::Suit::Spades => SPADE,
         bridge_types::Suit::Hearts => HEART,
@@ -57,12 +83,17 @@ fn convert_suit(suit: bridge_types::Suit) -> Suit {
 /// Convert bridge_types::Rank to solver Rank
 /// bridge_types: Two=2, Three=3, ..., Ace=14
 /// solver: TWO=0, THREE=1, ..., ACE=12
-fn convert_rank(rank: bridge_types::Rank) -> Rank {
+fn convert_rank(rank: bridge_types::Rank) -> RankIndex {
     (rank as usize) - 2
 }
 
+/// Convert an optional bridge_types::Suit (None for notrump) to solver strain
+fn convert_trump(trump: Option<bridge_types::Suit>) -> usize {
+    trump.map_or(NOTRUMP, convert_suit)
+}
+
 /// Convert solver Seat to bridge_types::Direction
-pub fn seat_to_direction(seat: Seat) -> bridge_types::Direction {
+pub fn seat_to_direction(seat: SeatIndex) -> bridge_types::Direction {
     match seat {
         NORTH => bridge_types::Direction::North,
         EAST => bridge_types::Direction::East,
@@ -73,7 +104,7 @@ pub fn seat_to_direction(seat: Seat) -> bridge_types::Direction {
 }
 
 /// Convert bridge_types::Direction to solver Seat
-pub fn direction_to_seat(dir: bridge_types::Direction) -> Seat {
+pub fn direction_to_seat(dir: bridge_types::Direction) -> SeatIndex {
     match dir {
         bridge_types::Direction::North => NORTH,
         bridge_types::Direction::East => EAST,
@@ -82,6 +113,133 @@ pub fn direction_to_seat(dir: bridge_types::Direction) -> Seat {
     }
 }
 
+impl From<bridge_types::Direction> for typed::Seat {
+    fn from(dir: bridge_types::Direction) -> Self {
+        typed::Seat::ALL[direction_to_seat(dir)]
+    }
+}
+
+impl From<typed::Seat> for bridge_types::Direction {
+    fn from(seat: typed::Seat) -> Self {
+        seat_to_direction(seat.index())
+    }
+}
+
+impl From<bridge_types::Suit> for typed::Suit {
+    fn from(suit: bridge_types::Suit) -> Self {
+        typed::Suit::ALL[convert_suit(suit)]
+    }
+}
+
+impl From<typed::Suit> for bridge_types::Suit {
+    fn from(suit: typed::Suit) -> Self {
+        match suit {
+            typed::Suit::Spades => bridge_types::Suit::Spades,
+            typed::Suit::Hearts => bridge_types::Suit::Hearts,
+            typed::Suit::Diamonds => bridge_types::Suit::Diamonds,
+            typed::Suit::Clubs => bridge_types::Suit::Clubs,
+        }
+    }
+}
+
+impl From<&bridge_types::Card> for typed::Card {
+    fn from(card: &bridge_types::Card) -> Self {
+        typed::Card::from_index(convert_card(card)).unwrap()
+    }
+}
+
+impl From<typed::Card> for bridge_types::Card {
+    fn from(card: typed::Card) -> Self {
+        index_to_card(card.index())
+    }
+}
+
+impl From<bridge_types::Rank> for typed::Rank {
+    fn from(rank: bridge_types::Rank) -> Self {
+        typed::Rank::ALL[convert_rank(rank)]
+    }
+}
+
+impl From<typed::Rank> for bridge_types::Rank {
+    fn from(rank: typed::Rank) -> Self {
+        match rank {
+            typed::Rank::Two => bridge_types::Rank::Two,
+            typed::Rank::Three => bridge_types::Rank::Three,
+            typed::Rank::Four => bridge_types::Rank::Four,
+            typed::Rank::Five => bridge_types::Rank::Five,
+            typed::Rank::Six => bridge_types::Rank::Six,
+            typed::Rank::Seven => bridge_types::Rank::Seven,
+            typed::Rank::Eight => bridge_types::Rank::Eight,
+            typed::Rank::Nine => bridge_types::Rank::Nine,
+            typed::Rank::Ten => bridge_types::Rank::Ten,
+            typed::Rank::Jack => bridge_types::Rank::Jack,
+            typed::Rank::Queen => bridge_types::Rank::Queen,
+            typed::Rank::King => bridge_types::Rank::King,
+            typed::Rank::Ace => bridge_types::Rank::Ace,
+        }
+    }
+}
+
+impl PlayedCard {
+    /// Create a played card from bridge-types values
+    pub fn from_bridge(card: &bridge_types::Card, direction: bridge_types::Direction) -> Self {
+        PlayedCard::new(typed::Card::from(card), typed::Seat::from(direction))
+    }
+
+    /// The card played, as a bridge_types::Card
+    pub fn bridge_card(&self) -> bridge_types::Card {
+        index_to_card(self.card)
+    }
+
+    /// The seat that played the card, as a bridge_types::Direction
+    pub fn direction(&self) -> bridge_types::Direction {
+        seat_to_direction(self.seat)
+    }
+}
+
+impl CardAnalysis {
+    /// The card analysed, as a bridge_types::Card
+    pub fn bridge_card(&self) -> bridge_types::Card {
+        index_to_card(self.card)
+    }
+}
+
+impl DdTable {
+    /// Tricks taken by `declarer` with `trump` as trumps (None for notrump)
+    pub fn tricks_for(
+        &self,
+        declarer: bridge_types::Direction,
+        trump: Option<bridge_types::Suit>,
+    ) -> u8 {
+        self.get_index(direction_to_seat(declarer), convert_trump(trump))
+    }
+}
+
+/// Solve the full double-dummy table of a bridge_types::Deal
+///
+/// Read the results with `DdTable::tricks_for`.
+pub fn solve_deal(deal: &bridge_types::Deal) -> DdTable {
+    solve_dd_table(&Hands::from_deal(deal))
+}
+
+/// Tricks taken by `declarer` playing `deal` with `trump` as trumps (None for
+/// notrump), with declarer's left-hand opponent on lead
+pub fn solve_contract(
+    deal: &bridge_types::Deal,
+    declarer: bridge_types::Direction,
+    trump: Option<bridge_types::Suit>,
+) -> u8 {
+    let hands = Hands::from_deal(deal);
+    let declarer = direction_to_seat(declarer);
+    let leader = left_hand_opp(declarer);
+    let ns_tricks = Solver::with_indices(hands, convert_trump(trump), leader).solve();
+    if is_ns(declarer) {
+        ns_tricks
+    } else {
+        hands.num_tricks() as u8 - ns_tricks
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -118,6 +276,55 @@ mod tests {
         assert_eq!(hands_from_deal, hands_from_pbn);
     }
 
+    #[test]
+    fn test_to_deal_round_trip() {
+        let pbn = "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72";
+        let hands = Hands::from_pbn(pbn).unwrap();
+        let deal = hands.to_deal().unwrap();
+        assert_eq!(Hands::from_deal(&deal), hands);
+    }
+
+    #[test]
+    fn test_card_conversion() {
+        for card in 0..TOTAL_CARDS {
+            assert_eq!(card_to_index(&index_to_card(card)), card);
+        }
+        let ace = index_to_card(card_of(SPADE, ACE));
+        assert!(matches!(ace.suit, bridge_types::Suit::Spades));
+        assert_eq!(ace.rank as usize, 14);
+
+        let played = PlayedCard::from_bridge(&ace, bridge_types::Direction::East);
+        assert_eq!((played.card, played.seat), (card_of(SPADE, ACE), EAST));
+        assert_eq!(card_to_index(&played.bridge_card()), played.card);
+        assert_eq!(played.direction(), bridge_types::Direction::East);
+    }
+
+    #[test]
+    fn test_solve_deal() {
+        let pbn = "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72";
+        let deal = Deal::from_pbn(pbn).unwrap();
+        let table = solve_deal(&deal);
+        let hands = Hands::from_pbn(pbn).unwrap();
+        assert_eq!(table, solve_dd_table(&hands));
+
+        let north = bridge_types::Direction::North;
+        let west = bridge_types::Direction::West;
+        let spades = Some(bridge_types::Suit::Spades);
+        assert_eq!(
+            table.tricks_for(north, spades),
+            table.get_index(NORTH, SPADE)
+        );
+        assert_eq!(table.tricks_for(west, None), table.get_index(WEST, NOTRUMP));
+        assert_eq!(
+            solve_contract(&deal, north, spades),
+            table.get_index(NORTH, SPADE)
+        );
+        assert_eq!(
+            solve_contract(&deal, west, None),
+            table.get_index(WEST, NOTRUMP)
+        );
+    }
+
     #[test]
     fn test_direction_conversion() {
         assert_eq!(direction_to_seat(bridge_types::Direction::North), NORTH);
@@ -129,5 +336,14 @@ mod tests {
         assert_eq!(seat_to_direction(EAST), bridge_types::Direction::East);
         assert_eq!(seat_to_direction(SOUTH), bridge_types::Direction::South);
         assert_eq!(seat_to_direction(WEST), bridge_types::Direction::West);
+
+        for seat in typed::Seat::iter() {
+            let dir = bridge_types::Direction::from(seat);
+            assert_eq!(typed::Seat::from(dir), seat);
+        }
+        for suit in typed::Suit::iter() {
+            let converted = bridge_types::Suit::from(suit);
+            assert_eq!(typed::Suit::from(converted), suit);
+        }
     }
 }
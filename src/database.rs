@@ -0,0 +1,719 @@
+//! Results database: solved deals kept on disk between runs
+//!
+//! A plain local file mapping full deals to their double-dummy table, their
+//! par for every vulnerability and dealing side, and the version of the solver
+//! that solved them. Batch runs look deals up before solving and append the
+//! ones they solve, so a deal is only solved once however often it is seen.
+//!
+//! Deals are keyed by their deal number: the position of the deal among all
+//! 52!/(13!)^4 deals, counting card by card from the ace of spades and seat by
+//! seat from West. It identifies the deal as dealt, so a rotated deal is a
+//! different one (its table is the same, turned).
+//!
+//! # File format
+//!
+//! All integers are little-endian. The file is a header followed by a record
+//! per stored deal, appended as deals are solved. A later record for a deal
+//! replaces earlier ones; [`ResultsDb::compact`] rewrites the file with one
+//! record each.
+//!
+//! ```text
+//! magic     8 bytes  "BSRESDB\0"
+//! version   u32      RESULTS_DB_VERSION
+//! records, each:
+//!   length    u32    payload length in bytes
+//!   payload          deal number   u128
+//!                    tricks        20 x u8, [declarer][strain] in solver order
+//!                    par           8 x (NS score i32, level u8 (0 when passed
+//!                                  out), strain u8, declarer u8, doubled u8,
+//!                                  tricks u8), by vulnerability (None, NS, EW,
+//!                                  Both) then dealer's side (EW, NS)
+//!                    solver version  u8 length, UTF-8
+//!   checksum  u64    FNV-1a hash of the payload
+//! ```
+//!
+//! A last record cut short by an interrupted write is dropped when the file is
+//! opened; any other damage fails the open.
+
+use super::cache::{checksum, invalid_data, put_u32, put_u64, ByteReader};
+use super::canonical::ResultCache;
+use super::hands::Hands;
+use super::par::{par, ParContract, ParResult};
+use super::pbn::{decode_ddt, encode_ddt};
+use super::scoring::Vulnerability;
+use super::table::{solve_dd_table_cached, DdTable};
+use super::types::*;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Version of the results database format, bumped on any incompatible change
+pub const RESULTS_DB_VERSION: u32 = 1;
+
+/// Version of this solver, recorded with every result it stores
+pub const SOLVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Number of full deals: deal numbers run from 0 to one less
+pub const NUM_DEALS: u128 = 53_644_737_765_488_792_839_237_440_000;
+
+const MAGIC: &[u8; 8] = b"BSRESDB\0";
+
+const HEADER_LEN: usize = 12;
+
+/// Offset of the solver version's length in a record payload: deal number,
+/// tricks and pars come first
+const VERSION_LEN_OFFSET: usize = 16 + NUM_SEATS * NUM_STRAINS + 8 * 9;
+
+/// Payload lengths a record can have, with an empty to a 255-byte version
+const MIN_PAYLOAD_LEN: usize = VERSION_LEN_OFFSET + 1;
+const MAX_PAYLOAD_LEN: usize = MIN_PAYLOAD_LEN + u8::MAX as usize;
+
+/// Vulnerabilities in the order pars are stored
+const VULNERABILITIES: [Vulnerability; 4] = [
+    Vulnerability::None,
+    Vulnerability::NS,
+    Vulnerability::EW,
+    Vulnerability::Both,
+];
+
+/// Number of ways to deal the remaining cards, `counts[seat]` to each seat
+fn num_deals(counts: &[u32; NUM_SEATS]) -> u128 {
+    let mut remaining: u32 = counts.iter().sum();
+    counts.iter().fold(1, |deals, &count| {
+        let ways = binomial(remaining, count);
+        remaining -= count;
+        deals * ways
+    })
+}
+
+fn binomial(n: u32, k: u32) -> u128 {
+    // Each step is exact: the running value is C(n, i)
+    (0..k).fold(1, |value, i| value * (n - i) as u128 / (i + 1) as u128)
+}
+
+/// Deal number of full hands, None unless every seat holds 13 cards
+pub fn deal_number(hands: &Hands) -> Option<u128> {
+    if !hands.is_valid() || hands.num_tricks() != TOTAL_TRICKS {
+        return None;
+    }
+    let mut counts = [TOTAL_TRICKS as u32; NUM_SEATS];
+    let mut number = 0;
+    for card in 0..TOTAL_CARDS {
+        let owner = (0..NUM_SEATS).find(|&seat| hands[seat].have_index(card))?;
+        // Skip the deals giving this card to an earlier seat
+        for seat in 0..owner {
+            if counts[seat] > 0 {
+                counts[seat] -= 1;
+                number += num_deals(&counts);
+                counts[seat] += 1;
+            }
+        }
+        counts[owner] -= 1;
+    }
+    Some(number)
+}
+
+/// Full hands of a deal number (inverse of [`deal_number`])
+pub fn deal_from_number(mut number: u128) -> Option<Hands> {
+    if number >= NUM_DEALS {
+        return None;
+    }
+    let mut hands = Hands::new();
+    let mut counts = [TOTAL_TRICKS as u32; NUM_SEATS];
+    for card in 0..TOTAL_CARDS {
+        for seat in 0..NUM_SEATS {
+            if counts[seat] == 0 {
+                continue;
+            }
+            counts[seat] -= 1;
+            let deals = num_deals(&counts);
+            if number < deals {
+                hands[seat].add_index(card);
+                break;
+            }
+            number -= deals;
+            counts[seat] += 1;
+        }
+    }
+    Some(hands)
+}
+
+/// A stored deal's results
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DbEntry {
+    pub table: DdTable,
+    /// Par by vulnerability and dealer's side, see the file format
+    pars: [[ParResult; 2]; 4],
+    /// Version of the solver that produced the table
+    pub solver_version: String,
+}
+
+impl DbEntry {
+    /// An entry for a table solved by this solver version
+    pub fn new(table: DdTable) -> Self {
+        Self::with_version(table, SOLVER_VERSION.to_string())
+    }
+
+    fn with_version(table: DdTable, solver_version: String) -> Self {
+        let pars = std::array::from_fn(|vulnerability| {
+            std::array::from_fn(|side| {
+                par(&table, VULNERABILITIES[vulnerability], [WEST, NORTH][side])
+            })
+        });
+        DbEntry {
+            table,
+            pars,
+            solver_version,
+        }
+    }
+
+    /// Par when `dealer` deals at `vulnerability`
+    pub fn par(&self, vulnerability: Vulnerability, dealer: SeatIndex) -> ParResult {
+        let vulnerability = VULNERABILITIES
+            .iter()
+            .position(|&v| v == vulnerability)
+            .unwrap();
+        self.pars[vulnerability][is_ns(dealer) as usize]
+    }
+}
+
+/// Solved deals in a local file, loaded into memory and appended to as deals
+/// are stored
+#[derive(Debug)]
+pub struct ResultsDb {
+    path: PathBuf,
+    file: File,
+    entries: HashMap<u128, DbEntry>,
+    /// Records in the file, including those replaced by later ones
+    records: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl ResultsDb {
+    /// Open the database at `path`, creating it if it does not exist
+    ///
+    /// Fails with `InvalidData` if the file is not a results database, was
+    /// written by another format version, or is corrupt.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        if data.is_empty() {
+            data = header();
+            file.write_all(&data)?;
+        }
+
+        let (entries, records, valid_len) = read_records(&data)?;
+        if valid_len < data.len() {
+            // Drop a record cut short by an interrupted write
+            file.set_len(valid_len as u64)?;
+        }
+        file.seek(SeekFrom::Start(valid_len as u64))?;
+        Ok(ResultsDb {
+            path,
+            file,
+            entries,
+            records,
+            hits: 0,
+            misses: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Stored results of `hands`, if it is a full deal in the database
+    pub fn get(&mut self, hands: &Hands) -> Option<&DbEntry> {
+        let entry = deal_number(hands).and_then(|number| self.entries.get(&number));
+        if entry.is_some() {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        entry
+    }
+
+    /// Store the table of `hands`, solved by this solver version
+    ///
+    /// Fails with `InvalidInput` unless `hands` is a full deal.
+    pub fn insert(&mut self, hands: &Hands, table: DdTable) -> io::Result<()> {
+        let number = deal_number(hands).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "only full deals can be stored")
+        })?;
+        self.append(number, DbEntry::new(table))
+    }
+
+    fn append(&mut self, number: u128, entry: DbEntry) -> io::Result<()> {
+        // One write per record, so an interruption leaves at most the last one short
+        self.file.write_all(&encode_record(number, &entry))?;
+        self.records += 1;
+        self.entries.insert(number, entry);
+        Ok(())
+    }
+
+    /// Number of distinct deals stored
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Stored entries, in no particular order
+    pub fn entries(&self) -> impl Iterator<Item = &DbEntry> {
+        self.entries.values()
+    }
+
+    /// Records in the file; more than [`ResultsDb::len`] when deals were
+    /// stored again
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    /// Lookups answered from the database
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Lookups that found nothing
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Rewrite the file with one record per deal, in deal number order
+    ///
+    /// With `current_only`, deals solved by other solver versions are dropped.
+    /// The new file replaces the old one only once it is completely written.
+    pub fn compact(&mut self, current_only: bool) -> io::Result<()> {
+        if current_only {
+            self.entries
+                .retain(|_, entry| entry.solver_version == SOLVER_VERSION);
+        }
+        let mut numbers: Vec<u128> = self.entries.keys().copied().collect();
+        numbers.sort_unstable();
+
+        let temp_path = self.path.with_extension("compact");
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        writer.write_all(&header())?;
+        for number in &numbers {
+            writer.write_all(&encode_record(*number, &self.entries[number]))?;
+        }
+        writer.into_inner()?.sync_all()?;
+        fs::rename(&temp_path, &self.path)?;
+
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.records = numbers.len();
+        Ok(())
+    }
+
+    /// Write every deal as a text line, in deal number order: the PBN deal,
+    /// its DoubleDummyTricks encoding and the solver version, separated by tabs
+    ///
+    /// Returns the number of deals written.
+    pub fn export(&self, writer: &mut impl Write) -> io::Result<usize> {
+        let mut numbers: Vec<u128> = self.entries.keys().copied().collect();
+        numbers.sort_unstable();
+        for number in &numbers {
+            let entry = &self.entries[number];
+            let hands = deal_from_number(*number).unwrap();
+            writeln!(
+                writer,
+                "{}\t{}\t{}",
+                hands.to_pbn(),
+                encode_ddt(&entry.table),
+                entry.solver_version
+            )?;
+        }
+        Ok(numbers.len())
+    }
+
+    /// Store the deals of text lines written by [`ResultsDb::export`]
+    ///
+    /// Blank lines and lines starting with `#` are skipped, as are deals
+    /// already stored with the same table and version. Returns the number of
+    /// deals stored; fails with `InvalidData` at the first malformed line.
+    pub fn import(&mut self, reader: impl BufRead) -> io::Result<usize> {
+        let mut stored = 0;
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (number, entry) = parse_export_line(line)
+                .ok_or_else(|| invalid_data(format!("line {}: malformed result", index + 1)))?;
+            if self.entries.get(&number) != Some(&entry) {
+                self.append(number, entry)?;
+                stored += 1;
+            }
+        }
+        Ok(stored)
+    }
+}
+
+/// The table of `hands`: from `db` when it holds the deal, otherwise solved
+/// with `cache` and stored in `db`
+///
+/// Only full deals are looked up and stored; others are just solved.
+pub(crate) fn solve_with_db(
+    hands: &Hands,
+    cache: &mut ResultCache,
+    db: Option<&mut ResultsDb>,
+) -> io::Result<DdTable> {
+    let Some(db) = db.filter(|_| hands.num_tricks() == TOTAL_TRICKS) else {
+        return Ok(solve_dd_table_cached(hands, cache));
+    };
+    if let Some(entry) = db.get(hands) {
+        return Ok(entry.table);
+    }
+    let table = solve_dd_table_cached(hands, cache);
+    db.insert(hands, table)?;
+    Ok(table)
+}
+
+fn header() -> Vec<u8> {
+    let mut header = MAGIC.to_vec();
+    put_u32(&mut header, RESULTS_DB_VERSION);
+    header
+}
+
+/// Entries, number of records and length of the complete records (with the
+/// header) of a database file
+fn read_records(data: &[u8]) -> io::Result<(HashMap<u128, DbEntry>, usize, usize)> {
+    if data.len() < HEADER_LEN || &data[..8] != MAGIC {
+        return Err(invalid_data("not a results database"));
+    }
+    let version = u32::from_le_bytes(data[8..12].try_into().unwrap());
+    if version != RESULTS_DB_VERSION {
+        return Err(invalid_data(format!(
+            "results database version {} (expected {})",
+            version, RESULTS_DB_VERSION
+        )));
+    }
+
+    let mut entries = HashMap::new();
+    let mut records = 0;
+    let mut pos = HEADER_LEN;
+    while let Some(length) = data.get(pos..pos + 4) {
+        let length = u32::from_le_bytes(length.try_into().unwrap()) as usize;
+        if !(MIN_PAYLOAD_LEN..=MAX_PAYLOAD_LEN).contains(&length) {
+            return Err(invalid_data("invalid results database record length"));
+        }
+        let Some(record) = data.get(pos + 4..pos + 4 + length + 8) else {
+            // Cut short, so this can only be the last record as far as it was
+            // written. Its version length, inside even the shortest record,
+            // fixes the payload length: a damaged length field fails here
+            // rather than dropping the records after it.
+            let version_len = data.get(pos + 4 + VERSION_LEN_OFFSET);
+            if version_len.is_some_and(|&len| length != MIN_PAYLOAD_LEN + len as usize) {
+                return Err(invalid_data("invalid results database record length"));
+            }
+            break;
+        };
+        let (payload, stored_checksum) = record.split_at(length);
+        if u64::from_le_bytes(stored_checksum.try_into().unwrap()) != checksum(payload) {
+            return Err(invalid_data("results database checksum mismatch"));
+        }
+        let (number, entry) = decode_record(payload)?;
+        entries.insert(number, entry);
+        records += 1;
+        pos += 4 + length + 8;
+    }
+    Ok((entries, records, pos))
+}
+
+fn encode_record(number: u128, entry: &DbEntry) -> Vec<u8> {
+    let mut payload = number.to_le_bytes().to_vec();
+    payload.extend(entry.table.tricks().iter().flatten());
+    for par in entry.pars.iter().flatten() {
+        payload.extend_from_slice(&par.ns_score.to_le_bytes());
+        payload.extend_from_slice(&match par.contract {
+            Some(contract) => [
+                contract.level,
+                contract.strain as u8,
+                contract.declarer as u8,
+                contract.doubled as u8,
+                contract.tricks,
+            ],
+            None => [0; 5],
+        });
+    }
+    payload.push(entry.solver_version.len() as u8);
+    payload.extend_from_slice(entry.solver_version.as_bytes());
+
+    let mut record = Vec::with_capacity(payload.len() + 12);
+    put_u32(&mut record, payload.len() as u32);
+    record.extend_from_slice(&payload);
+    put_u64(&mut record, checksum(&payload));
+    record
+}
+
+fn decode_record(payload: &[u8]) -> io::Result<(u128, DbEntry)> {
+    let mut input = ByteReader::new(payload);
+    let number = input.u128()?;
+    if number >= NUM_DEALS {
+        return Err(invalid_data("invalid deal number"));
+    }
+
+    let mut tricks = [[0; NUM_STRAINS]; NUM_SEATS];
+    for value in tricks.iter_mut().flatten() {
+        *value = input.u8()?;
+        if *value as usize > TOTAL_TRICKS {
+            return Err(invalid_data("invalid tricks"));
+        }
+    }
+
+    let passed_out = ParResult {
+        ns_score: 0,
+        contract: None,
+    };
+    let mut pars = [[passed_out; 2]; 4];
+    for par in pars.iter_mut().flatten() {
+        par.ns_score = input.u32()? as i32;
+        let level = input.u8()?;
+        let strain = input.u8()? as usize;
+        let declarer = input.u8()? as usize;
+        let doubled = input.u8()?;
+        let tricks = input.u8()?;
+        if level == 0 {
+            continue;
+        }
+        if level > 7
+            || strain > NOTRUMP
+            || declarer >= NUM_SEATS
+            || doubled > 1
+            || tricks as usize > TOTAL_TRICKS
+        {
+            return Err(invalid_data("invalid par contract"));
+        }
+        par.contract = Some(ParContract {
+            level,
+            strain,
+            declarer,
+            doubled: doubled == 1,
+            tricks,
+        });
+    }
+
+    let version_len = input.u8()? as usize;
+    let version = (0..version_len)
+        .map(|_| input.u8())
+        .collect::<io::Result<Vec<u8>>>()?;
+    let solver_version =
+        String::from_utf8(version).map_err(|_| invalid_data("invalid solver version"))?;
+    if !input.is_empty() {
+        return Err(invalid_data("unexpected data after record"));
+    }
+
+    let entry = DbEntry {
+        table: DdTable::from_tricks(tricks),
+        pars,
+        solver_version,
+    };
+    Ok((number, entry))
+}
+
+/// Deal number and entry of an exported line
+fn parse_export_line(line: &str) -> Option<(u128, DbEntry)> {
+    let mut fields = line.split('\t');
+    let number = deal_number(&Hands::from_pbn(fields.next()?)?)?;
+    let table = decode_ddt(fields.next()?)?;
+    let solver_version = fields.next()?.trim();
+    if fields.next().is_some() || solver_version.len() > u8::MAX as usize {
+        return None;
+    }
+    Some((
+        number,
+        DbEntry::with_version(table, solver_version.to_string()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pbn::annotate_pbn_with_db;
+
+    const DEAL: &str = "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72";
+
+    /// A path in the temp directory unique to this process and test
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("bridge-solver-{}-{}.db", std::process::id(), name))
+    }
+
+    /// A made-up table for `DEAL`: NS make 9 tricks everywhere
+    fn table() -> DdTable {
+        DdTable::from_tricks([
+            [4; NUM_STRAINS],
+            [9; NUM_STRAINS],
+            [4; NUM_STRAINS],
+            [9; NUM_STRAINS],
+        ])
+    }
+
+    #[test]
+    fn test_deal_numbers() {
+        assert_eq!(num_deals(&[13; NUM_SEATS]), NUM_DEALS);
+
+        // West holds the first 13 cards (spades) and so on: the first deal
+        let first = "N:.AKQJT98765432.. ..AKQJT98765432. ...AKQJT98765432 AKQJT98765432...";
+        let last = "N:..AKQJT98765432. .AKQJT98765432.. AKQJT98765432... ...AKQJT98765432";
+        assert_eq!(deal_number(&Hands::from_pbn(first).unwrap()), Some(0));
+        assert_eq!(
+            deal_number(&Hands::from_pbn(last).unwrap()),
+            Some(NUM_DEALS - 1)
+        );
+
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        let number = deal_number(&hands).unwrap();
+        assert_eq!(deal_from_number(number), Some(hands));
+        assert_eq!(deal_from_number(NUM_DEALS), None);
+        assert_eq!(
+            deal_number(&Hands::from_pbn("N:A... K... Q... J...").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_store_and_reopen() {
+        let path = temp_path("reopen");
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        {
+            let mut db = ResultsDb::open(&path).unwrap();
+            assert!(db.get(&hands).is_none());
+            db.insert(&hands, table()).unwrap();
+            db.insert(&hands, table()).unwrap();
+            assert_eq!((db.len(), db.records()), (1, 2));
+        }
+
+        let mut db = ResultsDb::open(&path).unwrap();
+        assert_eq!((db.len(), db.records()), (1, 2));
+        let entry = db.get(&hands).unwrap().clone();
+        assert_eq!(entry, DbEntry::new(table()));
+        assert_eq!(entry.solver_version, SOLVER_VERSION);
+        assert_eq!(
+            entry.par(Vulnerability::None, SOUTH),
+            par(&table(), Vulnerability::None, NORTH)
+        );
+
+        db.compact(false).unwrap();
+        assert_eq!(db.records(), 1);
+        drop(db);
+        let db = ResultsDb::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!((db.len(), db.records()), (1, 1));
+    }
+
+    #[test]
+    fn test_interrupted_write() {
+        let path = temp_path("interrupted");
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        ResultsDb::open(&path)
+            .unwrap()
+            .insert(&hands, table())
+            .unwrap();
+        let full_len = fs::metadata(&path).unwrap().len();
+
+        // Half a second record, as left by a crash mid-write
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&encode_record(0, &DbEntry::new(table()))[..20])
+            .unwrap();
+        drop(file);
+        let db = ResultsDb::open(&path).unwrap();
+        assert_eq!(db.len(), 1);
+        assert_eq!(fs::metadata(&path).unwrap().len(), full_len);
+
+        // Damage inside a complete record is an error
+        let mut data = fs::read(&path).unwrap();
+        data[HEADER_LEN + 10] ^= 1;
+        fs::write(&path, &data).unwrap();
+        let error = ResultsDb::open(&path).err().unwrap().to_string();
+        fs::write(&path, b"not a database").unwrap();
+        let not_db = ResultsDb::open(&path).err().unwrap().to_string();
+        fs::remove_file(&path).unwrap();
+        assert!(error.contains("checksum"));
+        assert!(not_db.contains("not a results database"));
+    }
+
+    #[test]
+    fn test_damaged_length() {
+        let path = temp_path("length");
+        let mut db = ResultsDb::open(&path).unwrap();
+        for number in 0..3 {
+            db.insert(&deal_from_number(number).unwrap(), table())
+                .unwrap();
+        }
+        drop(db);
+        let data = fs::read(&path).unwrap();
+        let first_len = u32::from_le_bytes(data[HEADER_LEN..HEADER_LEN + 4].try_into().unwrap());
+        let middle = HEADER_LEN + 4 + first_len as usize + 8;
+
+        // Out of range, or in range and running past the end of the file:
+        // either way an error, never a truncation of the records after it
+        for length in [u32::MAX, MAX_PAYLOAD_LEN as u32] {
+            let mut damaged = data.clone();
+            damaged[middle..middle + 4].copy_from_slice(&length.to_le_bytes());
+            fs::write(&path, &damaged).unwrap();
+            let error = ResultsDb::open(&path).err().unwrap().to_string();
+            assert!(error.contains("record length"), "{}", error);
+            assert_eq!(fs::read(&path).unwrap(), damaged);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_export_import() {
+        let path = temp_path("export");
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        let mut db = ResultsDb::open(&path).unwrap();
+        db.insert(&hands, table()).unwrap();
+        let mut exported = Vec::new();
+        assert_eq!(db.export(&mut exported).unwrap(), 1);
+        let text = String::from_utf8(exported).unwrap();
+        assert_eq!(
+            text,
+            format!("{}\t99999999994444444444\t{}\n", DEAL, SOLVER_VERSION)
+        );
+
+        // Importing what is already stored changes nothing
+        assert_eq!(db.import(text.as_bytes()).unwrap(), 0);
+        let older = text.replace(SOLVER_VERSION, "0.1.0");
+        assert_eq!(
+            db.import(format!("# old\n\n{}", older).as_bytes()).unwrap(),
+            1
+        );
+        assert_eq!(db.get(&hands).unwrap().solver_version, "0.1.0");
+        let error = db.import("N:AKQ... bad".as_bytes()).err().unwrap();
+        assert!(error.to_string().contains("line 1"));
+
+        db.compact(true).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(db.is_empty());
+    }
+
+    #[test]
+    fn test_annotate_with_db() {
+        let path = temp_path("annotate");
+        let pbn = format!("[Board \"1\"]\n[Deal \"{}\"]\n", DEAL);
+        let mut db = ResultsDb::open(&path).unwrap();
+        let solved = annotate_pbn_with_db(&pbn, Some(&mut db)).unwrap();
+        assert_eq!((db.hits(), db.misses(), db.len()), (0, 1, 1));
+        assert_eq!((solved.db_hits, solved.db_misses), (0, 1));
+
+        let mut db = ResultsDb::open(&path).unwrap();
+        let looked_up = annotate_pbn_with_db(&pbn, Some(&mut db)).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!((db.hits(), db.misses()), (1, 0));
+        assert_eq!((looked_up.db_hits, looked_up.db_misses), (1, 0));
+        assert_eq!(looked_up.pbn, solved.pbn);
+    }
+}
@@ -0,0 +1,722 @@
+//! DD datasets: random deals with their double-dummy tables, for training
+//!
+//! [`generate_dataset`] deals random hands, optionally limited by each seat's
+//! high card points, solves their full tables on every core and writes them
+//! in chunks; [`DatasetReader`] reads them back. The deals of a chunk depend
+//! only on the seed and the chunk's number, so a dataset is the same however
+//! many threads made it, and an interrupted run picks up after the last
+//! complete chunk when run again.
+//!
+//! # File format
+//!
+//! All integers are little-endian.
+//!
+//! ```text
+//! magic        8 bytes  "BSDDSET\0"
+//! version      u32      DATASET_FILE_VERSION
+//! seed         u64
+//! chunk deals  u32      deals per chunk (the last one may hold fewer), at
+//!                       most MAX_CHUNK_DEALS
+//! hcp          4 x (min u8, max u8), by seat WEST..SOUTH
+//! solver       u8 length, UTF-8: version of the solver that made the tables
+//! chunks, each:
+//!   index      u64      chunk number, from 0
+//!   count      u32      deals in the chunk
+//!   records    count x 23 bytes: the deal packed in 13 bytes and its table
+//!              in 10, as by `pack_deal` and `pack_table`
+//!   checksum   u64      FNV-1a hash of index, count and records
+//! ```
+
+use super::cache::{checksum, invalid_data, put_u32, put_u64, ByteReader};
+use super::codec::{
+    pack_deal, pack_table, unpack_deal, unpack_table, PACKED_DEAL_LEN, PACKED_TABLE_LEN,
+};
+use super::database::SOLVER_VERSION;
+use super::hands::Hands;
+use super::table::{solve_dd_table, DdTable};
+use super::types::*;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Condvar, Mutex};
+
+/// Version of the dataset file format, bumped on any incompatible change
+pub const DATASET_FILE_VERSION: u32 = 1;
+
+/// Deals per chunk unless chosen otherwise: a few seconds' work per thread
+pub const DEFAULT_CHUNK_DEALS: u32 = 256;
+
+/// Most deals a chunk may hold, which bounds what reading one allocates
+pub const MAX_CHUNK_DEALS: u32 = 1 << 16;
+
+const MAGIC: &[u8; 8] = b"BSDDSET\0";
+
+/// Bytes of a deal and its table
+const RECORD_LEN: usize = PACKED_DEAL_LEN + PACKED_TABLE_LEN;
+
+/// Bytes of a chunk's index and count
+const CHUNK_HEADER_LEN: usize = 12;
+
+/// Most high card points one hand can hold
+const MAX_HCP: u8 = 37;
+
+/// Limits on the deals of a dataset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DealConstraints {
+    /// Smallest and largest high card points, by seat
+    pub hcp: [(u8, u8); NUM_SEATS],
+}
+
+impl Default for DealConstraints {
+    fn default() -> Self {
+        DealConstraints {
+            hcp: [(0, MAX_HCP); NUM_SEATS],
+        }
+    }
+}
+
+impl DealConstraints {
+    /// Check that some deal meets the constraints
+    ///
+    /// The 40 points of a deal must fit the ranges; a very narrow fit is
+    /// possible but makes generation slow.
+    pub fn is_satisfiable(&self) -> bool {
+        let min: u32 = self.hcp.iter().map(|&(min, _)| min as u32).sum();
+        let max: u32 = self.hcp.iter().map(|&(_, max)| max as u32).sum();
+        self.hcp
+            .iter()
+            .all(|&(min, max)| min <= max && max <= MAX_HCP)
+            && min <= 40
+            && max >= 40
+    }
+
+    pub fn accepts(&self, hands: &Hands) -> bool {
+        (0..NUM_SEATS).all(|seat| {
+            let (min, max) = self.hcp[seat];
+            (min as usize..=max as usize).contains(&hands[seat].points())
+        })
+    }
+}
+
+/// What a dataset is made from: the same header always gives the same deals
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DatasetHeader {
+    pub seed: u64,
+    /// Deals per chunk
+    pub chunk_deals: u32,
+    pub constraints: DealConstraints,
+    /// Version of the solver that made the tables, at most 255 bytes
+    pub solver_version: String,
+}
+
+impl DatasetHeader {
+    /// A header for tables solved by this solver version, with chunks of
+    /// [`DEFAULT_CHUNK_DEALS`]
+    pub fn new(seed: u64, constraints: DealConstraints) -> Self {
+        DatasetHeader {
+            seed,
+            chunk_deals: DEFAULT_CHUNK_DEALS,
+            constraints,
+            solver_version: SOLVER_VERSION.to_string(),
+        }
+    }
+
+    /// Serialize the header; `generate_dataset` has checked the version fits
+    fn write_to(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(MAGIC);
+        put_u32(out, DATASET_FILE_VERSION);
+        put_u64(out, self.seed);
+        put_u32(out, self.chunk_deals);
+        for (min, max) in self.constraints.hcp {
+            out.extend_from_slice(&[min, max]);
+        }
+        out.push(self.solver_version.len() as u8);
+        out.extend_from_slice(self.solver_version.as_bytes());
+    }
+
+    fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let mut fixed = [0u8; 33];
+        reader
+            .read_exact(&mut fixed)
+            .map_err(|_| invalid_data("not a dataset file"))?;
+        if &fixed[..8] != MAGIC {
+            return Err(invalid_data("not a dataset file"));
+        }
+        let mut input = ByteReader::new(&fixed[8..]);
+        let version = input.u32()?;
+        if version != DATASET_FILE_VERSION {
+            return Err(invalid_data(format!(
+                "dataset file version {} (expected {})",
+                version, DATASET_FILE_VERSION
+            )));
+        }
+        let seed = input.u64()?;
+        let chunk_deals = input.u32()?;
+        let mut constraints = DealConstraints::default();
+        for range in &mut constraints.hcp {
+            *range = (input.u8()?, input.u8()?);
+        }
+        let mut solver_version = vec![0; input.u8()? as usize];
+        reader.read_exact(&mut solver_version)?;
+        let solver_version = String::from_utf8(solver_version)
+            .map_err(|_| invalid_data("invalid solver version"))?;
+        if !(1..=MAX_CHUNK_DEALS).contains(&chunk_deals) || !constraints.is_satisfiable() {
+            return Err(invalid_data("invalid dataset header"));
+        }
+        Ok(DatasetHeader {
+            seed,
+            chunk_deals,
+            constraints,
+            solver_version,
+        })
+    }
+
+    /// The first `count` deals of chunk `index`
+    pub fn chunk_hands(&self, index: u64, count: usize) -> Vec<Hands> {
+        let mut random = Random::new(self.seed, index);
+        let mut deals = Vec::with_capacity(count);
+        while deals.len() < count {
+            let hands = random.deal();
+            if self.constraints.accepts(&hands) {
+                deals.push(hands);
+            }
+        }
+        deals
+    }
+
+    /// Deals in chunk `index` of a dataset of `num_deals`
+    fn chunk_len(&self, index: u64, num_deals: u64) -> usize {
+        let start = index * self.chunk_deals as u64;
+        num_deals.saturating_sub(start).min(self.chunk_deals as u64) as usize
+    }
+}
+
+/// A deal and its double-dummy table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DatasetRecord {
+    pub hands: Hands,
+    pub table: DdTable,
+}
+
+impl DatasetRecord {
+    fn write_to(&self, out: &mut Vec<u8>) {
+        // Datasets only hold full deals
+        out.extend_from_slice(&pack_deal(&self.hands).unwrap());
+        out.extend_from_slice(&pack_table(&self.table));
+    }
+
+    fn read_from(bytes: &[u8]) -> io::Result<Self> {
+        let (deal, tricks) = bytes.split_at(PACKED_DEAL_LEN);
+        let hands = unpack_deal(deal.try_into().unwrap())
+            .ok_or_else(|| invalid_data("invalid deal in dataset"))?;
+        let table = unpack_table(tricks.try_into().unwrap())
+            .ok_or_else(|| invalid_data("invalid tricks in dataset"))?;
+        Ok(DatasetRecord { hands, table })
+    }
+}
+
+/// Reads the records of a dataset, chunk by chunk
+///
+/// Yields an `InvalidData` error at a corrupt chunk and an `UnexpectedEof`
+/// error at a chunk cut short, as a run still going or interrupted leaves.
+pub struct DatasetReader<R> {
+    reader: R,
+    header: DatasetHeader,
+    records: std::vec::IntoIter<DatasetRecord>,
+    next_chunk: u64,
+    failed: bool,
+}
+
+impl DatasetReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> DatasetReader<R> {
+    /// Read the header; records follow as the reader is iterated
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let header = DatasetHeader::read_from(&mut reader)?;
+        Ok(DatasetReader {
+            reader,
+            header,
+            records: Vec::new().into_iter(),
+            next_chunk: 0,
+            failed: false,
+        })
+    }
+
+    pub fn header(&self) -> &DatasetHeader {
+        &self.header
+    }
+
+    /// The records of the next chunk, None at the end of the data
+    fn read_chunk(&mut self) -> io::Result<Option<Vec<DatasetRecord>>> {
+        let mut chunk = vec![0u8; CHUNK_HEADER_LEN];
+        let read = read_full(&mut self.reader, &mut chunk)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if read < CHUNK_HEADER_LEN {
+            return Err(truncated());
+        }
+        let mut input = ByteReader::new(&chunk);
+        let index = input.u64()?;
+        let count = input.u32()? as usize;
+        if index != self.next_chunk || count == 0 || count > self.header.chunk_deals as usize {
+            return Err(invalid_data("invalid dataset chunk"));
+        }
+
+        chunk.resize(CHUNK_HEADER_LEN + count * RECORD_LEN + 8, 0);
+        if read_full(&mut self.reader, &mut chunk[CHUNK_HEADER_LEN..])? < count * RECORD_LEN + 8 {
+            return Err(truncated());
+        }
+        let (data, stored_checksum) = chunk.split_at(chunk.len() - 8);
+        if u64::from_le_bytes(stored_checksum.try_into().unwrap()) != checksum(data) {
+            return Err(invalid_data("dataset chunk checksum mismatch"));
+        }
+        let records = data[CHUNK_HEADER_LEN..]
+            .chunks(RECORD_LEN)
+            .map(DatasetRecord::read_from)
+            .collect::<io::Result<Vec<_>>>()?;
+        self.next_chunk += 1;
+        Ok(Some(records))
+    }
+}
+
+impl<R: Read> Iterator for DatasetReader<R> {
+    type Item = io::Result<DatasetRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.records.next() {
+                return Some(Ok(record));
+            }
+            if self.failed {
+                return None;
+            }
+            match self.read_chunk() {
+                Ok(Some(records)) => self.records = records.into_iter(),
+                Ok(None) => return None,
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Read until `buf` is full or the input ends, returning the bytes read
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "dataset chunk is cut short")
+}
+
+/// Generate a dataset of `num_deals` deals at `path` on `threads` threads
+///
+/// If the file exists it is resumed: its header must match `header`, its
+/// complete chunks are kept and generation carries on from there (a last
+/// chunk cut short, or short of `chunk_deals` when more deals are wanted, is
+/// made again). `progress` is called with the number of deals in the file
+/// each time a chunk is written. Returns that number at the end.
+pub fn generate_dataset(
+    path: impl AsRef<Path>,
+    header: &DatasetHeader,
+    num_deals: u64,
+    threads: usize,
+    mut progress: impl FnMut(u64),
+) -> io::Result<u64> {
+    if !header.constraints.is_satisfiable() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no deal meets the dataset constraints",
+        ));
+    }
+    if !(1..=MAX_CHUNK_DEALS).contains(&header.chunk_deals) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("chunks must hold 1 to {} deals", MAX_CHUNK_DEALS),
+        ));
+    }
+    if header.solver_version.len() > u8::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "solver version is longer than 255 bytes",
+        ));
+    }
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    let (mut done, first_chunk) = resume_point(&mut file, header, num_deals)?;
+
+    let chunk_deals = header.chunk_deals as u64;
+    let end_chunk = num_deals.div_ceil(chunk_deals);
+    if first_chunk >= end_chunk {
+        return Ok(done);
+    }
+
+    let next_chunk = AtomicU64::new(first_chunk);
+    let written = WriteProgress::new(first_chunk);
+    // Chunks made ahead of the next to write, held back until it is done
+    let window = 2 * threads.max(1) as u64;
+    let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(threads);
+    let result = std::thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            let sender = sender.clone();
+            let next_chunk = &next_chunk;
+            let written = &written;
+            scope.spawn(move || {
+                let _guard = StopOnPanic(written);
+                loop {
+                    let index = next_chunk.fetch_add(1, Ordering::Relaxed);
+                    if index >= end_chunk || !written.wait_for(index, window) {
+                        break;
+                    }
+                    let chunk = solve_chunk(header, index, header.chunk_len(index, num_deals));
+                    if sender.send(chunk).is_err() {
+                        // The writer failed and gave up
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        let result = write_chunks(&mut file, receiver, &written, &mut done, &mut progress);
+        // Release workers waiting for chunks that will not be written
+        written.stop();
+        result
+    });
+    result.map(|()| done)
+}
+
+/// Write chunks in order as they arrive from `receiver`, holding back those
+/// finished early
+fn write_chunks(
+    file: &mut File,
+    receiver: mpsc::Receiver<Vec<u8>>,
+    written: &WriteProgress,
+    done: &mut u64,
+    progress: &mut impl FnMut(u64),
+) -> io::Result<()> {
+    let mut pending = BTreeMap::new();
+    for chunk in receiver {
+        let index = u64::from_le_bytes(chunk[..8].try_into().unwrap());
+        pending.insert(index, chunk);
+        while let Some(chunk) = pending.remove(&written.next_write()) {
+            file.write_all(&chunk)?;
+            *done += ((chunk.len() - CHUNK_HEADER_LEN - 8) / RECORD_LEN) as u64;
+            written.advance();
+            progress(*done);
+        }
+    }
+    file.sync_all()
+}
+
+/// The next chunk to write, which workers wait on to stay within a window of it
+struct WriteProgress {
+    /// Next chunk to write, and whether writing has stopped
+    state: Mutex<(u64, bool)>,
+    changed: Condvar,
+}
+
+impl WriteProgress {
+    fn new(next_write: u64) -> Self {
+        WriteProgress {
+            state: Mutex::new((next_write, false)),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Wait until chunk `index` is fewer than `window` chunks ahead of the
+    /// next to write; false if writing stopped first
+    fn wait_for(&self, index: u64, window: u64) -> bool {
+        let mut state = self.state.lock().unwrap();
+        while !state.1 && index >= state.0 + window {
+            state = self.changed.wait(state).unwrap();
+        }
+        !state.1
+    }
+
+    fn next_write(&self) -> u64 {
+        self.state.lock().unwrap().0
+    }
+
+    fn advance(&self) {
+        self.state.lock().unwrap().0 += 1;
+        self.changed.notify_all();
+    }
+
+    fn stop(&self) {
+        self.state.lock().unwrap().1 = true;
+        self.changed.notify_all();
+    }
+}
+
+/// Stops writing if a worker panics, so the others do not wait for its chunk
+struct StopOnPanic<'a>(&'a WriteProgress);
+
+impl Drop for StopOnPanic<'_> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.0.stop();
+        }
+    }
+}
+
+/// Check an existing dataset against `header` and position the file after
+/// the chunks to keep; returns the deals kept and the next chunk to make
+fn resume_point(file: &mut File, header: &DatasetHeader, num_deals: u64) -> io::Result<(u64, u64)> {
+    if file.metadata()?.len() == 0 {
+        let mut bytes = Vec::new();
+        header.write_to(&mut bytes);
+        file.write_all(&bytes)?;
+        return Ok((0, 0));
+    }
+
+    let mut reader = DatasetReader::new(BufReader::new(&*file))?;
+    if reader.header != *header {
+        return Err(invalid_data(
+            "dataset was made with another seed, chunk size, constraints or solver version",
+        ));
+    }
+    let mut header_bytes = Vec::new();
+    header.write_to(&mut header_bytes);
+    let mut keep = header_bytes.len() as u64;
+    let mut done = 0;
+    loop {
+        let records = match reader.read_chunk() {
+            Ok(Some(records)) => records,
+            Ok(None) => break,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        };
+        let count = records.len() as u64;
+        if count < header.chunk_deals as u64 && done + count < num_deals {
+            // A short last chunk: made again at full size
+            reader.next_chunk -= 1;
+            break;
+        }
+        keep += (CHUNK_HEADER_LEN + records.len() * RECORD_LEN + 8) as u64;
+        done += count;
+    }
+    let next_chunk = reader.next_chunk;
+    drop(reader);
+    file.set_len(keep)?;
+    file.seek(SeekFrom::Start(keep))?;
+    Ok((done, next_chunk))
+}
+
+/// Deal and solve chunk `index`, returning it as written to the file
+fn solve_chunk(header: &DatasetHeader, index: u64, count: usize) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(CHUNK_HEADER_LEN + count * RECORD_LEN + 8);
+    put_u64(&mut chunk, index);
+    put_u32(&mut chunk, count as u32);
+    for hands in header.chunk_hands(index, count) {
+        let table = solve_dd_table(&hands);
+        DatasetRecord { hands, table }.write_to(&mut chunk);
+    }
+    let hash = checksum(&chunk);
+    put_u64(&mut chunk, hash);
+    chunk
+}
+
+/// SplitMix64, started from a mix of the seed and chunk number
+struct Random(u64);
+
+impl Random {
+    fn new(seed: u64, chunk: u64) -> Self {
+        Random(mix(seed ^ mix(chunk)))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        mix(self.0)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// A uniformly random deal
+    fn deal(&mut self) -> Hands {
+        let mut deck: [usize; TOTAL_CARDS] = std::array::from_fn(|card| card);
+        for i in (1..TOTAL_CARDS).rev() {
+            deck.swap(i, self.below(i + 1));
+        }
+        let mut hands = Hands::new();
+        for (i, &card) in deck.iter().enumerate() {
+            hands[i / TOTAL_TRICKS].add_index(card);
+        }
+        hands
+    }
+}
+
+/// The SplitMix64 output function
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A path in the temp directory unique to this process and test
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("bridge-solver-{}-{}.dds", std::process::id(), name))
+    }
+
+    fn small_header(seed: u64) -> DatasetHeader {
+        DatasetHeader {
+            chunk_deals: 2,
+            ..DatasetHeader::new(seed, DealConstraints::default())
+        }
+    }
+
+    fn read_all(path: &Path) -> Vec<DatasetRecord> {
+        DatasetReader::open(path)
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let hands = Hands::from_pbn(
+            "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72",
+        )
+        .unwrap();
+        let mut table = DdTable::default();
+        table.set_index(NORTH, SPADE, 13);
+        table.set_index(WEST, NOTRUMP, 7);
+        let record = DatasetRecord { hands, table };
+        let mut bytes = Vec::new();
+        record.write_to(&mut bytes);
+        assert_eq!(bytes.len(), RECORD_LEN);
+        assert_eq!(DatasetRecord::read_from(&bytes).unwrap(), record);
+    }
+
+    #[test]
+    fn test_deals_are_reproducible() {
+        let mut constraints = DealConstraints::default();
+        constraints.hcp[NORTH] = (15, 17);
+        let header = DatasetHeader::new(7, constraints);
+        let deals = header.chunk_hands(3, 20);
+        assert_eq!(deals, header.chunk_hands(3, 20));
+        assert_ne!(deals, header.chunk_hands(4, 20));
+        for hands in &deals {
+            assert!(hands.is_valid() && hands.num_tricks() == TOTAL_TRICKS);
+            assert!((15..=17).contains(&hands[NORTH].points()));
+        }
+
+        constraints.hcp[SOUTH] = (30, 37);
+        assert!(!constraints.is_satisfiable());
+    }
+
+    #[test]
+    fn test_generate_and_resume() {
+        let path = temp_path("resume");
+        let header = small_header(11);
+        assert_eq!(generate_dataset(&path, &header, 3, 2, |_| {}).unwrap(), 3);
+        let first = read_all(&path);
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[0].table, solve_dd_table(&first[0].hands));
+
+        // Extending remakes the short last chunk; an interrupted write is dropped
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[1, 0, 0]).unwrap();
+        drop(file);
+        let mut reports = Vec::new();
+        let done = generate_dataset(&path, &header, 5, 3, |done| reports.push(done)).unwrap();
+        assert_eq!(done, 5);
+        assert_eq!(reports, vec![4, 5]);
+        let all = read_all(&path);
+        assert_eq!(&all[..3], &first[..]);
+        assert_eq!(all.len(), 5);
+
+        // Another seed does not resume this dataset
+        let error = generate_dataset(&path, &small_header(12), 6, 1, |_| {});
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.unwrap_err().to_string().contains("seed"));
+    }
+
+    #[test]
+    fn test_reader_reports_damage() {
+        let path = temp_path("damage");
+        generate_dataset(&path, &small_header(5), 3, 1, |_| {}).unwrap();
+        let mut bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // Cut into the second chunk: the first still reads
+        let cut = &bytes[..bytes.len() - 5];
+        let results: Vec<_> = DatasetReader::new(cut).unwrap().collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[..2].iter().all(|result| result.is_ok()));
+        assert_eq!(
+            results[2].as_ref().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        let error = DatasetReader::new(&bytes[..])
+            .unwrap()
+            .find_map(Result::err)
+            .unwrap();
+        assert!(error.to_string().contains("checksum"));
+        assert!(DatasetReader::new(&b"not a dataset"[..]).is_err());
+
+        // A chunk size past the limit is refused before any chunk is read
+        bytes[20..24].copy_from_slice(&u32::MAX.to_le_bytes());
+        let error = DatasetReader::new(&bytes[..]).err().unwrap();
+        assert!(error.to_string().contains("invalid dataset header"));
+        let header = DatasetHeader {
+            chunk_deals: MAX_CHUNK_DEALS + 1,
+            ..small_header(5)
+        };
+        let error = generate_dataset(&path, &header, 3, 1, |_| {}).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_rejects_long_solver_version() {
+        let path = temp_path("version");
+        let header = DatasetHeader {
+            solver_version: "x".repeat(256),
+            ..small_header(5)
+        };
+        let error = generate_dataset(&path, &header, 1, 1, |_| {}).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+
+        // The longest version that fits round-trips
+        let header = DatasetHeader {
+            solver_version: "x".repeat(255),
+            ..small_header(5)
+        };
+        let mut bytes = Vec::new();
+        header.write_to(&mut bytes);
+        assert_eq!(DatasetReader::new(&bytes[..]).unwrap().header(), &header);
+    }
+}
@@ -0,0 +1,798 @@
+//! Endgame tables for the last few tricks
+//!
+//! An endgame table holds the exact result of every position with 2 to
+//! `max_tricks` cards per hand, keyed by the relative hands the pattern cache
+//! uses (each suit's remaining cards renumbered from the ace down), the strain
+//! and the seat on lead. Tables are built offline (see the `endgame-gen`
+//! binary) and consulted by the search at trick starts, ahead of the pattern
+//! cache, once installed with `set_endgame_table`; without one the search is
+//! unchanged.
+//!
+//! Two symmetries keep the tables small: the hands are rotated so the seat on
+//! lead is West, and in a trump contract the trump suit is swapped with
+//! spades. Each trick count then needs a notrump table and a spades table,
+//! indexed densely by the suit lengths and by which seat holds each card, with
+//! two bits per position for the tricks of the side on lead.
+//!
+//! A bare result would not be enough for the search, which generalizes what
+//! it learns by the cards whose ranks mattered (the rank winners). So each
+//! position also records, per suit, how many of the top cards the proof of
+//! each bound depends on, worked out during generation the way the search
+//! collects its rank winners: the winner of a trick contested in its suit, one
+//! child where a single move proves the bound and every child where all moves
+//! must. The counts per suit are packed into a byte for each bound.
+//!
+//! # File format
+//!
+//! All integers are little-endian.
+//!
+//! ```text
+//! magic       8 bytes  "BSENDGM\0"
+//! version     u32      ENDGAME_FILE_VERSION
+//! max tricks  u8
+//! length      u64      payload length in bytes
+//! payload              for 2..=max tricks, the notrump then the spades
+//!                      values, as u64 words of 32 positions each, then
+//!                      the notrump then the spades ranks, two bytes
+//!                      (lower bound, upper bound) per position
+//! checksum    u64      FNV-1a hash of the payload
+//! ```
+
+use super::cache::{invalid_data, put_u64, Checksum};
+use super::cards::*;
+use super::hands::Hands;
+use super::parallel::for_each_chunk;
+use super::pattern::RelativeHands;
+use super::types::*;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Version of the endgame file format, bumped on any incompatible change
+pub const ENDGAME_FILE_VERSION: u32 = 1;
+
+/// Most tricks an endgame table can cover (four would need 10^11 positions)
+pub const MAX_ENDGAME_TRICKS: usize = 3;
+
+const MAGIC: &[u8; 8] = b"BSENDGM\0";
+
+/// Positions per u64 word, at two bits each
+const VALUES_PER_WORD: u64 = 32;
+
+/// Bytes of payload read or written at a time
+const IO_CHUNK_BYTES: usize = 1 << 16;
+
+/// Words of values solved as one unit of work when generating
+const FILL_BLOCK_WORDS: usize = 1 << 10;
+
+/// Exact results of all positions with up to `max_tricks` cards per hand
+pub struct EndgameTable {
+    /// Tables for 2, 3, ... tricks
+    levels: Vec<Level>,
+}
+
+/// The tables for one number of tricks
+struct Level {
+    tricks: usize,
+    /// Suit lengths (spades to clubs) in index order
+    compositions: Vec<[u8; NUM_SUITS]>,
+    /// Index into `compositions`, by the lengths of spades, hearts and diamonds
+    composition_index: Vec<u32>,
+    /// Ways to deal cards to the seats, by the cards each still needs
+    completions: Vec<u64>,
+    /// Ways to deal `4 * tricks` cards with `tricks` to each seat
+    deals: u64,
+    /// Tricks of the side on lead: notrump, then spades as trumps
+    values: [Vec<u64>; 2],
+    /// The ranks each result depends on, packed by `encode_ranks`: for the
+    /// lower bound, then the upper bound
+    ranks: [Vec<[u8; 2]>; 2],
+}
+
+/// A result with the ranks it depends on: for each suit, how many of its top
+/// cards the proof that the side on lead takes at least (`lower`) or at most
+/// (`upper`) `tricks` tricks needs in place
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Outcome {
+    pub(crate) tricks: u8,
+    pub(crate) lower: [u8; NUM_SUITS],
+    pub(crate) upper: [u8; NUM_SUITS],
+}
+
+impl Outcome {
+    /// The same result for the other side, out of `tricks` tricks
+    fn flip(self, tricks: u8) -> Self {
+        Outcome {
+            tricks: tricks - self.tricks,
+            lower: self.upper,
+            upper: self.lower,
+        }
+    }
+}
+
+impl Level {
+    fn new(tricks: usize) -> Self {
+        let cards = tricks * NUM_SEATS;
+        let side = cards + 1;
+        let mut compositions = Vec::new();
+        let mut composition_index = vec![u32::MAX; side * side * side];
+        for spades in 0..=cards {
+            for hearts in 0..=cards - spades {
+                for diamonds in 0..=cards - spades - hearts {
+                    let clubs = cards - spades - hearts - diamonds;
+                    composition_index[(spades * side + hearts) * side + diamonds] =
+                        compositions.len() as u32;
+                    compositions.push([spades as u8, hearts as u8, diamonds as u8, clubs as u8]);
+                }
+            }
+        }
+
+        let factorial = |n: usize| (1..=n as u64).product::<u64>();
+        let mut completions = vec![0; (tricks + 1).pow(NUM_SEATS as u32)];
+        for (index, completion) in completions.iter_mut().enumerate() {
+            let counts = Self::counts_of(index, tricks);
+            *completion = factorial(counts.iter().sum())
+                / counts
+                    .iter()
+                    .map(|&count| factorial(count))
+                    .product::<u64>();
+        }
+        let deals = completions[completions.len() - 1];
+
+        let positions = (compositions.len() as u64 * deals) as usize;
+        let words = positions.div_ceil(VALUES_PER_WORD as usize);
+        Level {
+            tricks,
+            compositions,
+            composition_index,
+            completions,
+            deals,
+            values: [vec![0; words], vec![0; words]],
+            ranks: [vec![[0; 2]; positions], vec![[0; 2]; positions]],
+        }
+    }
+
+    /// Bytes of this level in the file payload
+    fn payload_len(&self) -> u64 {
+        let words = self.values[0].len() + self.values[1].len();
+        let ranks = self.ranks[0].len() + self.ranks[1].len();
+        (8 * words + 2 * ranks) as u64
+    }
+
+    fn counts_of(index: usize, tricks: usize) -> [usize; NUM_SEATS] {
+        let mut counts = [0; NUM_SEATS];
+        let mut rest = index;
+        for count in counts.iter_mut().rev() {
+            *count = rest % (tricks + 1);
+            rest /= tricks + 1;
+        }
+        counts
+    }
+
+    /// Ways to deal the cards still needed, `counts[seat]` to each seat
+    #[inline]
+    fn completions(&self, counts: &[usize; NUM_SEATS]) -> u64 {
+        let side = self.tricks + 1;
+        self.completions[((counts[0] * side + counts[1]) * side + counts[2]) * side + counts[3]]
+    }
+
+    /// Index of relative `hands` with `leader` on lead, as seen with the
+    /// leader rotated to West and the trump suit (if any) swapped with spades
+    fn index(&self, hands: &Hands, trump: usize, leader: SeatIndex) -> u64 {
+        let mut lengths = [0; NUM_SUITS];
+        let mut needed = [self.tricks; NUM_SEATS];
+        let mut deal = 0;
+        for (table_suit, length) in lengths.iter_mut().enumerate() {
+            let suit = table_suit_of(table_suit, trump);
+            let holdings: [u64; NUM_SEATS] = std::array::from_fn(|seat| {
+                hands[(seat + leader) % NUM_SEATS].suit(suit).value() >> (suit * NUM_RANKS)
+            });
+            let mut cards = holdings.iter().fold(0, |all, holding| all | holding);
+            *length = cards.count_ones() as usize;
+            // Highest card first: relative cards are packed from the ace down
+            while cards != 0 {
+                let card = cards & cards.wrapping_neg();
+                let owner = holdings
+                    .iter()
+                    .position(|&holding| holding & card != 0)
+                    .unwrap();
+                for seat in 0..owner {
+                    if needed[seat] > 0 {
+                        needed[seat] -= 1;
+                        deal += self.completions(&needed);
+                        needed[seat] += 1;
+                    }
+                }
+                needed[owner] -= 1;
+                cards &= cards - 1;
+            }
+        }
+        let side = self.tricks * NUM_SEATS + 1;
+        let composition =
+            self.composition_index[(lengths[0] * side + lengths[1]) * side + lengths[2]];
+        composition as u64 * self.deals + deal
+    }
+
+    /// The relative hands at `index`, as seen from the table: West on lead
+    /// and spades as the trump suit (if any)
+    fn hands_at(&self, index: u64) -> Hands {
+        let lengths = self.compositions[(index / self.deals) as usize];
+        let mut deal = index % self.deals;
+        let mut needed = [self.tricks; NUM_SEATS];
+        let mut hands = Hands::new();
+        for (suit, &length) in lengths.iter().enumerate() {
+            for rank in 0..length as usize {
+                let mut owner = 0;
+                loop {
+                    if needed[owner] > 0 {
+                        needed[owner] -= 1;
+                        let ways = self.completions(&needed);
+                        if deal < ways {
+                            break;
+                        }
+                        deal -= ways;
+                        needed[owner] += 1;
+                    }
+                    owner += 1;
+                }
+                hands[owner].add_index(suit * NUM_RANKS + rank);
+            }
+        }
+        hands
+    }
+
+    #[inline]
+    fn value(&self, table: usize, index: u64) -> u8 {
+        let word = self.values[table][(index / VALUES_PER_WORD) as usize];
+        ((word >> (2 * (index % VALUES_PER_WORD))) & 3) as u8
+    }
+
+    fn outcome(&self, table: usize, index: u64) -> Outcome {
+        let lengths = &self.compositions[(index / self.deals) as usize];
+        let [lower, upper] = self.ranks[table][index as usize];
+        Outcome {
+            tricks: self.value(table, index),
+            lower: decode_ranks(lower, lengths),
+            upper: decode_ranks(upper, lengths),
+        }
+    }
+}
+
+/// Pack top-card counts (each at most its suit's length) into a byte: the
+/// lengths of a position with 3 cards per hand allow at most 4^4 values
+fn encode_ranks(ranks: &[u8; NUM_SUITS], lengths: &[u8; NUM_SUITS]) -> u8 {
+    let mut code = 0;
+    for suit in (0..NUM_SUITS).rev() {
+        code = code * (lengths[suit] as usize + 1) + ranks[suit] as usize;
+    }
+    code as u8
+}
+
+fn decode_ranks(mut code: u8, lengths: &[u8; NUM_SUITS]) -> [u8; NUM_SUITS] {
+    std::array::from_fn(|suit| {
+        let radix = lengths[suit] + 1;
+        let count = code % radix;
+        code /= radix;
+        count
+    })
+}
+
+/// Ranks either of two proofs depends on
+fn union_ranks(a: [u8; NUM_SUITS], b: [u8; NUM_SUITS]) -> [u8; NUM_SUITS] {
+    std::array::from_fn(|suit| a[suit].max(b[suit]))
+}
+
+/// Top-card counts among the `remaining` cards after a trick, as counts among
+/// all the cards before it
+fn restore_ranks(ranks: &[u8; NUM_SUITS], remaining: Cards) -> [u8; NUM_SUITS] {
+    std::array::from_fn(|suit| {
+        let mut cards = remaining.suit(suit);
+        if ranks[suit] == 0 {
+            return 0;
+        }
+        for _ in 1..ranks[suit] {
+            cards.remove_index(cards.top());
+        }
+        (cards.top() % NUM_RANKS + 1) as u8
+    })
+}
+
+/// The rank of a complete trick's winning card counts if another card of its
+/// suit was played to the trick
+fn trick_ranks(trick: &[usize; NUM_SEATS], winner: usize) -> [u8; NUM_SUITS] {
+    let card = trick[winner];
+    let suit = suit_of(card);
+    let mut ranks = [0; NUM_SUITS];
+    if trick
+        .iter()
+        .filter(|&&other| suit_of(other) == suit)
+        .count()
+        > 1
+    {
+        ranks[suit] = (card % NUM_RANKS + 1) as u8;
+    }
+    ranks
+}
+
+/// The actual suit shown as `table_suit` in the tables for `trump`
+#[inline]
+fn table_suit_of(table_suit: SuitIndex, trump: usize) -> SuitIndex {
+    if trump == NOTRUMP || (table_suit != SPADE && table_suit != trump) {
+        table_suit
+    } else if table_suit == SPADE {
+        trump
+    } else {
+        SPADE
+    }
+}
+
+impl EndgameTable {
+    /// Build the tables for 2 to `max_tricks` tricks (at most
+    /// [`MAX_ENDGAME_TRICKS`]), using every available core
+    ///
+    /// Three tricks means 336 million positions: a quarter of an hour per
+    /// core and 760 MB.
+    pub fn generate(max_tricks: usize) -> Self {
+        assert!(
+            (2..=MAX_ENDGAME_TRICKS).contains(&max_tricks),
+            "endgame tables cover 2 to {} tricks",
+            MAX_ENDGAME_TRICKS
+        );
+        let mut table = EndgameTable { levels: Vec::new() };
+        for tricks in 2..=max_tricks {
+            let mut level = Level::new(tricks);
+            for (table_index, trump) in [NOTRUMP, SPADE].into_iter().enumerate() {
+                let mut values = std::mem::take(&mut level.values[table_index]);
+                let mut ranks = std::mem::take(&mut level.ranks[table_index]);
+                table.fill(&level, trump, &mut values, &mut ranks);
+                level.values[table_index] = values;
+                level.ranks[table_index] = ranks;
+            }
+            table.levels.push(level);
+        }
+        table
+    }
+
+    /// Solve every position of `level` in parallel, using the smaller levels
+    fn fill(&self, level: &Level, trump: usize, values: &mut [u64], ranks: &mut [[u8; 2]]) {
+        let per_word = VALUES_PER_WORD as usize;
+        // Blocks of words with the ranks of their positions, so each thread
+        // gets whole words
+        let mut blocks: Vec<_> = values
+            .chunks_mut(FILL_BLOCK_WORDS)
+            .zip(ranks.chunks_mut(FILL_BLOCK_WORDS * per_word))
+            .collect();
+        for_each_chunk(&mut blocks, None, |first_block, blocks| {
+            for (i, (words, ranks)) in blocks.iter_mut().enumerate() {
+                let first = (first_block + i) * FILL_BLOCK_WORDS * per_word;
+                for (offset, ranks) in ranks.iter_mut().enumerate() {
+                    let index = (first + offset) as u64;
+                    let mut hands = level.hands_at(index);
+                    let outcome = self.play_trick(&mut hands, trump, &mut [0; NUM_SEATS], 0);
+                    words[offset / per_word] |=
+                        (outcome.tricks as u64) << (2 * (offset % per_word));
+                    let lengths = &level.compositions[(index / level.deals) as usize];
+                    *ranks = [
+                        encode_ranks(&outcome.lower, lengths),
+                        encode_ranks(&outcome.upper, lengths),
+                    ];
+                }
+            }
+        });
+    }
+
+    /// Tricks of the side on lead (West) with best play, with `played` cards
+    /// already in the trick
+    fn play_trick(
+        &self,
+        hands: &mut Hands,
+        trump: usize,
+        trick: &mut [usize; NUM_SEATS],
+        played: usize,
+    ) -> Outcome {
+        if played == NUM_SEATS {
+            let winner = trick_winner(trick, trump);
+            let remaining = hands.all_cards();
+            let mut relative = RelativeHands::default();
+            relative.compute(hands, remaining);
+            let next = self.leader_outcome(&relative.hands, trump, winner);
+            let mut outcome = Outcome {
+                tricks: next.tricks,
+                lower: restore_ranks(&next.lower, remaining),
+                upper: restore_ranks(&next.upper, remaining),
+            };
+            // West's side is West and East
+            if winner & 1 == 0 {
+                outcome.tricks += 1;
+            } else {
+                outcome = outcome.flip(hands.num_tricks() as u8);
+            }
+            let ranks = trick_ranks(trick, winner);
+            outcome.lower = union_ranks(outcome.lower, ranks);
+            outcome.upper = union_ranks(outcome.upper, ranks);
+            return outcome;
+        }
+
+        let seat = played;
+        let hand = hands[seat];
+        let playable = if played == 0 || hand.suit(suit_of(trick[0])).is_empty() {
+            hand
+        } else {
+            hand.suit(suit_of(trick[0]))
+        };
+        // The side to play proves its bound with its best move (the one
+        // needing fewest ranks among equals), the other bound with every move
+        let maximize = seat & 1 == 0;
+        let proof = |outcome: &Outcome| {
+            if maximize {
+                outcome.lower
+            } else {
+                outcome.upper
+            }
+        };
+        let weight = |ranks: [u8; NUM_SUITS]| ranks.iter().sum::<u8>();
+        let mut best: Option<Outcome> = None;
+        let mut every_move = [0; NUM_SUITS];
+        for card in playable.iter() {
+            // Touching cards of one hand are equivalent: play the top one
+            if !card.is_multiple_of(NUM_RANKS) && hand.have_index(card - 1) {
+                continue;
+            }
+            hands[seat].remove_index(card);
+            trick[seat] = card;
+            let outcome = self.play_trick(hands, trump, trick, played + 1);
+            hands[seat].add_index(card);
+            every_move = union_ranks(
+                every_move,
+                if maximize {
+                    outcome.upper
+                } else {
+                    outcome.lower
+                },
+            );
+            let better = match &best {
+                None => true,
+                Some(best) if outcome.tricks != best.tricks => {
+                    (outcome.tricks > best.tricks) == maximize
+                }
+                Some(best) => weight(proof(&outcome)) < weight(proof(best)),
+            };
+            if better {
+                best = Some(outcome);
+            }
+        }
+        let best = best.unwrap_or_default();
+        if maximize {
+            Outcome {
+                upper: every_move,
+                ..best
+            }
+        } else {
+            Outcome {
+                lower: every_move,
+                ..best
+            }
+        }
+    }
+
+    /// Outcome for `leader`'s side in relative `hands`, from the tables (or
+    /// by playing out a single trick)
+    fn leader_outcome(&self, hands: &Hands, trump: usize, leader: SeatIndex) -> Outcome {
+        match hands.num_tricks() {
+            0 => Outcome::default(),
+            1 => {
+                let trick = std::array::from_fn(|i| hands[(leader + i) % NUM_SEATS].top());
+                let winner = trick_winner(&trick, trump);
+                let ranks = trick_ranks(&trick, winner);
+                Outcome {
+                    tricks: (winner & 1 == 0) as u8,
+                    lower: ranks,
+                    upper: ranks,
+                }
+            }
+            tricks => {
+                let level = &self.levels[tricks - 2];
+                let table = (trump != NOTRUMP) as usize;
+                level.outcome(table, level.index(hands, trump, leader))
+            }
+        }
+    }
+
+    /// Most cards per hand covered
+    pub fn max_tricks(&self) -> usize {
+        self.levels.len() + 1
+    }
+
+    /// NS tricks of the position with `relative_hands` (as computed by
+    /// `RelativeHands`) and `leader` to lead, if the table covers it
+    ///
+    /// None unless the hands are valid: all the same size, no card twice.
+    pub fn ns_tricks(&self, relative_hands: &Hands, trump: usize, leader: SeatIndex) -> Option<u8> {
+        self.probe(relative_hands, trump, leader)
+            .map(|outcome| outcome.tricks)
+    }
+
+    /// Like [`ns_tricks`](Self::ns_tricks), with the ranks the bounds on NS
+    /// tricks depend on, by actual suit
+    pub(crate) fn probe(
+        &self,
+        relative_hands: &Hands,
+        trump: usize,
+        leader: SeatIndex,
+    ) -> Option<Outcome> {
+        let tricks = relative_hands.num_tricks();
+        if !(2..=self.max_tricks()).contains(&tricks) || !relative_hands.is_valid() {
+            return None;
+        }
+        let mut outcome = self.leader_outcome(relative_hands, trump, leader);
+        if !is_ns(leader) {
+            outcome = outcome.flip(tricks as u8);
+        }
+        let by_suit =
+            |ranks: [u8; NUM_SUITS]| std::array::from_fn(|suit| ranks[table_suit_of(suit, trump)]);
+        Some(Outcome {
+            tricks: outcome.tricks,
+            lower: by_suit(outcome.lower),
+            upper: by_suit(outcome.upper),
+        })
+    }
+
+    /// NS tricks of actual `hands` with `leader` to lead, if the table
+    /// covers them and they are valid
+    pub fn solve(&self, hands: &Hands, trump: usize, leader: SeatIndex) -> Option<u8> {
+        let mut relative = RelativeHands::default();
+        relative.compute(hands, hands.all_cards());
+        self.ns_tricks(&relative.hands, trump, leader)
+    }
+
+    /// Write the table in the endgame file format
+    ///
+    /// The payload is streamed from the tables in pieces, never copied whole.
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&ENDGAME_FILE_VERSION.to_le_bytes())?;
+        writer.write_all(&[self.max_tricks() as u8])?;
+        let length: u64 = self.levels.iter().map(Level::payload_len).sum();
+        writer.write_all(&length.to_le_bytes())?;
+
+        let mut hash = Checksum::new();
+        let mut buffer = Vec::with_capacity(IO_CHUNK_BYTES);
+        let mut write_chunk = |buffer: &mut Vec<u8>| {
+            hash.update(buffer);
+            let result = writer.write_all(buffer);
+            buffer.clear();
+            result
+        };
+        for level in &self.levels {
+            for values in &level.values {
+                for words in values.chunks(IO_CHUNK_BYTES / 8) {
+                    for &word in words {
+                        put_u64(&mut buffer, word);
+                    }
+                    write_chunk(&mut buffer)?;
+                }
+            }
+            for ranks in &level.ranks {
+                for pairs in ranks.chunks(IO_CHUNK_BYTES / 2) {
+                    buffer.extend(pairs.iter().flatten());
+                    write_chunk(&mut buffer)?;
+                }
+            }
+        }
+        writer.write_all(&hash.value().to_le_bytes())
+    }
+
+    /// Read a table written by [`write_to`](Self::write_to)
+    ///
+    /// The payload is read straight into the tables. Fails with
+    /// `InvalidData` if the data is not an endgame file, was written by
+    /// another format version, or is truncated or corrupt.
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let mut header = [0u8; 21];
+        reader.read_exact(&mut header)?;
+        if &header[..8] != MAGIC {
+            return Err(invalid_data("not an endgame file"));
+        }
+        let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        if version != ENDGAME_FILE_VERSION {
+            return Err(invalid_data(format!(
+                "endgame file version {} (expected {})",
+                version, ENDGAME_FILE_VERSION
+            )));
+        }
+        let max_tricks = header[12] as usize;
+        if !(2..=MAX_ENDGAME_TRICKS).contains(&max_tricks) {
+            return Err(invalid_data(format!(
+                "endgame file covers {} tricks",
+                max_tricks
+            )));
+        }
+        let mut levels: Vec<Level> = (2..=max_tricks).map(Level::new).collect();
+        let expected: u64 = levels.iter().map(Level::payload_len).sum();
+        let length = u64::from_le_bytes(header[13..21].try_into().unwrap());
+        if length != expected {
+            return Err(invalid_data("endgame file has the wrong length"));
+        }
+
+        let mut hash = Checksum::new();
+        let mut buffer = vec![0u8; IO_CHUNK_BYTES];
+        let mut read_chunk = |bytes: &mut [u8]| -> io::Result<()> {
+            reader.read_exact(bytes).map_err(|e| match e.kind() {
+                io::ErrorKind::UnexpectedEof => invalid_data("endgame file is truncated"),
+                _ => e,
+            })?;
+            hash.update(bytes);
+            Ok(())
+        };
+        for level in &mut levels {
+            for values in &mut level.values {
+                for words in values.chunks_mut(IO_CHUNK_BYTES / 8) {
+                    let bytes = &mut buffer[..8 * words.len()];
+                    read_chunk(bytes)?;
+                    for (word, bytes) in words.iter_mut().zip(bytes.chunks_exact(8)) {
+                        *word = u64::from_le_bytes(bytes.try_into().unwrap());
+                    }
+                }
+            }
+            for ranks in &mut level.ranks {
+                for pairs in ranks.chunks_mut(IO_CHUNK_BYTES / 2) {
+                    let bytes = &mut buffer[..2 * pairs.len()];
+                    read_chunk(bytes)?;
+                    for (pair, bytes) in pairs.iter_mut().zip(bytes.chunks_exact(2)) {
+                        *pair = [bytes[0], bytes[1]];
+                    }
+                }
+            }
+        }
+        let mut stored_checksum = [0u8; 8];
+        if reader.read_exact(&mut stored_checksum).is_err() {
+            return Err(invalid_data("endgame file is truncated"));
+        }
+        if u64::from_le_bytes(stored_checksum) != hash.value() {
+            return Err(invalid_data("endgame file checksum mismatch"));
+        }
+        Ok(EndgameTable { levels })
+    }
+
+    /// Save the table to a file
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Load a table saved by [`save`](Self::save)
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+/// The seat (index into `trick`) winning a complete trick
+fn trick_winner(trick: &[usize; NUM_SEATS], trump: usize) -> usize {
+    let lead_suit = suit_of(trick[0]);
+    let mut winner = 0;
+    for (seat, &card) in trick.iter().enumerate().skip(1) {
+        let best = trick[winner];
+        let wins = if suit_of(card) == suit_of(best) {
+            higher_rank(card, best)
+        } else {
+            suit_of(card) == trump || (suit_of(best) != trump && suit_of(card) == lead_suit)
+        };
+        if wins {
+            winner = seat;
+        }
+    }
+    winner
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reference::{random_hands, Random};
+    use crate::search::Search;
+    use crate::{reference_solve, CutoffCache, PatternCache};
+    use std::sync::{Arc, OnceLock};
+
+    /// The two-trick table, built once for all tests
+    fn table() -> Arc<EndgameTable> {
+        static TABLE: OnceLock<Arc<EndgameTable>> = OnceLock::new();
+        TABLE
+            .get_or_init(|| Arc::new(EndgameTable::generate(2)))
+            .clone()
+    }
+
+    #[test]
+    fn test_index_round_trip() {
+        let level = Level::new(2);
+        assert_eq!(level.deals, 2520);
+        assert_eq!(level.ranks[0].len(), 165 * 2520);
+        for index in (0..level.ranks[0].len() as u64).step_by(997) {
+            let hands = level.hands_at(index);
+            assert!(hands.is_valid());
+            assert_eq!(level.index(&hands, NOTRUMP, WEST), index);
+            assert_eq!(level.index(&hands, SPADE, WEST), index);
+        }
+    }
+
+    #[test]
+    fn test_matches_reference() {
+        let table = table();
+        let mut random = Random(0x1234_5678_9abc_def0);
+        for _ in 0..200 {
+            let hands = random_hands(&mut random, 2);
+            for trump in 0..NUM_STRAINS {
+                for leader in 0..NUM_SEATS {
+                    assert_eq!(
+                        table.solve(&hands, trump, leader),
+                        Some(reference_solve(hands, trump, leader)),
+                        "{} trump {} leader {}",
+                        hands.to_pbn(),
+                        strain_name(trump),
+                        seat_letter(leader)
+                    );
+                }
+            }
+        }
+
+        // Out of range
+        let hands = Hands::from_pbn("N:A... K... Q... J...").unwrap();
+        assert_eq!(table.solve(&hands, NOTRUMP, WEST), None);
+
+        // Unequal hands: West holds two cards, North three
+        let hands = Hands::from_pbn("N:AKQ... J2... T9... 87...").unwrap();
+        assert_eq!(table.solve(&hands, NOTRUMP, WEST), None);
+        let mut relative = RelativeHands::default();
+        relative.compute(&hands, hands.all_cards());
+        assert_eq!(table.ns_tricks(&relative.hands, NOTRUMP, WEST), None);
+    }
+
+    #[test]
+    fn test_search_with_table() {
+        let table = table();
+        let mut hits = 0;
+        let mut random = Random(0xe4d);
+        for _ in 0..40 {
+            let hands = random_hands(&mut random, 4);
+            for trump in 0..NUM_STRAINS {
+                let leader = WEST;
+                let mut cutoff = CutoffCache::new(8);
+                let mut pattern = PatternCache::new(8);
+                let mut search_hands = hands;
+                let mut search =
+                    Search::new(&mut search_hands, trump, leader, &mut cutoff, &mut pattern)
+                        .with_endgame_table(Some(table.clone()));
+                // The tricks NS can make are the betas they reach
+                let ns_tricks = (1..=4).filter(|&beta| search.search(beta) >= beta as u8);
+                assert_eq!(
+                    ns_tricks.count() as u8,
+                    reference_solve(hands, trump, leader),
+                    "{} trump {}",
+                    hands.to_pbn(),
+                    strain_name(trump)
+                );
+                hits += search.stats().endgame_hits;
+            }
+        }
+        assert!(hits > 0);
+    }
+
+    #[test]
+    fn test_read_write() {
+        let table = table();
+        let mut bytes = Vec::new();
+        table.write_to(&mut bytes).unwrap();
+        let read = EndgameTable::read_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read.max_tricks(), 2);
+        assert_eq!(read.levels[0].values, table.levels[0].values);
+        assert_eq!(read.levels[0].ranks, table.levels[0].ranks);
+
+        let last = bytes.len() - 9;
+        bytes[last] ^= 1;
+        let error = EndgameTable::read_from(&mut bytes.as_slice())
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let error = EndgameTable::read_from(&mut &bytes[..100]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
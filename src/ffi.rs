@@ -0,0 +1,812 @@
+//! DDS-compatible C interface (requires the `dds` feature)
+//!
+//! Exposes the entry points of Bo Haglund's DDS that most bridge programs use,
+//! with the same struct layouts and return codes (see `include/dll.h`):
+//! `SolveBoard`, `CalcDDtable`, `CalcAllTables` and `Par`.
+//!
+//! DDS numbers hands North=0, East=1, South=2, West=3 and holds cards as rank
+//! bitmasks with bit 2 for the deuce up to bit 14 for the ace. Suits and
+//! strains are in the same order as in this crate (spades first, NT last).
+//!
+//! Differences from DDS:
+//! - `mode` and `threadIndex` are validated but otherwise ignored; every call
+//!   uses fresh caches, so all entry points are thread-safe.
+//! - `futureTricks.nodes` is always 0.
+//! - `SolveBoard` with `solutions` 3 ignores `target` and scores every card.
+
+#![allow(non_snake_case, non_camel_case_types)]
+
+use super::analysis::{analyze_plays, CardAnalysis};
+use super::bridge_solver::{new_caches, PartialTrick};
+use super::cards::*;
+use super::hands::Hands;
+use super::par::{par, ParContract};
+use super::parallel::parallel_map;
+use super::scoring::Vulnerability;
+use super::table::{solve_dd_table, DdTable};
+use super::types::*;
+use std::os::raw::{c_char, c_int, c_uint};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+pub const DDS_HANDS: usize = 4;
+pub const DDS_SUITS: usize = 4;
+pub const DDS_STRAINS: usize = 5;
+pub const MAXNOOFTABLES: usize = 40;
+
+pub const RETURN_NO_FAULT: c_int = 1;
+pub const RETURN_UNKNOWN_FAULT: c_int = -1;
+pub const RETURN_ZERO_CARDS: c_int = -2;
+pub const RETURN_TARGET_TOO_HIGH: c_int = -3;
+pub const RETURN_DUPLICATE_CARDS: c_int = -4;
+pub const RETURN_TARGET_WRONG_LO: c_int = -5;
+pub const RETURN_TARGET_WRONG_HI: c_int = -7;
+pub const RETURN_SOLNS_WRONG_LO: c_int = -8;
+pub const RETURN_SOLNS_WRONG_HI: c_int = -9;
+pub const RETURN_TOO_MANY_CARDS: c_int = -10;
+pub const RETURN_SUIT_OR_RANK: c_int = -12;
+pub const RETURN_PLAYED_CARD: c_int = -13;
+pub const RETURN_CARD_COUNT: c_int = -14;
+pub const RETURN_THREAD_INDEX: c_int = -15;
+pub const RETURN_MODE_WRONG_LO: c_int = -16;
+pub const RETURN_MODE_WRONG_HI: c_int = -17;
+pub const RETURN_TRUMP_WRONG: c_int = -18;
+pub const RETURN_FIRST_WRONG: c_int = -19;
+pub const RETURN_NO_SUIT: c_int = -201;
+pub const RETURN_TOO_MANY_TABLES: c_int = -202;
+
+/// Valid rank bits of a DDS holding (deuce to ace)
+const HOLDING_MASK: c_uint = 0x7ffc;
+
+/// A position to solve, possibly with cards already played to the trick
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct deal {
+    pub trump: c_int,
+    pub first: c_int,
+    pub currentTrickSuit: [c_int; 3],
+    pub currentTrickRank: [c_int; 3],
+    pub remainCards: [[c_uint; DDS_SUITS]; DDS_HANDS],
+}
+
+/// Cards and scores returned by `SolveBoard`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct futureTricks {
+    pub nodes: c_int,
+    pub cards: c_int,
+    pub suit: [c_int; 13],
+    pub rank: [c_int; 13],
+    pub equals: [c_int; 13],
+    pub score: [c_int; 13],
+}
+
+/// A full deal for `CalcDDtable`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ddTableDeal {
+    pub cards: [[c_uint; DDS_SUITS]; DDS_HANDS],
+}
+
+/// Deals for `CalcAllTables`
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct ddTableDeals {
+    pub noOfTables: c_int,
+    pub deals: [ddTableDeal; MAXNOOFTABLES * DDS_STRAINS],
+}
+
+/// Declarer tricks indexed `[strain][hand]`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ddTableResults {
+    pub resTable: [[c_int; DDS_HANDS]; DDS_STRAINS],
+}
+
+/// Tables computed by `CalcAllTables`
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct ddTablesRes {
+    pub noOfBoards: c_int,
+    pub results: [ddTableResults; MAXNOOFTABLES * DDS_STRAINS],
+}
+
+/// Par scores and contracts, from NS's (index 0) and EW's (index 1) side
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct parResults {
+    pub parScore: [[c_char; 16]; 2],
+    pub parContractsString: [[c_char; 128]; 2],
+}
+
+/// Par results for each table of `CalcAllTables`
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct allParResults {
+    pub presults: [parResults; MAXNOOFTABLES],
+}
+
+/// Convert a DDS hand number to a seat
+fn seat_of_hand(hand: usize) -> SeatIndex {
+    (hand + 1) % NUM_SEATS
+}
+
+/// Convert a DDS suit and rank (2-14) to a card
+fn dds_card(suit: c_int, rank: c_int) -> Option<usize> {
+    if !(0..NUM_SUITS as c_int).contains(&suit) || !(2..=14).contains(&rank) {
+        return None;
+    }
+    Some(card_of(suit as usize, rank as usize - 2))
+}
+
+/// Convert DDS holdings to hands, rejecting stray bits and duplicate cards
+fn hands_from_holdings(holdings: &[[c_uint; DDS_SUITS]; DDS_HANDS]) -> Result<Hands, c_int> {
+    let mut hands = Hands::new();
+    for (hand, suits) in holdings.iter().enumerate() {
+        let seat = seat_of_hand(hand);
+        for (suit, &holding) in suits.iter().enumerate() {
+            if holding & !HOLDING_MASK != 0 {
+                return Err(RETURN_SUIT_OR_RANK);
+            }
+            for rank in 2..=14 {
+                if holding & (1 << rank) == 0 {
+                    continue;
+                }
+                let card = card_of(suit, rank as usize - 2);
+                if hands.all_cards().have_index(card) {
+                    return Err(RETURN_DUPLICATE_CARDS);
+                }
+                hands[seat].add_index(card);
+            }
+        }
+    }
+    Ok(hands)
+}
+
+/// Check that a table deal has equal, non-empty hands
+fn table_hands(table_deal: &ddTableDeal) -> Result<Hands, c_int> {
+    let hands = hands_from_holdings(&table_deal.cards)?;
+    let num_tricks = hands.num_tricks();
+    if num_tricks == 0 {
+        return Err(RETURN_ZERO_CARDS);
+    }
+    if (0..NUM_SEATS).any(|seat| hands[seat].size() != num_tricks) {
+        return Err(RETURN_CARD_COUNT);
+    }
+    Ok(hands)
+}
+
+fn table_results(table: &DdTable) -> ddTableResults {
+    let mut results = ddTableResults::default();
+    for strain in 0..DDS_STRAINS {
+        for hand in 0..DDS_HANDS {
+            results.resTable[strain][hand] = table.get_index(seat_of_hand(hand), strain) as c_int;
+        }
+    }
+    results
+}
+
+/// Run an entry point, turning panics into `RETURN_UNKNOWN_FAULT`
+fn guarded(f: impl FnOnce() -> Result<(), c_int>) -> c_int {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => RETURN_NO_FAULT,
+        Ok(Err(code)) => code,
+        Err(_) => RETURN_UNKNOWN_FAULT,
+    }
+}
+
+/// Solve one position, returning the best card(s) for the hand to play
+///
+/// - `target`: -1 for the maximum, or the number of tricks to reach (0-13)
+/// - `solutions`: 1 for one best card, 2 for all best cards, 3 for all cards
+/// - `mode`: 0-2, accepted for compatibility
+///
+/// Scores are tricks for the side to play, from the current trick on. With a
+/// target and `solutions` 1 or 2, cards reaching it are scored as the target;
+/// if none does, one card is returned with score -1.
+///
+/// # Safety
+///
+/// `futp` must be null or point to a writable `futureTricks`.
+#[no_mangle]
+pub unsafe extern "system" fn SolveBoard(
+    dl: deal,
+    target: c_int,
+    solutions: c_int,
+    mode: c_int,
+    futp: *mut futureTricks,
+    threadIndex: c_int,
+) -> c_int {
+    if futp.is_null() {
+        return RETURN_UNKNOWN_FAULT;
+    }
+    guarded(|| {
+        let future = solve_board(&dl, target, solutions, mode, threadIndex)?;
+        // SAFETY: checked non-null above; the caller guarantees it is writable
+        unsafe { *futp = future };
+        Ok(())
+    })
+}
+
+fn solve_board(
+    dl: &deal,
+    target: c_int,
+    solutions: c_int,
+    mode: c_int,
+    thread_index: c_int,
+) -> Result<futureTricks, c_int> {
+    match target {
+        t if t < -1 => return Err(RETURN_TARGET_WRONG_LO),
+        t if t > TOTAL_TRICKS as c_int => return Err(RETURN_TARGET_WRONG_HI),
+        _ => {}
+    }
+    match solutions {
+        s if s < 1 => return Err(RETURN_SOLNS_WRONG_LO),
+        s if s > 3 => return Err(RETURN_SOLNS_WRONG_HI),
+        _ => {}
+    }
+    match mode {
+        m if m < 0 => return Err(RETURN_MODE_WRONG_LO),
+        m if m > 2 => return Err(RETURN_MODE_WRONG_HI),
+        _ => {}
+    }
+    if thread_index < 0 {
+        return Err(RETURN_THREAD_INDEX);
+    }
+    if !(0..=NOTRUMP as c_int).contains(&dl.trump) {
+        return Err(RETURN_TRUMP_WRONG);
+    }
+    if !(0..NUM_SEATS as c_int).contains(&dl.first) {
+        return Err(RETURN_FIRST_WRONG);
+    }
+    let trump = dl.trump as usize;
+    let leader = seat_of_hand(dl.first as usize);
+
+    let hands = hands_from_holdings(&dl.remainCards)?;
+    let mut trick = PartialTrick::new();
+    for i in 0..3 {
+        if dl.currentTrickRank[i] == 0 {
+            break;
+        }
+        let card =
+            dds_card(dl.currentTrickSuit[i], dl.currentTrickRank[i]).ok_or(RETURN_SUIT_OR_RANK)?;
+        if hands.all_cards().have_index(card) || trick.plays.iter().any(|p| p.card == card) {
+            return Err(RETURN_PLAYED_CARD);
+        }
+        trick.add_index(card, trick.next_to_play().unwrap_or(leader));
+    }
+
+    // Hands yet to play to the trick hold one card more than those that have played
+    let seat_to_play = trick.next_to_play().unwrap_or(leader);
+    let num_tricks = hands[seat_to_play].size();
+    if num_tricks == 0 {
+        return Err(RETURN_ZERO_CARDS);
+    }
+    if num_tricks > TOTAL_TRICKS {
+        return Err(RETURN_TOO_MANY_CARDS);
+    }
+    let played: Vec<SeatIndex> = trick.plays.iter().map(|p| p.seat).collect();
+    for seat in 0..NUM_SEATS {
+        let expected = num_tricks - played.contains(&seat) as usize;
+        if hands[seat].size() != expected {
+            return Err(RETURN_CARD_COUNT);
+        }
+    }
+    if target > num_tricks as c_int {
+        return Err(RETURN_TARGET_TOO_HIGH);
+    }
+
+    let (mut cutoff_cache, mut pattern_cache) = new_caches();
+    let analysis = analyze_plays(
+        hands,
+        trump,
+        leader,
+        &trick,
+        &mut cutoff_cache,
+        &mut pattern_cache,
+    )
+    .map_err(|_| RETURN_UNKNOWN_FAULT)?;
+
+    let mut groups = equivalent_groups(&hands, &trick, seat_to_play, &analysis);
+    for group in &mut groups {
+        if !is_ns(seat_to_play) {
+            group.score = num_tricks as c_int - group.score;
+        }
+    }
+    // Best first; ties keep card order (spades first, high to low)
+    groups.sort_by_key(|group| std::cmp::Reverse(group.score));
+
+    let best = groups[0].score;
+    let selected: Vec<CardGroup> = match (solutions, target) {
+        (3, _) => groups,
+        (_, -1) => groups.into_iter().filter(|g| g.score == best).collect(),
+        (_, target) if best < target => vec![CardGroup {
+            score: -1,
+            ..groups[0]
+        }],
+        (_, target) => groups
+            .into_iter()
+            .filter(|g| g.score >= target)
+            .map(|g| CardGroup { score: target, ..g })
+            .collect(),
+    };
+    let count = if solutions == 1 { 1 } else { selected.len() };
+
+    let mut future = futureTricks::default();
+    for (i, group) in selected.iter().take(count).enumerate() {
+        future.suit[i] = suit_of(group.card) as c_int;
+        future.rank[i] = rank_of(group.card) as c_int + 2;
+        future.equals[i] = group.equals as c_int;
+        future.score[i] = group.score;
+    }
+    future.cards = count as c_int;
+    Ok(future)
+}
+
+/// A card with the lower cards it is equivalent to
+#[derive(Clone, Copy)]
+struct CardGroup {
+    card: usize,
+    /// DDS rank bits of the equivalent lower cards
+    equals: c_uint,
+    score: c_int,
+}
+
+/// Merge cards of one suit that are adjacent among the cards still in play
+///
+/// Such cards always take the same number of tricks; DDS reports the highest
+/// of them with the rest in `equals`. Cards on the table in `trick` still
+/// separate the cards around them. `analysis` is in card order.
+fn equivalent_groups(
+    hands: &Hands,
+    trick: &PartialTrick,
+    seat: SeatIndex,
+    analysis: &[CardAnalysis],
+) -> Vec<CardGroup> {
+    let mut others = hands.all_cards().different(hands[seat]);
+    for play in &trick.plays {
+        others.add_index(play.card);
+    }
+    let mut groups: Vec<CardGroup> = Vec::new();
+    let mut previous: Option<usize> = None;
+    for result in analysis {
+        let adjacent = previous.is_some_and(|prev| {
+            suit_of(prev) == suit_of(result.card)
+                && (prev + 1..result.card).all(|c| !others.have_index(c))
+        }) && groups.last().unwrap().score == result.ns_tricks as c_int;
+        if adjacent {
+            groups.last_mut().unwrap().equals |= 1 << (rank_of(result.card) + 2);
+        } else {
+            groups.push(CardGroup {
+                card: result.card,
+                equals: 0,
+                score: result.ns_tricks as c_int,
+            });
+        }
+        previous = Some(result.card);
+    }
+    groups
+}
+
+/// Solve the double-dummy table of a deal (20 solves)
+///
+/// # Safety
+///
+/// `tablep` must be null or point to a writable `ddTableResults`.
+#[no_mangle]
+pub unsafe extern "system" fn CalcDDtable(
+    tableDeal: ddTableDeal,
+    tablep: *mut ddTableResults,
+) -> c_int {
+    if tablep.is_null() {
+        return RETURN_UNKNOWN_FAULT;
+    }
+    guarded(|| {
+        let hands = table_hands(&tableDeal)?;
+        let results = table_results(&solve_dd_table(&hands));
+        // SAFETY: checked non-null above; the caller guarantees it is writable
+        unsafe { *tablep = results };
+        Ok(())
+    })
+}
+
+/// Solve the tables of several deals, optionally with par
+///
+/// `mode` is -1 for no par, otherwise the vulnerability as for `Par`.
+/// Strains with a non-zero `trumpFilter` entry are skipped; par needs all five.
+/// Tables are solved in parallel.
+///
+/// # Safety
+///
+/// `dealsp` must point to a valid `ddTableDeals`, `trumpFilter` to 5 ints and
+/// `resp` to a writable `ddTablesRes`. `presp` must be writable when `mode` is
+/// not -1, and may be null otherwise.
+#[no_mangle]
+pub unsafe extern "system" fn CalcAllTables(
+    dealsp: *const ddTableDeals,
+    mode: c_int,
+    trumpFilter: *const c_int,
+    resp: *mut ddTablesRes,
+    presp: *mut allParResults,
+) -> c_int {
+    if dealsp.is_null() || trumpFilter.is_null() || resp.is_null() {
+        return RETURN_UNKNOWN_FAULT;
+    }
+    if mode != -1 && presp.is_null() {
+        return RETURN_UNKNOWN_FAULT;
+    }
+    guarded(|| {
+        // SAFETY: pointers checked non-null above; the caller guarantees validity
+        let (deals, filter, results) = unsafe {
+            (
+                &*dealsp,
+                std::slice::from_raw_parts(trumpFilter, DDS_STRAINS),
+                &mut *resp,
+            )
+        };
+        let strains: Vec<usize> = (0..DDS_STRAINS).filter(|&s| filter[s] == 0).collect();
+        if strains.is_empty() {
+            return Err(RETURN_NO_SUIT);
+        }
+        let vulnerability = match mode {
+            -1 => None,
+            vulnerable => Some(dds_vulnerability(vulnerable).ok_or(RETURN_UNKNOWN_FAULT)?),
+        };
+        let num_tables = usize::try_from(deals.noOfTables).map_err(|_| RETURN_UNKNOWN_FAULT)?;
+        // As in DDS, fewer strains leave room for more deals
+        if num_tables * strains.len() > MAXNOOFTABLES * DDS_STRAINS {
+            return Err(RETURN_TOO_MANY_TABLES);
+        }
+        let hands = deals.deals[..num_tables]
+            .iter()
+            .map(table_hands)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let tables = solve_tables(&hands, &strains);
+        results.noOfBoards = (num_tables * strains.len()) as c_int;
+        for (i, table) in tables.iter().enumerate() {
+            results.results[i] = table_results(table);
+        }
+
+        if let Some(vulnerability) = vulnerability {
+            // SAFETY: checked non-null above when par is requested
+            let par_results = unsafe { &mut *presp };
+            if strains.len() == DDS_STRAINS {
+                for (i, table) in tables.iter().enumerate() {
+                    par_results.presults[i] = par_results_of(table, vulnerability);
+                }
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Solve the given strains of each deal, spreading deals over threads
+fn solve_tables(hands: &[Hands], strains: &[usize]) -> Vec<DdTable> {
+    parallel_map(hands, None, |hands| {
+        let mut table = DdTable::default();
+        for &strain in strains {
+            let (mut cutoff_cache, mut pattern_cache) = new_caches();
+            table.solve_strain(hands, strain, &mut cutoff_cache, &mut pattern_cache);
+        }
+        table
+    })
+}
+
+/// Par from a solved table
+///
+/// `vulnerable` is 0 for none, 1 for both, 2 for NS and 3 for EW.
+///
+/// # Safety
+///
+/// `tablep` must point to a valid `ddTableResults` and `presp` to a writable
+/// `parResults`.
+#[no_mangle]
+pub unsafe extern "system" fn Par(
+    tablep: *const ddTableResults,
+    presp: *mut parResults,
+    vulnerable: c_int,
+) -> c_int {
+    if tablep.is_null() || presp.is_null() {
+        return RETURN_UNKNOWN_FAULT;
+    }
+    guarded(|| {
+        let vulnerability = dds_vulnerability(vulnerable).ok_or(RETURN_UNKNOWN_FAULT)?;
+        // SAFETY: checked non-null above; the caller guarantees validity
+        let results = unsafe { &*tablep };
+        let mut table = DdTable::default();
+        for strain in 0..DDS_STRAINS {
+            for hand in 0..DDS_HANDS {
+                let tricks = results.resTable[strain][hand];
+                if !(0..=TOTAL_TRICKS as c_int).contains(&tricks) {
+                    return Err(RETURN_UNKNOWN_FAULT);
+                }
+                table.set_index(seat_of_hand(hand), strain, tricks as u8);
+            }
+        }
+        let par_results = par_results_of(&table, vulnerability);
+        // SAFETY: checked non-null above; the caller guarantees it is writable
+        unsafe { *presp = par_results };
+        Ok(())
+    })
+}
+
+fn dds_vulnerability(vulnerable: c_int) -> Option<Vulnerability> {
+    match vulnerable {
+        0 => Some(Vulnerability::None),
+        1 => Some(Vulnerability::Both),
+        2 => Some(Vulnerability::NS),
+        3 => Some(Vulnerability::EW),
+        _ => None,
+    }
+}
+
+/// Par in DDS text form, with NS bidding first in entry 0 and EW in entry 1
+fn par_results_of(table: &DdTable, vulnerability: Vulnerability) -> parResults {
+    let mut results = parResults {
+        parScore: [[0; 16]; 2],
+        parContractsString: [[0; 128]; 2],
+    };
+    for (i, (side, dealer)) in [("NS", NORTH), ("EW", EAST)].into_iter().enumerate() {
+        let result = par(table, vulnerability, dealer);
+        let score = if i == 0 {
+            result.ns_score
+        } else {
+            -result.ns_score
+        };
+        let contract = result
+            .contract
+            .map(|c| dds_contract(table, &c))
+            .unwrap_or_default();
+        copy_c_string(&mut results.parScore[i], &format!("{} {}", side, score));
+        copy_c_string(
+            &mut results.parContractsString[i],
+            &format!("{}:{}", side, contract),
+        );
+    }
+    results
+}
+
+/// DDS contract text, e.g. "NS 4S", "E 3N" or "EW 5Hx"
+fn dds_contract(table: &DdTable, contract: &ParContract) -> String {
+    let partner = partner(contract.declarer);
+    let declarers = if table.get_index(partner, contract.strain) == contract.tricks {
+        if is_ns(contract.declarer) {
+            "NS".to_string()
+        } else {
+            "EW".to_string()
+        }
+    } else {
+        seat_letter(contract.declarer).to_string()
+    };
+    let strain = ['S', 'H', 'D', 'C', 'N'][contract.strain];
+    let doubled = if contract.doubled { "x" } else { "" };
+    format!("{} {}{}{}", declarers, contract.level, strain, doubled)
+}
+
+/// Copy a string into a fixed C buffer, truncating and NUL-terminating
+fn copy_c_string(buffer: &mut [c_char], s: &str) {
+    let len = s.len().min(buffer.len() - 1);
+    for (dst, &src) in buffer.iter_mut().zip(&s.as_bytes()[..len]) {
+        *dst = src as c_char;
+    }
+    buffer[len] = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    /// DDS holdings for "N:A.A.. K.K.. 2.2.. 3.3.."
+    fn small_holdings() -> [[c_uint; DDS_SUITS]; DDS_HANDS] {
+        let ranks = [14, 13, 2, 3];
+        let mut holdings = [[0; DDS_SUITS]; DDS_HANDS];
+        for (hand, rank) in ranks.into_iter().enumerate() {
+            holdings[hand][SPADE] = 1 << rank;
+            holdings[hand][HEART] = 1 << rank;
+        }
+        holdings
+    }
+
+    #[test]
+    fn test_calc_dd_table() {
+        let mut results = ddTableResults::default();
+        let table_deal = ddTableDeal {
+            cards: small_holdings(),
+        };
+        let code = unsafe { CalcDDtable(table_deal, &mut results) };
+        assert_eq!(code, RETURN_NO_FAULT);
+        for strain in 0..DDS_STRAINS {
+            assert_eq!(results.resTable[strain], [2, 0, 2, 0]);
+        }
+
+        let mut duplicate = table_deal;
+        duplicate.cards[1][SPADE] |= 1 << 14;
+        assert_eq!(
+            unsafe { CalcDDtable(duplicate, &mut results) },
+            RETURN_DUPLICATE_CARDS
+        );
+    }
+
+    #[test]
+    fn test_calc_all_tables() {
+        let mut deals = ddTableDeals {
+            noOfTables: 2,
+            deals: [ddTableDeal::default(); MAXNOOFTABLES * DDS_STRAINS],
+        };
+        deals.deals[0].cards = small_holdings();
+        // Swap North and East: EW now take both tricks
+        deals.deals[1].cards = small_holdings();
+        deals.deals[1].cards.swap(0, 1);
+        let mut results = ddTablesRes {
+            noOfBoards: 0,
+            results: [ddTableResults::default(); MAXNOOFTABLES * DDS_STRAINS],
+        };
+        let mut par_results = allParResults {
+            presults: [parResults {
+                parScore: [[0; 16]; 2],
+                parContractsString: [[0; 128]; 2],
+            }; MAXNOOFTABLES],
+        };
+        let filter = [0; DDS_STRAINS];
+        let code =
+            unsafe { CalcAllTables(&deals, 0, filter.as_ptr(), &mut results, &mut par_results) };
+        assert_eq!(code, RETURN_NO_FAULT);
+        assert_eq!(results.noOfBoards, 10);
+        assert_eq!(results.results[0].resTable[NOTRUMP], [2, 0, 2, 0]);
+        assert_eq!(results.results[1].resTable[NOTRUMP], [0, 2, 0, 2]);
+        let score = unsafe { CStr::from_ptr(par_results.presults[1].parScore[0].as_ptr()) };
+        assert_eq!(score.to_str().unwrap(), "NS 0");
+
+        let filter = [1; DDS_STRAINS];
+        let code = unsafe {
+            CalcAllTables(
+                &deals,
+                -1,
+                filter.as_ptr(),
+                &mut results,
+                std::ptr::null_mut(),
+            )
+        };
+        assert_eq!(code, RETURN_NO_SUIT);
+    }
+
+    #[test]
+    fn test_calc_all_tables_filtered() {
+        let mut deals = ddTableDeals {
+            noOfTables: (MAXNOOFTABLES * DDS_STRAINS) as c_int,
+            deals: [ddTableDeal {
+                cards: small_holdings(),
+            }; MAXNOOFTABLES * DDS_STRAINS],
+        };
+        let mut results = ddTablesRes {
+            noOfBoards: 0,
+            results: [ddTableResults::default(); MAXNOOFTABLES * DDS_STRAINS],
+        };
+        // Notrump only: one board per deal, so every slot may hold a deal
+        let mut filter = [1; DDS_STRAINS];
+        filter[NOTRUMP] = 0;
+        let calc = |deals: &ddTableDeals, filter: &[c_int], results: &mut ddTablesRes| unsafe {
+            CalcAllTables(deals, -1, filter.as_ptr(), results, std::ptr::null_mut())
+        };
+        assert_eq!(calc(&deals, &filter, &mut results), RETURN_NO_FAULT);
+        assert_eq!(results.noOfBoards, (MAXNOOFTABLES * DDS_STRAINS) as c_int);
+        let last = MAXNOOFTABLES * DDS_STRAINS - 1;
+        assert_eq!(results.results[last].resTable[NOTRUMP], [2, 0, 2, 0]);
+
+        // Two strains: half as many deals
+        filter[SPADE] = 0;
+        assert_eq!(calc(&deals, &filter, &mut results), RETURN_TOO_MANY_TABLES);
+        deals.noOfTables = (MAXNOOFTABLES * DDS_STRAINS / 2) as c_int;
+        assert_eq!(calc(&deals, &filter, &mut results), RETURN_NO_FAULT);
+        assert_eq!(results.noOfBoards, (MAXNOOFTABLES * DDS_STRAINS) as c_int);
+    }
+
+    #[test]
+    fn test_solve_board() {
+        // East leads; North's ace wins whatever is led
+        let dl = deal {
+            trump: NOTRUMP as c_int,
+            first: 1,
+            remainCards: small_holdings(),
+            ..Default::default()
+        };
+        let mut future = futureTricks::default();
+        let code = unsafe { SolveBoard(dl, -1, 3, 1, &mut future, 0) };
+        assert_eq!(code, RETURN_NO_FAULT);
+        assert_eq!(future.cards, 2);
+        assert_eq!(&future.score[..2], &[0, 0]);
+        assert_eq!(future.suit[0], SPADE as c_int);
+        assert_eq!(future.rank[0], 13);
+
+        // Mid-trick: East led the HK, South to play holds S2, H2
+        let mut dl = dl;
+        dl.remainCards[1][HEART] = 0;
+        dl.currentTrickSuit[0] = HEART as c_int;
+        dl.currentTrickRank[0] = 13;
+        let code = unsafe { SolveBoard(dl, -1, 1, 1, &mut future, 0) };
+        assert_eq!(code, RETURN_NO_FAULT);
+        assert_eq!(future.cards, 1);
+        assert_eq!((future.suit[0], future.rank[0]), (HEART as c_int, 2));
+        assert_eq!(future.score[0], 2);
+
+        assert_eq!(
+            unsafe { SolveBoard(dl, 5, 1, 1, &mut future, 0) },
+            RETURN_TARGET_TOO_HIGH
+        );
+        dl.first = 4;
+        assert_eq!(
+            unsafe { SolveBoard(dl, -1, 1, 1, &mut future, 0) },
+            RETURN_FIRST_WRONG
+        );
+    }
+
+    #[test]
+    fn test_equivalent_cards() {
+        // North holds SK,SQ,S2 with the SJ already played: K and Q are equivalent
+        let hands = Hands::from_pbn("N:KQ2... A3... 54... 6.7..").unwrap();
+        let analysis: Vec<CardAnalysis> = [KING, QUEEN, TWO]
+            .into_iter()
+            .map(|rank| CardAnalysis {
+                card: card_of(SPADE, rank),
+                ns_tricks: 1,
+            })
+            .collect();
+        let groups = equivalent_groups(&hands, &PartialTrick::new(), NORTH, &analysis);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].card, card_of(SPADE, KING));
+        assert_eq!(groups[0].equals, 1 << 12);
+        assert_eq!(groups[1].equals, 0);
+    }
+
+    #[test]
+    fn test_equivalent_cards_mid_trick() {
+        // West led the SK; North's SA and SQ are not equivalent around it
+        let hands = Hands::from_pbn("N:AQ... 32... 54... 6...").unwrap();
+        let mut trick = PartialTrick::new();
+        trick.add_index(card_of(SPADE, KING), WEST);
+        let results = |ace_tricks, queen_tricks| {
+            [(ACE, ace_tricks), (QUEEN, queen_tricks)].map(|(rank, ns_tricks)| CardAnalysis {
+                card: card_of(SPADE, rank),
+                ns_tricks,
+            })
+        };
+        let groups = equivalent_groups(&hands, &trick, NORTH, &results(1, 1));
+        assert_eq!(groups.len(), 2);
+        assert_eq!((groups[0].card, groups[0].equals), (card_of(SPADE, ACE), 0));
+        assert_eq!(groups[1].card, card_of(SPADE, QUEEN));
+
+        // Adjacent cards taking different tricks are not merged either
+        let hands = Hands::from_pbn("N:AK... 32... 54... 6...").unwrap();
+        let mut analysis = results(1, 0);
+        analysis[1].card = card_of(SPADE, KING);
+        let groups = equivalent_groups(&hands, &PartialTrick::new(), NORTH, &analysis);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[1].score, 0);
+    }
+
+    #[test]
+    fn test_par() {
+        // NS make 10 tricks in spades with either declarer, EW 6 tricks anywhere
+        let mut results = ddTableResults::default();
+        for strain in 0..DDS_STRAINS {
+            let ns = if strain == SPADE { 10 } else { 7 };
+            results.resTable[strain] = [ns, 6, ns, 6];
+        }
+        let mut par_results = parResults {
+            parScore: [[0; 16]; 2],
+            parContractsString: [[0; 128]; 2],
+        };
+        let code = unsafe { Par(&results, &mut par_results, 0) };
+        assert_eq!(code, RETURN_NO_FAULT);
+        let text = |s: &[c_char]| {
+            unsafe { CStr::from_ptr(s.as_ptr()) }
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(text(&par_results.parScore[0]), "NS 420");
+        assert_eq!(text(&par_results.parScore[1]), "EW -420");
+        assert_eq!(text(&par_results.parContractsString[0]), "NS:NS 4S");
+    }
+}
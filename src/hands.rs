@@ -6,7 +6,7 @@ use super::cards::*;
 use super::types::*;
 
 /// Four hands, one per seat - no heap allocation
-#[derive(Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct Hands {
     hands: [Cards; NUM_SEATS],
 }
@@ -22,13 +22,13 @@ impl Hands {
 
     /// Get hand for a seat
     #[inline]
-    pub fn hand(&self, seat: Seat) -> Cards {
+    pub fn hand(&self, seat: SeatIndex) -> Cards {
         self.hands[seat]
     }
 
     /// Get mutable reference to hand
     #[inline]
-    pub fn hand_mut(&mut self, seat: Seat) -> &mut Cards {
+    pub fn hand_mut(&mut self, seat: SeatIndex) -> &mut Cards {
         &mut self.hands[seat]
     }
 
@@ -43,16 +43,25 @@ impl Hands {
 
     /// Get cards for a partnership (seat and partner)
     #[inline]
-    pub fn partnership_cards(&self, seat: Seat) -> Cards {
+    pub fn partnership_cards(&self, seat: SeatIndex) -> Cards {
         self.hands[seat].union(self.hands[partner(seat)])
     }
 
     /// Get opponent cards
     #[inline]
-    pub fn opponent_cards(&self, seat: Seat) -> Cards {
+    pub fn opponent_cards(&self, seat: SeatIndex) -> Cards {
         self.hands[left_hand_opp(seat)].union(self.hands[right_hand_opp(seat)])
     }
 
+    /// Check that each card appears at most once and all hands hold the same,
+    /// non-zero number of cards, as the solver requires
+    pub fn is_valid(&self) -> bool {
+        let num_tricks = self.num_tricks();
+        (0..NUM_SEATS).all(|seat| self.hands[seat].size() == num_tricks)
+            && self.all_cards().size() == num_tricks * NUM_SEATS
+            && num_tricks > 0
+    }
+
     /// Get number of tricks (cards per hand)
     #[inline]
     pub fn num_tricks(&self) -> usize {
@@ -66,11 +75,10 @@ impl Hands {
         let mut hands = Hands::new();
 
         // Find the starting seat indicator
-        let (start_seat, rest) = if s.len() >= 2 && s.chars().nth(1) == Some(':') {
-            let seat_char = s.chars().next()?;
-            (char_to_seat(seat_char)?, &s[2..])
-        } else {
-            (NORTH, s) // Default to North
+        let mut chars = s.chars();
+        let (start_seat, rest) = match (chars.next(), chars.next()) {
+            (Some(seat_char), Some(':')) => (char_to_seat(seat_char)?, chars.as_str()),
+            _ => (NORTH, s), // Default to North
         };
 
         // Split into four hands
@@ -88,6 +96,26 @@ impl Hands {
         Some(hands)
     }
 
+    /// Format as a PBN deal string starting from North (inverse of `from_pbn`)
+    pub fn to_pbn(&self) -> String {
+        let hands: Vec<String> = [NORTH, EAST, SOUTH, WEST]
+            .iter()
+            .map(|&seat| {
+                let suits: Vec<String> = (0..NUM_SUITS)
+                    .map(|suit| {
+                        self.hands[seat]
+                            .suit(suit)
+                            .iter()
+                            .map(|card| rank_name(rank_of(card)))
+                            .collect()
+                    })
+                    .collect();
+                suits.join(".")
+            })
+            .collect();
+        format!("N:{}", hands.join(" "))
+    }
+
     /// Parse from solver-style format (4 lines: N, W E, S with spaces between suits)
     /// Each hand has suits separated by spaces in S H D C order
     pub fn from_solver_format(n: &str, w: &str, e: &str, s: &str) -> Option<Self> {
@@ -98,20 +126,27 @@ impl Hands {
         hands.hands[SOUTH] = parse_hand_spaces(s)?;
         Some(hands)
     }
+
+    /// Parse the first three lines of a solver-format file: North, then West
+    /// and East on one line (separated by 2+ spaces or a tab), then South
+    pub fn from_solver_lines(north: &str, west_east: &str, south: &str) -> Option<Self> {
+        let (west, east) = split_west_east(west_east);
+        Self::from_solver_format(north.trim(), &west, &east, south.trim())
+    }
 }
 
-impl std::ops::Index<Seat> for Hands {
+impl std::ops::Index<SeatIndex> for Hands {
     type Output = Cards;
 
     #[inline]
-    fn index(&self, seat: Seat) -> &Self::Output {
+    fn index(&self, seat: SeatIndex) -> &Self::Output {
         &self.hands[seat]
     }
 }
 
-impl std::ops::IndexMut<Seat> for Hands {
+impl std::ops::IndexMut<SeatIndex> for Hands {
     #[inline]
-    fn index_mut(&mut self, seat: Seat) -> &mut Self::Output {
+    fn index_mut(&mut self, seat: SeatIndex) -> &mut Self::Output {
         &mut self.hands[seat]
     }
 }
@@ -134,7 +169,7 @@ impl std::fmt::Display for Hands {
 }
 
 /// Parse a single hand from PBN format (SHDC separated by dots)
-fn parse_hand(s: &str) -> Option<Cards> {
+pub(super) fn parse_hand(s: &str) -> Option<Cards> {
     let mut cards = Cards::new();
     let suits: Vec<&str> = s.split('.').collect();
     if suits.len() != 4 {
@@ -147,7 +182,7 @@ fn parse_hand(s: &str) -> Option<Cards> {
                 continue; // Void marker
             }
             let rank = char_to_rank(c)?;
-            cards.add(card_of(suit, rank));
+            cards.add_index(card_of(suit, rank));
         }
     }
 
@@ -174,13 +209,51 @@ fn parse_hand_spaces(s: &str) -> Option<Cards> {
                 continue;
             }
             let rank = char_to_rank(c)?;
-            cards.add(card_of(suit, rank));
+            cards.add_index(card_of(suit, rank));
         }
     }
 
     Some(cards)
 }
 
+/// Split the West/East line which has both hands separated by 2+ spaces
+fn split_west_east(line: &str) -> (String, String) {
+    // Find the first run of 2+ spaces (the separator between West and East)
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b' ' {
+            let start = i;
+            while i < bytes.len() && bytes[i] == b' ' {
+                i += 1;
+            }
+            // If we found 2+ spaces, split here
+            if i - start >= 2 {
+                let west = line[..start].trim().to_string();
+                let east = line[i..].trim().to_string();
+                return (west, east);
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    // Fallback: try splitting by tab
+    let trimmed = line.trim();
+    if let Some(pos) = trimmed.find('\t') {
+        let west = trimmed[..pos].trim().to_string();
+        let east = trimmed[pos..].trim().to_string();
+        (west, east)
+    } else {
+        // Last resort: split by whitespace and divide in half
+        let tokens: Vec<&str> = trimmed.split_whitespace().collect();
+        let mid = tokens.len() / 2;
+        let west = tokens[0..mid].join(" ");
+        let east = tokens[mid..].join(" ");
+        (west, east)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -191,9 +264,9 @@ mod tests {
         assert_eq!(hands.num_tricks(), 0);
 
         // Add a card to North
-        hands[NORTH].add(card_of(SPADE, ACE));
+        hands[NORTH].add_index(card_of(SPADE, ACE));
         assert_eq!(hands[NORTH].size(), 1);
-        assert!(hands[NORTH].have(card_of(SPADE, ACE)));
+        assert!(hands[NORTH].have_index(card_of(SPADE, ACE)));
     }
 
     #[test]
@@ -202,11 +275,11 @@ mod tests {
         let hands = Hands::from_pbn(pbn).expect("Should parse");
 
         // North should have AKQT3 of spades
-        assert!(hands[NORTH].have(card_of(SPADE, ACE)));
-        assert!(hands[NORTH].have(card_of(SPADE, KING)));
-        assert!(hands[NORTH].have(card_of(SPADE, QUEEN)));
-        assert!(hands[NORTH].have(card_of(SPADE, TEN)));
-        assert!(hands[NORTH].have(card_of(SPADE, THREE)));
+        assert!(hands[NORTH].have_index(card_of(SPADE, ACE)));
+        assert!(hands[NORTH].have_index(card_of(SPADE, KING)));
+        assert!(hands[NORTH].have_index(card_of(SPADE, QUEEN)));
+        assert!(hands[NORTH].have_index(card_of(SPADE, TEN)));
+        assert!(hands[NORTH].have_index(card_of(SPADE, THREE)));
 
         // Each hand should have 13 cards
         assert_eq!(hands[NORTH].size(), 13);
@@ -215,6 +288,16 @@ mod tests {
         assert_eq!(hands[WEST].size(), 13);
     }
 
+    #[test]
+    fn test_hands_to_pbn() {
+        let pbn = "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72";
+        let hands = Hands::from_pbn(pbn).expect("Should parse");
+        assert_eq!(hands.to_pbn(), pbn);
+
+        let hands = Hands::from_pbn("W:2... A... K... Q...").expect("Should parse");
+        assert_eq!(hands.to_pbn(), "N:A... K... Q... 2...");
+    }
+
     #[test]
     fn test_partnership_cards() {
         let pbn = "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72";
@@ -251,4 +334,43 @@ mod tests {
         assert_eq!(hands[SOUTH].size(), 13);
         assert_eq!(hands.all_cards().size(), 52);
     }
+
+    #[test]
+    fn test_hands_from_solver_lines() {
+        let lines = [
+            "AKQT3 J6 KJ42 95",
+            "98 873 9653 QJ72    652 AK42 AQ87 T4",
+            "J74 QT95 T AK863",
+        ];
+        let hands = Hands::from_solver_lines(lines[0], lines[1], lines[2]).expect("Should parse");
+        assert_eq!(
+            hands.to_pbn(),
+            "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72"
+        );
+
+        // Tab separated
+        let hands = Hands::from_solver_lines("A", "2\tK", "Q").expect("Should parse");
+        assert_eq!(hands.to_pbn(), "N:A... K... Q... 2...");
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        for pbn in [
+            "",
+            "N:",
+            "é:A... K... Q... J...",
+            "X:A... K... Q... J...",
+            "N:A... K...",
+        ] {
+            assert!(Hands::from_pbn(pbn).is_none(), "{}", pbn);
+        }
+
+        assert!(Hands::from_pbn("N:A... K... Q... J...").unwrap().is_valid());
+        // Duplicate card, unequal hands, no cards
+        assert!(!Hands::from_pbn("N:A... A... Q... J...").unwrap().is_valid());
+        assert!(!Hands::from_pbn("N:AK... Q... J... T...")
+            .unwrap()
+            .is_valid());
+        assert!(!Hands::from_pbn("N:... ... ... ...").unwrap().is_valid());
+    }
 }
@@ -0,0 +1,730 @@
+//! JSON request/response service (requires the `json` feature)
+//!
+//! Used by `bridge-solver --serve`, which reads one JSON request per line on
+//! stdin and writes one JSON response per line on stdout.
+//!
+//! # Requests
+//!
+//! Every request is an object with a `method` and an optional `id`, which is
+//! echoed back unchanged in the response:
+//!
+//! ```text
+//! {"id": 1, "method": "solve", "deal": "N:AKQT3.J6.KJ42.95 ...", "trump": "N", "leader": "W"}
+//! {"id": 2, "method": "solve", "deal": "...", "trump": "S", "leader": "W", "played": ["HK", "HA"]}
+//! {"id": 3, "method": "table", "deal": "..."}
+//! {"id": 4, "method": "analyze", "deal": "...", "trump": "H", "leader": "E"}
+//! {"id": 4, "method": "leads", "deal": "...", "trump": "H", "leader": "E"}
+//! {"id": 4, "method": "par", "deal": "...", "vulnerability": "NS", "dealer": "E"}
+//! {"id": 5, "method": "session.start", "deal": "...", "trump": "N", "leader": "W"}
+//! {"id": 6, "method": "session.play", "session": 1, "card": "D5"}
+//! {"id": 7, "method": "session.analyze", "session": 1}
+//! {"id": 8, "method": "session.end", "session": 1}
+//! ```
+//!
+//! - `deal` is a PBN deal string holding the cards at the start of the trick.
+//! - `trump` is one of `N`/`NT`, `S`, `H`, `D`, `C`; `leader` one of `W`, `N`, `E`, `S`.
+//! - `played` lists cards already played to the current trick, in order, starting
+//!   with `leader`. They are removed from the deal before solving. `leads` is
+//!   `analyze` restricted to the opening lead, so takes no `played` cards.
+//! - `vulnerability` is a PBN vulnerability (`None`, `NS`, `EW`, `All`) and
+//!   `dealer` a seat letter; both are optional and default to `None` and `N`.
+//! - Cards are written suit first, e.g. `SA`, `HT`, `C2`.
+//!
+//! # Responses
+//!
+//! ```text
+//! {"id": 1, "result": {"ns_tricks": 9, "ew_tricks": 4}}
+//! {"id": 3, "result": {"table": {"N": {"NT": 9, "S": 10, "H": 8, "D": 7, "C": 8}, ...}}}
+//! {"id": 4, "result": {"seat": "E", "cards": [{"card": "SA", "ns_tricks": 9}, ...]}}
+//! {"id": 4, "result": {"ns_score": 420, "contract": "4S-N", "table": {...}}}
+//! {"id": 9, "error": {"code": "invalid_deal", "message": "..."}}
+//! ```
+//!
+//! Analysis and session results report NS tricks for the whole deal, including
+//! tricks already won in a session. Sessions report their state after every step.
+//!
+//! # Caches
+//!
+//! Transposition and cutoff caches are kept per position family, i.e. per deal
+//! (as it stood at the start of the first trick solved) and strain, so repeated
+//! requests on one deal - different leaders, what-ifs, or a session played out
+//! step by step - reuse the work of earlier requests.
+//!
+//! # Limits
+//!
+//! A service can be given a timeout per request and a node limit per solve.
+//! Requests that hit a limit fail with the `limit_exceeded` error code.
+
+use super::analysis::{analyze_plays_with_limit, AnalysisError, CardAnalysis};
+use super::bridge_solver::{new_caches, PartialTrick, SolveLimit, Solver};
+use super::cards::*;
+use super::hands::Hands;
+use super::par::par;
+use super::pattern::PatternCache;
+use super::scoring::Vulnerability;
+use super::search::CutoffCache;
+use super::session::PlaySession;
+use super::table::DdTable;
+use super::types::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+/// Number of position families whose caches are kept between requests
+const MAX_FAMILIES: usize = 8;
+
+/// A parsed request line
+#[derive(Deserialize)]
+struct Request {
+    method: String,
+    deal: Option<String>,
+    trump: Option<String>,
+    leader: Option<String>,
+    #[serde(default)]
+    played: Vec<String>,
+    session: Option<u64>,
+    card: Option<String>,
+    vulnerability: Option<String>,
+    dealer: Option<String>,
+}
+
+/// Error reported to the client as `{"code": ..., "message": ...}`
+#[derive(Serialize, Debug)]
+pub struct ServiceError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ServiceError {
+    fn new(code: &'static str, message: impl Into<String>) -> Self {
+        ServiceError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Result of a solve: tricks for each side from the position given
+#[derive(Serialize)]
+pub struct SolveResult {
+    pub ns_tricks: u8,
+    pub ew_tricks: u8,
+}
+
+/// Double-dummy table keyed by declarer letter, then strain name
+#[derive(Serialize)]
+pub struct TableResult {
+    pub table: BTreeMap<String, BTreeMap<String, u8>>,
+}
+
+impl From<&DdTable> for TableResult {
+    fn from(dd_table: &DdTable) -> Self {
+        let mut table = BTreeMap::new();
+        for declarer in 0..NUM_SEATS {
+            let row = (0..NUM_STRAINS)
+                .map(|strain| {
+                    (
+                        strain_name(strain).to_string(),
+                        dd_table.get_index(declarer, strain),
+                    )
+                })
+                .collect();
+            table.insert(seat_letter(declarer).to_string(), row);
+        }
+        TableResult { table }
+    }
+}
+
+/// Par score (from NS's point of view) and contract, with the table it came from
+#[derive(Serialize)]
+pub struct ParResult {
+    pub ns_score: i32,
+    /// e.g. "4S-N" or "5HX-E"; None when passed out
+    pub contract: Option<String>,
+    #[serde(flatten)]
+    pub table: TableResult,
+}
+
+/// The value of one card in a per-card analysis
+#[derive(Serialize)]
+pub struct CardResult {
+    pub card: String,
+    pub ns_tricks: u8,
+}
+
+/// Per-card analysis of the seat to play
+#[derive(Serialize)]
+pub struct AnalysisResult {
+    pub seat: String,
+    pub cards: Vec<CardResult>,
+}
+
+impl AnalysisResult {
+    fn new(seat: SeatIndex, analysis: &[CardAnalysis]) -> Self {
+        AnalysisResult {
+            seat: seat_letter(seat).to_string(),
+            cards: analysis
+                .iter()
+                .map(|a| CardResult {
+                    card: name_of(a.card),
+                    ns_tricks: a.ns_tricks,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// State of a play session after a step
+#[derive(Serialize)]
+pub struct SessionState {
+    pub session: u64,
+    pub seat_to_play: Option<String>,
+    pub trick: Vec<String>,
+    pub ns_tricks_won: u8,
+    pub ew_tricks_won: u8,
+    pub legal: Vec<String>,
+    pub finished: bool,
+}
+
+impl SessionState {
+    fn new(id: u64, session: &PlaySession) -> Self {
+        let finished = session.is_finished();
+        SessionState {
+            session: id,
+            seat_to_play: (!finished).then(|| seat_letter(session.seat_to_play()).to_string()),
+            trick: session
+                .current_trick()
+                .plays
+                .iter()
+                .map(|p| name_of(p.card))
+                .collect(),
+            ns_tricks_won: session.ns_tricks_won(),
+            ew_tricks_won: session.ew_tricks_won(),
+            legal: session.legal_cards().iter().map(name_of).collect(),
+            finished,
+        }
+    }
+}
+
+/// Caches shared by all requests on one deal and strain
+struct Caches {
+    cutoff: CutoffCache,
+    pattern: PatternCache,
+}
+
+/// A session along with the deal it started from
+struct Session {
+    deal: Hands,
+    play: PlaySession,
+}
+
+/// A position parsed from a request
+struct Position {
+    /// Hands at the start of the current trick
+    deal: Hands,
+    /// Hands with the current trick's cards removed
+    hands: Hands,
+    trump: usize,
+    leader: SeatIndex,
+    trick: PartialTrick,
+}
+
+/// Stateful request handler: holds caches and play sessions between requests
+#[derive(Default)]
+pub struct Service {
+    /// Most recently used family last
+    families: Vec<((Hands, usize), Caches)>,
+    sessions: HashMap<u64, Session>,
+    next_session: u64,
+    timeout: Option<Duration>,
+    max_nodes: Option<u64>,
+}
+
+impl Service {
+    /// Create a service with no cached state
+    pub fn new() -> Self {
+        Service::default()
+    }
+
+    /// Fail requests that take longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Fail requests needing a solve of more than `max_nodes` search nodes
+    pub fn with_max_nodes(mut self, max_nodes: u64) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    /// Limit for the solves of a request starting now
+    fn limit(&self) -> SolveLimit {
+        let mut limit = SolveLimit::unlimited();
+        if let Some(timeout) = self.timeout {
+            limit = limit.with_timeout(timeout);
+        }
+        if let Some(max_nodes) = self.max_nodes {
+            limit = limit.with_max_nodes(max_nodes);
+        }
+        limit
+    }
+
+    /// Handle one request line, returning the response line (without newline)
+    pub fn handle_line(&mut self, line: &str) -> String {
+        let response = match serde_json::from_str::<Value>(line) {
+            Ok(request) => self.handle(&request),
+            Err(e) => error_response(
+                Value::Null,
+                ServiceError::new("parse_error", format!("invalid JSON: {}", e)),
+            ),
+        };
+        response.to_string()
+    }
+
+    /// Handle one parsed request, returning the response object
+    pub fn handle(&mut self, request: &Value) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let result = serde_json::from_value::<Request>(request.clone())
+            .map_err(|e| ServiceError::new("invalid_request", e.to_string()))
+            .and_then(|request| self.dispatch(&request));
+        match result {
+            Ok(result) => serde_json::json!({ "id": id, "result": result }),
+            Err(error) => error_response(id, error),
+        }
+    }
+
+    fn dispatch(&mut self, request: &Request) -> Result<Value, ServiceError> {
+        let result = match request.method.as_str() {
+            "solve" => to_value(self.solve(&parse_position(request)?)?),
+            "table" => to_value(TableResult::from(&self.table(&parse_deal(request)?)?)),
+            "par" => to_value(self.par(request)?),
+            "analyze" => to_value(self.analyze(&parse_position(request)?)?),
+            "leads" => {
+                if !request.played.is_empty() {
+                    return Err(ServiceError::new(
+                        "invalid_request",
+                        "leads takes no played cards; use analyze",
+                    ));
+                }
+                to_value(self.analyze(&parse_position(request)?)?)
+            }
+            "session.start" => to_value(self.start_session(&parse_position(request)?)?),
+            "session.play" => {
+                let card = parse_card_field(request.card.as_deref())?;
+                to_value(self.play(session_id(request)?, card)?)
+            }
+            "session.analyze" => to_value(self.analyze_session(session_id(request)?)?),
+            "session.end" => {
+                let id = session_id(request)?;
+                self.sessions
+                    .remove(&id)
+                    .ok_or_else(|| unknown_session(id))?;
+                serde_json::json!({ "session": id })
+            }
+            method => {
+                return Err(ServiceError::new(
+                    "unknown_method",
+                    format!("unknown method '{}'", method),
+                ))
+            }
+        };
+        Ok(result)
+    }
+
+    /// Solve a position, as NS and EW tricks from the current trick on
+    fn solve(&mut self, position: &Position) -> Result<SolveResult, ServiceError> {
+        let limit = self.limit();
+        let caches = self.caches(position.deal, position.trump);
+        let ns_tricks =
+            if position.trick.is_empty() {
+                Solver::with_indices(position.hands, position.trump, position.leader)
+                    .solve_with_limit(&mut caches.cutoff, &mut caches.pattern, &limit)
+            } else {
+                Solver::mid_trick_with_indices(position.hands, position.trump, &position.trick)
+                    .unwrap()
+                    .solve_mid_trick_with_limit(
+                        &mut caches.cutoff,
+                        &mut caches.pattern,
+                        &position.trick,
+                        &limit,
+                    )
+            }
+            .ok_or_else(limit_exceeded)?;
+        Ok(SolveResult {
+            ns_tricks,
+            ew_tricks: position.deal.num_tricks() as u8 - ns_tricks,
+        })
+    }
+
+    /// Solve the full double-dummy table of a deal
+    fn table(&mut self, deal: &Hands) -> Result<DdTable, ServiceError> {
+        let limit = self.limit();
+        let mut table = DdTable::default();
+        for strain in 0..NUM_STRAINS {
+            let caches = self.caches(*deal, strain);
+            table
+                .solve_strain_with_limit(
+                    deal,
+                    strain,
+                    &mut caches.cutoff,
+                    &mut caches.pattern,
+                    &limit,
+                )
+                .ok_or_else(limit_exceeded)?;
+        }
+        Ok(table)
+    }
+
+    /// Par score and contract of a deal
+    fn par(&mut self, request: &Request) -> Result<ParResult, ServiceError> {
+        let deal = parse_deal(request)?;
+        let vulnerability = match request.vulnerability.as_deref() {
+            Some(s) => Vulnerability::from_pbn(s).ok_or_else(|| {
+                ServiceError::new("invalid_request", format!("invalid vulnerability '{}'", s))
+            })?,
+            None => Vulnerability::None,
+        };
+        let dealer = match request.dealer.as_deref() {
+            Some(s) => parse_seat(s, "dealer")?,
+            None => NORTH,
+        };
+        let table = self.table(&deal)?;
+        let result = par(&table, vulnerability, dealer);
+        Ok(ParResult {
+            ns_score: result.ns_score,
+            contract: result.contract.map(|c| c.to_string()),
+            table: TableResult::from(&table),
+        })
+    }
+
+    /// Analyse every legal play of the seat to move
+    fn analyze(&mut self, position: &Position) -> Result<AnalysisResult, ServiceError> {
+        let limit = self.limit();
+        let caches = self.caches(position.deal, position.trump);
+        let analysis = analyze_plays_with_limit(
+            position.hands,
+            position.trump,
+            position.leader,
+            &position.trick,
+            &mut caches.cutoff,
+            &mut caches.pattern,
+            &limit,
+        )
+        .map_err(|e| match e {
+            AnalysisError::LimitReached => limit_exceeded(),
+            AnalysisError::InvalidPosition => ServiceError::new("illegal_play", e.to_string()),
+        })?;
+        let seat = position.trick.next_to_play().unwrap_or(position.leader);
+        Ok(AnalysisResult::new(seat, &analysis))
+    }
+
+    fn start_session(&mut self, position: &Position) -> Result<SessionState, ServiceError> {
+        let mut play = PlaySession::with_indices(position.deal, position.trump, position.leader)
+            .ok_or_else(|| {
+                ServiceError::new(
+                    "invalid_deal",
+                    "hands must all hold the same number of cards",
+                )
+            })?;
+        for played in &position.trick.plays {
+            play.play_index(played.card)
+                .map_err(|e| ServiceError::new("illegal_play", e.to_string()))?;
+        }
+
+        self.next_session += 1;
+        let id = self.next_session;
+        let state = SessionState::new(id, &play);
+        self.sessions.insert(
+            id,
+            Session {
+                deal: position.deal,
+                play,
+            },
+        );
+        Ok(state)
+    }
+
+    fn play(&mut self, id: u64, card: usize) -> Result<SessionState, ServiceError> {
+        let session = self
+            .sessions
+            .get_mut(&id)
+            .ok_or_else(|| unknown_session(id))?;
+        session
+            .play
+            .play_index(card)
+            .map_err(|e| ServiceError::new("illegal_play", e.to_string()))?;
+        Ok(SessionState::new(id, &session.play))
+    }
+
+    fn analyze_session(&mut self, id: u64) -> Result<AnalysisResult, ServiceError> {
+        let session = self.sessions.get(&id).ok_or_else(|| unknown_session(id))?;
+        let (deal, play) = (session.deal, session.play.clone());
+        let limit = self.limit();
+        let caches = self.caches(deal, play.trump());
+        let analysis = play
+            .analyze_with_limit(&mut caches.cutoff, &mut caches.pattern, &limit)
+            .ok_or_else(limit_exceeded)?;
+        Ok(AnalysisResult::new(play.seat_to_play(), &analysis))
+    }
+
+    /// Get the caches for a deal and strain, creating (and evicting) as needed
+    fn caches(&mut self, deal: Hands, trump: usize) -> &mut Caches {
+        let key = (deal, trump);
+        if let Some(pos) = self.families.iter().position(|(k, _)| *k == key) {
+            let family = self.families.remove(pos);
+            self.families.push(family);
+        } else {
+            if self.families.len() >= MAX_FAMILIES {
+                self.families.remove(0);
+            }
+            let (cutoff, pattern) = new_caches();
+            let caches = Caches { cutoff, pattern };
+            self.families.push((key, caches));
+        }
+        &mut self.families.last_mut().unwrap().1
+    }
+}
+
+fn to_value(result: impl Serialize) -> Value {
+    serde_json::to_value(result).unwrap()
+}
+
+fn error_response(id: Value, error: ServiceError) -> Value {
+    serde_json::json!({ "id": id, "error": error })
+}
+
+fn unknown_session(id: u64) -> ServiceError {
+    ServiceError::new("unknown_session", format!("no session with id {}", id))
+}
+
+fn limit_exceeded() -> ServiceError {
+    ServiceError::new(
+        "limit_exceeded",
+        "solve abandoned on reaching the time or node limit",
+    )
+}
+
+fn session_id(request: &Request) -> Result<u64, ServiceError> {
+    request
+        .session
+        .ok_or_else(|| ServiceError::new("invalid_request", "missing field 'session'"))
+}
+
+fn parse_deal(request: &Request) -> Result<Hands, ServiceError> {
+    let deal = request
+        .deal
+        .as_deref()
+        .ok_or_else(|| ServiceError::new("invalid_request", "missing field 'deal'"))?;
+    let hands = Hands::from_pbn(deal).ok_or_else(|| {
+        ServiceError::new("invalid_deal", format!("cannot parse deal '{}'", deal))
+    })?;
+    if !hands.is_valid() {
+        return Err(ServiceError::new(
+            "invalid_deal",
+            "each card must appear once and all hands must hold the same number of cards",
+        ));
+    }
+    Ok(hands)
+}
+
+fn parse_position(request: &Request) -> Result<Position, ServiceError> {
+    let deal = parse_deal(request)?;
+    let trump = match request.trump.as_deref() {
+        Some("NT") | Some("nt") => NOTRUMP,
+        Some(s) if s.chars().count() == 1 => {
+            char_to_suit(s.chars().next().unwrap()).ok_or_else(|| {
+                ServiceError::new("invalid_request", format!("invalid trump '{}'", s))
+            })?
+        }
+        Some(s) => {
+            return Err(ServiceError::new(
+                "invalid_request",
+                format!("invalid trump '{}'", s),
+            ))
+        }
+        None => {
+            return Err(ServiceError::new(
+                "invalid_request",
+                "missing field 'trump'",
+            ))
+        }
+    };
+    let leader = match request.leader.as_deref() {
+        Some(s) => parse_seat(s, "leader")?,
+        None => {
+            return Err(ServiceError::new(
+                "invalid_request",
+                "missing field 'leader'",
+            ))
+        }
+    };
+
+    if request.played.len() >= NUM_SEATS {
+        return Err(ServiceError::new(
+            "invalid_request",
+            "at most 3 cards can be played to the current trick",
+        ));
+    }
+    let mut hands = deal;
+    let mut trick = PartialTrick::new();
+    for name in &request.played {
+        let card = parse_card_field(Some(name))?;
+        let seat = trick.next_to_play().unwrap_or(leader);
+        let playable = super::play::get_playable_cards(&hands, seat, trick.lead_suit());
+        if !playable.have_index(card) {
+            return Err(ServiceError::new(
+                "illegal_play",
+                format!("{} cannot play {}", seat_name(seat), name),
+            ));
+        }
+        hands[seat].remove_index(card);
+        trick.add_index(card, seat);
+    }
+
+    Ok(Position {
+        deal,
+        hands,
+        trump,
+        leader,
+        trick,
+    })
+}
+
+fn parse_seat(s: &str, field: &str) -> Result<SeatIndex, ServiceError> {
+    let mut chars = s.chars();
+    match (chars.next().and_then(char_to_seat), chars.next()) {
+        (Some(seat), None) => Ok(seat),
+        _ => Err(ServiceError::new(
+            "invalid_request",
+            format!("invalid {} '{}'", field, s),
+        )),
+    }
+}
+
+fn parse_card_field(name: Option<&str>) -> Result<usize, ServiceError> {
+    let name = name.ok_or_else(|| ServiceError::new("invalid_request", "missing field 'card'"))?;
+    parse_card(name)
+        .ok_or_else(|| ServiceError::new("invalid_card", format!("invalid card '{}'", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEAL: &str = "N:A.A.. K.K.. 2.2.. 3.3..";
+
+    fn request(service: &mut Service, line: &str) -> Value {
+        serde_json::from_str(&service.handle_line(line)).unwrap()
+    }
+
+    #[test]
+    fn test_solve_and_table() {
+        let mut service = Service::new();
+        let response = request(
+            &mut service,
+            &format!(
+                r#"{{"id": 7, "method": "solve", "deal": "{}", "trump": "NT", "leader": "W"}}"#,
+                DEAL
+            ),
+        );
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["result"]["ns_tricks"], 2);
+        assert_eq!(response["result"]["ew_tricks"], 0);
+
+        let response = request(
+            &mut service,
+            &format!(r#"{{"id": "t", "method": "table", "deal": "{}"}}"#, DEAL),
+        );
+        assert_eq!(response["id"], "t");
+        assert_eq!(response["result"]["table"]["N"]["NT"], 2);
+        assert_eq!(response["result"]["table"]["E"]["S"], 0);
+    }
+
+    #[test]
+    fn test_analyze_mid_trick() {
+        let mut service = Service::new();
+        let response = request(
+            &mut service,
+            &format!(
+                r#"{{"method": "analyze", "deal": "{}", "trump": "S", "leader": "W", "played": ["S3"]}}"#,
+                DEAL
+            ),
+        );
+        assert_eq!(response["result"]["seat"], "N");
+        assert_eq!(response["result"]["cards"][0]["card"], "SA");
+        assert_eq!(response["result"]["cards"][0]["ns_tricks"], 2);
+    }
+
+    #[test]
+    fn test_session_steps() {
+        let mut service = Service::new();
+        let response = request(
+            &mut service,
+            &format!(
+                r#"{{"method": "session.start", "deal": "{}", "trump": "N", "leader": "W"}}"#,
+                DEAL
+            ),
+        );
+        let id = response["result"]["session"].as_u64().unwrap();
+        assert_eq!(response["result"]["seat_to_play"], "W");
+
+        let play = |card: &str| {
+            format!(
+                r#"{{"method": "session.play", "session": {}, "card": "{}"}}"#,
+                id, card
+            )
+        };
+        let response = request(&mut service, &play("H3"));
+        assert_eq!(response["result"]["seat_to_play"], "N");
+        assert_eq!(response["result"]["legal"][0], "HA");
+
+        let response = request(&mut service, &play("SA"));
+        assert_eq!(response["error"]["code"], "illegal_play");
+
+        let response = request(
+            &mut service,
+            &format!(r#"{{"method": "session.analyze", "session": {}}}"#, id),
+        );
+        assert_eq!(response["result"]["cards"][0]["ns_tricks"], 2);
+
+        let response = request(
+            &mut service,
+            &format!(r#"{{"method": "session.end", "session": {}}}"#, id),
+        );
+        assert_eq!(response["result"]["session"], id);
+        let response = request(&mut service, &play("HA"));
+        assert_eq!(response["error"]["code"], "unknown_session");
+    }
+
+    #[test]
+    fn test_malformed_requests() {
+        let mut service = Service::new();
+        let response = request(&mut service, "{not json");
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(response["error"]["code"], "parse_error");
+
+        let response = request(&mut service, r#"{"id": 1, "method": "fly"}"#);
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["error"]["code"], "unknown_method");
+
+        let response = request(&mut service, r#"{"id": 2, "method": "solve"}"#);
+        assert_eq!(response["error"]["code"], "invalid_request");
+
+        let response = request(
+            &mut service,
+            r#"{"id": 3, "method": "table", "deal": "N:AK.. Q.. 2.. 3.."}"#,
+        );
+        assert_eq!(response["error"]["code"], "invalid_deal");
+
+        let response = request(
+            &mut service,
+            &format!(
+                r#"{{"method": "solve", "deal": "{}", "trump": "N", "leader": "W", "played": ["ZZ"]}}"#,
+                DEAL
+            ),
+        );
+        assert_eq!(response["error"]["code"], "invalid_card");
+    }
+}
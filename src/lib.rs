@@ -12,7 +12,7 @@
 //! # Example using bridge-types
 //!
 //! ```
-//! use bridge_solver::{Hands, Solver, CutoffCache, PatternCache, NOTRUMP, WEST};
+//! use bridge_solver::{Hands, Solver, CutoffCache, PatternCache, Seat, Strain};
 //! use bridge_types::Deal;
 //!
 //! let deal = Deal::from_pbn("N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72").unwrap();
@@ -20,32 +20,124 @@
 //!
 //! let mut cutoff = CutoffCache::new(16);
 //! let mut pattern = PatternCache::new(16);
-//! let solver = Solver::new(hands, NOTRUMP, WEST);
+//! let solver = Solver::new(hands, Strain::NoTrump, Seat::West);
 //! let ns_tricks = solver.solve_with_caches(&mut cutoff, &mut pattern);
+//!
+//! // Or solve the whole table and read it in bridge-types terms
+//! let table = bridge_solver::solve_deal(&deal);
+//! let tricks = table.tricks_for(bridge_types::Direction::North, None);
 //! ```
 
+mod analysis;
 mod bridge_solver;
 mod cache;
+mod canonical;
 pub mod cards;
+mod codec;
+mod combination;
 mod convert;
+mod database;
+mod dataset;
+mod endgame;
+#[cfg(feature = "dds")]
+pub mod ffi;
 mod hands;
+#[cfg(feature = "json")]
+pub mod json;
+mod par;
+mod parallel;
 mod pattern;
+mod pbn;
 mod play;
+#[cfg(feature = "python")]
+mod python;
+mod reference;
+mod review;
+mod scoring;
 mod search;
+#[cfg(feature = "serde")]
+mod serialize;
+mod session;
+mod stats;
+mod table;
+mod trace;
+mod tree;
+mod typed;
 pub mod types;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
+pub use analysis::{analyze_plays, analyze_plays_with_limit, AnalysisError, CardAnalysis};
 pub use bridge_solver::{
-    get_node_count, order_follows, order_leads, set_no_pruning, set_no_rank_skip, set_no_tt,
-    set_show_perf, set_xray_limit, OrderedCards, PartialTrick, PlayedCard, Solver,
+    cache_bits, cache_memory_limit, endgame_table, get_node_count, new_caches, order_follows,
+    order_leads, set_cache_bits, set_cache_memory_limit, set_endgame_table, set_no_bmi2,
+    set_no_pruning, set_no_rank_skip, set_no_tt, set_show_perf, set_xray_limit, OrderedCards,
+    PartialTrick, PlayedCard, SolveLimit, Solver, DEFAULT_CACHE_BITS,
+};
+pub use cache::{
+    load_caches, read_caches, save_caches, write_caches, CacheContext, CACHE_FILE_VERSION,
+};
+pub use canonical::{Canonical, CanonicalKey, ResultCache};
+pub use cards::{parse_card, Cards};
+pub use codec::{
+    pack_deal, pack_position, pack_table, unpack_deal, unpack_position, unpack_table, Code,
+    CodeError, FullDeal, CODE_VERSION, PACKED_DEAL_LEN, PACKED_TABLE_LEN,
+};
+pub use combination::{Holding, Layout, LineStep, SuitCombination, SuitLine};
+pub use convert::{
+    card_to_index, direction_to_seat, index_to_card, seat_to_direction, solve_contract, solve_deal,
 };
-pub use cards::Cards;
-pub use convert::{direction_to_seat, seat_to_direction};
+pub use database::{
+    deal_from_number, deal_number, DbEntry, ResultsDb, NUM_DEALS, RESULTS_DB_VERSION,
+    SOLVER_VERSION,
+};
+pub use dataset::{
+    generate_dataset, DatasetHeader, DatasetReader, DatasetRecord, DealConstraints,
+    DATASET_FILE_VERSION, DEFAULT_CHUNK_DEALS, MAX_CHUNK_DEALS,
+};
+pub use endgame::{EndgameTable, ENDGAME_FILE_VERSION, MAX_ENDGAME_TRICKS};
 pub use hands::Hands;
-pub use pattern::PatternCache;
+pub use par::{par, ParContract, ParResult};
+pub use pattern::{PatternCache, ReplacementPolicy};
+pub use pbn::{annotate_pbn, annotate_pbn_with_db, Annotation};
+pub use reference::{reference_solve, reference_solve_mid_trick};
+pub use review::{
+    review_pbn, review_pbn_with_db, BoardReview, ReviewBy, ReviewSummary, SummaryEntry, TableResult,
+};
+pub use scoring::{contract_score, imps, matchpoints, Contract, Doubled, Vulnerability};
 pub use search::{slow_trump_tricks_opponent, CutoffCache};
-pub use types::{Seat, Suit, NOTRUMP, NUM_RANKS, NUM_SEATS, NUM_SUITS, TOTAL_CARDS, TOTAL_TRICKS};
-pub use types::{CLUB, DIAMOND, HEART, SPADE};
-pub use types::{EAST, NORTH, SOUTH, WEST};
+pub use session::{PlayError, PlaySession};
+pub use stats::{CutoffCacheStats, PatternCacheStats, PatternTreeStats, SearchStats, SolveReport};
+pub use table::{solve_dd_table, solve_dd_table_cached, DdTable};
+#[cfg(feature = "json")]
+pub use trace::JsonTracer;
+pub use trace::{NoObserver, SearchNode, SearchObserver, XrayTracer};
+pub use tree::{NodeCutoff, SearchTree, TreeNode};
+pub use typed::{Card, Rank, Seat, Strain, Suit};
+pub use types::{NUM_RANKS, NUM_SEATS, NUM_STRAINS, NUM_SUITS, TOTAL_CARDS, TOTAL_TRICKS};
+
+// Raw indices from before the typed API. They stay in `types`, where the
+// solver uses them, without the deprecation.
+#[deprecated(note = "use `Suit::Spades` or `Strain::Spades`, or `types::SPADE` for the index")]
+pub const SPADE: usize = types::SPADE;
+#[deprecated(note = "use `Suit::Hearts` or `Strain::Hearts`, or `types::HEART` for the index")]
+pub const HEART: usize = types::HEART;
+#[deprecated(
+    note = "use `Suit::Diamonds` or `Strain::Diamonds`, or `types::DIAMOND` for the index"
+)]
+pub const DIAMOND: usize = types::DIAMOND;
+#[deprecated(note = "use `Suit::Clubs` or `Strain::Clubs`, or `types::CLUB` for the index")]
+pub const CLUB: usize = types::CLUB;
+#[deprecated(note = "use `Strain::NoTrump`, or `types::NOTRUMP` for the index")]
+pub const NOTRUMP: usize = types::NOTRUMP;
+#[deprecated(note = "use `Seat::West`, or `types::WEST` for the index")]
+pub const WEST: usize = types::WEST;
+#[deprecated(note = "use `Seat::North`, or `types::NORTH` for the index")]
+pub const NORTH: usize = types::NORTH;
+#[deprecated(note = "use `Seat::East`, or `types::EAST` for the index")]
+pub const EAST: usize = types::EAST;
+#[deprecated(note = "use `Seat::South`, or `types::SOUTH` for the index")]
+pub const SOUTH: usize = types::SOUTH;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,251 @@
+//! Par: the result when both sides bid perfectly with double-dummy knowledge
+//!
+//! The auction is modelled as a game between the two sides over the 35 bids.
+//! The side not holding the current contract may pass or overbid it; after a
+//! pass the side holding it may stop or raise. A contract that fails is
+//! doubled. Each side maximises its own score; ties go to the lower contract.
+
+use super::scoring::{contract_score, Doubled, Vulnerability};
+use super::table::DdTable;
+use super::types::*;
+use std::fmt;
+
+/// Number of bids from 1C to 7NT
+const NUM_BIDS: usize = 35;
+
+/// Strains in bidding order, lowest first
+const BID_STRAINS: [usize; NUM_STRAINS] = [CLUB, DIAMOND, HEART, SPADE, NOTRUMP];
+
+/// The par contract
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParContract {
+    /// Level 1-7
+    pub level: u8,
+    /// Suit or NOTRUMP
+    pub strain: usize,
+    pub declarer: SeatIndex,
+    /// Doubled because it fails (a sacrifice)
+    pub doubled: bool,
+    /// Tricks declarer takes
+    pub tricks: u8,
+}
+
+impl fmt::Display for ParContract {
+    /// Formats as e.g. "4S-N" or "5HX-E"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let doubled = if self.doubled { "X" } else { "" };
+        write!(
+            f,
+            "{}{}{}-{}",
+            self.level,
+            strain_name(self.strain),
+            doubled,
+            seat_letter(self.declarer)
+        )
+    }
+}
+
+/// Par score and contract
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParResult {
+    /// Score from NS's point of view
+    pub ns_score: i32,
+    /// None if the deal is passed out
+    pub contract: Option<ParContract>,
+}
+
+/// Compute par from a double-dummy table
+///
+/// The side of `dealer` gets the first chance to bid, which only matters when
+/// both sides could open profitably.
+pub fn par(table: &DdTable, vulnerability: Vulnerability, dealer: SeatIndex) -> ParResult {
+    let mut auction = Auction {
+        table,
+        vulnerability,
+        contested: [[None; 2]; NUM_BIDS],
+        uncontested: [[None; 2]; NUM_BIDS],
+    };
+
+    let first = side_of(dealer);
+    let second = 1 - first;
+    // Dealer's side passes and the other side passes too: passed out
+    let mut best = Outcome {
+        ns_score: 0,
+        contract: None,
+    };
+    for bid in 0..NUM_BIDS {
+        let outcome = auction.contested(bid, second);
+        if better_for(second, &outcome, &best) {
+            best = outcome;
+        }
+    }
+    for bid in 0..NUM_BIDS {
+        let outcome = auction.contested(bid, first);
+        if better_for(first, &outcome, &best) {
+            best = outcome;
+        }
+    }
+
+    ParResult {
+        ns_score: best.ns_score,
+        contract: best.contract.map(|(bid, side)| auction.contract(bid, side)),
+    }
+}
+
+/// 0 for NS, 1 for EW
+fn side_of(seat: SeatIndex) -> usize {
+    if is_ns(seat) {
+        0
+    } else {
+        1
+    }
+}
+
+/// Final result of an auction: NS score and the (bid, side) that plays
+#[derive(Clone, Copy)]
+struct Outcome {
+    ns_score: i32,
+    contract: Option<(usize, usize)>,
+}
+
+/// Check if `a` is strictly better than `b` for `side`
+fn better_for(side: usize, a: &Outcome, b: &Outcome) -> bool {
+    if side == 0 {
+        a.ns_score > b.ns_score
+    } else {
+        a.ns_score < b.ns_score
+    }
+}
+
+/// Memoised auction game
+struct Auction<'a> {
+    table: &'a DdTable,
+    vulnerability: Vulnerability,
+    /// Result once `side` has bid, with the other side to act
+    contested: [[Option<Outcome>; 2]; NUM_BIDS],
+    /// Result once the other side has passed `side`'s bid, with `side` to act
+    uncontested: [[Option<Outcome>; 2]; NUM_BIDS],
+}
+
+impl Auction<'_> {
+    /// Declarer of `side` taking the most tricks in the strain of `bid`
+    fn declarer(&self, bid: usize, side: usize) -> SeatIndex {
+        let strain = BID_STRAINS[bid % NUM_STRAINS];
+        let (first, second) = if side == 0 {
+            (NORTH, SOUTH)
+        } else {
+            (EAST, WEST)
+        };
+        if self.table.get_index(second, strain) > self.table.get_index(first, strain) {
+            second
+        } else {
+            first
+        }
+    }
+
+    fn contract(&self, bid: usize, side: usize) -> ParContract {
+        let level = (bid / NUM_STRAINS + 1) as u8;
+        let strain = BID_STRAINS[bid % NUM_STRAINS];
+        let declarer = self.declarer(bid, side);
+        let tricks = self.table.get_index(declarer, strain);
+        ParContract {
+            level,
+            strain,
+            declarer,
+            doubled: tricks < level + 6,
+            tricks,
+        }
+    }
+
+    /// NS score when `side` plays `bid`, doubled if it fails
+    fn play(&self, bid: usize, side: usize) -> Outcome {
+        let contract = self.contract(bid, side);
+        let doubled = if contract.doubled {
+            Doubled::Doubled
+        } else {
+            Doubled::Undoubled
+        };
+        let score = contract_score(
+            contract.level,
+            contract.strain,
+            doubled,
+            self.vulnerability.is_vulnerable(contract.declarer),
+            contract.tricks,
+        );
+        Outcome {
+            ns_score: if side == 0 { score } else { -score },
+            contract: Some((bid, side)),
+        }
+    }
+
+    /// `side` has bid `bid`; the other side passes or overbids
+    fn contested(&mut self, bid: usize, side: usize) -> Outcome {
+        if let Some(outcome) = self.contested[bid][side] {
+            return outcome;
+        }
+        let other = 1 - side;
+        let mut best = self.uncontested(bid, side);
+        for higher in bid + 1..NUM_BIDS {
+            let outcome = self.contested(higher, other);
+            if better_for(other, &outcome, &best) {
+                best = outcome;
+            }
+        }
+        self.contested[bid][side] = Some(best);
+        best
+    }
+
+    /// The other side has passed `side`'s bid; `side` stops or raises
+    fn uncontested(&mut self, bid: usize, side: usize) -> Outcome {
+        if let Some(outcome) = self.uncontested[bid][side] {
+            return outcome;
+        }
+        let mut best = self.play(bid, side);
+        for higher in bid + 1..NUM_BIDS {
+            let outcome = self.contested(higher, side);
+            if better_for(side, &outcome, &best) {
+                best = outcome;
+            }
+        }
+        self.uncontested[bid][side] = Some(best);
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Table where every declarer takes `tricks[side][strain]`
+    fn table(ns: [u8; NUM_STRAINS], ew: [u8; NUM_STRAINS]) -> DdTable {
+        DdTable::from_tricks([ew, ns, ew, ns])
+    }
+
+    #[test]
+    fn test_par_game() {
+        // NS make 10 tricks in spades, EW nothing much
+        let dd = table([10, 7, 6, 6, 8], [3, 6, 7, 7, 5]);
+        let result = par(&dd, Vulnerability::None, NORTH);
+        assert_eq!(result.ns_score, 420);
+        let contract = result.contract.unwrap();
+        assert_eq!(contract.to_string(), "4S-N");
+        assert!(!contract.doubled);
+    }
+
+    #[test]
+    fn test_par_sacrifice() {
+        // NS vulnerable make 4S; EW not vulnerable take 9 tricks in hearts
+        let dd = table([10, 4, 7, 7, 8], [3, 9, 6, 6, 4]);
+        let result = par(&dd, Vulnerability::NS, NORTH);
+        assert_eq!(result.ns_score, 300);
+        assert_eq!(result.contract.unwrap().to_string(), "5HX-E");
+    }
+
+    #[test]
+    fn test_par_passed_out() {
+        let dd = table([6; NUM_STRAINS], [6; NUM_STRAINS]);
+        let result = par(&dd, Vulnerability::Both, WEST);
+        assert_eq!(result.ns_score, 0);
+        assert!(result.contract.is_none());
+    }
+}
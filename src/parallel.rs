@@ -0,0 +1,80 @@
+//! Spreading work over threads
+//!
+//! The library has no thread pool: batch entry points split their work into
+//! one chunk per thread and run the chunks under `std::thread::scope`.
+
+use std::thread;
+
+/// Split `items` into one chunk per thread (as many threads as the machine
+/// has when `threads` is None) and run `f` on each chunk on its own thread,
+/// with the index of the chunk's first item
+pub(crate) fn for_each_chunk<T: Send>(
+    items: &mut [T],
+    threads: Option<usize>,
+    f: impl Fn(usize, &mut [T]) + Sync,
+) {
+    let num_threads = threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()))
+        .max(1);
+    let chunk_size = items.len().div_ceil(num_threads).max(1);
+    let f = &f;
+    thread::scope(|scope| {
+        for (i, chunk) in items.chunks_mut(chunk_size).enumerate() {
+            scope.spawn(move || f(i * chunk_size, chunk));
+        }
+    });
+}
+
+/// Apply `f` to every item, spreading the items over threads as
+/// `for_each_chunk` does
+#[cfg(any(feature = "dds", feature = "python"))]
+pub(crate) fn parallel_map<T: Sync, R: Send + Clone + Default>(
+    items: &[T],
+    threads: Option<usize>,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let mut results = vec![R::default(); items.len()];
+    for_each_chunk(&mut results, threads, |first, results| {
+        for (item, result) in items[first..].iter().zip(results) {
+            *result = f(item);
+        }
+    });
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(any(feature = "dds", feature = "python"))]
+    fn test_parallel_map() {
+        use crate::hands::Hands;
+        use crate::table::solve_dd_table;
+        use crate::types::*;
+
+        let deals: Vec<Hands> = ["N:A.A.. K.K.. 2.2.. 3.3..", "N:2.2.. A.A.. 3.3.. K.K.."]
+            .iter()
+            .map(|pbn| Hands::from_pbn(pbn).unwrap())
+            .collect();
+        let tables = parallel_map(&deals, Some(2), solve_dd_table);
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].get_index(NORTH, NOTRUMP), 2);
+        assert_eq!(tables[1].get_index(EAST, NOTRUMP), 2);
+        assert_eq!(
+            parallel_map(&deals, None, |hands| hands.num_tricks()),
+            [2, 2]
+        );
+    }
+
+    #[test]
+    fn test_for_each_chunk() {
+        let mut items = vec![0; 10];
+        for_each_chunk(&mut items, Some(3), |first, chunk| {
+            for (offset, item) in chunk.iter_mut().enumerate() {
+                *item = first + offset;
+            }
+        });
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+    }
+}
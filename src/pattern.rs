@@ -7,67 +7,144 @@
 //!
 //! This allows matching positions that are equivalent due to card equivalence.
 
+#[cfg(target_arch = "x86_64")]
+use super::bridge_solver::NO_BMI2;
+use super::cache::{invalid_data, put_u32, put_u64, ByteReader, MAX_CACHE_FILE_BITS};
 use super::cards::{mask_of, suit_of, Cards};
 use super::hands::Hands;
+use super::stats::{PatternCacheStats, PatternTreeStats};
 use super::types::*;
+use std::io;
+#[cfg(target_arch = "x86_64")]
+use std::sync::atomic::Ordering;
+#[cfg(target_arch = "x86_64")]
+use std::sync::OnceLock;
+
+/// Deepest pattern tree accepted when reading a file; each level is strictly
+/// more specific than its parent, so real trees are far shallower
+const MAX_PATTERN_DEPTH: usize = TOTAL_CARDS;
 
 /// Pack bits: extract bits from source where mask has 1s, compress them to low bits
 /// Example: PackBits(0b10100, 0b11100) = 0b101 (extracts bits 2,3,4 and packs to 0,1,2)
+///
+/// Uses the BMI2 PEXT instruction where the CPU runs it fast (see `use_bmi2`).
 #[inline]
 pub fn pack_bits(source: u64, mask: u64) -> u64 {
-    #[cfg(target_feature = "bmi2")]
-    {
-        // Use PEXT instruction if available
-        unsafe { core::arch::x86_64::_pext_u64(source, mask) }
-    }
-    #[cfg(not(target_feature = "bmi2"))]
-    {
-        if source == 0 {
-            return 0;
-        }
-        let mut packed = 0u64;
-        let mut bit = 1u64;
-        let mut m = mask;
-        while m != 0 {
-            let lowest = m & m.wrapping_neg(); // isolate lowest bit
-            if source & lowest != 0 {
-                packed |= bit;
-            }
-            bit <<= 1;
-            m &= m - 1; // clear lowest bit
-        }
-        packed
+    #[cfg(target_arch = "x86_64")]
+    if use_bmi2() {
+        // SAFETY: the CPU supports BMI2
+        return unsafe { pext(source, mask) };
     }
+    pack_bits_portable(source, mask)
 }
 
 /// Unpack bits: scatter source bits to positions where mask has 1s
 /// Example: UnpackBits(0b101, 0b11100) = 0b10100 (scatters bits 0,1,2 to positions 2,3,4)
+///
+/// Uses the BMI2 PDEP instruction where the CPU runs it fast (see `use_bmi2`).
 #[inline]
 pub fn unpack_bits(source: u64, mask: u64) -> u64 {
-    #[cfg(target_feature = "bmi2")]
-    {
-        // Use PDEP instruction if available
-        unsafe { core::arch::x86_64::_pdep_u64(source, mask) }
-    }
-    #[cfg(not(target_feature = "bmi2"))]
-    {
-        if source == 0 {
-            return 0;
-        }
-        let mut unpacked = 0u64;
-        let mut bit = 1u64;
-        let mut src = source;
-        let mut m = mask;
-        while src != 0 && m != 0 {
-            if src & bit != 0 {
-                unpacked |= m & m.wrapping_neg();
-                src &= !bit;
-            }
-            bit <<= 1;
-            m &= m - 1;
+    #[cfg(target_arch = "x86_64")]
+    if use_bmi2() {
+        // SAFETY: the CPU supports BMI2
+        return unsafe { pdep(source, mask) };
+    }
+    unpack_bits_portable(source, mask)
+}
+
+/// Check if BMI2 is available, fast, and not disabled with `set_no_bmi2`
+///
+/// AMD CPUs before Zen 3 report BMI2 but run PEXT and PDEP in microcode, at
+/// a cost growing with the mask, far slower than the portable loops; they
+/// keep the portable path. The detection runs once, so this is two loads.
+#[cfg(target_arch = "x86_64")]
+#[inline]
+fn use_bmi2() -> bool {
+    static FAST_BMI2: OnceLock<bool> = OnceLock::new();
+    !NO_BMI2.load(Ordering::Relaxed)
+        && *FAST_BMI2.get_or_init(|| std::is_x86_feature_detected!("bmi2") && !microcoded_bmi2())
+}
+
+/// Check if this CPU runs PEXT and PDEP in microcode
+#[cfg(target_arch = "x86_64")]
+fn microcoded_bmi2() -> bool {
+    use core::arch::x86_64::__cpuid;
+    #[allow(unused_unsafe)] // `__cpuid` is safe on newer compilers
+    // SAFETY: CPUID is available on every x86_64 CPU
+    let (vendor, signature) = unsafe {
+        let leaf0 = __cpuid(0);
+        let mut vendor = [0u8; 12];
+        vendor[..4].copy_from_slice(&leaf0.ebx.to_le_bytes());
+        vendor[4..8].copy_from_slice(&leaf0.edx.to_le_bytes());
+        vendor[8..].copy_from_slice(&leaf0.ecx.to_le_bytes());
+        (vendor, __cpuid(1).eax)
+    };
+    is_microcoded_bmi2(&vendor, signature)
+}
+
+/// Check if the CPU with this vendor string and CPUID signature (leaf 1 EAX)
+/// runs PEXT and PDEP in microcode: AMD (and Hygon, licensed Zen 1) before
+/// family 0x19, Zen 3
+#[cfg(target_arch = "x86_64")]
+fn is_microcoded_bmi2(vendor: &[u8; 12], signature: u32) -> bool {
+    let base_family = (signature >> 8) & 0xF;
+    let family = if base_family == 0xF {
+        base_family + ((signature >> 20) & 0xFF)
+    } else {
+        base_family
+    };
+    matches!(vendor, b"AuthenticAMD" | b"HygonGenuine") && family < 0x19
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "bmi2")]
+unsafe fn pext(source: u64, mask: u64) -> u64 {
+    core::arch::x86_64::_pext_u64(source, mask)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "bmi2")]
+unsafe fn pdep(source: u64, mask: u64) -> u64 {
+    core::arch::x86_64::_pdep_u64(source, mask)
+}
+
+/// Portable `pack_bits`, one mask bit at a time
+fn pack_bits_portable(source: u64, mask: u64) -> u64 {
+    if source == 0 {
+        return 0;
+    }
+    let mut packed = 0u64;
+    let mut bit = 1u64;
+    let mut m = mask;
+    while m != 0 {
+        let lowest = m & m.wrapping_neg(); // isolate lowest bit
+        if source & lowest != 0 {
+            packed |= bit;
+        }
+        bit <<= 1;
+        m &= m - 1; // clear lowest bit
+    }
+    packed
+}
+
+/// Portable `unpack_bits`, one source bit at a time
+fn unpack_bits_portable(source: u64, mask: u64) -> u64 {
+    if source == 0 {
+        return 0;
+    }
+    let mut unpacked = 0u64;
+    let mut bit = 1u64;
+    let mut src = source;
+    let mut m = mask;
+    while src != 0 && m != 0 {
+        if src & bit != 0 {
+            unpacked |= m & m.wrapping_neg();
+            src &= !bit;
         }
-        unpacked
+        bit <<= 1;
+        m &= m - 1;
     }
+    unpacked
 }
 
 /// Shape encodes the suit lengths for all 4 hands in 64 bits
@@ -91,7 +168,7 @@ impl Shape {
     }
 
     /// Update shape after a trick is played
-    pub fn play_cards(&mut self, seat: Seat, c1: usize, c2: usize, c3: usize, c4: usize) {
+    pub fn play_cards(&mut self, seat: SeatIndex, c1: usize, c2: usize, c3: usize, c4: usize) {
         self.value -= 1u64 << Self::offset(seat, suit_of(c1));
         self.value -= 1u64 << Self::offset((seat + 1) % NUM_SEATS, suit_of(c2));
         self.value -= 1u64 << Self::offset((seat + 2) % NUM_SEATS, suit_of(c3));
@@ -103,7 +180,7 @@ impl Shape {
     }
 
     #[inline]
-    fn offset(seat: Seat, suit: Suit) -> u32 {
+    fn offset(seat: SeatIndex, suit: SuitIndex) -> u32 {
         (60 - (seat * NUM_SUITS + suit) * 4) as u32
     }
 }
@@ -138,12 +215,13 @@ impl Bounds {
 }
 
 /// A Pattern stores hands with relative cards and bounds
-/// Patterns form a tree where more specific patterns are children of more general ones
-#[derive(Clone)]
+///
+/// In the cache, patterns form a tree where more specific patterns are
+/// children of more general ones (see [`PatternCache`]).
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Pattern {
     pub hands: Hands,
     pub bounds: Bounds,
-    pub children: Vec<Pattern>,
 }
 
 impl Default for Pattern {
@@ -151,116 +229,13 @@ impl Default for Pattern {
         Pattern {
             hands: Hands::default(),
             bounds: Bounds::new(0, TOTAL_TRICKS as i8),
-            children: Vec::new(),
         }
     }
 }
 
 impl Pattern {
     pub fn new(hands: Hands, bounds: Bounds) -> Self {
-        Pattern {
-            hands,
-            bounds,
-            children: Vec::new(),
-        }
-    }
-
-    pub fn reset(&mut self) {
-        self.hands = Hands::default();
-        self.bounds = Bounds::new(0, TOTAL_TRICKS as i8);
-        self.children.clear();
-    }
-
-    /// Look up a pattern that matches and causes a cutoff at beta
-    pub fn lookup(&self, new_pattern: &Pattern, beta: i8) -> Option<&Pattern> {
-        for child in &self.children {
-            if !new_pattern.is_subset_of(child) {
-                continue;
-            }
-            if child.bounds.cutoff(beta) {
-                return Some(child);
-            }
-            if let Some(detail) = child.lookup(new_pattern, beta) {
-                return Some(detail);
-            }
-        }
-        None
-    }
-
-    /// Update the pattern tree with a new pattern
-    pub fn update(&mut self, mut new_pattern: Pattern) {
-        for i in 0..self.children.len() {
-            let child = &mut self.children[i];
-
-            if new_pattern.hands_equal(child) {
-                // Same pattern - update bounds
-                child.update_bounds(new_pattern.bounds);
-                return;
-            } else if new_pattern.is_subset_of(child) {
-                // New pattern is more specific - add under existing
-                new_pattern.bounds = new_pattern.bounds.intersect(child.bounds);
-                if !new_pattern.bounds.is_empty() && new_pattern.bounds != child.bounds {
-                    child.update(new_pattern);
-                }
-                return;
-            } else if child.is_subset_of(&new_pattern) {
-                // New pattern is more general - absorb child
-                child.update_bounds(new_pattern.bounds);
-                if child.bounds != new_pattern.bounds {
-                    new_pattern.children.push(self.children.swap_remove(i));
-                } else {
-                    // Transfer children
-                    let mut old_children = Vec::new();
-                    std::mem::swap(&mut old_children, &mut self.children[i].children);
-                    new_pattern.children.append(&mut old_children);
-                    self.children.swap_remove(i);
-                }
-                // Check remaining children
-                let mut j = i;
-                while j < self.children.len() {
-                    if self.children[j].is_subset_of(&new_pattern) {
-                        self.children[j].update_bounds(new_pattern.bounds);
-                        if self.children[j].bounds != new_pattern.bounds {
-                            new_pattern.children.push(self.children.swap_remove(j));
-                        } else if new_pattern.children.is_empty() {
-                            let mut old_children = Vec::new();
-                            std::mem::swap(&mut old_children, &mut self.children[j].children);
-                            new_pattern.children = old_children;
-                            self.children.swap_remove(j);
-                        } else {
-                            let removed = self.children.swap_remove(j);
-                            new_pattern.children.extend(removed.children);
-                        }
-                    } else {
-                        j += 1;
-                    }
-                }
-                self.children.push(new_pattern);
-                return;
-            }
-        }
-        // No relationship - add as new child
-        self.children.push(new_pattern);
-    }
-
-    /// Update bounds and propagate to children
-    fn update_bounds(&mut self, new_bounds: Bounds) {
-        let old_bounds = self.bounds;
-        self.bounds = self.bounds.intersect(new_bounds);
-        if self.bounds.is_empty() || self.bounds == old_bounds {
-            return;
-        }
-        let mut i = 0;
-        while i < self.children.len() {
-            self.children[i].update_bounds(self.bounds);
-            if self.children[i].bounds != self.bounds {
-                i += 1;
-            } else {
-                // Child bounds now match parent - flatten
-                let removed = self.children.swap_remove(i);
-                self.children.extend(removed.children);
-            }
-        }
+        Pattern { hands, bounds }
     }
 
     /// Check if this pattern is a subset of (more specific than) another
@@ -297,85 +272,697 @@ impl Pattern {
     }
 }
 
-/// ShapeEntry is what gets stored in the cache
-#[derive(Default)]
-pub struct ShapeEntry {
-    pub hash: u64,
-    pub pattern: Pattern,
+/// Index of a node in the pattern arena
+type NodeId = u32;
+
+/// No node: the end of a list, or an entry without a tree
+const NONE: NodeId = NodeId::MAX;
+
+/// Children of a node, as a doubly-linked list through `Node::prev/next`
+///
+/// Removing a child moves the last one into its place, keeping the order
+/// `Vec::swap_remove` would.
+#[derive(Clone, Copy)]
+struct Children {
+    first: NodeId,
+    last: NodeId,
 }
 
-impl ShapeEntry {
-    pub fn reset(&mut self, hash: u64) {
-        self.hash = hash;
-        self.pattern.reset();
-    }
+impl Children {
+    const EMPTY: Children = Children {
+        first: NONE,
+        last: NONE,
+    };
+}
 
-    /// Look up a matching pattern that causes a cutoff
-    pub fn lookup(&self, new_pattern: &Pattern, beta: i8) -> Option<(&Hands, Bounds)> {
-        // First check root pattern
-        if self.pattern.bounds.cutoff(beta) && new_pattern.is_subset_of(&self.pattern) {
-            return Some((&self.pattern.hands, self.pattern.bounds));
+/// A pattern tree node, linked to its children and siblings by index
+#[derive(Clone, Copy)]
+struct Node {
+    pattern: Pattern,
+    children: Children,
+    prev: NodeId,
+    /// Next sibling, or next free node while on the free list
+    next: NodeId,
+}
+
+impl Node {
+    fn new(pattern: Pattern) -> Self {
+        Node {
+            pattern,
+            children: Children::EMPTY,
+            prev: NONE,
+            next: NONE,
         }
-        // Then search children
-        if let Some(matched) = self.pattern.lookup(new_pattern, beta) {
-            return Some((&matched.hands, matched.bounds));
+    }
+}
+
+/// Where a list of children hangs: the root of an entry's tree or a node
+///
+/// The root pattern itself (no cards, bounds 0..=13) is never stored: it
+/// matches everything and its bounds never change.
+#[derive(Clone, Copy)]
+enum Parent {
+    Root(usize),
+    Node(NodeId),
+}
+
+/// ShapeEntry is what gets stored in the cache
+#[derive(Clone, Copy)]
+struct ShapeEntry {
+    hash: u64,
+    /// Clock value of the last store
+    last_used: u64,
+    /// Patterns under the root of the tree (none = unused)
+    children: Children,
+    /// Nodes in the pattern tree
+    nodes: u32,
+    /// The entry is only valid while this matches the cache's epoch
+    epoch: u32,
+    /// Cards left in the position (fewer = deeper in the search)
+    cards: u8,
+}
+
+impl Default for ShapeEntry {
+    fn default() -> Self {
+        ShapeEntry {
+            hash: 0,
+            last_used: 0,
+            children: Children::EMPTY,
+            nodes: 0,
+            epoch: 0,
+            cards: 0,
         }
-        None
     }
 }
 
+/// Which entry gives way when a cache bucket is full or memory runs short
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReplacementPolicy {
+    /// Replace the entry with the fewest cards left, which is cheapest to
+    /// recompute (least recently used among equals)
+    #[default]
+    Depth,
+    /// Replace the least recently used entry
+    Age,
+    /// Replace the entry whose pattern tree uses the most memory
+    Size,
+}
+
+/// Entries per bucket; a shape can live in any entry of its bucket
+const BUCKET_WAYS: usize = 4;
+
 /// The common bounds cache - hash table of ShapeEntries
+///
+/// Entries are grouped in buckets of `BUCKET_WAYS`. Each entry owns a tree
+/// of patterns, whose nodes live in one arena shared by all entries; freed
+/// nodes go on a free list for reuse, and `clear()` drops everything at once.
+/// A cache made with [`PatternCache::with_memory_limit`] caps the arena,
+/// evicting entries to make room as needed.
 pub struct PatternCache {
     entries: Box<[ShapeEntry]>,
+    /// Number of buckets - 1
     mask: usize,
+    policy: ReplacementPolicy,
+    nodes: Vec<Node>,
+    /// Head of the free list, linked through `Node::next`
+    free: NodeId,
+    free_count: usize,
+    /// Most nodes the arena may hold
+    max_nodes: usize,
+    /// Memory budget in bytes (None = unbounded)
+    budget: Option<usize>,
+    /// Current generation of entries; bumped by `clear()`
+    epoch: u32,
+    /// Incremented on every store, for ages
+    clock: u64,
+    /// Next bucket to evict from when the arena is full
+    sweep: usize,
+    stats: PatternCacheStats,
 }
 
 impl PatternCache {
+    /// Create a cache of 2^bits entries with unbounded pattern trees
     pub fn new(bits: usize) -> Self {
-        let size = 1 << bits;
-        let mut entries = Vec::with_capacity(size);
-        for _ in 0..size {
-            entries.push(ShapeEntry::default());
-        }
+        let size = 1 << bits.max(BUCKET_WAYS.trailing_zeros() as usize);
         PatternCache {
-            entries: entries.into_boxed_slice(),
-            mask: size - 1,
+            entries: vec![ShapeEntry::default(); size].into_boxed_slice(),
+            mask: size / BUCKET_WAYS - 1,
+            policy: ReplacementPolicy::default(),
+            nodes: Vec::new(),
+            free: NONE,
+            free_count: 0,
+            // Node ids must stay below NONE
+            max_nodes: NONE as usize,
+            budget: None,
+            epoch: 1,
+            clock: 0,
+            sweep: 0,
+            stats: PatternCacheStats::default(),
         }
     }
 
+    /// Create a cache using at most `megabytes` of memory in total
+    ///
+    /// A quarter of the budget goes to the table of entries and the rest to
+    /// pattern trees.
+    pub fn with_memory_limit(megabytes: usize) -> Self {
+        let budget = megabytes.max(1) << 20;
+        let entry_size = std::mem::size_of::<ShapeEntry>();
+        let bits = (budget / 4 / entry_size).ilog2() as usize;
+        let mut cache = PatternCache::new(bits);
+        let table_bytes = cache.entries.len() * entry_size;
+        cache.max_nodes = (budget - table_bytes) / std::mem::size_of::<Node>();
+        cache.budget = Some(budget);
+        cache
+    }
+
+    /// Use `policy` to choose entries to replace
+    pub fn with_policy(mut self, policy: ReplacementPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Memory in use: the table of entries plus the pattern arena, in bytes
+    pub fn memory_used(&self) -> usize {
+        self.entries.len() * std::mem::size_of::<ShapeEntry>()
+            + self.nodes.capacity() * std::mem::size_of::<Node>()
+    }
+
+    /// Memory budget in bytes, if any
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.budget
+    }
+
+    /// Size of the table, as 2^bits entries
+    pub(crate) fn bits(&self) -> usize {
+        self.entries.len().trailing_zeros() as usize
+    }
+
+    /// Number of entries holding a shape
+    pub fn len(&self) -> usize {
+        (0..self.entries.len()).filter(|&i| self.is_used(i)).count()
+    }
+
+    /// Check if no entry holds a shape
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Counters since the cache was created or `reset_stats` was called
+    pub fn stats(&self) -> PatternCacheStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = PatternCacheStats::default();
+    }
+
+    /// Sizes of the pattern trees now held (walks every tree)
+    pub fn tree_stats(&self) -> PatternTreeStats {
+        let mut stats = PatternTreeStats::default();
+        for index in (0..self.entries.len()).filter(|&i| self.is_used(i)) {
+            stats.trees += 1;
+            stats.largest = stats.largest.max(self.entries[index].nodes as u64);
+            self.add_tree_stats(&mut stats, Parent::Root(index), 1);
+        }
+        stats
+    }
+
+    fn add_tree_stats(&self, stats: &mut PatternTreeStats, parent: Parent, depth: u64) {
+        let mut child = self.children(parent).first;
+        while child != NONE {
+            stats.patterns += 1;
+            stats.total_depth += depth;
+            stats.max_depth = stats.max_depth.max(depth);
+            self.add_tree_stats(stats, Parent::Node(child), depth + 1);
+            child = self.nodes[child as usize].next;
+        }
+    }
+
+    /// Empty the cache, keeping the table and arena for reuse
+    pub fn clear(&mut self) {
+        self.epoch = self.epoch.wrapping_add(1);
+        if self.epoch == 0 {
+            // Entries of the old epoch 1 would look valid again
+            self.entries.fill(ShapeEntry::default());
+            self.epoch = 1;
+        }
+        self.nodes.clear();
+        self.free = NONE;
+        self.free_count = 0;
+        self.clock = 0;
+        self.sweep = 0;
+    }
+
     /// Hash function matching C++ Cache template
-    fn hash(shape: u64, seat_to_play: Seat) -> u64 {
+    fn hash(shape: u64, seat_to_play: SeatIndex) -> u64 {
         const HASH_RAND: [u64; 2] = [0x9b8b4567327b23c7, 0x643c986966334873];
         let key0 = shape.wrapping_add(HASH_RAND[0]);
         let key1 = (seat_to_play as u64).wrapping_add(HASH_RAND[1]);
         key0.wrapping_mul(key1)
     }
 
+    /// Entries of the bucket for `hash`
     #[inline]
-    fn index(&self, hash: u64) -> usize {
+    fn bucket(&self, hash: u64) -> std::ops::Range<usize> {
         // Use top bits for index (like C++)
-        (hash >> (64 - (self.mask + 1).trailing_zeros())) as usize & self.mask
+        let bucket_bits = (self.mask + 1).trailing_zeros();
+        let bucket = hash.checked_shr(64 - bucket_bits).unwrap_or(0) as usize & self.mask;
+        bucket * BUCKET_WAYS..(bucket + 1) * BUCKET_WAYS
+    }
+
+    #[inline]
+    fn is_used(&self, index: usize) -> bool {
+        let entry = &self.entries[index];
+        entry.epoch == self.epoch && entry.children.first != NONE
     }
 
-    /// Look up a shape entry
-    pub fn lookup(&self, shape: u64, seat_to_play: Seat) -> Option<&ShapeEntry> {
+    /// Look up a stored pattern matching `new_pattern` that causes a cutoff
+    /// at `beta`, in the tree of a shape
+    pub fn lookup(
+        &mut self,
+        shape: u64,
+        seat_to_play: SeatIndex,
+        new_pattern: &Pattern,
+        beta: i8,
+    ) -> Option<Pattern> {
+        self.stats.lookups += 1;
         let hash = Self::hash(shape, seat_to_play);
-        let entry = &self.entries[self.index(hash)];
-        if entry.hash == hash {
-            Some(entry)
+        let index = self
+            .bucket(hash)
+            .find(|&i| self.entries[i].hash == hash && self.is_used(i))?;
+        self.stats.shape_hits += 1;
+        // First check root pattern
+        let root = Pattern::default();
+        let matched = if root.bounds.cutoff(beta) && new_pattern.is_subset_of(&root) {
+            Some(root)
         } else {
-            None
+            // Then search children
+            self.lookup_under(Parent::Root(index), new_pattern, beta)
+                .map(|node| self.nodes[node as usize].pattern)
+        };
+        self.stats.cutoffs += matched.is_some() as u64;
+        matched
+    }
+
+    /// Look up a pattern under `parent` that matches and causes a cutoff
+    fn lookup_under(&self, parent: Parent, new_pattern: &Pattern, beta: i8) -> Option<NodeId> {
+        let mut child = self.children(parent).first;
+        while child != NONE {
+            let node = &self.nodes[child as usize];
+            if new_pattern.is_subset_of(&node.pattern) {
+                if node.pattern.bounds.cutoff(beta) {
+                    return Some(child);
+                }
+                if let Some(detail) = self.lookup_under(Parent::Node(child), new_pattern, beta) {
+                    return Some(detail);
+                }
+            }
+            child = node.next;
         }
+        None
     }
 
-    /// Get or create a shape entry for update
-    pub fn get_or_create(&mut self, shape: u64, seat_to_play: Seat) -> &mut ShapeEntry {
+    /// Add `pattern` to the pattern tree of a shape, making room if needed
+    pub fn store(&mut self, shape: u64, seat_to_play: SeatIndex, pattern: Pattern) {
+        self.stats.stores += 1;
         let hash = Self::hash(shape, seat_to_play);
-        let idx = self.index(hash);
-        if self.entries[idx].hash != hash {
-            self.entries[idx].reset(hash);
+        let index = self.find_or_replace(hash, shape);
+        self.clock += 1;
+        self.entries[index].last_used = self.clock;
+
+        // A store allocates at most one node, for the new pattern
+        self.make_room(index, 1);
+        self.update(index, Parent::Root(index), pattern);
+    }
+
+    /// Index of the entry for `hash`, claiming one of its bucket if needed
+    fn find_or_replace(&mut self, hash: u64, shape: u64) -> usize {
+        let bucket = self.bucket(hash);
+        if let Some(index) = bucket
+            .clone()
+            .find(|&i| self.entries[i].hash == hash && self.is_used(i))
+        {
+            return index;
+        }
+        let index = match bucket.clone().find(|&i| !self.is_used(i)) {
+            Some(index) => index,
+            None => {
+                self.stats.collisions += 1;
+                self.victim(bucket, None).unwrap()
+            }
+        };
+        self.evict(index);
+        self.entries[index] = ShapeEntry {
+            hash,
+            // Each nibble of the shape is a suit length
+            cards: (0..16).map(|i| (shape >> (i * 4)) & 0xF).sum::<u64>() as u8,
+            epoch: self.epoch,
+            ..ShapeEntry::default()
+        };
+        index
+    }
+
+    /// The entry of `bucket` the policy replaces first, other than `keep`
+    fn victim(&self, bucket: std::ops::Range<usize>, keep: Option<usize>) -> Option<usize> {
+        let candidates = bucket.filter(|&i| Some(i) != keep && self.is_used(i));
+        let entries = &self.entries;
+        match self.policy {
+            ReplacementPolicy::Depth => {
+                candidates.min_by_key(|&i| (entries[i].cards, entries[i].last_used))
+            }
+            ReplacementPolicy::Age => candidates.min_by_key(|&i| entries[i].last_used),
+            ReplacementPolicy::Size => candidates.max_by_key(|&i| entries[i].nodes),
+        }
+    }
+
+    /// Drop an entry's pattern tree, freeing its nodes
+    fn evict(&mut self, index: usize) {
+        if self.is_used(index) {
+            let mut child = self.entries[index].children.first;
+            while child != NONE {
+                let next = self.nodes[child as usize].next;
+                self.free_tree(index, child);
+                child = next;
+            }
+        }
+        self.entries[index].children = Children::EMPTY;
+    }
+
+    /// Nodes that can be allocated without growing past the limit
+    fn available(&self) -> usize {
+        self.free_count + (self.max_nodes - self.nodes.len())
+    }
+
+    /// Evict entries, sweeping through the buckets, until `needed` nodes can
+    /// be allocated for the entry at `keep`
+    fn make_room(&mut self, keep: usize, needed: usize) {
+        for _ in 0..=self.mask {
+            if self.available() >= needed {
+                return;
+            }
+            let bucket = self.sweep * BUCKET_WAYS..(self.sweep + 1) * BUCKET_WAYS;
+            self.sweep = (self.sweep + 1) & self.mask;
+            if let Some(victim) = self.victim(bucket, Some(keep)) {
+                self.stats.memory_evictions += 1;
+                self.evict(victim);
+            }
+        }
+        // Only the entry being stored holds nodes: start its tree over
+        if self.available() < needed {
+            self.stats.memory_evictions += 1;
+            self.evict(keep);
+        }
+    }
+
+    /// Allocate a node for `pattern` in the tree of entry `index`
+    fn alloc(&mut self, index: usize, pattern: Pattern) -> NodeId {
+        self.entries[index].nodes += 1;
+        if self.free != NONE {
+            let node = self.free;
+            self.free = self.nodes[node as usize].next;
+            self.free_count -= 1;
+            self.nodes[node as usize] = Node::new(pattern);
+            return node;
+        }
+        if self.nodes.len() == self.nodes.capacity() {
+            // Grow as a Vec would, but never past the limit
+            let len = self.nodes.len();
+            self.nodes
+                .reserve_exact(len.max(64).min(self.max_nodes - len));
+        }
+        self.nodes.push(Node::new(pattern));
+        (self.nodes.len() - 1) as NodeId
+    }
+
+    /// Return a node (not its children) to the free list
+    fn free_node(&mut self, index: usize, node: NodeId) {
+        self.entries[index].nodes -= 1;
+        self.nodes[node as usize].next = self.free;
+        self.free = node;
+        self.free_count += 1;
+    }
+
+    /// Return a node and its whole subtree to the free list
+    fn free_tree(&mut self, index: usize, node: NodeId) {
+        let mut child = self.nodes[node as usize].children.first;
+        while child != NONE {
+            let next = self.nodes[child as usize].next;
+            self.free_tree(index, child);
+            child = next;
+        }
+        self.free_node(index, node);
+    }
+
+    fn children(&self, parent: Parent) -> Children {
+        match parent {
+            Parent::Root(index) => self.entries[index].children,
+            Parent::Node(node) => self.nodes[node as usize].children,
+        }
+    }
+
+    fn children_mut(&mut self, parent: Parent) -> &mut Children {
+        match parent {
+            Parent::Root(index) => &mut self.entries[index].children,
+            Parent::Node(node) => &mut self.nodes[node as usize].children,
+        }
+    }
+
+    /// Append `child` to the children of `parent`
+    fn push_child(&mut self, parent: Parent, child: NodeId) {
+        let last = self.children(parent).last;
+        self.nodes[child as usize].prev = last;
+        self.nodes[child as usize].next = NONE;
+        match last {
+            NONE => self.children_mut(parent).first = child,
+            _ => self.nodes[last as usize].next = child,
+        }
+        self.children_mut(parent).last = child;
+    }
+
+    /// Move all children of `from` to the end of the children of `to`
+    fn append_children(&mut self, to: NodeId, from: NodeId) {
+        let moved = std::mem::replace(&mut self.nodes[from as usize].children, Children::EMPTY);
+        if moved.first == NONE {
+            return;
+        }
+        let last = self.nodes[to as usize].children.last;
+        self.nodes[moved.first as usize].prev = last;
+        match last {
+            NONE => self.nodes[to as usize].children.first = moved.first,
+            _ => self.nodes[last as usize].next = moved.first,
+        }
+        self.nodes[to as usize].children.last = moved.last;
+    }
+
+    /// Unlink `child` from `parent`, moving the last child into its place
+    /// (like `Vec::swap_remove`); returns the child now in its place, if any
+    fn swap_remove(&mut self, parent: Parent, child: NodeId) -> NodeId {
+        let last = self.children(parent).last;
+        // Detach the last child
+        let before_last = self.nodes[last as usize].prev;
+        match before_last {
+            NONE => self.children_mut(parent).first = NONE,
+            _ => self.nodes[before_last as usize].next = NONE,
+        }
+        self.children_mut(parent).last = before_last;
+        if last == child {
+            return NONE;
+        }
+        // And put it where `child` was
+        let Node { prev, next, .. } = self.nodes[child as usize];
+        self.nodes[last as usize].prev = prev;
+        self.nodes[last as usize].next = next;
+        match prev {
+            NONE => self.children_mut(parent).first = last,
+            _ => self.nodes[prev as usize].next = last,
+        }
+        match next {
+            NONE => self.children_mut(parent).last = last,
+            _ => self.nodes[next as usize].prev = last,
+        }
+        last
+    }
+
+    /// Update the pattern tree under `parent` with a new pattern
+    fn update(&mut self, index: usize, mut parent: Parent, mut new_pattern: Pattern) {
+        'descend: loop {
+            let mut child = self.children(parent).first;
+            while child != NONE {
+                let node = self.nodes[child as usize];
+                if new_pattern.hands_equal(&node.pattern) {
+                    // Same pattern - update bounds
+                    self.update_bounds(index, child, new_pattern.bounds);
+                    return;
+                } else if new_pattern.is_subset_of(&node.pattern) {
+                    // New pattern is more specific - add under existing
+                    new_pattern.bounds = new_pattern.bounds.intersect(node.pattern.bounds);
+                    if !new_pattern.bounds.is_empty() && new_pattern.bounds != node.pattern.bounds {
+                        parent = Parent::Node(child);
+                        continue 'descend;
+                    }
+                    return;
+                } else if node.pattern.is_subset_of(&new_pattern) {
+                    // New pattern is more general - absorb this child and
+                    // any later ones it also covers
+                    let new_node = self.alloc(index, new_pattern);
+                    let mut next = child;
+                    while next != NONE {
+                        let node = self.nodes[next as usize];
+                        if !node.pattern.is_subset_of(&new_pattern) {
+                            next = node.next;
+                            continue;
+                        }
+                        self.update_bounds(index, next, new_pattern.bounds);
+                        let absorbed = next;
+                        next = self.swap_remove(parent, absorbed);
+                        if self.nodes[absorbed as usize].pattern.bounds != new_pattern.bounds {
+                            self.push_child(Parent::Node(new_node), absorbed);
+                        } else {
+                            // Transfer children
+                            self.append_children(new_node, absorbed);
+                            self.free_node(index, absorbed);
+                        }
+                    }
+                    self.push_child(parent, new_node);
+                    return;
+                }
+                child = node.next;
+            }
+            // No relationship - add as new child
+            let new_node = self.alloc(index, new_pattern);
+            self.push_child(parent, new_node);
+            return;
+        }
+    }
+
+    /// Update bounds of `node` and propagate to its children
+    fn update_bounds(&mut self, index: usize, node: NodeId, new_bounds: Bounds) {
+        let old_bounds = self.nodes[node as usize].pattern.bounds;
+        let bounds = old_bounds.intersect(new_bounds);
+        self.nodes[node as usize].pattern.bounds = bounds;
+        if bounds.is_empty() || bounds == old_bounds {
+            return;
+        }
+        let mut child = self.nodes[node as usize].children.first;
+        while child != NONE {
+            self.update_bounds(index, child, bounds);
+            if self.nodes[child as usize].pattern.bounds != bounds {
+                child = self.nodes[child as usize].next;
+            } else {
+                // Child bounds now match parent - flatten. Its children go
+                // to the end, so they are visited next if it was the last.
+                let grandchild = self.nodes[child as usize].children.first;
+                let next = self.swap_remove(Parent::Node(node), child);
+                self.append_children(node, child);
+                self.free_node(index, child);
+                child = if next != NONE { next } else { grandchild };
+            }
         }
-        &mut self.entries[idx]
+    }
+
+    /// Serialize the used entries (see the `cache` module)
+    pub(crate) fn write_to(&self, out: &mut Vec<u8>) {
+        put_u32(out, self.bits() as u32);
+        let used: Vec<_> = (0..self.entries.len())
+            .filter(|&i| self.is_used(i))
+            .collect();
+        put_u64(out, used.len() as u64);
+        for index in used {
+            let entry = &self.entries[index];
+            put_u32(out, index as u32);
+            put_u64(out, entry.hash);
+            out.push(entry.cards);
+            self.write_tree(out, &Pattern::default(), Parent::Root(index));
+        }
+    }
+
+    /// Serialize a pattern and the tree under it: pattern, number of
+    /// children, children
+    fn write_tree(&self, out: &mut Vec<u8>, pattern: &Pattern, parent: Parent) {
+        for seat in 0..NUM_SEATS {
+            put_u64(out, pattern.hands[seat].value());
+        }
+        out.push(pattern.bounds.lower as u8);
+        out.push(pattern.bounds.upper as u8);
+        let mut children = Vec::new();
+        let mut child = self.children(parent).first;
+        while child != NONE {
+            children.push(child);
+            child = self.nodes[child as usize].next;
+        }
+        put_u32(out, children.len() as u32);
+        for child in children {
+            let pattern = &self.nodes[child as usize].pattern;
+            self.write_tree(out, pattern, Parent::Node(child));
+        }
+    }
+
+    /// Deserialize a cache written by `write_to`
+    ///
+    /// The sizes are checked against the payload before the table is allocated.
+    pub(crate) fn read_from(input: &mut ByteReader) -> io::Result<Self> {
+        let bits = input.u32()? as usize;
+        let min_bits = BUCKET_WAYS.trailing_zeros() as usize;
+        let num_used = input.u64()?;
+        // Each entry takes at least 51 bytes: index, hash, cards and its root pattern
+        if !(min_bits..=MAX_CACHE_FILE_BITS).contains(&bits)
+            || num_used > 1 << bits
+            || num_used > input.remaining() as u64 / 51
+        {
+            return Err(invalid_data("invalid pattern cache size"));
+        }
+        let mut cache = PatternCache::new(bits);
+        for _ in 0..num_used {
+            let index = input.u32()? as usize;
+            let hash = input.u64()?;
+            let cards = input.u8()?;
+            if index >= cache.entries.len() || cache.is_used(index) || cards as usize > TOTAL_CARDS
+            {
+                return Err(invalid_data("invalid pattern cache entry"));
+            }
+            cache.entries[index] = ShapeEntry {
+                hash,
+                cards,
+                epoch: cache.epoch,
+                ..ShapeEntry::default()
+            };
+            if cache.read_tree(input, index, Parent::Root(index), 0)? != Pattern::default() {
+                return Err(invalid_data("invalid pattern tree root"));
+            }
+        }
+        Ok(cache)
+    }
+
+    /// Deserialize a pattern written by `write_tree`, adding its children to
+    /// `parent` in entry `index`
+    fn read_tree(
+        &mut self,
+        input: &mut ByteReader,
+        index: usize,
+        parent: Parent,
+        depth: usize,
+    ) -> io::Result<Pattern> {
+        if depth > MAX_PATTERN_DEPTH {
+            return Err(invalid_data("pattern tree too deep"));
+        }
+        let mut hands = Hands::new();
+        for seat in 0..NUM_SEATS {
+            hands[seat] = Cards::from_bits(input.u64()?);
+        }
+        let bounds = Bounds::new(input.i8()?, input.i8()?);
+        let num_children = input.u32()? as usize;
+        // Each child takes at least 38 bytes, which bounds the allocation
+        if num_children > input.remaining() / 38 {
+            return Err(invalid_data("invalid pattern tree"));
+        }
+        for _ in 0..num_children {
+            let child = self.alloc(index, Pattern::default());
+            self.push_child(parent, child);
+            let pattern = self.read_tree(input, index, Parent::Node(child), depth + 1)?;
+            self.nodes[child as usize].pattern = pattern;
+        }
+        Ok(Pattern::new(hands, bounds))
     }
 }
 
@@ -388,7 +975,7 @@ pub struct RelativeHands {
 
 impl RelativeHands {
     /// Convert a suit to relative cards
-    pub fn convert_suit(&mut self, hands: &Hands, suit: Suit, all_suit_cards: Cards) {
+    pub fn convert_suit(&mut self, hands: &Hands, suit: SuitIndex, all_suit_cards: Cards) {
         let all_value = all_suit_cards.value();
         for seat in 0..NUM_SEATS {
             let hand_suit = hands[seat].suit(suit);
@@ -443,7 +1030,7 @@ pub fn compute_pattern_hands(
         let mut actual_rel_bottom = rel_bottom;
         for seat in 0..NUM_SEATS {
             let rel_hand = relative_hands[seat].suit(suit);
-            if rel_hand.have(rel_bottom) {
+            if rel_hand.have_index(rel_bottom) {
                 // Extend to lowest equivalent card
                 let suit_value = rel_hand.value() >> (suit * NUM_RANKS);
                 let shift = rel_bottom - suit * NUM_RANKS + 1;
@@ -493,8 +1080,217 @@ fn relative_card_in_suit(card: usize, all_suit_cards: Cards) -> usize {
 }
 
 /// Helper: construct card from suit and rank
-fn card_of(suit: Suit, rank: usize) -> usize {
+fn card_of(suit: SuitIndex, rank: usize) -> usize {
     suit * NUM_RANKS + (ACE - rank)
 }
 
 const ACE: usize = 12;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::CutoffCache;
+    use crate::Solver;
+
+    const DEAL: &str = "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72";
+
+    /// A shape with `cards` cards in West's spades, distinct per `id`
+    fn shape(cards: u64, id: u64) -> u64 {
+        (cards << Shape::offset(WEST, SPADE)) | id
+    }
+
+    fn pattern(lower: i8) -> Pattern {
+        Pattern::new(Hands::default(), Bounds::new(lower, TOTAL_TRICKS as i8))
+    }
+
+    /// A pattern holding `cards` (as bits of North's hand)
+    fn north(cards: u64, lower: i8, upper: i8) -> Pattern {
+        let mut hands = Hands::default();
+        hands[NORTH] = Cards::from_bits(cards);
+        Pattern::new(hands, Bounds::new(lower, upper))
+    }
+
+    /// Check if the cache holds a tree for a shape
+    fn holds(cache: &mut PatternCache, shape: u64) -> bool {
+        // Any tree matches at beta 0, through its root
+        cache.lookup(shape, WEST, &Pattern::default(), 0).is_some()
+    }
+
+    /// Check that every arena node is either in exactly one tree or free
+    fn check_arena(cache: &PatternCache) {
+        fn count(cache: &PatternCache, node: NodeId) -> usize {
+            let mut total = 1;
+            let mut child = cache.nodes[node as usize].children.first;
+            while child != NONE {
+                total += count(cache, child);
+                child = cache.nodes[child as usize].next;
+            }
+            total
+        }
+        let mut in_trees = 0;
+        for index in (0..cache.entries.len()).filter(|&i| cache.is_used(i)) {
+            let mut nodes = 0;
+            let mut child = cache.entries[index].children.first;
+            while child != NONE {
+                nodes += count(cache, child);
+                child = cache.nodes[child as usize].next;
+            }
+            assert_eq!(nodes, cache.entries[index].nodes as usize);
+            in_trees += nodes;
+        }
+        assert_eq!(in_trees + cache.free_count, cache.nodes.len());
+    }
+
+    #[test]
+    fn test_bit_packing_matches_portable() {
+        // xorshift64, for reproducible masks of mixed density
+        let mut state = 0x2545f4914f6cdd1du64;
+        let mut random = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for i in 0..100_000 {
+            let mask = match i % 4 {
+                0 => random() & random(),
+                1 => random() | random(),
+                2 => random() >> (random() % 64),
+                _ => [0, u64::MAX, 0x1FFF, 1 << 63][i / 4 % 4],
+            };
+            let source = random();
+            let packed = pack_bits(source, mask);
+            assert_eq!(packed, pack_bits_portable(source, mask));
+            assert_eq!(
+                unpack_bits(source, mask),
+                unpack_bits_portable(source, mask)
+            );
+            assert_eq!(unpack_bits(packed, mask), source & mask);
+        }
+    }
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn test_microcoded_bmi2() {
+        // CPUID leaf 1 signatures: Zen 2 and Hygon Dhyana microcode PEXT,
+        // Zen 3, Zen 5 and Intel Haswell do not
+        assert!(is_microcoded_bmi2(b"AuthenticAMD", 0x0087_0F10));
+        assert!(is_microcoded_bmi2(b"HygonGenuine", 0x0090_0F01));
+        assert!(!is_microcoded_bmi2(b"AuthenticAMD", 0x00A2_0F10));
+        assert!(!is_microcoded_bmi2(b"AuthenticAMD", 0x00B4_0F00));
+        assert!(!is_microcoded_bmi2(b"GenuineIntel", 0x0003_06C3));
+    }
+
+    #[test]
+    fn test_bucket_holds_colliding_shapes() {
+        // A single bucket: four shapes fit, a fifth replaces one of them
+        let mut cache = PatternCache::new(2);
+        for id in 0..4 {
+            cache.store(shape(8, id), WEST, pattern(id as i8));
+        }
+        assert_eq!(cache.len(), 4);
+        for id in 0..4 {
+            let beta = id as i8;
+            let matched = cache.lookup(shape(8, id), WEST, &Pattern::default(), beta);
+            assert_eq!(matched.unwrap().bounds.lower, id as i8);
+        }
+        cache.store(shape(8, 4), WEST, pattern(4));
+        assert_eq!(cache.len(), 4);
+        assert!(holds(&mut cache, shape(8, 4)));
+        check_arena(&cache);
+    }
+
+    #[test]
+    fn test_pattern_tree() {
+        let mut cache = PatternCache::new(4);
+        let key = shape(8, 0);
+        cache.store(key, WEST, north(0b0011, 0, 5));
+        cache.store(key, WEST, north(0b0101, 0, 6));
+        // More general than both: absorbs them
+        cache.store(key, WEST, north(0b0001, 0, 7));
+        // More specific than the first, with tighter bounds
+        cache.store(key, WEST, north(0b0111, 0, 4));
+        check_arena(&cache);
+
+        let probe = north(0b1111, 0, 8);
+        assert_eq!(cache.lookup(key, WEST, &probe, 6).unwrap().bounds.upper, 5);
+        assert_eq!(cache.lookup(key, WEST, &probe, 5).unwrap().bounds.upper, 4);
+        // Only the general pattern matches a position without the second card
+        let probe = north(0b1001, 0, 8);
+        assert_eq!(cache.lookup(key, WEST, &probe, 8).unwrap().bounds.upper, 7);
+        assert!(cache.lookup(key, WEST, &probe, 7).is_none());
+
+        // Tightening the general pattern to its children's bounds merges them
+        // into it, moving the most specific pattern up
+        let nodes = cache.nodes.len() - cache.free_count;
+        cache.store(key, WEST, north(0b0001, 0, 5));
+        assert_eq!(cache.nodes.len() - cache.free_count, nodes - 2);
+        check_arena(&cache);
+        assert_eq!(cache.lookup(key, WEST, &probe, 6).unwrap().bounds.upper, 5);
+    }
+
+    #[test]
+    fn test_replacement_policies() {
+        let victim = |policy| {
+            let mut cache = PatternCache::new(2).with_policy(policy);
+            cache.store(shape(9, 0), WEST, pattern(0));
+            cache.store(shape(5, 1), WEST, pattern(0));
+            cache.store(shape(7, 2), WEST, pattern(0));
+            cache.store(shape(9, 3), WEST, pattern(0));
+            // Give shape 2 a bigger tree and make shape 0 recently used
+            for lower in 1..4 {
+                cache.store(shape(7, 2), WEST, north(1 << lower, lower, 13));
+            }
+            cache.store(shape(9, 0), WEST, pattern(1));
+            cache.store(shape(9, 4), WEST, pattern(0));
+            check_arena(&cache);
+            (0..4)
+                .find(|&id| !holds(&mut cache, shape([9, 5, 7, 9][id], id as u64)))
+                .unwrap()
+        };
+        assert_eq!(victim(ReplacementPolicy::Depth), 1);
+        assert_eq!(victim(ReplacementPolicy::Age), 1);
+        assert_eq!(victim(ReplacementPolicy::Size), 2);
+
+        // Age alone: the least recently stored entry goes, whatever its depth
+        let mut cache = PatternCache::new(2).with_policy(ReplacementPolicy::Age);
+        for id in 0..4 {
+            cache.store(shape(5 + id, id), WEST, pattern(0));
+        }
+        cache.store(shape(5, 0), WEST, pattern(1));
+        cache.store(shape(9, 4), WEST, pattern(0));
+        assert!(holds(&mut cache, shape(5, 0)));
+        assert!(!holds(&mut cache, shape(6, 1)));
+    }
+
+    #[test]
+    fn test_memory_limit_and_clear() {
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
+        let expected = solver.solve();
+
+        let mut cutoff = CutoffCache::with_memory_limit(1);
+        let mut pattern = PatternCache::with_memory_limit(1);
+        assert_eq!(pattern.memory_limit(), Some(1 << 20));
+        assert_eq!(
+            solver.solve_with_caches(&mut cutoff, &mut pattern),
+            expected
+        );
+        assert!(!pattern.is_empty());
+        assert!(pattern.memory_used() <= 1 << 20);
+        assert!(cutoff.memory_used() <= 1 << 20);
+        check_arena(&pattern);
+
+        // Cleared caches are reused without reallocating and give the same results
+        let used = pattern.memory_used();
+        pattern.clear();
+        cutoff.clear();
+        assert!(pattern.is_empty() && cutoff.is_empty());
+        assert_eq!(pattern.memory_used(), used);
+        assert_eq!(
+            solver.solve_with_caches(&mut cutoff, &mut pattern),
+            expected
+        );
+        check_arena(&pattern);
+    }
+}
@@ -0,0 +1,772 @@
+//! PBN double-dummy annotation
+//!
+//! Finds the deals in a PBN file, solves them and writes the results as
+//! Bridge Composer compatible tags:
+//! - DoubleDummyTricks (compact encoding)
+//! - OptimumResultTable (full table)
+//!
+//! Boards with a `[Contract]`, `[Declarer]` and `[Vulnerable]` also get a
+//! DoubleDummyScore tag: the NS score of that contract with its DD tricks.
+
+use super::canonical::ResultCache;
+use super::database::{solve_with_db, ResultsDb};
+use super::hands::Hands;
+use super::scoring::{Contract, Vulnerability};
+use super::table::DdTable;
+use super::types::*;
+use std::io;
+use std::ops::Range;
+
+/// Double-dummy results for all 20 combinations
+#[derive(Debug, Clone)]
+struct DdResults {
+    /// results[declarer][denomination] = tricks (declarer: 0=N,1=S,2=E,3=W; denom: 0=NT,1=S,2=H,3=D,4=C)
+    tricks: [[u8; 5]; 4],
+}
+
+impl DdResults {
+    /// Encode as DoubleDummyTricks string (20 hex-like chars)
+    /// Format: N(NT,S,H,D,C) + S(NT,S,H,D,C) + E(NT,S,H,D,C) + W(NT,S,H,D,C)
+    fn encode_ddt(&self) -> String {
+        let mut s = String::with_capacity(20);
+        for decl in 0..4 {
+            for denom in 0..5 {
+                let tricks = self.tricks[decl][denom];
+                let ch = if tricks <= 9 {
+                    (b'0' + tricks) as char
+                } else {
+                    (b'a' + (tricks - 10)) as char
+                };
+                s.push(ch);
+            }
+        }
+        s
+    }
+
+    /// Decode a DoubleDummyTricks string (inverse of `encode_ddt`)
+    fn decode_ddt(s: &str) -> Option<Self> {
+        let chars: Vec<char> = s.trim().chars().collect();
+        if chars.len() != 20 {
+            return None;
+        }
+        let mut tricks = [[0u8; 5]; 4];
+        for (i, &ch) in chars.iter().enumerate() {
+            let value = ch.to_digit(16).filter(|&value| value <= 13)? as u8;
+            tricks[i / 5][i % 5] = value;
+        }
+        Some(DdResults { tricks })
+    }
+
+    /// Reorder a table by solver seat and strain into DdResults order
+    fn from_table(table: &DdTable) -> Self {
+        let declarers = [NORTH, SOUTH, EAST, WEST];
+        let denominations = [NOTRUMP, SPADE, HEART, DIAMOND, CLUB];
+        let tricks =
+            declarers.map(|declarer| denominations.map(|strain| table.get_index(declarer, strain)));
+        DdResults { tricks }
+    }
+
+    /// The table by solver seat and strain (inverse of `from_table`)
+    fn to_table(&self) -> DdTable {
+        let mut table = DdTable::default();
+        for declarer in 0..NUM_SEATS {
+            for strain in 0..NUM_STRAINS {
+                table.set_index(declarer, strain, self.tricks_for(declarer, strain));
+            }
+        }
+        table
+    }
+
+    /// Get tricks for a specific declarer and denomination
+    fn get(&self, declarer: usize, denom: usize) -> u8 {
+        self.tricks[declarer][denom]
+    }
+
+    /// Get tricks by solver seat and strain
+    fn tricks_for(&self, declarer: SeatIndex, strain: usize) -> u8 {
+        let decl = [WEST_IDX, NORTH_IDX, EAST_IDX, SOUTH_IDX][declarer];
+        let denom = if strain == NOTRUMP { 0 } else { strain + 1 };
+        self.get(decl, denom)
+    }
+}
+
+/// DdResults declarer indices of the solver seats
+const NORTH_IDX: usize = 0;
+const SOUTH_IDX: usize = 1;
+const EAST_IDX: usize = 2;
+const WEST_IDX: usize = 3;
+
+/// An annotated PBN file, with counts of how its deals were solved
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Annotation {
+    /// The annotated file
+    pub pbn: String,
+    /// Deals found and annotated
+    pub deals: usize,
+    /// Solves answered by an equivalent position already solved
+    pub equivalent_hits: u64,
+    /// Deals found in the results database
+    pub db_hits: u64,
+    /// Deals not in the results database, solved and added to it
+    pub db_misses: u64,
+}
+
+/// Annotate a PBN file: find deals, solve them, insert/replace DD tags
+///
+/// Blocks without a parsable deal of equal hands are passed through unchanged.
+/// Deals repeated in the file, even rotated or with suits permuted, are only
+/// solved once.
+pub fn annotate_pbn(content: &str) -> Annotation {
+    // Without a database nothing is written, so nothing can fail
+    annotate_pbn_with_db(content, None).unwrap()
+}
+
+/// Like [`annotate_pbn`], taking full deals found in `db` from it instead of
+/// solving them and storing the ones solved
+///
+/// Fails only if the database cannot be written.
+pub fn annotate_pbn_with_db(
+    content: &str,
+    mut db: Option<&mut ResultsDb>,
+) -> io::Result<Annotation> {
+    let db_counts =
+        |db: &Option<&mut ResultsDb>| db.as_ref().map_or((0, 0), |db| (db.hits(), db.misses()));
+    let (db_hits, db_misses) = db_counts(&db);
+    let mut result = String::new();
+    let mut deal_count = 0;
+    let mut results = ResultCache::new();
+
+    // Process the file block by block, preserving the blank lines between blocks
+    let lines: Vec<&str> = content.lines().collect();
+    let mut next = 0;
+    for block in pbn_blocks(&lines) {
+        for line in &lines[next..block.start] {
+            result.push_str(line);
+            result.push('\n');
+        }
+        let processed = process_deal_block(
+            &lines[block.clone()],
+            &mut deal_count,
+            &mut results,
+            db.as_deref_mut(),
+        )?;
+        result.push_str(&processed);
+        next = block.end;
+    }
+    for line in &lines[next..] {
+        result.push_str(line);
+        result.push('\n');
+    }
+
+    let (db_hits_after, db_misses_after) = db_counts(&db);
+    Ok(Annotation {
+        pbn: result,
+        deals: deal_count,
+        equivalent_hits: results.hits(),
+        db_hits: db_hits_after - db_hits,
+        db_misses: db_misses_after - db_misses,
+    })
+}
+
+/// Line ranges of the blocks of a PBN file
+///
+/// A block is a sequence of lines until a blank line outside of {} comments.
+pub(super) fn pbn_blocks(lines: &[&str]) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        // Skip blank lines between blocks
+        while i < lines.len() && lines[i].trim().is_empty() {
+            i += 1;
+        }
+
+        if i >= lines.len() {
+            break;
+        }
+
+        // Collect a deal block (all lines until next blank line outside of {} comments)
+        let block_start = i;
+        let mut in_brace_comment = false;
+
+        while i < lines.len() {
+            let line = lines[i];
+
+            // Track brace comment state
+            // Note: braces don't nest per PBN spec
+            for ch in line.chars() {
+                if ch == '{' {
+                    in_brace_comment = true;
+                } else if ch == '}' {
+                    in_brace_comment = false;
+                }
+            }
+
+            i += 1;
+
+            // Check if next line would be a blank line outside of comment
+            if i < lines.len() && lines[i].trim().is_empty() && !in_brace_comment {
+                break;
+            }
+        }
+        blocks.push(block_start..i);
+    }
+
+    blocks
+}
+
+/// Process a single deal block
+fn process_deal_block(
+    lines: &[&str],
+    deal_count: &mut usize,
+    results: &mut ResultCache,
+    db: Option<&mut ResultsDb>,
+) -> io::Result<String> {
+    // Find the Deal tag to extract hands
+    let mut deal_str: Option<&str> = None;
+    let mut vulnerability: Option<Vulnerability> = None;
+    let mut contract_str: Option<&str> = None;
+    let mut declarer: Option<SeatIndex> = None;
+
+    for line in lines {
+        if deal_str.is_none() {
+            if let Some(d) = extract_deal_tag(line) {
+                deal_str = Some(d);
+            }
+        }
+        if vulnerability.is_none() {
+            if let Some(v) = extract_vulnerability_tag(line) {
+                vulnerability = Some(v);
+            }
+        }
+        if contract_str.is_none() {
+            contract_str = extract_tag_value(line, "Contract");
+        }
+        if declarer.is_none() {
+            declarer = extract_tag_value(line, "Declarer").and_then(parse_seat_value);
+        }
+    }
+
+    // If no Deal tag, just pass through unchanged
+    let Some(deal_str) = deal_str else {
+        let mut out = String::new();
+        for line in lines {
+            out.push_str(line);
+            out.push('\n');
+        }
+        return Ok(out);
+    };
+
+    // Parse the deal
+    let Some(hands) = Hands::from_pbn(deal_str).filter(Hands::is_valid) else {
+        // Can't parse or solve, pass through unchanged
+        let mut out = String::new();
+        for line in lines {
+            out.push_str(line);
+            out.push('\n');
+        }
+        return Ok(out);
+    };
+
+    *deal_count += 1;
+
+    // Solve the deal
+    let dd_results = DdResults::from_table(&solve_with_db(&hands, results, db)?);
+
+    // Generate the DD tags
+    let mut dd_tags = String::new();
+    if let (Some(contract_str), Some(vulnerability)) = (contract_str, vulnerability) {
+        if let Some(score) = contract_dd_score(&dd_results, contract_str, declarer, vulnerability) {
+            dd_tags.push_str(&format!("[DoubleDummyScore \"NS {}\"]\n", score));
+        }
+    }
+    dd_tags.push_str(&generate_dd_tags(&dd_results, vulnerability));
+
+    // Now reconstruct the block:
+    // 1. Remove any existing DD tags
+    // 2. Insert our new DD tags in the right place
+
+    let mut output_lines: Vec<String> = Vec::new();
+    let mut found_dd_tag = false;
+    let mut skipping_optimum_data = false;
+    let mut insertion_point: Option<usize> = None;
+
+    // Tags we generate (need to remove existing ones)
+    let dd_tag_names = [
+        "DoubleDummyScore",
+        "DoubleDummyTricks",
+        "OptimumScore",
+        "ParContract",
+        "OptimumResultTable",
+    ];
+
+    for line in lines {
+        let trimmed = line.trim();
+
+        // Check if this is one of our DD tags
+        if let Some(tag_name) = extract_tag_name(trimmed) {
+            if dd_tag_names.contains(&tag_name) {
+                if !found_dd_tag {
+                    // Remember where to insert (we'll insert our new tags here)
+                    insertion_point = Some(output_lines.len());
+                    found_dd_tag = true;
+                }
+                if tag_name == "OptimumResultTable" {
+                    skipping_optimum_data = true;
+                }
+                continue;
+            }
+        }
+
+        // Skip data lines that follow OptimumResultTable
+        if skipping_optimum_data {
+            if is_optimum_result_data_line(line) {
+                continue;
+            } else {
+                // Stop skipping when we hit a non-data line
+                skipping_optimum_data = false;
+            }
+        }
+
+        output_lines.push(line.to_string());
+
+        // Track potential insertion points (after Result tag, or alphabetically among supplemental tags)
+        if !found_dd_tag {
+            if trimmed.starts_with("[Result ") {
+                // Insert after Result tag (last mandatory tag)
+                insertion_point = Some(output_lines.len());
+            } else if trimmed.starts_with('[') {
+                if let Some(tag_name) = extract_tag_name(trimmed) {
+                    // DoubleDummyScore comes first alphabetically among our tags
+                    if tag_name > "DoubleDummyScore" && insertion_point.is_none() {
+                        // Insert before this tag
+                        insertion_point = Some(output_lines.len() - 1);
+                    } else if tag_name < "DoubleDummyScore" {
+                        // Insert after this tag
+                        insertion_point = Some(output_lines.len());
+                    }
+                }
+            }
+        }
+    }
+
+    // Build the output
+    let mut result = String::new();
+    let insert_at = insertion_point.unwrap_or(output_lines.len());
+
+    for (idx, line) in output_lines.iter().enumerate() {
+        if idx == insert_at {
+            result.push_str(&dd_tags);
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+
+    // If insertion point was at the end
+    if insert_at >= output_lines.len() {
+        result.push_str(&dd_tags);
+    }
+
+    Ok(result)
+}
+
+/// Extract the deal string from a [Deal "..."] tag
+pub(super) fn extract_deal_tag(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    if !trimmed.starts_with("[Deal ") {
+        return None;
+    }
+
+    // Find the quoted content
+    let start = trimmed.find('"')? + 1;
+    let end = trimmed.rfind('"')?;
+    if end <= start {
+        return None;
+    }
+
+    Some(&trimmed[start..end])
+}
+
+/// Extract vulnerability from [Vulnerable "..."] tag
+pub(super) fn extract_vulnerability_tag(line: &str) -> Option<Vulnerability> {
+    let trimmed = line.trim();
+    if !trimmed.starts_with("[Vulnerable ") {
+        return None;
+    }
+
+    let start = trimmed.find('"')? + 1;
+    let end = trimmed.rfind('"')?;
+    if end <= start {
+        return None;
+    }
+
+    let value = &trimmed[start..end];
+    match value.to_uppercase().as_str() {
+        "NONE" | "LOVE" | "-" => Some(Vulnerability::None),
+        "NS" | "N" => Some(Vulnerability::NS),
+        "EW" | "E" => Some(Vulnerability::EW),
+        "ALL" | "BOTH" => Some(Vulnerability::Both),
+        _ => None,
+    }
+}
+
+/// Extract the quoted value of a `[name "..."]` tag
+pub(super) fn extract_tag_value<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let trimmed = line.trim();
+    if extract_tag_name(trimmed) != Some(name) {
+        return None;
+    }
+
+    let start = trimmed.find('"')? + 1;
+    let end = trimmed.rfind('"')?;
+    if end < start {
+        return None;
+    }
+
+    Some(&trimmed[start..end])
+}
+
+/// Parse a single seat letter ("N", "E", "S" or "W")
+pub(super) fn parse_seat_value(value: &str) -> Option<SeatIndex> {
+    let mut chars = value.trim().chars();
+    match (chars.next().and_then(char_to_seat), chars.next()) {
+        (Some(seat), None) => Some(seat),
+        _ => None,
+    }
+}
+
+/// NS score of the board's contract with its double-dummy tricks
+///
+/// "Pass" scores 0; None if the contract or declarer can't be read.
+fn contract_dd_score(
+    results: &DdResults,
+    contract: &str,
+    declarer: Option<SeatIndex>,
+    vulnerability: Vulnerability,
+) -> Option<i32> {
+    if contract.trim().eq_ignore_ascii_case("pass") {
+        return Some(0);
+    }
+    let contract = Contract::from_pbn(contract, declarer?)?;
+    let tricks = results.tricks_for(contract.declarer, contract.strain);
+    Some(contract.ns_score(vulnerability, tricks))
+}
+
+/// Extract the tag name from a tag line like "[TagName ...]"
+fn extract_tag_name(line: &str) -> Option<&str> {
+    if !line.starts_with('[') {
+        return None;
+    }
+    let rest = &line[1..];
+    let end = rest.find([' ', ']'])?;
+    Some(&rest[..end])
+}
+
+/// Check if a line is OptimumResultTable data (e.g., "N NT  3")
+fn is_optimum_result_data_line(line: &str) -> bool {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return false;
+    }
+
+    // Must start with a seat letter
+    let first_char = trimmed.chars().next().unwrap_or(' ');
+    if !['N', 'S', 'E', 'W'].contains(&first_char) {
+        return false;
+    }
+
+    let parts: Vec<&str> = trimmed.split_whitespace().collect();
+    if parts.len() != 3 {
+        return false;
+    }
+
+    // First part should be a seat (N/S/E/W)
+    let seat = parts[0];
+    if !["N", "S", "E", "W"].contains(&seat) {
+        return false;
+    }
+
+    // Second part should be a denomination (NT/S/H/D/C)
+    let denom = parts[1];
+    if !["NT", "S", "H", "D", "C"].contains(&denom) {
+        return false;
+    }
+
+    // Third part should be a number
+    parts[2].parse::<u8>().is_ok()
+}
+
+/// DoubleDummyTricks encoding of a table
+pub(super) fn encode_ddt(table: &DdTable) -> String {
+    DdResults::from_table(table).encode_ddt()
+}
+
+/// Table from a DoubleDummyTricks encoding, None if malformed
+pub(super) fn decode_ddt(s: &str) -> Option<DdTable> {
+    DdResults::decode_ddt(s).map(|results| results.to_table())
+}
+
+/// Generate all DD tags as a string
+fn generate_dd_tags(results: &DdResults, _vulnerability: Option<Vulnerability>) -> String {
+    let mut output = String::new();
+
+    // 1. DoubleDummyTricks
+    output.push_str(&format!(
+        "[DoubleDummyTricks \"{}\"]\n",
+        results.encode_ddt()
+    ));
+
+    // Note: OptimumScore and ParContract require proper par calculation which is complex.
+    // Par calculation involves game theory (both sides competing) and is not implemented yet.
+    // These tags are omitted for now.
+
+    // 2. OptimumResultTable
+    output.push_str("[OptimumResultTable \"Declarer;Denomination\\2R;Result\\2R\"]\n");
+
+    let decl_names = ["N", "S", "E", "W"];
+    let denom_names = ["NT", " S", " H", " D", " C"];
+
+    for (decl_idx, decl_name) in decl_names.iter().enumerate() {
+        for (denom_idx, denom_name) in denom_names.iter().enumerate() {
+            output.push_str(&format!(
+                "{} {} {:2}\n",
+                decl_name,
+                denom_name,
+                results.get(decl_idx, denom_idx)
+            ));
+        }
+    }
+
+    output
+}
+
+// Note: Par calculation (OptimumScore, ParContract) is not implemented.
+// Proper par calculation requires game theory to handle competitive bidding
+// and is complex to implement correctly. For now, we only generate
+// DoubleDummyTricks and OptimumResultTable.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_deal_tag() {
+        let line = r#"[Deal "N:AK.QJ.T9.8765 432.A.K.QJT94 QJT.KT9.QJ.AK3 9876.8765.A8765.2"]"#;
+        let deal = extract_deal_tag(line).unwrap();
+        assert!(deal.starts_with("N:"));
+    }
+
+    #[test]
+    fn test_extract_deal_tag_no_match() {
+        assert!(extract_deal_tag("[Event \"Test\"]").is_none());
+        assert!(extract_deal_tag("N NT 3").is_none());
+    }
+
+    #[test]
+    fn test_extract_vulnerability() {
+        assert_eq!(
+            extract_vulnerability_tag("[Vulnerable \"None\"]"),
+            Some(Vulnerability::None)
+        );
+        assert_eq!(
+            extract_vulnerability_tag("[Vulnerable \"NS\"]"),
+            Some(Vulnerability::NS)
+        );
+        assert_eq!(
+            extract_vulnerability_tag("[Vulnerable \"EW\"]"),
+            Some(Vulnerability::EW)
+        );
+        assert_eq!(
+            extract_vulnerability_tag("[Vulnerable \"All\"]"),
+            Some(Vulnerability::Both)
+        );
+        assert_eq!(
+            extract_vulnerability_tag("[Vulnerable \"Both\"]"),
+            Some(Vulnerability::Both)
+        );
+    }
+
+    #[test]
+    fn test_extract_tag_name() {
+        assert_eq!(extract_tag_name("[Event \"Test\"]"), Some("Event"));
+        assert_eq!(
+            extract_tag_name("[OptimumResultTable \"...\"]"),
+            Some("OptimumResultTable")
+        );
+        assert_eq!(extract_tag_name("[Deal \"N:...\"]"), Some("Deal"));
+        assert_eq!(extract_tag_name("N NT 3"), None);
+    }
+
+    #[test]
+    fn test_is_optimum_result_data_line() {
+        assert!(is_optimum_result_data_line("N NT  3"));
+        assert!(is_optimum_result_data_line("S  S 10"));
+        assert!(is_optimum_result_data_line("E  H  7"));
+        assert!(!is_optimum_result_data_line("[Deal \"...\"]"));
+        assert!(!is_optimum_result_data_line(""));
+        assert!(!is_optimum_result_data_line("[OptimumResultTable \"...\"]"));
+    }
+
+    #[test]
+    fn test_encode_ddt() {
+        // Test the encoding: 0-9 -> '0'-'9', 10-13 -> 'a'-'d'
+        // From Bridge Composer: "32691326914a74a4a74a"
+        // Format: N(NT,S,H,D,C) S(NT,S,H,D,C) E(NT,S,H,D,C) W(NT,S,H,D,C)
+        let results = DdResults {
+            tricks: [
+                [3, 2, 6, 9, 1],   // N: NT=3, S=2, H=6, D=9, C=1 -> "32691"
+                [3, 2, 6, 9, 1],   // S: same -> "32691"
+                [4, 10, 7, 4, 10], // E: NT=4, S=10, H=7, D=4, C=10 -> "4a74a"
+                [4, 10, 7, 4, 10], // W: same -> "4a74a"
+            ],
+        };
+        assert_eq!(results.encode_ddt(), "32691326914a74a4a74a");
+    }
+
+    #[test]
+    fn test_decode_ddt() {
+        let table = decode_ddt("32691326914a74a4a74a").unwrap();
+        assert_eq!(table.get_index(NORTH, NOTRUMP), 3);
+        assert_eq!(table.get_index(EAST, SPADE), 10);
+        assert_eq!(table.get_index(WEST, CLUB), 10);
+        assert_eq!(encode_ddt(&table), "32691326914a74a4a74a");
+        for bad in [
+            "",
+            "3269132691",
+            "32691326914a74a4a74e",
+            "32691326914a74a4a74a0",
+        ] {
+            assert_eq!(decode_ddt(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_process_simple_pbn() {
+        // Use a real 52-card deal from Bridge Composer reference
+        let pbn = r#"[Event "Test"]
+[Vulnerable "None"]
+[Deal "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72"]
+[Dealer "N"]
+"#;
+        let annotation = annotate_pbn(pbn);
+        let result = &annotation.pbn;
+        assert!(result.contains("[DoubleDummyTricks"));
+        assert!(result.contains("[OptimumResultTable"));
+        assert!(result.contains("N NT"));
+        assert_eq!((annotation.deals, annotation.equivalent_hits), (1, 0));
+
+        // The same deal rotated: all 20 solves are answered by the first
+        let rotated = pbn.replace("N:", "E:");
+        let annotation = annotate_pbn(&format!("{}\n{}", pbn, rotated));
+        assert_eq!((annotation.deals, annotation.equivalent_hits), (2, 20));
+    }
+
+    #[test]
+    fn test_replaces_existing_dd_tags() {
+        let pbn = r#"[Event "Test"]
+[Vulnerable "None"]
+[Deal "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72"]
+[DoubleDummyTricks "00000000000000000000"]
+[OptimumScore "NS 0"]
+[ParContract "NS Pass"]
+[OptimumResultTable "Declarer;Denomination\2R;Result\2R"]
+N NT  0
+N  S  0
+N  H  0
+N  D  0
+N  C  0
+S NT  0
+S  S  0
+S  H  0
+S  D  0
+S  C  0
+E NT  0
+E  S  0
+E  H  0
+E  D  0
+E  C  0
+W NT  0
+W  S  0
+W  H  0
+W  D  0
+W  C  0
+[Dealer "N"]
+"#;
+        let result = annotate_pbn(pbn).pbn;
+        // Should have exactly one of each DD tag we generate
+        assert_eq!(result.matches("[DoubleDummyTricks").count(), 1);
+        assert_eq!(result.matches("[OptimumResultTable").count(), 1);
+        // Old OptimumScore and ParContract should be removed (we don't generate them)
+        assert_eq!(result.matches("[OptimumScore").count(), 0);
+        assert_eq!(result.matches("[ParContract").count(), 0);
+        // Should have correct values, not zeros
+        assert!(!result.contains("\"00000000000000000000\""));
+    }
+
+    #[test]
+    fn test_invalid_deals_pass_through() {
+        // Duplicate card, unequal hands, no cards
+        for deal in [
+            "N:A... A... Q... J...",
+            "N:AK... Q... J... T...",
+            "N:... ... ... ...",
+        ] {
+            let pbn = format!("[Event \"Test\"]\n[Deal \"{}\"]\n", deal);
+            assert_eq!(annotate_pbn(&pbn).pbn, pbn);
+        }
+    }
+
+    #[test]
+    fn test_tricks_for() {
+        let results = DdResults {
+            tricks: [
+                [3, 2, 6, 9, 1],
+                [3, 2, 6, 9, 1],
+                [4, 10, 7, 4, 10],
+                [4, 10, 7, 4, 10],
+            ],
+        };
+        assert_eq!(results.tricks_for(NORTH, NOTRUMP), 3);
+        assert_eq!(results.tricks_for(SOUTH, DIAMOND), 9);
+        assert_eq!(results.tricks_for(EAST, SPADE), 10);
+        assert_eq!(results.tricks_for(WEST, HEART), 7);
+
+        // 4S by East making exactly, 3NT by North three down
+        assert_eq!(
+            contract_dd_score(&results, "4S", Some(EAST), Vulnerability::EW),
+            Some(-620)
+        );
+        assert_eq!(
+            contract_dd_score(&results, "3NT", Some(NORTH), Vulnerability::None),
+            Some(-300)
+        );
+        assert_eq!(
+            contract_dd_score(&results, "Pass", None, Vulnerability::None),
+            Some(0)
+        );
+        assert_eq!(
+            contract_dd_score(&results, "4S", None, Vulnerability::None),
+            None
+        );
+    }
+
+    #[test]
+    fn test_contract_dd_score_tag() {
+        let pbn = r#"[Event "Test"]
+[Vulnerable "None"]
+[Deal "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72"]
+[Declarer "N"]
+[Contract "7NT"]
+[Result "10"]
+"#;
+        let result = annotate_pbn(pbn).pbn;
+        assert_eq!(result.matches("[DoubleDummyScore \"NS -").count(), 1);
+        // Annotating again replaces the tag
+        let again = annotate_pbn(&result).pbn;
+        assert_eq!(again, result);
+
+        // No score without vulnerability
+        let result = annotate_pbn(&pbn.replace("[Vulnerable \"None\"]\n", "")).pbn;
+        assert!(!result.contains("[DoubleDummyScore"));
+    }
+}
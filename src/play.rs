@@ -5,7 +5,7 @@ use super::hands::Hands;
 use super::types::*;
 
 /// Get playable cards for current player
-pub fn get_playable_cards(hands: &Hands, seat: Seat, lead_suit: Option<Suit>) -> Cards {
+pub fn get_playable_cards(hands: &Hands, seat: SeatIndex, lead_suit: Option<SuitIndex>) -> Cards {
     let hand = hands[seat];
 
     if let Some(suit) = lead_suit {
@@ -0,0 +1,391 @@
+//! Python bindings (PyO3)
+//!
+//! Builds the `bridge_solver` extension module, e.g. with maturin (see
+//! pyproject.toml). Cards, seats and strains are passed as strings ("SA",
+//! "N", "NT") and results come back as ints, lists, tuples and dicts.
+//!
+//! ```python
+//! import bridge_solver as bs
+//!
+//! hands = bs.Hands.from_pbn("N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72")
+//! bs.Solver(hands, "NT", "W").solve()        # NS tricks
+//! bs.Solver(hands, "S", "W").analyze()       # [("SA", 9), ...]
+//! bs.dd_table(hands)["N"]["NT"]              # North's tricks in notrump
+//! bs.dd_tables([hands, ...], threads=8)      # batch, in parallel
+//! ```
+//!
+//! The GIL is released while solving, so other Python threads keep running.
+
+use super::analysis::{analyze_plays, CardAnalysis};
+use super::bridge_solver::{new_caches, PartialTrick, Solver};
+use super::cards::{name_of, parse_card};
+use super::hands::Hands;
+use super::parallel::parallel_map;
+use super::play::get_playable_cards;
+use super::table::{solve_dd_table, DdTable};
+use super::types::*;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Seats in the order tables are reported
+const TABLE_SEATS: [SeatIndex; NUM_SEATS] = [NORTH, EAST, SOUTH, WEST];
+
+/// A deal: the cards held by each seat
+#[pyclass(name = "Hands", module = "bridge_solver", frozen, eq, hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct PyHands {
+    hands: Hands,
+}
+
+#[pymethods]
+impl PyHands {
+    /// Parse a PBN deal such as "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 ..."
+    ///
+    /// All four hands must hold the same number of cards.
+    #[staticmethod]
+    fn from_pbn(pbn: &str) -> PyResult<Self> {
+        let hands = Hands::from_pbn(pbn)
+            .ok_or_else(|| PyValueError::new_err(format!("cannot parse deal '{}'", pbn)))?;
+        if !hands.is_valid() {
+            return Err(PyValueError::new_err(
+                "each card must appear once and all hands must hold the same number of cards",
+            ));
+        }
+        Ok(PyHands { hands })
+    }
+
+    /// The deal as a PBN string starting from North
+    fn to_pbn(&self) -> String {
+        self.hands.to_pbn()
+    }
+
+    /// Cards held by `seat` ("N", "E", "S" or "W"), e.g. ["SA", "SK", "H2"]
+    fn hand(&self, seat: &str) -> PyResult<Vec<String>> {
+        let seat = parse_seat(seat)?;
+        Ok(self.hands[seat].iter().map(name_of).collect())
+    }
+
+    /// Cards per hand
+    #[getter]
+    fn num_tricks(&self) -> usize {
+        self.hands.num_tricks()
+    }
+
+    fn __str__(&self) -> String {
+        self.hands.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Hands.from_pbn('{}')", self.hands.to_pbn())
+    }
+}
+
+/// Cards played so far to the current trick
+#[pyclass(name = "PartialTrick", module = "bridge_solver")]
+#[derive(Clone, Default)]
+struct PyPartialTrick {
+    trick: PartialTrick,
+}
+
+#[pymethods]
+impl PyPartialTrick {
+    #[new]
+    fn new() -> Self {
+        PyPartialTrick::default()
+    }
+
+    /// Add `card` (e.g. "SA") played by `seat`; seats must follow clockwise
+    fn add(&mut self, card: &str, seat: &str) -> PyResult<()> {
+        let card = parse_card_name(card)?;
+        let seat = parse_seat(seat)?;
+        if self.trick.len() >= 3 {
+            return Err(PyValueError::new_err(
+                "at most 3 cards can be played to the current trick",
+            ));
+        }
+        if self.trick.next_to_play().is_some_and(|next| next != seat) {
+            return Err(PyValueError::new_err(format!(
+                "{} is not next to play",
+                seat_name(seat)
+            )));
+        }
+        if self.trick.plays.iter().any(|play| play.card == card) {
+            return Err(PyValueError::new_err(format!(
+                "{} is already played",
+                name_of(card)
+            )));
+        }
+        self.trick.add_index(card, seat);
+        Ok(())
+    }
+
+    /// The plays as (card, seat) tuples
+    #[getter]
+    fn plays(&self) -> Vec<(String, char)> {
+        self.trick
+            .plays
+            .iter()
+            .map(|play| (name_of(play.card), seat_letter(play.seat)))
+            .collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.trick.len()
+    }
+
+    fn __repr__(&self) -> String {
+        let plays: Vec<String> = self
+            .plays()
+            .iter()
+            .map(|(card, seat)| format!("{}:{}", seat, card))
+            .collect();
+        format!("PartialTrick([{}])", plays.join(", "))
+    }
+}
+
+/// A position to solve: the cards not yet played, trump, leader and current trick
+#[pyclass(name = "Solver", module = "bridge_solver", frozen)]
+#[derive(Clone)]
+struct PySolver {
+    hands: Hands,
+    trump: usize,
+    leader: SeatIndex,
+    trick: PartialTrick,
+}
+
+#[pymethods]
+impl PySolver {
+    /// Position at the start of a trick with `leader` on lead
+    #[new]
+    fn new(hands: &PyHands, trump: &str, leader: &str) -> PyResult<Self> {
+        Ok(PySolver {
+            hands: hands.hands,
+            trump: parse_strain(trump)?,
+            leader: parse_seat(leader)?,
+            trick: PartialTrick::new(),
+        })
+    }
+
+    /// Position part way through a trick
+    ///
+    /// `hands` is the deal at the start of the trick, so still holds the
+    /// cards played to `trick`.
+    #[staticmethod]
+    fn mid_trick(hands: &PyHands, trump: &str, trick: &PyPartialTrick) -> PyResult<Self> {
+        let trump = parse_strain(trump)?;
+        let trick = &trick.trick;
+        let leader = trick
+            .leader()
+            .ok_or_else(|| PyValueError::new_err("the trick has no cards played"))?;
+
+        let mut remaining = hands.hands;
+        let mut check = PartialTrick::new();
+        for play in &trick.plays {
+            let playable = get_playable_cards(&remaining, play.seat, check.lead_suit());
+            if !playable.have_index(play.card) {
+                return Err(PyValueError::new_err(format!(
+                    "{} cannot play {}",
+                    seat_name(play.seat),
+                    name_of(play.card)
+                )));
+            }
+            remaining[play.seat].remove_index(play.card);
+            check.add_index(play.card, play.seat);
+        }
+
+        Ok(PySolver {
+            hands: remaining,
+            trump,
+            leader,
+            trick: trick.clone(),
+        })
+    }
+
+    /// NS tricks from this position with best play
+    fn solve(&self, py: Python<'_>) -> u8 {
+        py.allow_threads(|| self.solve_tricks())
+    }
+
+    /// NS tricks after each legal play of the seat to move, as (card, tricks) tuples
+    fn analyze(&self, py: Python<'_>) -> Vec<(String, u8)> {
+        let analysis = py.allow_threads(|| self.analysis());
+        analysis
+            .iter()
+            .map(|result| (name_of(result.card), result.ns_tricks))
+            .collect()
+    }
+
+    fn __repr__(&self) -> String {
+        if self.trick.is_empty() {
+            return format!(
+                "Solver(Hands.from_pbn('{}'), '{}', '{}')",
+                self.hands.to_pbn(),
+                strain_name(self.trump),
+                seat_letter(self.leader)
+            );
+        }
+        let mut deal = self.hands;
+        for play in &self.trick.plays {
+            deal[play.seat].add_index(play.card);
+        }
+        let trick = PyPartialTrick {
+            trick: self.trick.clone(),
+        };
+        format!(
+            "Solver.mid_trick(Hands.from_pbn('{}'), '{}', {})",
+            deal.to_pbn(),
+            strain_name(self.trump),
+            trick.__repr__()
+        )
+    }
+}
+
+impl PySolver {
+    fn solve_tricks(&self) -> u8 {
+        let (mut cutoff_cache, mut pattern_cache) = new_caches();
+        if self.trick.is_empty() {
+            Solver::with_indices(self.hands, self.trump, self.leader)
+                .solve_with_caches(&mut cutoff_cache, &mut pattern_cache)
+        } else {
+            // Validated by PySolver::mid_trick
+            Solver::mid_trick_with_indices(self.hands, self.trump, &self.trick)
+                .unwrap()
+                .solve_mid_trick(&mut cutoff_cache, &mut pattern_cache, &self.trick)
+        }
+    }
+
+    fn analysis(&self) -> Vec<CardAnalysis> {
+        let (mut cutoff_cache, mut pattern_cache) = new_caches();
+        analyze_plays(
+            self.hands,
+            self.trump,
+            self.leader,
+            &self.trick,
+            &mut cutoff_cache,
+            &mut pattern_cache,
+        )
+        // Validated by PySolver::mid_trick
+        .unwrap()
+    }
+}
+
+/// Double-dummy table as {declarer: {strain: tricks}}, e.g. table["N"]["NT"]
+#[pyfunction]
+fn dd_table<'py>(py: Python<'py>, hands: &PyHands) -> PyResult<Bound<'py, PyDict>> {
+    let hands = hands.hands;
+    let table = py.allow_threads(|| solve_dd_table(&hands));
+    table_to_dict(py, &table)
+}
+
+/// Double-dummy tables for many deals, solved on `threads` threads
+/// (default: one per CPU)
+#[pyfunction]
+#[pyo3(signature = (deals, threads = None))]
+fn dd_tables<'py>(
+    py: Python<'py>,
+    deals: Vec<PyRef<'py, PyHands>>,
+    threads: Option<usize>,
+) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    let deals: Vec<Hands> = deals.iter().map(|hands| hands.hands).collect();
+    let tables = py.allow_threads(|| parallel_map(&deals, threads, solve_dd_table));
+    tables
+        .iter()
+        .map(|table| table_to_dict(py, table))
+        .collect()
+}
+
+/// NS tricks for many positions, solved on `threads` threads (default: one per CPU)
+#[pyfunction]
+#[pyo3(signature = (solvers, threads = None))]
+fn solve_many(
+    py: Python<'_>,
+    solvers: Vec<PyRef<'_, PySolver>>,
+    threads: Option<usize>,
+) -> Vec<u32> {
+    let solvers: Vec<PySolver> = solvers.iter().map(|solver| (*solver).clone()).collect();
+    py.allow_threads(|| parallel_map(&solvers, threads, |solver| solver.solve_tricks() as u32))
+}
+
+#[pymodule]
+#[pyo3(name = "bridge_solver")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyHands>()?;
+    m.add_class::<PyPartialTrick>()?;
+    m.add_class::<PySolver>()?;
+    m.add_function(wrap_pyfunction!(dd_table, m)?)?;
+    m.add_function(wrap_pyfunction!(dd_tables, m)?)?;
+    m.add_function(wrap_pyfunction!(solve_many, m)?)?;
+    Ok(())
+}
+
+fn table_to_dict<'py>(py: Python<'py>, table: &DdTable) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    for declarer in TABLE_SEATS {
+        let strains = PyDict::new(py);
+        for strain in 0..NUM_STRAINS {
+            strains.set_item(strain_name(strain), table.get_index(declarer, strain))?;
+        }
+        dict.set_item(seat_letter(declarer), strains)?;
+    }
+    Ok(dict)
+}
+
+fn parse_seat(s: &str) -> PyResult<SeatIndex> {
+    let mut chars = s.chars();
+    match (chars.next().and_then(char_to_seat), chars.next()) {
+        (Some(seat), None) => Ok(seat),
+        _ => Err(PyValueError::new_err(format!("invalid seat '{}'", s))),
+    }
+}
+
+fn parse_strain(s: &str) -> PyResult<usize> {
+    let mut chars = s.chars();
+    let strain = match (chars.next().and_then(char_to_suit), chars.next()) {
+        (Some(strain), None) => Some(strain),
+        (Some(NOTRUMP), Some('T' | 't')) if chars.next().is_none() => Some(NOTRUMP),
+        _ => None,
+    };
+    strain.ok_or_else(|| PyValueError::new_err(format!("invalid trump '{}'", s)))
+}
+
+fn parse_card_name(name: &str) -> PyResult<usize> {
+    parse_card(name).ok_or_else(|| PyValueError::new_err(format!("invalid card '{}'", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_names() {
+        assert_eq!(parse_strain("NT").unwrap(), NOTRUMP);
+        assert_eq!(parse_strain("n").unwrap(), NOTRUMP);
+        assert_eq!(parse_strain("h").unwrap(), HEART);
+        assert!(parse_strain("NX").is_err());
+        assert!(parse_strain("").is_err());
+        assert_eq!(parse_seat("W").unwrap(), WEST);
+        assert!(parse_seat("WE").is_err());
+    }
+
+    #[test]
+    fn test_mid_trick() {
+        let hands = PyHands::from_pbn("N:A.A.. K.K.. 2.2.. 3.3..").unwrap();
+        let mut trick = PyPartialTrick::new();
+        trick.add("S3", "W").unwrap();
+        assert!(trick.add("SA", "E").is_err());
+        assert!(trick.add("S3", "N").is_err());
+        trick.add("SA", "N").unwrap();
+        let solver = PySolver::mid_trick(&hands, "NT", &trick).unwrap();
+        assert_eq!(solver.solve_tricks(), 2);
+
+        // Any card may be led, but North must follow to a spade
+        let mut trick = PyPartialTrick::new();
+        trick.add("H3", "W").unwrap();
+        assert!(PySolver::mid_trick(&hands, "NT", &trick).is_ok());
+        let mut trick = PyPartialTrick::new();
+        trick.add("S3", "W").unwrap();
+        trick.add("HA", "N").unwrap();
+        assert!(PySolver::mid_trick(&hands, "NT", &trick).is_err());
+    }
+}
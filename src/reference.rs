@@ -0,0 +1,346 @@
+//! Brute-force reference solver
+//!
+//! Plain minimax over every legal card: no transposition table, no pruning,
+//! no equivalent cards. It is exponential, so only practical up to about five
+//! tricks, and exists to check `Solver` against (see the tests below, which
+//! compare the two on random endings and shrink any disagreement).
+
+use super::bridge_solver::PartialTrick;
+use super::cards::*;
+use super::hands::Hands;
+use super::types::*;
+
+/// NS tricks with best play when `leader` leads to the first trick of `hands`
+pub fn reference_solve(hands: Hands, trump: usize, leader: SeatIndex) -> u8 {
+    let mut hands = hands;
+    minimax(&mut hands, trump, &mut Vec::new(), leader)
+}
+
+/// NS tricks with best play after the cards of `partial_trick` (already
+/// removed from `hands`), counting the trick being played
+pub fn reference_solve_mid_trick(hands: Hands, trump: usize, partial_trick: &PartialTrick) -> u8 {
+    let mut hands = hands;
+    let mut trick: Vec<(SeatIndex, usize)> = partial_trick
+        .plays
+        .iter()
+        .map(|play| (play.seat, play.card))
+        .collect();
+    let seat = partial_trick.next_to_play().unwrap_or(WEST);
+    minimax(&mut hands, trump, &mut trick, seat)
+}
+
+/// NS tricks from here on, with `seat` to play to `trick`
+fn minimax(
+    hands: &mut Hands,
+    trump: usize,
+    trick: &mut Vec<(SeatIndex, usize)>,
+    seat: SeatIndex,
+) -> u8 {
+    if trick.len() == 4 {
+        let winner = trick_winner(trick, trump);
+        let mut next_trick = Vec::new();
+        return is_ns(winner) as u8 + minimax(hands, trump, &mut next_trick, winner);
+    }
+    if trick.is_empty() && hands[seat].is_empty() {
+        return 0;
+    }
+
+    let hand = hands[seat];
+    let playable = match trick.first() {
+        Some(&(_, lead)) if !hand.suit(suit_of(lead)).is_empty() => hand.suit(suit_of(lead)),
+        _ => hand,
+    };
+    let mut best: Option<u8> = None;
+    for card in playable.iter() {
+        hands[seat].remove_index(card);
+        trick.push((seat, card));
+        let tricks = minimax(hands, trump, trick, next_seat(seat));
+        trick.pop();
+        hands[seat].add_index(card);
+        best = Some(match best {
+            None => tricks,
+            Some(best) if is_ns(seat) => best.max(tricks),
+            Some(best) => best.min(tricks),
+        });
+    }
+    best.unwrap_or(0)
+}
+
+/// The seat winning a complete trick: the highest trump, else the highest
+/// card of the suit led
+fn trick_winner(trick: &[(SeatIndex, usize)], trump: usize) -> SeatIndex {
+    let lead_suit = suit_of(trick[0].1);
+    let strength = |card: usize| {
+        let suit = suit_of(card);
+        if suit == trump {
+            2 * NUM_RANKS + rank_of(card)
+        } else if suit == lead_suit {
+            NUM_RANKS + rank_of(card)
+        } else {
+            0
+        }
+    };
+    trick
+        .iter()
+        .max_by_key(|&&(_, card)| strength(card))
+        .unwrap()
+        .0
+}
+
+/// xorshift64, for reproducible endings in tests
+#[cfg(test)]
+pub(crate) struct Random(pub(crate) u64);
+
+#[cfg(test)]
+impl Random {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// A random deal of `tricks` cards per hand
+#[cfg(test)]
+pub(crate) fn random_hands(random: &mut Random, tricks: usize) -> Hands {
+    let mut deck: Vec<usize> = (0..TOTAL_CARDS).collect();
+    let mut hands = Hands::new();
+    for seat in 0..NUM_SEATS {
+        for _ in 0..tricks {
+            hands[seat].add_index(deck.swap_remove(random.below(deck.len())));
+        }
+    }
+    hands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CutoffCache, PatternCache, Solver};
+
+    /// A position to solve: an ending, optionally part way through a trick
+    #[derive(Clone, Debug)]
+    struct Position {
+        hands: Hands,
+        trump: usize,
+        leader: SeatIndex,
+        partial: PartialTrick,
+    }
+
+    impl Position {
+        fn describe(&self) -> String {
+            let plays: Vec<String> = self
+                .partial
+                .plays
+                .iter()
+                .map(|play| format!("{} {}", seat_letter(play.seat), name_of(play.card)))
+                .collect();
+            format!(
+                "{} trump {} leader {} played [{}]",
+                self.hands.to_pbn(),
+                strain_name(self.trump),
+                seat_letter(self.leader),
+                plays.join(", ")
+            )
+        }
+
+        /// NS tricks from the solver and from the reference, if they differ
+        fn disagreement(&self) -> Option<(u8, u8)> {
+            let mut cutoff = CutoffCache::new(8);
+            let mut pattern = PatternCache::new(8);
+            let (solver, reference) = if self.partial.is_empty() {
+                (
+                    Solver::with_indices(self.hands, self.trump, self.leader)
+                        .solve_with_caches(&mut cutoff, &mut pattern),
+                    reference_solve(self.hands, self.trump, self.leader),
+                )
+            } else {
+                (
+                    Solver::mid_trick_with_indices(self.hands, self.trump, &self.partial)
+                        .unwrap()
+                        .solve_mid_trick(&mut cutoff, &mut pattern, &self.partial),
+                    reference_solve_mid_trick(self.hands, self.trump, &self.partial),
+                )
+            };
+            (solver != reference).then_some((solver, reference))
+        }
+
+        /// Positions one step simpler: with the last card of the trick taken
+        /// back, or one card fewer in every hand
+        fn simpler(&self) -> Vec<Position> {
+            let mut simpler = Vec::new();
+            if let Some(play) = self.partial.plays.last() {
+                let mut position = self.clone();
+                position.hands[play.seat].add_index(play.card);
+                position.partial.plays.pop();
+                simpler.push(position);
+            }
+            let tricks = (0..NUM_SEATS)
+                .map(|seat| self.hands[seat].size())
+                .max()
+                .unwrap();
+            if tricks < 2 || (0..NUM_SEATS).any(|seat| self.hands[seat].is_empty()) {
+                return simpler;
+            }
+            let mut removed = [0; NUM_SEATS];
+            self.remove_one_each(0, &mut removed, &mut simpler);
+            simpler
+        }
+
+        fn remove_one_each(
+            &self,
+            seat: SeatIndex,
+            removed: &mut [usize; 4],
+            out: &mut Vec<Position>,
+        ) {
+            if seat == NUM_SEATS {
+                let mut position = self.clone();
+                for (seat, &card) in removed.iter().enumerate() {
+                    position.hands[seat].remove_index(card);
+                }
+                out.push(position);
+                return;
+            }
+            for card in self.hands[seat].iter() {
+                removed[seat] = card;
+                self.remove_one_each(seat + 1, removed, out);
+            }
+        }
+    }
+
+    /// Shrink a position that `fails` until no simpler position fails
+    fn shrink(mut position: Position, fails: impl Fn(&Position) -> bool) -> Position {
+        while let Some(simpler) = position.simpler().into_iter().find(|p| fails(p)) {
+            position = simpler;
+        }
+        position
+    }
+
+    /// A random `tricks`-card ending, with up to three cards played to the
+    /// first trick when `mid_trick`
+    fn random_position(random: &mut Random, tricks: usize, mid_trick: bool) -> Position {
+        let mut hands = random_hands(random, tricks);
+        let leader = random.below(NUM_SEATS);
+        let mut partial = PartialTrick::new();
+        let mut seat = leader;
+        for _ in 0..if mid_trick { 1 + random.below(3) } else { 0 } {
+            let hand = hands[seat];
+            let playable = match partial.lead_suit() {
+                Some(suit) if !hand.suit(suit).is_empty() => hand.suit(suit),
+                _ => hand,
+            };
+            let card = playable.iter().nth(random.below(playable.size())).unwrap();
+            hands[seat].remove_index(card);
+            partial.add_index(card, seat);
+            seat = next_seat(seat);
+        }
+        Position {
+            hands,
+            trump: NOTRUMP,
+            leader,
+            partial,
+        }
+    }
+
+    /// Compare the solver with the reference on `deals` random endings of up
+    /// to `max_tricks` tricks, in every strain and (at trick start) from every
+    /// leader
+    fn check_random_endings(seed: u64, deals: usize, max_tricks: usize) {
+        let mut random = Random(seed);
+        for deal in 0..deals {
+            let tricks = 1 + deal % max_tricks;
+            let mid_trick = deal % 2 == 1;
+            let position = random_position(&mut random, tricks, mid_trick);
+            for trump in 0..NUM_STRAINS {
+                let leaders = if mid_trick {
+                    vec![position.leader]
+                } else {
+                    (0..NUM_SEATS).collect()
+                };
+                for leader in leaders {
+                    let position = Position {
+                        trump,
+                        leader,
+                        ..position.clone()
+                    };
+                    if let Some((solver, reference)) = position.disagreement() {
+                        let minimal = shrink(position.clone(), |p| p.disagreement().is_some());
+                        let (minimal_solver, minimal_reference) = minimal.disagreement().unwrap();
+                        panic!(
+                            "solver {} vs reference {} for {}\nminimal: solver {} vs reference {} for {}",
+                            solver,
+                            reference,
+                            position.describe(),
+                            minimal_solver,
+                            minimal_reference,
+                            minimal.describe()
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_reference_solver() {
+        // One trick: NS win it with the ace, unless East can ruff
+        let hands = Hands::from_pbn("N:A... .2.. Q... J...").unwrap();
+        assert_eq!(reference_solve(hands, NOTRUMP, WEST), 1);
+        assert_eq!(reference_solve(hands, HEART, WEST), 0);
+
+        // South leading towards AQ: the finesse wins only with the king on
+        // North's right
+        let hands = Hands::from_pbn("N:AQ... 32... 54... K6...").unwrap();
+        assert_eq!(reference_solve(hands, NOTRUMP, SOUTH), 2);
+        let hands = Hands::from_pbn("N:AQ... K6... 54... 32...").unwrap();
+        assert_eq!(reference_solve(hands, NOTRUMP, SOUTH), 1);
+
+        // Mid-trick: West's ace wins unless South can ruff
+        let mut partial = PartialTrick::new();
+        partial
+            .add_index(card_of(SPADE, 12), WEST)
+            .add_index(card_of(SPADE, 11), NORTH);
+        let mut hands = Hands::new();
+        hands[EAST].add_index(card_of(SPADE, 2));
+        hands[SOUTH].add_index(card_of(HEART, 0));
+        assert_eq!(reference_solve_mid_trick(hands, NOTRUMP, &partial), 0);
+        assert_eq!(reference_solve_mid_trick(hands, HEART, &partial), 1);
+    }
+
+    #[test]
+    fn test_solver_matches_reference() {
+        check_random_endings(0x5eed, 300, 4);
+    }
+
+    #[test]
+    #[ignore] // Slow: brute force on thousands of endings up to five tricks
+    fn test_solver_matches_reference_exhaustive() {
+        check_random_endings(0xb1d, 3000, 5);
+    }
+
+    #[test]
+    fn test_shrink() {
+        // Shrink a position where EW take a trick: the minimal one is a
+        // single trick they win
+        let ew_take_a_trick = |position: &Position| {
+            let tricks = position.hands[WEST].size() as u8;
+            reference_solve(position.hands, position.trump, position.leader) < tricks
+        };
+        let position = Position {
+            hands: Hands::from_pbn("N:A2.A.. KQ.2.. 43.3.. 65.4..").unwrap(),
+            trump: NOTRUMP,
+            leader: WEST,
+            partial: PartialTrick::new(),
+        };
+        assert!(ew_take_a_trick(&position));
+        let minimal = shrink(position, ew_take_a_trick);
+        assert!(ew_take_a_trick(&minimal));
+        assert_eq!(minimal.hands[WEST].size(), 1);
+        assert!(minimal.simpler().is_empty());
+    }
+}
@@ -0,0 +1,561 @@
+//! Results review: actual results against double dummy
+//!
+//! Reads the played boards of a PBN file and compares each result with the
+//! double-dummy tricks of its contract and with par. A board's results come
+//! from its `[ScoreTable]` rows when it has them (one per table), otherwise
+//! from its `[Contract]`, `[Declarer]` and `[Result]` tags.
+//!
+//! Tricks are counted from declarer's point of view: +1 means declarer took a
+//! trick more than double dummy. Scores are NS scores, and the par delta is
+//! the NS score less the par score.
+
+use super::canonical::ResultCache;
+use super::database::{solve_with_db, ResultsDb};
+use super::hands::Hands;
+use super::par::{par, ParResult};
+use super::pbn::{
+    extract_deal_tag, extract_tag_value, extract_vulnerability_tag, parse_seat_value, pbn_blocks,
+};
+use super::scoring::{imps, Contract, Vulnerability};
+use super::table::DdTable;
+use super::types::*;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+
+/// One table's result on a board
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableResult {
+    /// NS and EW pair names or ids, when known
+    pub pairs: [Option<String>; 2],
+    /// Player names by seat, when known
+    pub players: [Option<String>; NUM_SEATS],
+    /// None when passed out
+    pub contract: Option<Contract>,
+    /// Tricks declarer took (0 when passed out)
+    pub tricks: u8,
+    /// Tricks declarer takes double dummy
+    pub dd_tricks: u8,
+    pub ns_score: i32,
+    /// NS score with the double-dummy tricks
+    pub dd_ns_score: i32,
+    /// NS score less the par score
+    pub par_delta: i32,
+}
+
+impl TableResult {
+    /// Tricks declarer took over (or under, if negative) double dummy
+    pub fn trick_delta(&self) -> i32 {
+        self.tricks as i32 - self.dd_tricks as i32
+    }
+}
+
+/// A board's double-dummy analysis and its results
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoardReview {
+    /// The `[Board]` tag, or the board's position in the file
+    pub board: String,
+    pub vulnerability: Vulnerability,
+    pub table: DdTable,
+    pub par: ParResult,
+    pub results: Vec<TableResult>,
+}
+
+/// Review every board of a PBN file that has a full deal and a result
+pub fn review_pbn(content: &str) -> Vec<BoardReview> {
+    // Without a database nothing is written, so nothing can fail
+    review_pbn_with_db(content, None).unwrap()
+}
+
+/// Like [`review_pbn`], taking deals found in `db` from it instead of solving
+/// them and storing the ones solved
+///
+/// Fails only if the database cannot be written.
+pub fn review_pbn_with_db(
+    content: &str,
+    mut db: Option<&mut ResultsDb>,
+) -> io::Result<Vec<BoardReview>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut reviews = Vec::new();
+    let mut deal_count = 0;
+    let mut results = ResultCache::new();
+    for block in pbn_blocks(&lines) {
+        let block = &lines[block];
+        if !block.iter().any(|line| extract_deal_tag(line).is_some()) {
+            continue;
+        }
+        deal_count += 1;
+        if let Some(review) = review_block(block, deal_count, &mut results, db.as_deref_mut())? {
+            reviews.push(review);
+        }
+    }
+    Ok(reviews)
+}
+
+/// Review one PBN block; None if it has no full deal or no readable result
+fn review_block(
+    lines: &[&str],
+    deal_count: usize,
+    results: &mut ResultCache,
+    db: Option<&mut ResultsDb>,
+) -> io::Result<Option<BoardReview>> {
+    let tag = |name: &str| lines.iter().find_map(|line| extract_tag_value(line, name));
+
+    let hands = lines
+        .iter()
+        .find_map(|line| extract_deal_tag(line))
+        .and_then(Hands::from_pbn)
+        .filter(|hands| hands.is_valid() && hands.num_tricks() == TOTAL_TRICKS);
+    let Some(hands) = hands else {
+        return Ok(None);
+    };
+    let vulnerability = lines
+        .iter()
+        .find_map(|line| extract_vulnerability_tag(line))
+        .unwrap_or_default();
+    let dealer = tag("Dealer").and_then(parse_seat_value).unwrap_or(NORTH);
+    let board = tag("Board").map_or_else(|| deal_count.to_string(), str::to_string);
+
+    let entries = match score_table(lines) {
+        Some(rows) => rows,
+        None => {
+            let players = [WEST, NORTH, EAST, SOUTH].map(|seat| {
+                tag(["West", "North", "East", "South"][seat])
+                    .filter(|name| !name.is_empty() && *name != "?")
+                    .map(str::to_string)
+            });
+            vec![Entry {
+                pairs: pair_names(&players),
+                players,
+                contract: match tag("Contract") {
+                    Some(contract) => contract.to_string(),
+                    None => return Ok(None),
+                },
+                declarer: tag("Declarer").unwrap_or("").to_string(),
+                result: tag("Result").unwrap_or("").to_string(),
+            }]
+        }
+    };
+
+    let table = solve_with_db(&hands, results, db)?;
+    let par = par(&table, vulnerability, dealer);
+    let results: Vec<TableResult> = entries
+        .into_iter()
+        .filter_map(|entry| entry.review(&table, vulnerability, &par))
+        .collect();
+    if results.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(BoardReview {
+        board,
+        vulnerability,
+        table,
+        par,
+        results,
+    }))
+}
+
+/// A result as read from the PBN, before it is checked and scored
+struct Entry {
+    pairs: [Option<String>; 2],
+    players: [Option<String>; NUM_SEATS],
+    contract: String,
+    declarer: String,
+    result: String,
+}
+
+impl Entry {
+    fn review(
+        self,
+        table: &DdTable,
+        vulnerability: Vulnerability,
+        par: &ParResult,
+    ) -> Option<TableResult> {
+        let (contract, tricks, dd_tricks) = if self.contract.trim().eq_ignore_ascii_case("pass") {
+            (None, 0, 0)
+        } else {
+            let declarer = parse_seat_value(&self.declarer)?;
+            let contract = Contract::from_pbn(&self.contract, declarer)?;
+            let tricks = self.result.trim().parse::<u8>().ok()?;
+            if tricks as usize > TOTAL_TRICKS {
+                return None;
+            }
+            (
+                Some(contract),
+                tricks,
+                table.get_index(declarer, contract.strain),
+            )
+        };
+        let ns_score =
+            |tricks| contract.map_or(0, |contract| contract.ns_score(vulnerability, tricks));
+        Some(TableResult {
+            pairs: self.pairs,
+            players: self.players,
+            contract,
+            tricks,
+            dd_tricks,
+            ns_score: ns_score(tricks),
+            dd_ns_score: ns_score(dd_tricks),
+            par_delta: ns_score(tricks) - par.ns_score,
+        })
+    }
+}
+
+/// "North - South" and "East - West", when both names of a pair are known
+fn pair_names(players: &[Option<String>; NUM_SEATS]) -> [Option<String>; 2] {
+    [(NORTH, SOUTH), (EAST, WEST)].map(|(first, second)| {
+        match (&players[first], &players[second]) {
+            (Some(first), Some(second)) => Some(format!("{} - {}", first, second)),
+            _ => None,
+        }
+    })
+}
+
+/// The rows of the block's `[ScoreTable]`, if it has one with the columns we need
+fn score_table(lines: &[&str]) -> Option<Vec<Entry>> {
+    let start = lines
+        .iter()
+        .position(|line| extract_tag_value(line, "ScoreTable").is_some())?;
+    let columns: Vec<&str> = extract_tag_value(lines[start], "ScoreTable")?
+        .split(';')
+        .map(|column| column.split('\\').next().unwrap_or("").trim())
+        .collect();
+    let column = |name: &str| columns.iter().position(|&column| column == name);
+    let contract = column("Contract")?;
+    let declarer = column("Declarer")?;
+    let result = column("Result")?;
+    let ns = column("PairId_NS");
+    let ew = column("PairId_EW");
+
+    let rows = lines[start + 1..]
+        .iter()
+        .take_while(|line| !line.trim_start().starts_with('['))
+        .map(|line| score_table_fields(line))
+        .filter(|fields| fields.len() == columns.len())
+        .map(|fields| {
+            let field = |index: Option<usize>| {
+                index
+                    .map(|index| fields[index])
+                    .filter(|value| !value.is_empty() && *value != "-")
+                    .map(str::to_string)
+            };
+            Entry {
+                pairs: [field(ns), field(ew)],
+                players: Default::default(),
+                contract: fields[contract].to_string(),
+                declarer: fields[declarer].to_string(),
+                result: fields[result].to_string(),
+            }
+        })
+        .collect();
+    Some(rows)
+}
+
+/// Split a ScoreTable row into fields: words, or strings in double quotes
+fn score_table_fields(line: &str) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut rest = line.trim_start();
+    while !rest.is_empty() {
+        let (field, after) = match rest.strip_prefix('"') {
+            Some(quoted) => match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            },
+            None => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                rest.split_at(end)
+            }
+        };
+        fields.push(field);
+        rest = after.trim_start();
+    }
+    fields
+}
+
+impl fmt::Display for BoardReview {
+    /// The board and its par, then a line per result, e.g.
+    /// "  4S-N 9 tricks, DD 10 (-1): NS -50, DD 420, par -470 (-10 IMPs)"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.par.contract {
+            Some(contract) => writeln!(
+                f,
+                "Board {}: par {} NS {}",
+                self.board, contract, self.par.ns_score
+            )?,
+            None => writeln!(f, "Board {}: par passed out", self.board)?,
+        }
+        for result in &self.results {
+            match &result.contract {
+                Some(contract) => write!(
+                    f,
+                    "  {} {} tricks, DD {} ({:+}): NS {}, DD {}",
+                    contract,
+                    result.tricks,
+                    result.dd_tricks,
+                    result.trick_delta(),
+                    result.ns_score,
+                    result.dd_ns_score
+                )?,
+                None => write!(f, "  Passed out: NS 0")?,
+            }
+            write!(
+                f,
+                ", par {:+} ({:+} IMPs)",
+                result.par_delta,
+                imps(result.par_delta)
+            )?;
+            if let [Some(ns), Some(ew)] = &result.pairs {
+                write!(f, " [{} vs {}]", ns, ew)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Who a [`ReviewSummary`] adds results up for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReviewBy {
+    /// Pair ids from ScoreTable rows, or the pair's two names
+    Pair,
+    /// Players named in the seat tags
+    Player,
+}
+
+/// Results of one pair or player, added up over boards
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SummaryEntry {
+    pub name: String,
+    /// Results played
+    pub boards: usize,
+    /// Results as declarer
+    pub declared: usize,
+    /// Tricks taken over double dummy as declarer
+    pub declarer_tricks: i32,
+    /// Results as a defender
+    pub defended: usize,
+    /// Tricks held declarer below double dummy as a defender
+    pub defence_tricks: i32,
+    /// Score over par, for the pair's or player's side
+    pub par_points: i32,
+    /// IMPs over par, board by board
+    pub par_imps: i32,
+}
+
+/// Per pair or per player totals over a set of board reviews
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReviewSummary {
+    /// One entry per pair or player, by name
+    pub entries: Vec<SummaryEntry>,
+}
+
+impl ReviewSummary {
+    /// Add up `reviews`, skipping results whose pairs or players aren't named
+    pub fn new(reviews: &[BoardReview], by: ReviewBy) -> Self {
+        let mut entries: BTreeMap<String, SummaryEntry> = BTreeMap::new();
+        for result in reviews.iter().flat_map(|review| &review.results) {
+            let declarer = result.contract.map(|contract| contract.declarer);
+            let seats: Vec<(SeatIndex, &Option<String>)> = match by {
+                // A pair is its first seat: North or East
+                ReviewBy::Pair => vec![(NORTH, &result.pairs[0]), (EAST, &result.pairs[1])],
+                ReviewBy::Player => (0..NUM_SEATS)
+                    .map(|seat| (seat, &result.players[seat]))
+                    .collect(),
+            };
+            for (seat, name) in seats {
+                let Some(name) = name else {
+                    continue;
+                };
+                let entry = entries.entry(name.clone()).or_insert_with(|| SummaryEntry {
+                    name: name.clone(),
+                    ..Default::default()
+                });
+                let sign = if is_ns(seat) { 1 } else { -1 };
+                entry.boards += 1;
+                entry.par_points += sign * result.par_delta;
+                entry.par_imps += sign * imps(result.par_delta);
+                let Some(declarer) = declarer else {
+                    continue;
+                };
+                let declares = match by {
+                    ReviewBy::Pair => is_ns(seat) == is_ns(declarer),
+                    ReviewBy::Player => seat == declarer,
+                };
+                if declares {
+                    entry.declared += 1;
+                    entry.declarer_tricks += result.trick_delta();
+                } else if is_ns(seat) != is_ns(declarer) {
+                    entry.defended += 1;
+                    entry.defence_tricks -= result.trick_delta();
+                }
+            }
+        }
+        ReviewSummary {
+            entries: entries.into_values().collect(),
+        }
+    }
+}
+
+impl fmt::Display for ReviewSummary {
+    /// A table with a row per pair or player
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .entries
+            .iter()
+            .map(|entry| entry.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(4);
+        writeln!(
+            f,
+            "{:width$} Boards  Decl Tricks   Def Tricks  Par pts Par IMPs",
+            "Name"
+        )?;
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "{:width$} {:6} {:5} {:+6} {:5} {:+6} {:+8} {:+8}",
+                entry.name,
+                entry.boards,
+                entry.declared,
+                entry.declarer_tricks,
+                entry.defended,
+                entry.defence_tricks,
+                entry.par_points,
+                entry.par_imps
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each hand holds one suit: NS make 7S, EW make 7H, and nothing else makes
+    const DEAL: &str = "N:AKQJT98765432... .AKQJT98765432.. ..AKQJT98765432. ...AKQJT98765432";
+
+    fn pbn() -> String {
+        format!(
+            r#"[Board "1"]
+[Dealer "N"]
+[Vulnerable "None"]
+[Deal "{deal}"]
+[ScoreTable "PairId_NS\2R;PairId_EW\2R;Contract\4L;Declarer\1R;Result\2R"]
+ 1  2 6S  N 13
+ 3  4 7S  N 12
+ 5  6 Pass - -
+
+[Board "2"]
+[Dealer "E"]
+[Vulnerable "None"]
+[West "Di"]
+[North "Ann"]
+[East "Cy"]
+[South "Bob"]
+[Deal "{deal}"]
+[Declarer "E"]
+[Contract "7H"]
+[Result "13"]
+"#,
+            deal = DEAL
+        )
+    }
+
+    #[test]
+    fn test_score_table_fields() {
+        assert_eq!(
+            score_table_fields(r#" 1 "Smith - Jones" 4S  N 10"#),
+            vec!["1", "Smith - Jones", "4S", "N", "10"]
+        );
+        assert!(score_table_fields("   ").is_empty());
+    }
+
+    #[test]
+    fn test_review_pbn() {
+        let reviews = review_pbn(&pbn());
+        assert_eq!(reviews.len(), 2);
+
+        let board = &reviews[0];
+        assert_eq!(board.board, "1");
+        assert_eq!(board.par.ns_score, 1510);
+        assert_eq!(board.results.len(), 3);
+
+        let small_slam = &board.results[0];
+        assert_eq!(small_slam.contract, Contract::from_pbn("6S", NORTH));
+        assert_eq!((small_slam.tricks, small_slam.dd_tricks), (13, 13));
+        assert_eq!(small_slam.ns_score, 1010);
+        assert_eq!(small_slam.par_delta, -500);
+
+        let grand_slam = &board.results[1];
+        assert_eq!(grand_slam.trick_delta(), -1);
+        assert_eq!(grand_slam.ns_score, -50);
+        assert_eq!(grand_slam.dd_ns_score, 1510);
+
+        let passed = &board.results[2];
+        assert_eq!(passed.contract, None);
+        assert_eq!(passed.par_delta, -1510);
+
+        let board = &reviews[1];
+        let result = &board.results[0];
+        assert_eq!(result.pairs[0].as_deref(), Some("Ann - Bob"));
+        assert_eq!(result.pairs[1].as_deref(), Some("Cy - Di"));
+        assert_eq!(result.ns_score, -1510);
+        assert_eq!(result.par_delta, -3020);
+
+        let text = reviews[0].to_string();
+        assert!(text.starts_with("Board 1: par 7S-N NS 1510\n"));
+        assert!(text.contains(
+            "6S-N 13 tricks, DD 13 (+0): NS 1010, DD 1010, par -500 (-11 IMPs) [1 vs 2]"
+        ));
+    }
+
+    #[test]
+    fn test_summary_by_pair() {
+        let summary = ReviewSummary::new(&review_pbn(&pbn()), ReviewBy::Pair);
+        let entry = |name: &str| {
+            summary
+                .entries
+                .iter()
+                .find(|entry| entry.name == name)
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(summary.entries.len(), 8);
+
+        let pair = entry("3");
+        assert_eq!((pair.declared, pair.declarer_tricks), (1, -1));
+        assert_eq!((pair.par_points, pair.par_imps), (-1560, -17));
+        let pair = entry("4");
+        assert_eq!((pair.defended, pair.defence_tricks), (1, 1));
+        assert_eq!((pair.par_points, pair.par_imps), (1560, 17));
+
+        // Passed out: neither side declares
+        let pair = entry("5");
+        assert_eq!((pair.boards, pair.declared, pair.defended), (1, 0, 0));
+
+        let pair = entry("Ann - Bob");
+        assert_eq!((pair.defended, pair.par_imps), (1, -22));
+    }
+
+    #[test]
+    fn test_summary_by_player() {
+        let summary = ReviewSummary::new(&review_pbn(&pbn()), ReviewBy::Player);
+        let names: Vec<&str> = summary.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Ann", "Bob", "Cy", "Di"]);
+
+        let [ann, _, cy, di] = &summary.entries[..] else {
+            unreachable!()
+        };
+        assert_eq!((ann.defended, ann.par_points), (1, -3020));
+        assert_eq!((cy.declared, cy.declarer_tricks, cy.par_imps), (1, 0, 22));
+        // Dummy neither declares nor defends
+        assert_eq!((di.boards, di.declared, di.defended), (1, 0, 0));
+
+        let text = summary.to_string();
+        assert!(text.starts_with("Name Boards"));
+        assert_eq!(text.lines().count(), 5);
+    }
+}
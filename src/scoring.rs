@@ -0,0 +1,334 @@
+//! Duplicate bridge scoring: contract scores, IMPs and matchpoints
+
+use super::types::*;
+use std::fmt;
+
+/// Which sides are vulnerable
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Vulnerability {
+    #[default]
+    None,
+    NS,
+    EW,
+    Both,
+}
+
+impl Vulnerability {
+    /// Check if the side of `seat` is vulnerable
+    pub fn is_vulnerable(self, seat: SeatIndex) -> bool {
+        match self {
+            Vulnerability::None => false,
+            Vulnerability::NS => is_ns(seat),
+            Vulnerability::EW => !is_ns(seat),
+            Vulnerability::Both => true,
+        }
+    }
+
+    /// Parse a PBN vulnerability ("None", "NS", "EW", "All", plus the usual aliases)
+    pub fn from_pbn(s: &str) -> Option<Self> {
+        match s.trim() {
+            "None" | "none" | "Love" | "-" => Some(Vulnerability::None),
+            "NS" | "ns" | "N-S" => Some(Vulnerability::NS),
+            "EW" | "ew" | "E-W" => Some(Vulnerability::EW),
+            "All" | "all" | "Both" | "both" => Some(Vulnerability::Both),
+            _ => None,
+        }
+    }
+}
+
+/// Doubling state of a contract
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Doubled {
+    #[default]
+    Undoubled,
+    Doubled,
+    Redoubled,
+}
+
+/// A contract, as played
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Contract {
+    /// Level 1-7
+    pub level: u8,
+    /// Suit or NOTRUMP
+    pub strain: usize,
+    pub doubled: Doubled,
+    pub declarer: SeatIndex,
+}
+
+impl Contract {
+    pub fn new(level: u8, strain: usize, doubled: Doubled, declarer: SeatIndex) -> Self {
+        Contract {
+            level,
+            strain,
+            doubled,
+            declarer,
+        }
+    }
+
+    /// Parse a PBN contract such as "4S", "3NTX" or "6HXX" played by `declarer`
+    ///
+    /// Returns None for "Pass" as well as for anything malformed.
+    pub fn from_pbn(s: &str, declarer: SeatIndex) -> Option<Self> {
+        let s = s.trim().to_ascii_uppercase();
+        let mut chars = s.chars();
+        let level = chars.next()?.to_digit(10)? as u8;
+        if !(1..=7).contains(&level) {
+            return None;
+        }
+        let rest = chars.as_str();
+        let (strain, rest) = match rest.strip_prefix("NT") {
+            Some(rest) => (NOTRUMP, rest),
+            None => {
+                let mut chars = rest.chars();
+                let strain = chars.next().and_then(char_to_suit)?;
+                (strain, chars.as_str())
+            }
+        };
+        let doubled = match rest {
+            "" => Doubled::Undoubled,
+            "X" => Doubled::Doubled,
+            "XX" => Doubled::Redoubled,
+            _ => return None,
+        };
+        Some(Contract::new(level, strain, doubled, declarer))
+    }
+
+    /// Tricks declarer needs to make the contract
+    pub fn tricks_needed(&self) -> u8 {
+        self.level + 6
+    }
+
+    /// Duplicate score for declarer's side when declarer takes `tricks`
+    pub fn score(&self, vulnerability: Vulnerability, tricks: u8) -> i32 {
+        contract_score(
+            self.level,
+            self.strain,
+            self.doubled,
+            vulnerability.is_vulnerable(self.declarer),
+            tricks,
+        )
+    }
+
+    /// Like [`Contract::score`], from NS's point of view
+    pub fn ns_score(&self, vulnerability: Vulnerability, tricks: u8) -> i32 {
+        let score = self.score(vulnerability, tricks);
+        if is_ns(self.declarer) {
+            score
+        } else {
+            -score
+        }
+    }
+}
+
+impl fmt::Display for Contract {
+    /// Formats as e.g. "4S-N", "3NTX-E" or "6HXX-S"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let doubled = match self.doubled {
+            Doubled::Undoubled => "",
+            Doubled::Doubled => "X",
+            Doubled::Redoubled => "XX",
+        };
+        write!(
+            f,
+            "{}{}{}-{}",
+            self.level,
+            strain_name(self.strain),
+            doubled,
+            seat_letter(self.declarer)
+        )
+    }
+}
+
+/// Duplicate score for declarer's side
+///
+/// `level` is 1-7, `strain` a suit or NOTRUMP, `tricks` the tricks declarer took.
+/// Negative when the contract fails.
+pub fn contract_score(
+    level: u8,
+    strain: usize,
+    doubled: Doubled,
+    vulnerable: bool,
+    tricks: u8,
+) -> i32 {
+    let level = level as i32;
+    let tricks = tricks as i32;
+    let multiplier = match doubled {
+        Doubled::Undoubled => 1,
+        Doubled::Doubled => 2,
+        Doubled::Redoubled => 4,
+    };
+
+    let needed = level + 6;
+    if tricks < needed {
+        let down = needed - tricks;
+        return -match doubled {
+            Doubled::Undoubled => down * if vulnerable { 100 } else { 50 },
+            _ => {
+                // Doubled: 100/300/500/800... not vulnerable, 200/500/800... vulnerable
+                let doubled_penalty = if vulnerable {
+                    200 + (down - 1) * 300
+                } else {
+                    100 + (down - 1).min(2) * 200 + (down - 3).max(0) * 300
+                };
+                doubled_penalty * multiplier / 2
+            }
+        };
+    }
+
+    let per_trick = if strain == CLUB || strain == DIAMOND {
+        20
+    } else {
+        30
+    };
+    let no_trump_bonus = if strain == NOTRUMP { 10 } else { 0 };
+    let contract_points = (level * per_trick + no_trump_bonus) * multiplier;
+
+    let mut score = contract_points;
+    score += if contract_points >= 100 {
+        if vulnerable {
+            500
+        } else {
+            300
+        }
+    } else {
+        50
+    };
+    score += match level {
+        6 => {
+            if vulnerable {
+                750
+            } else {
+                500
+            }
+        }
+        7 => {
+            if vulnerable {
+                1500
+            } else {
+                1000
+            }
+        }
+        _ => 0,
+    };
+
+    let overtricks = tricks - needed;
+    score += match doubled {
+        Doubled::Undoubled => overtricks * per_trick,
+        _ => overtricks * multiplier / 2 * if vulnerable { 200 } else { 100 },
+    };
+    // The "insult" for making a doubled or redoubled contract
+    score += match doubled {
+        Doubled::Undoubled => 0,
+        Doubled::Doubled => 50,
+        Doubled::Redoubled => 100,
+    };
+    score
+}
+
+/// Smallest score difference worth each IMP, from 1 IMP up to 24
+const IMP_THRESHOLDS: [i32; 24] = [
+    20, 50, 90, 130, 170, 220, 270, 320, 370, 430, 500, 600, 750, 900, 1100, 1300, 1500, 1750,
+    2000, 2250, 2500, 3000, 3500, 4000,
+];
+
+/// Convert a score difference to IMPs, keeping its sign
+pub fn imps(difference: i32) -> i32 {
+    let imps = IMP_THRESHOLDS
+        .iter()
+        .take_while(|&&threshold| difference.abs() >= threshold)
+        .count() as i32;
+    imps * difference.signum()
+}
+
+/// Matchpoints for each of `scores`, all from the same side's point of view
+///
+/// Each score earns 1 for every other score it beats and 1/2 for every other
+/// score it ties, so the top gets `scores.len() - 1`.
+pub fn matchpoints(scores: &[i32]) -> Vec<f64> {
+    scores
+        .iter()
+        .map(|&score| {
+            let beaten = scores.iter().filter(|&&other| other < score).count();
+            let tied = scores.iter().filter(|&&other| other == score).count() - 1;
+            beaten as f64 + tied as f64 / 2.0
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_making_contracts() {
+        assert_eq!(contract_score(1, NOTRUMP, Doubled::Undoubled, false, 7), 90);
+        assert_eq!(contract_score(2, CLUB, Doubled::Undoubled, false, 9), 110);
+        assert_eq!(
+            contract_score(3, NOTRUMP, Doubled::Undoubled, false, 10),
+            430
+        );
+        assert_eq!(contract_score(4, SPADE, Doubled::Undoubled, true, 10), 620);
+        assert_eq!(contract_score(6, HEART, Doubled::Undoubled, false, 12), 980);
+        assert_eq!(
+            contract_score(7, NOTRUMP, Doubled::Undoubled, true, 13),
+            2220
+        );
+        assert_eq!(contract_score(2, HEART, Doubled::Doubled, false, 8), 470);
+        assert_eq!(contract_score(1, CLUB, Doubled::Redoubled, true, 8), 630);
+    }
+
+    #[test]
+    fn test_failing_contracts() {
+        assert_eq!(contract_score(4, SPADE, Doubled::Undoubled, false, 8), -100);
+        assert_eq!(contract_score(4, SPADE, Doubled::Undoubled, true, 9), -100);
+        assert_eq!(contract_score(5, HEART, Doubled::Doubled, false, 7), -800);
+        assert_eq!(contract_score(5, HEART, Doubled::Doubled, true, 9), -500);
+        assert_eq!(
+            contract_score(3, NOTRUMP, Doubled::Redoubled, false, 7),
+            -600
+        );
+    }
+
+    #[test]
+    fn test_contract() {
+        let contract = Contract::from_pbn("4S", NORTH).unwrap();
+        assert_eq!(contract, Contract::new(4, SPADE, Doubled::Undoubled, NORTH));
+        assert_eq!(contract.score(Vulnerability::NS, 10), 620);
+        assert_eq!(contract.ns_score(Vulnerability::EW, 11), 450);
+
+        let contract = Contract::from_pbn("3NTx", EAST).unwrap();
+        assert_eq!(contract.to_string(), "3NTX-E");
+        assert_eq!(contract.ns_score(Vulnerability::None, 6), 500);
+        assert_eq!(
+            Contract::from_pbn("6HXX", SOUTH).unwrap().doubled,
+            Doubled::Redoubled
+        );
+
+        for bad in ["Pass", "", "8S", "0NT", "4", "4Z", "4SXXX"] {
+            assert_eq!(Contract::from_pbn(bad, NORTH), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_imps() {
+        assert_eq!(imps(0), 0);
+        assert_eq!(imps(10), 0);
+        assert_eq!(imps(20), 1);
+        assert_eq!(imps(-30), -1);
+        assert_eq!(imps(620 - 170), 10);
+        assert_eq!(imps(-(1430 - 480)), -14);
+        assert_eq!(imps(3990), 23);
+        assert_eq!(imps(7600), 24);
+    }
+
+    #[test]
+    fn test_matchpoints() {
+        assert_eq!(
+            matchpoints(&[620, 170, 620, -100]),
+            vec![2.5, 1.0, 2.5, 0.0]
+        );
+        assert_eq!(matchpoints(&[50]), vec![0.0]);
+        assert!(matchpoints(&[]).is_empty());
+    }
+}
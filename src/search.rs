@@ -1,20 +1,26 @@
 //! Refactored search implementation matching C++ structure
 //!
 //! This module implements a 3-layer search structure matching the C++ reference:
-//! - SearchWithCache: Trick boundary handling, TT lookups, XRAY logging
+//! - SearchWithCache: Trick boundary handling, TT lookups
 //! - SearchAtTrickStart: Fast/slow tricks pruning
 //! - EvaluatePlayableCards: Main card evaluation loop with IsEquivalent
 
+use super::cache::{invalid_data, put_u32, put_u64, ByteReader, MAX_CACHE_FILE_BITS};
 use super::cards::*;
+use super::endgame::EndgameTable;
 use super::hands::Hands;
 use super::pattern::{compute_pattern_hands, Bounds, Pattern, PatternCache, RelativeHands, Shape};
 use super::play::*;
+use super::stats::{CutoffCacheStats, SearchStats};
+use super::trace::{NoObserver, SearchNode, SearchObserver};
 use super::types::*;
+use std::io;
 use std::sync::atomic::Ordering;
+use std::sync::Arc;
 
 // Re-export atomic counters from bridge_solver module
 use super::bridge_solver::{
-    xray_should_log, NODE_COUNT, NO_PRUNING, NO_RANK_SKIP, NO_TT, XRAY_COUNT, XRAY_LIMIT,
+    endgame_table, SolveLimit, NODE_COUNT, NO_PRUNING, NO_RANK_SKIP, NO_TT,
 };
 
 /// Search result - NS tricks and rank winners (cards whose rank affected the outcome)
@@ -43,7 +49,7 @@ pub struct PlayState {
     /// NS tricks won so far
     pub ns_tricks_won: u8,
     /// Current seat to play
-    pub seat_to_play: Seat,
+    pub seat_to_play: SeatIndex,
     /// Card played at this depth
     pub card_played: usize,
     /// Depth of the winning play in current trick
@@ -64,6 +70,9 @@ pub struct CutoffCache {
     mask: usize,
     probe_distance: usize,
     load_count: usize,
+    /// Largest size the cache may grow to, as 2^bits entries
+    max_bits: usize,
+    stats: CutoffCacheStats,
 }
 
 impl CutoffCache {
@@ -79,9 +88,61 @@ impl CutoffCache {
             mask: size - 1,
             probe_distance: 0,
             load_count: 0,
+            max_bits: usize::BITS as usize - 1,
+            stats: CutoffCacheStats::default(),
         }
     }
 
+    /// Create a cache using at most `megabytes` of memory
+    ///
+    /// Once full, the cache is cleared instead of growing.
+    pub fn with_memory_limit(megabytes: usize) -> Self {
+        let entries = (megabytes.max(1) << 20) / std::mem::size_of::<CutoffEntry>();
+        let max_bits = entries.ilog2() as usize;
+        let mut cache = CutoffCache::new(max_bits.min(16));
+        cache.max_bits = max_bits;
+        cache
+    }
+
+    /// Memory in use, in bytes
+    pub fn memory_used(&self) -> usize {
+        self.entries.len() * std::mem::size_of::<CutoffEntry>()
+    }
+
+    /// Number of entries in use
+    pub fn len(&self) -> usize {
+        self.load_count
+    }
+
+    /// Size of the table, as 2^bits entries
+    pub(crate) fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// Check if no entry is in use
+    pub fn is_empty(&self) -> bool {
+        self.load_count == 0
+    }
+
+    /// Counters since the cache was created or `reset_stats` was called
+    pub fn stats(&self) -> CutoffCacheStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = CutoffCacheStats::default();
+    }
+
+    /// Empty the cache, keeping the table for reuse
+    pub fn clear(&mut self) {
+        self.entries.fill(CutoffEntry {
+            hash: 0,
+            card: [255, 255, 255, 255],
+        });
+        self.probe_distance = 0;
+        self.load_count = 0;
+    }
+
     #[inline]
     fn index(&self, hash: u64) -> usize {
         // C++ uses: hash >> (BitSize(hash) - bits)
@@ -90,31 +151,48 @@ impl CutoffCache {
     }
 
     #[inline]
-    pub fn lookup(&self, hash: u64, seat: Seat) -> Option<usize> {
+    pub fn lookup(&mut self, hash: u64, seat: SeatIndex) -> Option<usize> {
         let base_index = self.index(hash);
+        let mut card = None;
+        let mut probes = 0;
         // Linear probing like C++
         for d in 0..self.probe_distance {
+            probes = d as u64 + 1;
             let entry = &self.entries[(base_index + d) & self.mask];
             if entry.hash == hash {
                 if entry.card[seat] != 255 {
-                    return Some(entry.card[seat] as usize);
-                } else {
-                    return None;
+                    card = Some(entry.card[seat] as usize);
                 }
+                break;
             }
             if entry.hash == 0 {
                 break; // Empty slot, entry not found
             }
         }
-        None
+        self.stats.lookups += 1;
+        self.stats.hits += card.is_some() as u64;
+        self.stats.probes += probes;
+        self.stats.max_probe = self.stats.max_probe.max(probes);
+        card
     }
 
     #[inline]
-    pub fn store(&mut self, hash: u64, seat: Seat, card: usize) {
-        // Resize if needed (at 75% load)
+    pub fn store(&mut self, hash: u64, seat: SeatIndex, card: usize) {
+        self.stats.stores += 1;
+        self.insert(hash, seat, card);
+    }
+
+    fn insert(&mut self, hash: u64, seat: SeatIndex, card: usize) {
+        // Resize if needed (at 75% load), or start over at the size limit
         let size = self.mask + 1;
         if self.load_count >= size * 3 / 4 {
-            self.resize();
+            if self.bits < self.max_bits {
+                self.stats.resizes += 1;
+                self.resize();
+            } else {
+                self.stats.clears += 1;
+                self.clear();
+            }
         }
 
         let base_index = self.index(hash);
@@ -159,12 +237,60 @@ impl CutoffCache {
                 // Store each seat's card if it was set
                 for seat in 0..4 {
                     if entry.card[seat] != 255 {
-                        self.store(entry.hash, seat, entry.card[seat] as usize);
+                        self.insert(entry.hash, seat, entry.card[seat] as usize);
                     }
                 }
             }
         }
     }
+
+    /// Serialize the table layout and used entries (see the `cache` module)
+    pub(crate) fn write_to(&self, out: &mut Vec<u8>) {
+        put_u32(out, self.bits as u32);
+        put_u32(out, self.probe_distance as u32);
+        put_u64(out, self.load_count as u64);
+        for (index, entry) in self.entries.iter().enumerate() {
+            if entry.hash != 0 {
+                put_u32(out, index as u32);
+                put_u64(out, entry.hash);
+                out.extend_from_slice(&entry.card);
+            }
+        }
+    }
+
+    /// Deserialize a cache written by `write_to`
+    ///
+    /// The sizes are checked against the payload before the table is allocated.
+    pub(crate) fn read_from(input: &mut ByteReader) -> io::Result<Self> {
+        let bits = input.u32()? as usize;
+        let probe_distance = input.u32()? as usize;
+        let load_count = input.u64()?;
+        // Each entry takes 16 bytes: index, hash and four cards
+        if !(1..=MAX_CACHE_FILE_BITS).contains(&bits)
+            || probe_distance > 1 << bits
+            || load_count > 1 << bits
+            || load_count > input.remaining() as u64 / 16
+        {
+            return Err(invalid_data("invalid cutoff cache size"));
+        }
+        let mut cache = CutoffCache::new(bits);
+        cache.probe_distance = probe_distance;
+        cache.load_count = load_count as usize;
+        for _ in 0..cache.load_count {
+            let index = input.u32()? as usize;
+            let hash = input.u64()?;
+            let mut card = [0u8; 4];
+            for card in &mut card {
+                *card = input.u8()?;
+            }
+            let valid_cards = card.iter().all(|&c| c == 255 || (c as usize) < TOTAL_CARDS);
+            if index >= cache.entries.len() || hash == 0 || !valid_cards {
+                return Err(invalid_data("invalid cutoff cache entry"));
+            }
+            cache.entries[index] = CutoffEntry { hash, card };
+        }
+        Ok(cache)
+    }
 }
 
 /// Hash constants (matching C++ hash_rand values)
@@ -178,19 +304,19 @@ fn hash_cutoff_index(key0: u64, key1: u64) -> u64 {
     (key0.wrapping_add(HASH_RAND[0])).wrapping_mul(key1.wrapping_add(HASH_RAND[1]))
 }
 
-/// Build cutoff index keys with debug info (returns hash, key0, key1)
+/// Build the cutoff cache index (matches C++ BuildCutoffIndex)
 #[inline]
 #[allow(clippy::too_many_arguments)]
-fn build_cutoff_index_debug(
+fn build_cutoff_index(
     hands: &Hands,
-    seat_to_play: Seat,
+    seat_to_play: SeatIndex,
     card_in_trick: usize,
     lead_suit: usize,
     winning_card: usize,
-    winning_seat: Seat,
+    winning_seat: SeatIndex,
     trump: usize,
     all_cards: Cards,
-) -> (u64, u64, u64) {
+) -> u64 {
     let key0: u64;
     let mut key1: u64 = 0;
 
@@ -216,18 +342,7 @@ fn build_cutoff_index_debug(
     // Always add position in trick (TOTAL_CARDS + card_in_trick)
     key1 |= 1u64 << (TOTAL_CARDS + card_in_trick);
 
-    (hash_cutoff_index(key0, key1), key0, key1)
-}
-
-/// Format a card as a string
-fn card_name(card: usize) -> String {
-    const SUITS: [char; 4] = ['S', 'H', 'D', 'C'];
-    const RANKS: [char; 13] = [
-        'A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2',
-    ];
-    let suit = suit_of(card);
-    let rank = rank_of(card);
-    format!("{}{}", SUITS[suit], RANKS[12 - rank])
+    hash_cutoff_index(key0, key1)
 }
 
 /// Ordered cards container for move ordering
@@ -263,7 +378,9 @@ impl OrderedCards {
 }
 
 /// The main search engine, matching C++ `class Play`
-pub struct Search<'a> {
+///
+/// `O` observes the search (see the `trace` module); the default observes nothing.
+pub struct Search<'a, O: SearchObserver = NoObserver> {
     // Fixed info (shared across all depths)
     hands: &'a mut Hands,
     trump: usize,
@@ -279,8 +396,19 @@ pub struct Search<'a> {
     cutoff_cache: &'a mut CutoffCache,
     pattern_cache: &'a mut PatternCache,
 
+    // Exact results of the last few tricks, if installed
+    endgame: Option<Arc<EndgameTable>>,
+
     // Starting depth for mid-trick positions (0 for normal positions)
     start_depth: usize,
+
+    // Work limit: nodes searched so far by this solve, and whether it gave up
+    limit: SolveLimit,
+    nodes: u64,
+    aborted: bool,
+
+    stats: SearchStats,
+    observer: O,
 }
 
 impl<'a> Search<'a> {
@@ -288,7 +416,7 @@ impl<'a> Search<'a> {
     pub fn new(
         hands: &'a mut Hands,
         trump: usize,
-        initial_leader: Seat,
+        initial_leader: SeatIndex,
         cutoff_cache: &'a mut CutoffCache,
         pattern_cache: &'a mut PatternCache,
     ) -> Self {
@@ -309,7 +437,7 @@ impl<'a> Search<'a> {
     pub fn new_with_partial_trick(
         hands: &'a mut Hands,
         trump: usize,
-        initial_leader: Seat,
+        initial_leader: SeatIndex,
         cutoff_cache: &'a mut CutoffCache,
         pattern_cache: &'a mut PatternCache,
         partial_trick: Option<&super::bridge_solver::PartialTrick>,
@@ -337,7 +465,7 @@ impl<'a> Search<'a> {
                 // Compute all_cards including the partial trick cards
                 let mut all_cards = hands.all_cards();
                 for played in &pt.plays {
-                    all_cards.add(played.card);
+                    all_cards.add_index(played.card);
                 }
                 tricks[0].all_cards = all_cards;
 
@@ -345,7 +473,7 @@ impl<'a> Search<'a> {
                 // We need to temporarily add the partial trick cards back to compute the shape
                 let mut full_hands = *hands;
                 for played in &pt.plays {
-                    full_hands[played.seat].add(played.card);
+                    full_hands[played.seat].add_index(played.card);
                 }
                 tricks[0].shape = Shape::from_hands(&full_hands);
                 tricks[0].relative_hands.compute(&full_hands, all_cards);
@@ -407,25 +535,76 @@ impl<'a> Search<'a> {
             tricks,
             cutoff_cache,
             pattern_cache,
+            endgame: endgame_table(),
             start_depth,
+            limit: SolveLimit::unlimited(),
+            nodes: 0,
+            aborted: false,
+            stats: SearchStats::default(),
+            observer: NoObserver,
         }
     }
 
-    /// Format the play sequence up to (but not including) the given depth
-    fn format_play_sequence(&self, depth: usize) -> String {
-        if depth == 0 {
-            return String::new();
+    /// Report the search's events to `observer`
+    pub fn with_observer<P: SearchObserver>(self, observer: P) -> Search<'a, P> {
+        Search {
+            hands: self.hands,
+            trump: self.trump,
+            num_tricks: self.num_tricks,
+            plays: self.plays,
+            tricks: self.tricks,
+            cutoff_cache: self.cutoff_cache,
+            pattern_cache: self.pattern_cache,
+            endgame: self.endgame,
+            start_depth: self.start_depth,
+            limit: self.limit,
+            nodes: self.nodes,
+            aborted: self.aborted,
+            stats: self.stats,
+            observer,
         }
-        let mut play_str = String::new();
-        for d in 0..depth {
-            if d > 0 && d % 4 == 0 {
-                play_str.push_str(" | ");
-            } else if d > 0 {
-                play_str.push(' ');
-            }
-            play_str.push_str(&card_name(self.plays[d].card_played));
+    }
+}
+
+impl<'a, O: SearchObserver> Search<'a, O> {
+    /// Apply a work limit, counting `nodes_used` by earlier searches of the same solve
+    pub fn with_limit(mut self, limit: SolveLimit, nodes_used: u64) -> Self {
+        self.limit = limit;
+        self.nodes = nodes_used;
+        self
+    }
+
+    /// Use `table` rather than the installed endgame table
+    #[cfg(test)]
+    pub fn with_endgame_table(mut self, table: Option<Arc<EndgameTable>>) -> Self {
+        self.endgame = table;
+        self
+    }
+
+    /// Nodes searched, including those counted before this search
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// Counters of this search alone
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    /// Check if the search gave up on reaching its limit (its result is then meaningless)
+    pub fn aborted(&self) -> bool {
+        self.aborted
+    }
+
+    /// The node at `depth`, for the observer
+    #[inline]
+    fn node(&self, depth: usize, beta: i8) -> SearchNode {
+        SearchNode {
+            depth,
+            seat: self.plays[depth].seat_to_play,
+            beta,
+            ns_tricks_won: self.plays[depth].ns_tricks_won,
         }
-        play_str
     }
 
     /// Main entry point - search with given beta (null-window)
@@ -441,6 +620,7 @@ impl<'a> Search<'a> {
             self.hands[2].size(),
             self.hands[3].size()
         );
+        self.observer.begin_search(self.hands, self.trump, beta);
         let result = self.search_with_cache(self.start_depth, beta);
         #[cfg(feature = "debug_search")]
         eprintln!(
@@ -458,18 +638,8 @@ impl<'a> Search<'a> {
     /// SearchWithCache - called at every depth, handles trick boundaries
     /// Matches C++ Play::SearchWithCache
     fn search_with_cache(&mut self, depth: usize, beta: i8) -> SearchResult {
-        let trick_idx = depth / 4;
         let card_in_trick = depth & 3;
 
-        // XRAY counter - increment on EVERY recursive call (not just trick boundaries)
-        let limit = XRAY_LIMIT.load(Ordering::Relaxed);
-        if limit > 0 {
-            let count = XRAY_COUNT.fetch_add(1, Ordering::Relaxed) + 1;
-            if count == limit + 1 {
-                eprintln!("XRAY_LIMIT_REACHED: {} iterations", limit);
-            }
-        }
-
         // Mid-trick: get state from previous play
         if card_in_trick != 0 {
             let prev_ns_tricks = self.plays[depth - 1].ns_tricks_won;
@@ -481,11 +651,8 @@ impl<'a> Search<'a> {
                 "search_with_cache depth={} mid-trick: ns_tricks={} seat={}",
                 depth, prev_ns_tricks, self.plays[depth].seat_to_play
             );
-            return self.evaluate_playable_cards(depth, beta);
-        }
-
-        // Trick start: compute ns_tricks_won and seat_to_play from previous trick
-        if depth > 0 {
+        } else if depth > 0 {
+            // Trick start: compute ns_tricks_won and seat_to_play from previous trick
             let prev_ns_tricks = self.plays[depth - 1].ns_tricks_won;
             let prev_winning_play = self.plays[depth - 1].winning_play;
             let prev_winner_seat = self.plays[prev_winning_play].seat_to_play;
@@ -497,48 +664,26 @@ impl<'a> Search<'a> {
                       depth, prev_winning_play, prev_winner_seat, ns_won, self.plays[depth].ns_tricks_won);
         }
 
+        let node = self.node(depth, beta);
+        self.observer.enter_node(&node, self.hands);
+        let result = if card_in_trick != 0 {
+            self.evaluate_playable_cards(depth, beta)
+        } else {
+            self.search_trick_with_cache(depth, beta)
+        };
+        self.observer.exit_node(&node, result.ns_tricks);
+        result
+    }
+
+    /// The trick start part of SearchWithCache: bounds, last trick and pattern cache
+    fn search_trick_with_cache(&mut self, depth: usize, beta: i8) -> SearchResult {
+        let trick_idx = depth / 4;
         let ns_tricks_won = self.plays[depth].ns_tricks_won;
         let seat_to_play = self.plays[depth].seat_to_play;
 
-        // XRAY detailed logging (at trick boundaries only, for readability)
-        if xray_should_log() && card_in_trick == 0 {
-            let count = XRAY_COUNT.load(Ordering::Relaxed);
-            let seat_name = match seat_to_play {
-                WEST => "West",
-                NORTH => "North",
-                EAST => "East",
-                SOUTH => "South",
-                _ => "?",
-            };
-            eprintln!(
-                "XRAY {}: depth={} seat={} beta={} ns_tricks_won={}",
-                count, depth, seat_name, beta, ns_tricks_won
-            );
-            // Log hands at trick start
-            eprintln!(
-                "HANDS: W={:x} N={:x} E={:x} S={:x}",
-                self.hands[WEST].value(),
-                self.hands[NORTH].value(),
-                self.hands[EAST].value(),
-                self.hands[SOUTH].value()
-            );
-            // Log cards played so far
-            if depth > 0 {
-                let mut play_str = String::new();
-                for d in 0..depth {
-                    if d > 0 && d % 4 == 0 {
-                        play_str.push_str(" | ");
-                    } else if d > 0 {
-                        play_str.push(' ');
-                    }
-                    play_str.push_str(&card_name(self.plays[d].card_played));
-                }
-                eprintln!("PLAY: {}", play_str);
-            }
-        }
-
         // Quick bounds check
         if ns_tricks_won as i8 >= beta {
+            self.stats.quick_bounds += 1;
             return SearchResult {
                 ns_tricks: ns_tricks_won,
                 rank_winners: Cards::new(),
@@ -546,6 +691,7 @@ impl<'a> Search<'a> {
         }
         let remaining = self.num_tricks - trick_idx;
         if (ns_tricks_won as usize + remaining) < beta as usize {
+            self.stats.quick_bounds += 1;
             return SearchResult {
                 ns_tricks: ns_tricks_won + remaining as u8,
                 rank_winners: Cards::new(),
@@ -554,6 +700,7 @@ impl<'a> Search<'a> {
 
         // Last trick optimization
         if remaining == 1 {
+            self.stats.last_tricks += 1;
             let result = self.collect_last_trick(depth);
             #[cfg(feature = "debug_search")]
             eprintln!(
@@ -597,54 +744,53 @@ impl<'a> Search<'a> {
                 .update(self.hands, prev_all_cards, all_cards);
         }
 
+        // An endgame table result is exact: it needs neither the pattern
+        // cache nor pruning, and is not worth storing
+        if let Some(result) = self.probe_endgame(depth, beta) {
+            return result;
+        }
+
         // Pattern cache lookup (matching C++ common_bounds_cache)
         let shape_value = self.tricks[trick_idx].shape.value();
         let mut pattern_cutoff = false;
         let rel_beta = beta - ns_tricks_won as i8;
         if !NO_TT.load(Ordering::Relaxed) {
-            if let Some(entry) = self.pattern_cache.lookup(shape_value, seat_to_play) {
-                // Create pattern from current relative hands for lookup
-                let new_pattern = Pattern::new(
-                    self.tricks[trick_idx].relative_hands.hands,
-                    Bounds::new(0, remaining as i8),
-                );
-                // Use relative beta for cutoff check (bounds are stored relative to ns_tricks_won)
-                if let Some((matched_hands, bounds)) = entry.lookup(&new_pattern, rel_beta) {
-                    // Compute rank_winners from matched pattern (matching C++ GetRankWinners)
-                    let matched_pattern = Pattern::new(*matched_hands, bounds);
-                    let rank_winners = matched_pattern.get_rank_winners(all_cards);
-
-                    let adj_lower = bounds.lower + ns_tricks_won as i8;
-                    let adj_upper = bounds.upper + ns_tricks_won as i8;
-                    if adj_lower >= beta {
-                        if xray_should_log() {
-                            eprintln!(
-                                "PATTERN_HIT: depth={} seat={} beta={} ns_tricks_won={} bounds=[{},{}] adj_lower={} LOWER_CUT shape={:x} hands=[{:x},{:x},{:x},{:x}]",
-                                depth, seat_to_play, beta, ns_tricks_won, bounds.lower, bounds.upper, adj_lower,
-                                shape_value,
-                                new_pattern.hands[WEST].value(), new_pattern.hands[NORTH].value(),
-                                new_pattern.hands[EAST].value(), new_pattern.hands[SOUTH].value()
-                            );
-                        }
-                        return SearchResult {
-                            ns_tricks: adj_lower as u8,
-                            rank_winners,
-                        };
-                    }
-                    if adj_upper < beta {
-                        if xray_should_log() {
-                            eprintln!(
-                                "PATTERN_HIT: depth={} seat={} beta={} ns_tricks_won={} bounds=[{},{}] adj_upper={} UPPER_CUT",
-                                depth, seat_to_play, beta, ns_tricks_won, bounds.lower, bounds.upper, adj_upper
-                            );
-                        }
-                        return SearchResult {
-                            ns_tricks: adj_upper as u8,
-                            rank_winners,
-                        };
-                    }
-                    pattern_cutoff = true;
+            // Create pattern from current relative hands for lookup
+            let new_pattern = Pattern::new(
+                self.tricks[trick_idx].relative_hands.hands,
+                Bounds::new(0, remaining as i8),
+            );
+            // Use relative beta for cutoff check (bounds are stored relative to ns_tricks_won)
+            let matched =
+                self.pattern_cache
+                    .lookup(shape_value, seat_to_play, &new_pattern, rel_beta);
+            let bounds = matched.map(|pattern| (pattern.bounds.lower, pattern.bounds.upper));
+            let node = self.node(depth, beta);
+            self.observer
+                .tt_lookup(&node, shape_value, &new_pattern.hands, bounds);
+            if let Some(matched_pattern) = matched {
+                // Compute rank_winners from matched pattern (matching C++ GetRankWinners)
+                let bounds = matched_pattern.bounds;
+                let rank_winners = matched_pattern.get_rank_winners(all_cards);
+
+                let adj_lower = bounds.lower + ns_tricks_won as i8;
+                let adj_upper = bounds.upper + ns_tricks_won as i8;
+                if adj_lower >= beta || adj_upper < beta {
+                    self.stats.pattern_cutoffs += 1;
+                }
+                if adj_lower >= beta {
+                    return SearchResult {
+                        ns_tricks: adj_lower as u8,
+                        rank_winners,
+                    };
+                }
+                if adj_upper < beta {
+                    return SearchResult {
+                        ns_tricks: adj_upper as u8,
+                        rank_winners,
+                    };
                 }
+                pattern_cutoff = true;
             }
         }
 
@@ -652,7 +798,7 @@ impl<'a> Search<'a> {
         let result = self.search_at_trick_start(depth, beta);
 
         // Pattern cache store (matching C++ common_bounds_cache)
-        if !NO_TT.load(Ordering::Relaxed) && !pattern_cutoff {
+        if !NO_TT.load(Ordering::Relaxed) && !pattern_cutoff && !self.aborted {
             let relative_tricks = (result.ns_tricks - ns_tricks_won) as i8;
             let bounds = if (result.ns_tricks as i8) < beta {
                 Bounds::new(0, relative_tricks)
@@ -669,18 +815,16 @@ impl<'a> Search<'a> {
             );
 
             let new_pattern = Pattern::new(pattern_hands, bounds);
-            if xray_should_log() {
-                eprintln!(
-                    "PATTERN_STORE: depth={} seat={} beta={} ns_tricks_won={} result={} bounds=[{},{}] shape={:x} hands=[{:x},{:x},{:x},{:x}] rank_winners={:x}",
-                    depth, seat_to_play, beta, ns_tricks_won, result.ns_tricks, bounds.lower, bounds.upper,
-                    shape_value,
-                    pattern_hands[WEST].value(), pattern_hands[NORTH].value(),
-                    pattern_hands[EAST].value(), pattern_hands[SOUTH].value(),
-                    result.rank_winners.value()
-                );
-            }
-            let entry = self.pattern_cache.get_or_create(shape_value, seat_to_play);
-            entry.pattern.update(new_pattern);
+            let node = self.node(depth, beta);
+            self.observer.tt_store(
+                &node,
+                shape_value,
+                &pattern_hands,
+                (bounds.lower, bounds.upper),
+                result.rank_winners,
+            );
+            self.pattern_cache
+                .store(shape_value, seat_to_play, new_pattern);
 
             // Return with extended_rank_winners instead of raw rank_winners
             return SearchResult {
@@ -703,22 +847,23 @@ impl<'a> Search<'a> {
         if !NO_PRUNING.load(Ordering::Relaxed) {
             // Fast tricks pruning
             let (fast, fast_rank_winners) = self.fast_tricks(depth);
-
-            // Debug logging for fast tricks
-            if xray_should_log() {
-                eprintln!(
-                    "FAST_TRICKS: depth={} seat={} fast={} trump={}",
-                    depth, seat_to_play, fast, self.trump
-                );
-            }
+            let node = self.node(depth, beta);
+            let pruned = if is_ns(seat_to_play) {
+                ns_tricks_won as usize + fast >= beta as usize
+            } else {
+                (ns_tricks_won as usize + remaining - fast) < beta as usize
+            };
+            self.observer.fast_tricks(&node, fast, pruned);
 
             if is_ns(seat_to_play) && ns_tricks_won as usize + fast >= beta as usize {
+                self.stats.fast_tricks += 1;
                 return SearchResult {
                     ns_tricks: (ns_tricks_won as usize + fast) as u8,
                     rank_winners: fast_rank_winners,
                 };
             }
             if !is_ns(seat_to_play) && (ns_tricks_won as usize + remaining - fast) < beta as usize {
+                self.stats.fast_tricks += 1;
                 return SearchResult {
                     ns_tricks: (ns_tricks_won as usize + remaining - fast) as u8,
                     rank_winners: fast_rank_winners,
@@ -744,18 +889,19 @@ impl<'a> Search<'a> {
                 self.slow_tricks_opponent(depth)
             };
 
-            // Debug logging for slow tricks
-            if xray_should_log() {
-                eprintln!(
-                    "SLOW_TRICKS: depth={} seat={} slow={} trump={}",
-                    depth, seat_to_play, slow, self.trump
-                );
-            }
+            let pruned = slow > 0
+                && if is_ns(seat_to_play) {
+                    (ns_tricks_won as usize + remaining - slow) < beta as usize
+                } else {
+                    ns_tricks_won as usize + slow >= beta as usize
+                };
+            self.observer.slow_tricks(&node, slow, pruned);
 
             if slow > 0 {
                 if is_ns(seat_to_play) {
                     // NS to play, check if EW's slow tricks limit NS
                     if (ns_tricks_won as usize + remaining - slow) < beta as usize {
+                        self.stats.slow_tricks += 1;
                         return SearchResult {
                             ns_tricks: (ns_tricks_won as usize + remaining - slow) as u8,
                             rank_winners: slow_rank_winners,
@@ -764,6 +910,7 @@ impl<'a> Search<'a> {
                 } else {
                     // EW to play, check if NS's slow tricks give them enough
                     if ns_tricks_won as usize + slow >= beta as usize {
+                        self.stats.slow_tricks += 1;
                         return SearchResult {
                             ns_tricks: (ns_tricks_won as usize + slow) as u8,
                             rank_winners: slow_rank_winners,
@@ -776,10 +923,43 @@ impl<'a> Search<'a> {
         self.evaluate_playable_cards(depth, beta)
     }
 
+    /// The exact result from the endgame table, if one covers this trick
+    /// start, with the rank winners of the bound the caller learns
+    fn probe_endgame(&mut self, depth: usize, beta: i8) -> Option<SearchResult> {
+        let table = self.endgame.as_ref()?;
+        let trick = &self.tricks[depth / 4];
+        let seat_to_play = self.plays[depth].seat_to_play;
+        let outcome = table.probe(&trick.relative_hands.hands, self.trump, seat_to_play)?;
+        self.stats.endgame_hits += 1;
+        let ns_tricks = self.plays[depth].ns_tricks_won + outcome.tricks;
+        let top_cards = if ns_tricks as i8 >= beta {
+            outcome.lower
+        } else {
+            outcome.upper
+        };
+        let mut rank_winners = Cards::new();
+        for (suit, &count) in top_cards.iter().enumerate() {
+            for card in trick.all_cards.suit(suit).iter().take(count as usize) {
+                rank_winners.add_index(card);
+            }
+        }
+        Some(SearchResult {
+            ns_tricks,
+            rank_winners,
+        })
+    }
+
     /// EvaluatePlayableCards - main card evaluation loop
     /// Matches C++ Play::EvaluatePlayableCards
     fn evaluate_playable_cards(&mut self, depth: usize, beta: i8) -> SearchResult {
         NODE_COUNT.fetch_add(1, Ordering::Relaxed);
+        self.nodes += 1;
+        self.stats.nodes += 1;
+        self.stats.nodes_by_trick[depth / 4] += 1;
+        if self.aborted || self.limit.exceeded(self.nodes) {
+            self.aborted = true;
+            return SearchResult::default();
+        }
 
         let trick_idx = depth / 4;
         let card_in_trick = depth & 3;
@@ -802,17 +982,6 @@ impl<'a> Search<'a> {
             };
         }
 
-        // XRAY logging for playable cards
-        if xray_should_log() {
-            eprintln!(
-                "PLAYABLE: depth={} seat={} count={} cards={:x}",
-                depth,
-                seat_to_play,
-                playable.size(),
-                playable.value()
-            );
-        }
-
         // Note: Even for single card, we go through the loop to ensure cutoff checks happen.
         // This matches C++ behavior where single cards still get cutoff checks.
 
@@ -833,7 +1002,7 @@ impl<'a> Search<'a> {
         // Check cutoff cache first (using C++ style 2-key index)
         let all_cards = self.tricks[trick_idx].all_cards;
         let lead_suit_for_cutoff = lead_suit.unwrap_or(0);
-        let (cutoff_hash, key0, key1) = build_cutoff_index_debug(
+        let cutoff_hash = build_cutoff_index(
             self.hands,
             seat_to_play,
             card_in_trick,
@@ -849,45 +1018,14 @@ impl<'a> Search<'a> {
             None
         };
 
-        // CUTOFF_INDEX logging
-        let iter_count = NODE_COUNT.load(Ordering::Relaxed);
-        if xray_should_log() {
-            eprintln!(
-                "CUTOFF_INDEX: iter={} depth={} key0={:x} key1={:x} seat={}",
-                iter_count, depth, key0, key1, seat_to_play
-            );
-        }
-
-        // MOVE_ORDER logging BEFORE update
-        let iter_count = NODE_COUNT.load(Ordering::Relaxed);
-        if xray_should_log() {
-            let mut playable_str = String::new();
-            for card in playable.iter() {
-                if !playable_str.is_empty() {
-                    playable_str.push(' ');
-                }
-                playable_str.push_str(&card_name(card));
-            }
-            let cutoff_str = match cutoff_card {
-                Some(cc) if playable.have(cc) => card_name(cc),
-                _ => String::new(),
-            };
-            eprintln!(
-                "MOVE_ORDER_BEFORE: iter={} depth={} play=[{}] playable=[{}] cutoff_cards=[{}]",
-                iter_count,
-                depth,
-                self.format_play_sequence(depth),
-                playable_str,
-                cutoff_str
-            );
-        }
-
         let mut remaining_playable = playable;
         let has_cutoff = if let Some(cc) = cutoff_card {
-            if playable.have(cc) {
+            if playable.have_index(cc) {
+                let node = self.node(depth, beta);
+                self.observer.cutoff_card(&node, cc);
                 // C++ behavior: add cutoff card first, keep remaining in remaining_playable
                 ordered_cards.add(cc);
-                remaining_playable.remove(cc);
+                remaining_playable.remove_index(cc);
                 true
             } else {
                 false
@@ -910,28 +1048,6 @@ impl<'a> Search<'a> {
             remaining_playable = Cards::new();
         }
 
-        // MOVE_ORDER logging AFTER update
-        if xray_should_log() {
-            let mut ordered_str = String::new();
-            for i in 0..ordered_cards.len() {
-                if !ordered_str.is_empty() {
-                    ordered_str.push(' ');
-                }
-                ordered_str.push_str(&card_name(ordered_cards.card(i)));
-            }
-            let mut remaining_str = String::new();
-            for card in remaining_playable.iter() {
-                if !remaining_str.is_empty() {
-                    remaining_str.push(' ');
-                }
-                remaining_str.push_str(&card_name(card));
-            }
-            eprintln!(
-                "MOVE_ORDER_AFTER: iter={} depth={} ordered=[{}] remaining=[{}]",
-                iter_count, depth, ordered_str, remaining_str
-            );
-        }
-
         #[cfg(feature = "debug_search")]
         {
             eprintln!(
@@ -946,30 +1062,12 @@ impl<'a> Search<'a> {
                 eprintln!(
                     "  ordered[{}] = {} (have={})",
                     i,
-                    card_name(c),
-                    self.hands[seat_to_play].have(c)
+                    name_of(c),
+                    self.hands[seat_to_play].have_index(c)
                 );
             }
         }
 
-        // XRAY logging for ordered cards
-        if xray_should_log() {
-            let mut cards_str = String::new();
-            for i in 0..ordered_cards.len() {
-                if i > 0 {
-                    cards_str.push(' ');
-                }
-                cards_str.push_str(&card_name(ordered_cards.card(i)));
-            }
-            eprintln!(
-                "ORDERED: depth={} playable={:x} count={} cards=[{}]",
-                depth,
-                playable.value(),
-                ordered_cards.len(),
-                cards_str
-            );
-        }
-
         // Get my_hand for equivalence checks (all_cards already computed above)
         let my_hand = self.hands[seat_to_play];
 
@@ -984,6 +1082,7 @@ impl<'a> Search<'a> {
         // min_relevant_ranks[suit] = minimum rank that matters for this suit (0 = TWO, 12 = ACE)
         let mut min_relevant_ranks = [0usize; NUM_SUITS];
         let no_rank_skip = NO_RANK_SKIP.load(Ordering::Relaxed);
+        let mut searched_cards = 0;
 
         let mut i = 0;
         while i < ordered_cards.len() {
@@ -993,7 +1092,8 @@ impl<'a> Search<'a> {
 
             // Skip if rank is below minimum relevant rank (unless no_rank_skip is set)
             if !no_rank_skip && rank < min_relevant_ranks[suit] {
-                tried_cards.add(card);
+                self.stats.rank_skips += 1;
+                tried_cards.add_index(card);
                 // C++ behavior: after trying first card, order remaining playable cards
                 if !remaining_playable.is_empty() {
                     Self::order_cards_static(
@@ -1013,7 +1113,8 @@ impl<'a> Search<'a> {
 
             // IsEquivalent check - always call, even for first card (matches C++ behavior)
             if self.is_equivalent(card, tried_cards.suit(suit), my_hand, all_cards) {
-                tried_cards.add(card);
+                self.stats.equivalent_skips += 1;
+                tried_cards.add_index(card);
                 // C++ behavior: after trying first card, order remaining playable cards
                 if !remaining_playable.is_empty() {
                     Self::order_cards_static(
@@ -1030,33 +1131,33 @@ impl<'a> Search<'a> {
                 i += 1;
                 continue;
             }
-            tried_cards.add(card);
+            tried_cards.add_index(card);
+            searched_cards += 1;
 
             // Play and search
             let branch_result = self.play_card_and_search(depth, card, beta);
+            if self.aborted {
+                return branch_result;
+            }
             let score = branch_result.ns_tricks;
             let branch_rank_winners = branch_result.rank_winners;
 
-            // XRAY logging for score
-            if xray_should_log() {
-                eprintln!(
-                    "SCORE: depth={} card={} score={} best={} beta={} maximizing={} rank_winners={:x} play=[{}]",
-                    depth, card_name(card), score, best, beta, maximizing, branch_rank_winners.value(), self.format_play_sequence(depth)
-                );
-            }
+            let node = self.node(depth, beta);
+            let new_best = if maximizing {
+                best.max(score)
+            } else {
+                best.min(score)
+            };
+            self.observer
+                .card_result(&node, card, score, new_best, branch_rank_winners);
 
             if maximizing {
                 if score > best {
                     best = score;
                 }
                 if best as i8 >= beta {
-                    // XRAY logging for cutoff
-                    if xray_should_log() {
-                        eprintln!(
-                            "CUTOFF: depth={} seat={} card={} score={} best={} beta={} maximizing=true play=[{}]",
-                            depth, seat_to_play, card_name(card), score, best, beta, self.format_play_sequence(depth)
-                        );
-                    }
+                    self.observer.beta_cutoff(&node, card, best);
+                    self.count_beta_cutoff(searched_cards);
                     // Store cutoff card
                     if cutoff_card != Some(card) && !NO_TT.load(Ordering::Relaxed) {
                         self.cutoff_cache.store(cutoff_hash, seat_to_play, card);
@@ -1071,13 +1172,8 @@ impl<'a> Search<'a> {
                     best = score;
                 }
                 if (best as i8) < beta {
-                    // XRAY logging for cutoff
-                    if xray_should_log() {
-                        eprintln!(
-                            "CUTOFF: depth={} seat={} card={} score={} best={} beta={} maximizing=false play=[{}]",
-                            depth, seat_to_play, card_name(card), score, best, beta, self.format_play_sequence(depth)
-                        );
-                    }
+                    self.observer.beta_cutoff(&node, card, best);
+                    self.count_beta_cutoff(searched_cards);
                     // Store cutoff card (for minimizer, cutoff is when score < beta)
                     if cutoff_card != Some(card) && !NO_TT.load(Ordering::Relaxed) {
                         self.cutoff_cache.store(cutoff_hash, seat_to_play, card);
@@ -1094,7 +1190,6 @@ impl<'a> Search<'a> {
 
             // Update min_relevant_ranks based on branch_rank_winners
             let suit_rank_winners = branch_rank_winners.suit(suit);
-            let old_min = min_relevant_ranks[suit];
             if suit_rank_winners.is_empty() {
                 // No rank winners in this suit - all higher cards are irrelevant
                 min_relevant_ranks[suit] = NUM_RANKS;
@@ -1108,19 +1203,6 @@ impl<'a> Search<'a> {
                 }
             }
 
-            // RANK_UPDATE logging
-            if min_relevant_ranks[suit] != old_min && xray_should_log() {
-                let winner_str = if suit_rank_winners.is_empty() {
-                    "none".to_string()
-                } else {
-                    card_name(suit_rank_winners.bottom())
-                };
-                eprintln!(
-                    "RANK_UPDATE: depth={} card={} suit={} old_min={} new_min={} suit_rank_winners={} play=[{}]",
-                    depth, card_name(card), suit, old_min, min_relevant_ranks[suit], winner_str, self.format_play_sequence(depth)
-                );
-            }
-
             // C++ behavior: after trying a card, order remaining playable cards
             if !remaining_playable.is_empty() {
                 Self::order_cards_static(
@@ -1144,6 +1226,14 @@ impl<'a> Search<'a> {
         }
     }
 
+    /// Count a beta cutoff by the `searched_cards`-th card searched
+    fn count_beta_cutoff(&mut self, searched_cards: usize) {
+        self.stats.beta_cutoffs += 1;
+        if searched_cards == 1 {
+            self.stats.first_card_cutoffs += 1;
+        }
+    }
+
     /// Play a card and continue search
     fn play_card_and_search(&mut self, depth: usize, card: usize, beta: i8) -> SearchResult {
         let trick_idx = depth / 4;
@@ -1152,19 +1242,21 @@ impl<'a> Search<'a> {
 
         // Record this play
         self.plays[depth].card_played = card;
+        let node = self.node(depth, beta);
+        self.observer.play_card(&node, card);
 
         #[cfg(feature = "debug_search")]
         let before_size = self.hands[seat_to_play].size();
 
         // Remove from hand
-        self.hands[seat_to_play].remove(card);
+        self.hands[seat_to_play].remove_index(card);
 
         #[cfg(feature = "debug_search")]
         {
             let after_size = self.hands[seat_to_play].size();
             if after_size != before_size - 1 {
                 eprintln!("ERROR: remove didn't work! depth={} seat={} card={} before={} after={} hand={:x}",
-                          depth, seat_to_play, card_name(card), before_size, after_size, self.hands[seat_to_play].value());
+                          depth, seat_to_play, name_of(card), before_size, after_size, self.hands[seat_to_play].value());
             }
         }
 
@@ -1178,7 +1270,7 @@ impl<'a> Search<'a> {
                 "  depth={} {} leads {} winning_play={}",
                 depth,
                 seat_to_play,
-                card_name(card),
+                name_of(card),
                 depth
             );
         } else {
@@ -1193,8 +1285,8 @@ impl<'a> Search<'a> {
                     "  depth={} {} plays {} WINS over {} winning_play={}",
                     depth,
                     seat_to_play,
-                    card_name(card),
-                    card_name(current_winner_card),
+                    name_of(card),
+                    name_of(current_winner_card),
                     depth
                 );
             } else {
@@ -1204,8 +1296,8 @@ impl<'a> Search<'a> {
                     "  depth={} {} plays {} loses to {} winning_play={}",
                     depth,
                     seat_to_play,
-                    card_name(card),
-                    card_name(current_winner_card),
+                    name_of(card),
+                    name_of(current_winner_card),
                     current_winner_idx
                 );
             }
@@ -1234,7 +1326,7 @@ impl<'a> Search<'a> {
                 }
             }
             if has_same_suit {
-                result.rank_winners.add(winning_card);
+                result.rank_winners.add_index(winning_card);
             }
         }
 
@@ -1242,7 +1334,7 @@ impl<'a> Search<'a> {
         let before_restore = self.hands[seat_to_play].size();
 
         // Restore hand
-        self.hands[seat_to_play].add(card);
+        self.hands[seat_to_play].add_index(card);
 
         #[cfg(feature = "debug_search")]
         {
@@ -1251,7 +1343,7 @@ impl<'a> Search<'a> {
                 eprintln!(
                     "ERROR: add didn't work! seat={} card={} before={} after={}",
                     seat_to_play,
-                    card_name(card),
+                    name_of(card),
                     before_restore,
                     after_restore
                 );
@@ -1263,7 +1355,7 @@ impl<'a> Search<'a> {
             "  depth={} {} played {} result={}",
             depth,
             seat_to_play,
-            card_name(card),
+            name_of(card),
             result.ns_tricks
         );
 
@@ -1310,21 +1402,6 @@ impl<'a> Search<'a> {
             }
         }
 
-        // EQUIV logging
-        if xray_should_log() {
-            let suit = suit_of(card);
-            let all_suit = all_cards.suit(suit);
-            let my_suit = my_hand.suit(suit);
-            eprintln!(
-                "EQUIV_V2: card={} tried=0x{:x} hand=0x{:x} all=0x{:x} -> {}",
-                card_name(card),
-                tried_suit.value(),
-                my_suit.value(),
-                all_suit.value(),
-                if result { "true" } else { "false" }
-            );
-        }
-
         result
     }
 
@@ -1387,7 +1464,7 @@ impl<'a> Search<'a> {
             }
         }
         if has_same_suit {
-            rank_winners.add(winning_card);
+            rank_winners.add_index(winning_card);
         }
 
         SearchResult {
@@ -1497,7 +1574,7 @@ impl<'a> Search<'a> {
     }
 
     /// Count top trump tricks for our side, returning (count, rank_winners)
-    fn top_trump_tricks_our_side(&self, seat: Seat, all_cards: Cards) -> (usize, Cards) {
+    fn top_trump_tricks_our_side(&self, seat: SeatIndex, all_cards: Cards) -> (usize, Cards) {
         let my_trumps = self.hands[seat].suit(self.trump);
         let pd_trumps = self.hands[partner(seat)].suit(self.trump);
         let all_trumps = all_cards.suit(self.trump);
@@ -1517,9 +1594,9 @@ impl<'a> Search<'a> {
 
         // Count consecutive top trumps held by our side
         for card in all_trumps.iter() {
-            if both_trumps.have(card) && sure_tricks < max_trump_tricks {
+            if both_trumps.have_index(card) && sure_tricks < max_trump_tricks {
                 sure_tricks += 1;
-                rank_winners.add(card);
+                rank_winners.add_index(card);
             } else {
                 break;
             }
@@ -1530,7 +1607,7 @@ impl<'a> Search<'a> {
 
     /// Count guaranteed fast tricks from a given seat's perspective.
     /// Returns (count, rank_winners) matching C++ FastTricks().
-    fn fast_tricks_from_seat(&self, seat: Seat, all_cards: Cards) -> (usize, Cards) {
+    fn fast_tricks_from_seat(&self, seat: SeatIndex, all_cards: Cards) -> (usize, Cards) {
         let my_hand = self.hands[seat];
         let pd_hand = self.hands[partner(seat)];
         let lho_hand = self.hands[left_hand_opp(seat)];
@@ -1581,10 +1658,10 @@ impl<'a> Search<'a> {
                 }
                 // Truncate our suit holdings to max_suit_winners
                 while my_suit.size() > max_suit_winners {
-                    my_suit.remove(my_suit.bottom());
+                    my_suit.remove_index(my_suit.bottom());
                 }
                 while pd_suit.size() > max_suit_winners {
-                    pd_suit.remove(pd_suit.bottom());
+                    pd_suit.remove_index(pd_suit.bottom());
                 }
             }
 
@@ -1592,15 +1669,15 @@ impl<'a> Search<'a> {
             let mut my_winners = 0;
             let mut pd_winners = 0;
             for card in all_suit.iter() {
-                if my_suit.have(card) {
+                if my_suit.have_index(card) {
                     my_winners += 1;
                     if my_winners <= my_max_rank_winners {
-                        rank_winners.add(card);
+                        rank_winners.add_index(card);
                     }
-                } else if pd_suit.have(card) {
+                } else if pd_suit.have_index(card) {
                     pd_winners += 1;
                     if pd_winners <= pd_max_rank_winners {
-                        pd_rank_winners.add(card);
+                        pd_rank_winners.add_index(card);
                     }
                 } else {
                     break;
@@ -1634,16 +1711,7 @@ impl<'a> Search<'a> {
         let trick_idx = depth / 4;
         let max_tricks = self.num_tricks - trick_idx;
         let (tricks, rank_winners) = self.fast_tricks_from_seat(seat_to_play, all_cards);
-        let result = tricks.min(max_tricks);
-
-        // Debug logging when XRAY is enabled and under limit
-        if xray_should_log() {
-            eprintln!(
-                "FAST_TRICKS: depth={} seat={} raw={} capped={} trump={}",
-                depth, seat_to_play, tricks, result, self.trump
-            );
-        }
-        (result, rank_winners)
+        (tricks.min(max_tricks), rank_winners)
     }
 
     /// Top trump tricks for opponents (trump contracts only)
@@ -1670,9 +1738,9 @@ impl<'a> Search<'a> {
 
         // Count consecutive top trumps held by opponents
         for card in all_trumps.iter() {
-            if both_trumps.have(card) && sure_tricks < max_trump_tricks {
+            if both_trumps.have_index(card) && sure_tricks < max_trump_tricks {
                 sure_tricks += 1;
-                rank_winners.add(card);
+                rank_winners.add_index(card);
             } else {
                 break;
             }
@@ -1702,12 +1770,12 @@ impl<'a> Search<'a> {
         // Get top 3 trumps (A, K, Q)
         let a = all_trumps.top();
         let mut remaining = all_trumps;
-        remaining.remove(a);
+        remaining.remove_index(a);
         if remaining.is_empty() {
             return (0, Cards::new());
         }
         let k = remaining.top();
-        remaining.remove(k);
+        remaining.remove_index(k);
         let q = if !remaining.is_empty() {
             remaining.top()
         } else {
@@ -1718,15 +1786,15 @@ impl<'a> Search<'a> {
 
         // Build rank_winners for a and k
         let mut ak_winners = Cards::new();
-        ak_winners.add(a);
-        ak_winners.add(k);
+        ak_winners.add_index(a);
+        ak_winners.add_index(k);
 
         // Kx behind A: partner has K (strictly, meaning more cards), LHO has A
         // OR: my hand has K (strictly), RHO has A (and not leading or enough tricks)
-        let pd_has_k_strictly = pd_trumps.have(k) && pd_trumps.size() > 1;
-        let my_has_k_strictly = my_trumps.have(k) && my_trumps.size() > 1;
-        let lho_has_a = lho_trumps.have(a);
-        let rho_has_a = rho_trumps.have(a);
+        let pd_has_k_strictly = pd_trumps.have_index(k) && pd_trumps.size() > 1;
+        let my_has_k_strictly = my_trumps.have_index(k) && my_trumps.size() > 1;
+        let lho_has_a = lho_trumps.have_index(a);
+        let rho_has_a = rho_trumps.have_index(a);
 
         // leading=false in the call from SearchAtTrickStart for slow tricks
         let leading = false;
@@ -1747,12 +1815,12 @@ impl<'a> Search<'a> {
         // Qxx behind AK: need at least 5 trumps
         if q < 64 && all_trumps.size() >= 5 {
             let mut akq_winners = ak_winners;
-            akq_winners.add(q);
+            akq_winners.add_index(q);
 
-            let pd_has_q_with_length = pd_trumps.have(q) && pd_trumps.size() >= 3;
-            let my_has_q_with_length = my_trumps.have(q) && my_trumps.size() >= 3;
-            let lho_has_ak = lho_trumps.have(a) && lho_trumps.have(k);
-            let rho_has_ak = rho_trumps.have(a) && rho_trumps.have(k);
+            let pd_has_q_with_length = pd_trumps.have_index(q) && pd_trumps.size() >= 3;
+            let my_has_q_with_length = my_trumps.have_index(q) && my_trumps.size() >= 3;
+            let lho_has_ak = lho_trumps.have_index(a) && lho_trumps.have_index(k);
+            let rho_has_ak = rho_trumps.have_index(a) && rho_trumps.have_index(k);
 
             if (pd_has_q_with_length && lho_has_ak)
                 || (my_has_q_with_length && rho_has_ak && (!leading || num_tricks >= 4))
@@ -1794,10 +1862,10 @@ impl<'a> Search<'a> {
             }
             let top = all_suit.top();
             // If current side has the top card, no slow trick for opponents
-            if my_side_cards.have(top) {
+            if my_side_cards.have_index(top) {
                 return (0, Cards::new());
             }
-            rank_winners.add(top);
+            rank_winners.add_index(top);
         }
 
         if rank_winners.is_empty() {
@@ -1817,8 +1885,8 @@ impl<'a> Search<'a> {
 /// This allows unit testing the finesse detection logic
 pub fn slow_trump_tricks_opponent(
     hands: &Hands,
-    trump: Suit,
-    seat_to_play: Seat,
+    trump: SuitIndex,
+    seat_to_play: SeatIndex,
     num_tricks: usize,
     leading: bool,
 ) -> usize {
@@ -1837,12 +1905,12 @@ pub fn slow_trump_tricks_opponent(
     // Get top 3 trumps
     let a = all_trumps.top();
     let mut remaining = all_trumps;
-    remaining.remove(a);
+    remaining.remove_index(a);
     if remaining.is_empty() {
         return 0;
     }
     let k = remaining.top();
-    remaining.remove(k);
+    remaining.remove_index(k);
     let q = if !remaining.is_empty() {
         remaining.top()
     } else {
@@ -1851,10 +1919,10 @@ pub fn slow_trump_tricks_opponent(
 
     // Kx behind A: partner has K (strictly, meaning more cards), LHO has A
     // OR: my hand has K (strictly), RHO has A (and not leading or enough tricks)
-    let pd_has_k_strictly = pd_trumps.have(k) && pd_trumps.size() > 1;
-    let my_has_k_strictly = my_trumps.have(k) && my_trumps.size() > 1;
-    let lho_has_a = lho_trumps.have(a);
-    let rho_has_a = rho_trumps.have(a);
+    let pd_has_k_strictly = pd_trumps.have_index(k) && pd_trumps.size() > 1;
+    let my_has_k_strictly = my_trumps.have_index(k) && my_trumps.size() > 1;
+    let lho_has_a = lho_trumps.have_index(a);
+    let rho_has_a = rho_trumps.have_index(a);
 
     if (pd_has_k_strictly && lho_has_a)
         || (my_has_k_strictly && rho_has_a && (!leading || num_tricks >= 3))
@@ -1871,10 +1939,10 @@ pub fn slow_trump_tricks_opponent(
 
     // Qxx behind AK: need at least 5 trumps
     if q < 64 && all_trumps.size() >= 5 {
-        let pd_has_q_with_length = pd_trumps.have(q) && pd_trumps.size() >= 3;
-        let my_has_q_with_length = my_trumps.have(q) && my_trumps.size() >= 3;
-        let lho_has_ak = lho_trumps.have(a) && lho_trumps.have(k);
-        let rho_has_ak = rho_trumps.have(a) && rho_trumps.have(k);
+        let pd_has_q_with_length = pd_trumps.have_index(q) && pd_trumps.size() >= 3;
+        let my_has_q_with_length = my_trumps.have_index(q) && my_trumps.size() >= 3;
+        let lho_has_ak = lho_trumps.have_index(a) && lho_trumps.have_index(k);
+        let rho_has_ak = rho_trumps.have_index(a) && rho_trumps.have_index(k);
 
         if (pd_has_q_with_length && lho_has_ak)
             || (my_has_q_with_length && rho_has_ak && (!leading || num_tricks >= 4))
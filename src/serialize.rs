@@ -0,0 +1,340 @@
+//! Serde support for the public types (the `serde` feature)
+//!
+//! The formats are meant for storage and for passing between services, and
+//! will not change without a major version:
+//!
+//! | Type | Format | Example |
+//! |------|--------|---------|
+//! | `Seat` | seat letter | `"N"` |
+//! | `Suit`, `Rank` | letter | `"H"`, `"T"` |
+//! | `Strain` | contract symbol (`"N"` also read) | `"NT"` |
+//! | `Card` | suit then rank | `"SA"` |
+//! | `Cards` | ranks by suit, S.H.D.C, `-` for a void | `"AKQ.JT9.-.5432"` |
+//! | `Hands` | PBN deal from North | `"N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 ..."` |
+//! | `PlayedCard` | card and seat | `{"card": "HK", "seat": "W"}` |
+//! | `PartialTrick` | played cards in order | `[{"card": "HK", "seat": "W"}]` |
+//! | `DdTable` | declarer, then strain, to tricks | `{"N": {"S": 8, ..., "NT": 9}, ...}` |
+//! | `CardAnalysis` | card and NS tricks | `{"card": "SA", "ns_tricks": 9}` |
+//!
+//! `SearchStats`, `SolveReport` and the cache stats serialize field by field
+//! under their Rust names, with `elapsed` as serde's `Duration`
+//! (`{"secs": 1, "nanos": 500}`). Input is checked: a card held twice, a
+//! table entry missing or above 13 tricks, or a bad name is an error.
+
+use super::analysis::CardAnalysis;
+use super::bridge_solver::{PartialTrick, PlayedCard};
+use super::cards::{rank_of, Cards};
+use super::hands::{parse_hand, Hands};
+use super::table::DdTable;
+use super::typed::{Card, Rank, Seat, Strain, Suit};
+use super::types::*;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+/// Serialize with `Display` and deserialize with a parser returning Option
+macro_rules! string_serde {
+    ($name:ty, $what:literal, $parse:expr) => {
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let text = String::deserialize(deserializer)?;
+                let parse: fn(&str) -> Option<$name> = $parse;
+                parse(&text)
+                    .ok_or_else(|| D::Error::custom(format!("invalid {} '{}'", $what, text)))
+            }
+        }
+    };
+}
+
+/// Seat, suit or rank from its one letter
+fn parse_letter<T>(text: &str, from_char: fn(char) -> Option<T>) -> Option<T> {
+    let mut chars = text.chars();
+    let c = chars.next()?;
+    chars.next().is_none().then_some(c).and_then(from_char)
+}
+
+/// Strain from its symbol, or from its letter
+fn parse_strain(text: &str) -> Option<Strain> {
+    match text {
+        "NT" | "nt" => Some(Strain::NoTrump),
+        _ => parse_letter(text, Strain::from_char),
+    }
+}
+
+string_serde!(Seat, "seat", |text| parse_letter(text, Seat::from_char));
+string_serde!(Suit, "suit", |text| parse_letter(text, Suit::from_char));
+string_serde!(Rank, "rank", |text| parse_letter(text, Rank::from_char));
+string_serde!(Strain, "strain", parse_strain);
+string_serde!(Card, "card", Card::from_name);
+
+/// Ranks of each suit joined with dots, "-" for a void
+fn cards_text(cards: &Cards) -> String {
+    let suits: Vec<String> = (0..NUM_SUITS)
+        .map(|suit| {
+            let suit_cards = cards.suit(suit);
+            if suit_cards.is_empty() {
+                return "-".to_string();
+            }
+            suit_cards
+                .iter()
+                .map(|card| rank_name(rank_of(card)))
+                .collect()
+        })
+        .collect();
+    suits.join(".")
+}
+
+impl Serialize for Cards {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&cards_text(self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Cards {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        parse_hand(&text).ok_or_else(|| D::Error::custom(format!("invalid cards '{}'", text)))
+    }
+}
+
+impl Serialize for Hands {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_pbn())
+    }
+}
+
+impl<'de> Deserialize<'de> for Hands {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let hands = Hands::from_pbn(&text)
+            .ok_or_else(|| D::Error::custom(format!("invalid deal '{}'", text)))?;
+        let held: usize = (0..NUM_SEATS).map(|seat| hands[seat].size()).sum();
+        if held != hands.all_cards().size() {
+            return Err(D::Error::custom(format!("card held twice in '{}'", text)));
+        }
+        Ok(hands)
+    }
+}
+
+/// A card index with its typed name, for the structs holding indices
+#[derive(Serialize, Deserialize)]
+struct PlayedCardRepr {
+    card: Card,
+    seat: Seat,
+}
+
+impl Serialize for PlayedCard {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = PlayedCardRepr {
+            card: Card::from_index(self.card)
+                .ok_or_else(|| <S::Error as serde::ser::Error>::custom("bad card"))?,
+            seat: Seat::from_index(self.seat)
+                .ok_or_else(|| <S::Error as serde::ser::Error>::custom("bad seat"))?,
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PlayedCard {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = PlayedCardRepr::deserialize(deserializer)?;
+        Ok(PlayedCard::new(repr.card, repr.seat))
+    }
+}
+
+impl Serialize for PartialTrick {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.plays.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PartialTrick {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let plays = Vec::<PlayedCard>::deserialize(deserializer)?;
+        if plays.len() > NUM_SEATS {
+            return Err(D::Error::custom("more than four cards in a trick"));
+        }
+        Ok(PartialTrick { plays })
+    }
+}
+
+impl Serialize for DdTable {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let table: BTreeMap<Seat, BTreeMap<Strain, u8>> = Seat::iter()
+            .map(|seat| {
+                let row = Strain::iter()
+                    .map(|strain| (strain, self.get(seat, strain)))
+                    .collect();
+                (seat, row)
+            })
+            .collect();
+        table.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DdTable {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rows = BTreeMap::<Seat, BTreeMap<Strain, u8>>::deserialize(deserializer)?;
+        let mut table = DdTable::default();
+        for seat in Seat::iter() {
+            for strain in Strain::iter() {
+                let tricks = rows.get(&seat).and_then(|row| row.get(&strain));
+                match tricks {
+                    Some(&tricks) if tricks as usize <= TOTAL_TRICKS => {
+                        table.set_index(seat.index(), strain.index(), tricks)
+                    }
+                    Some(tricks) => {
+                        return Err(D::Error::custom(format!(
+                            "{} tricks for {} in {}",
+                            tricks, seat, strain
+                        )))
+                    }
+                    None => {
+                        return Err(D::Error::custom(format!(
+                            "no tricks for {} in {}",
+                            seat, strain
+                        )))
+                    }
+                }
+            }
+        }
+        Ok(table)
+    }
+}
+
+/// A card index with its typed name, as `PlayedCardRepr`
+#[derive(Serialize, Deserialize)]
+struct CardAnalysisRepr {
+    card: Card,
+    ns_tricks: u8,
+}
+
+impl Serialize for CardAnalysis {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = CardAnalysisRepr {
+            card: Card::from_index(self.card)
+                .ok_or_else(|| <S::Error as serde::ser::Error>::custom("bad card"))?,
+            ns_tricks: self.ns_tricks,
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CardAnalysis {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = CardAnalysisRepr::deserialize(deserializer)?;
+        Ok(CardAnalysis {
+            card: repr.card.index(),
+            ns_tricks: repr.ns_tricks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{analyze_plays, new_caches, solve_dd_table, SolveReport, Solver};
+    use serde::de::DeserializeOwned;
+    use std::fmt::Debug;
+
+    const DEAL: &str = "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72";
+
+    /// Check the JSON of `value` and that it reads back the same
+    fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: &T, json: &str) {
+        assert_eq!(serde_json::to_string(value).unwrap(), json);
+        assert_eq!(&serde_json::from_str::<T>(json).unwrap(), value);
+    }
+
+    #[test]
+    fn test_names() {
+        round_trip(&Seat::North, "\"N\"");
+        round_trip(&Suit::Hearts, "\"H\"");
+        round_trip(&Rank::Ten, "\"T\"");
+        round_trip(&Strain::NoTrump, "\"NT\"");
+        round_trip(&Card::new(Suit::Spades, Rank::Ace), "\"SA\"");
+        assert_eq!(
+            serde_json::from_str::<Strain>("\"N\"").unwrap(),
+            Strain::NoTrump
+        );
+        assert!(serde_json::from_str::<Seat>("\"NE\"").is_err());
+        // "1" is accepted for the ten, as in card names elsewhere
+        assert_eq!(
+            serde_json::from_str::<Card>("\"S1\"").unwrap(),
+            Card::new(Suit::Spades, Rank::Ten)
+        );
+        assert!(serde_json::from_str::<Card>("\"SX\"").is_err());
+    }
+
+    #[test]
+    fn test_cards_and_hands() {
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        round_trip(&hands[NORTH], "\"AKQT3.J6.KJ42.95\"");
+        let mut cards = Cards::new();
+        cards.add_card(Card::from_name("DA").unwrap());
+        round_trip(&cards, "\"-.-.A.-\"");
+        round_trip(&Cards::new(), "\"-.-.-.-\"");
+        round_trip(&hands, &format!("\"{}\"", DEAL));
+
+        let twice = "\"N:A... A... ... ...\"";
+        assert!(serde_json::from_str::<Hands>(twice).is_err());
+    }
+
+    #[test]
+    fn test_tricks() {
+        let mut trick = PartialTrick::new();
+        trick.add(Card::from_name("HK").unwrap(), Seat::West);
+        trick.add(Card::from_name("HA").unwrap(), Seat::North);
+        let json = serde_json::to_string(&trick).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"card":"HK","seat":"W"},{"card":"HA","seat":"N"}]"#
+        );
+        let read: PartialTrick = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.plays.len(), 2);
+        assert_eq!(
+            (read.plays[1].card, read.plays[1].seat),
+            (trick.plays[1].card, NORTH)
+        );
+    }
+
+    #[test]
+    fn test_table_and_analysis() {
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        let table = solve_dd_table(&hands);
+        let json = serde_json::to_string(&table).unwrap();
+        assert!(json.starts_with(r#"{"W":{"S":"#));
+        assert_eq!(serde_json::from_str::<DdTable>(&json).unwrap(), table);
+        // A 2 before the first notrump count makes it 20 or more
+        let too_many = json.replacen(r#""NT":"#, r#""NT":2"#, 1);
+        assert!(serde_json::from_str::<DdTable>(&too_many).is_err());
+        let missing = r#"{"N":{"S":8}}"#;
+        assert!(serde_json::from_str::<DdTable>(missing).is_err());
+
+        let (mut cutoff, mut pattern) = new_caches();
+        let trick = PartialTrick::new();
+        let analysis =
+            analyze_plays(hands, NOTRUMP, WEST, &trick, &mut cutoff, &mut pattern).unwrap();
+        let json = serde_json::to_string(&analysis).unwrap();
+        assert!(json.starts_with(r#"[{"card":""#));
+        assert_eq!(
+            serde_json::from_str::<Vec<CardAnalysis>>(&json).unwrap(),
+            analysis
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        let (mut cutoff, mut pattern) = new_caches();
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
+        let (_, report) = solver.solve_with_report(&mut cutoff, &mut pattern);
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(serde_json::from_str::<SolveReport>(&json).unwrap(), report);
+    }
+}
@@ -0,0 +1,277 @@
+//! Play session: a deal played out card by card
+//!
+//! Tracks the remaining hands, the current trick and the tricks won so far,
+//! so that each step can be solved or analysed from the current position.
+
+use super::analysis::{analyze_plays_with_limit, AnalysisError, CardAnalysis};
+use super::bridge_solver::{PartialTrick, SolveLimit, Solver};
+use super::cards::*;
+use super::hands::Hands;
+use super::pattern::PatternCache;
+use super::play::get_playable_cards;
+use super::search::CutoffCache;
+use super::typed::{Card, Seat, Strain};
+use super::types::*;
+
+/// Why a card could not be played
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayError {
+    /// All tricks have been played
+    Finished,
+    /// The seat to play does not hold the card
+    NotInHand,
+    /// The seat to play holds a card of the suit led
+    MustFollowSuit,
+}
+
+impl std::fmt::Display for PlayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayError::Finished => write!(f, "all tricks have been played"),
+            PlayError::NotInHand => write!(f, "card is not in the hand of the seat to play"),
+            PlayError::MustFollowSuit => write!(f, "seat to play must follow suit"),
+        }
+    }
+}
+
+impl std::error::Error for PlayError {}
+
+/// A deal being played out, one card at a time
+#[derive(Clone, Debug)]
+pub struct PlaySession {
+    /// Cards not yet played (excludes the current trick)
+    hands: Hands,
+    trump: usize,
+    /// Leader of the current trick
+    leader: SeatIndex,
+    /// Cards played to the current trick
+    current_trick: PartialTrick,
+    /// Completed tricks, in play order
+    tricks: Vec<PartialTrick>,
+    ns_tricks_won: u8,
+}
+
+impl PlaySession {
+    /// Start a session with `leader` on lead to the first trick
+    ///
+    /// Returns None if the hands are not all the same size.
+    pub fn new(hands: Hands, trump: impl Into<Strain>, leader: Seat) -> Option<Self> {
+        Self::with_indices(hands, trump.into().index(), leader.index())
+    }
+
+    /// Start a session from a strain index and a seat index
+    #[deprecated(note = "use `PlaySession::new` with a `Strain` and a `Seat`")]
+    pub fn from_indices(hands: Hands, trump: usize, leader: usize) -> Option<Self> {
+        Self::with_indices(hands, trump, leader)
+    }
+
+    /// Start a session from the solver's own indices
+    pub(crate) fn with_indices(hands: Hands, trump: usize, leader: SeatIndex) -> Option<Self> {
+        let num_tricks = hands.num_tricks();
+        if (0..NUM_SEATS).any(|seat| hands[seat].size() != num_tricks) {
+            return None;
+        }
+        Some(PlaySession {
+            hands,
+            trump,
+            leader,
+            current_trick: PartialTrick::new(),
+            tricks: Vec::new(),
+            ns_tricks_won: 0,
+        })
+    }
+
+    /// Cards not yet played (excludes the current trick)
+    pub fn hands(&self) -> &Hands {
+        &self.hands
+    }
+
+    /// Trump suit, or NOTRUMP
+    pub fn trump(&self) -> usize {
+        self.trump
+    }
+
+    /// Cards played to the current trick
+    pub fn current_trick(&self) -> &PartialTrick {
+        &self.current_trick
+    }
+
+    /// Completed tricks, in play order
+    pub fn tricks(&self) -> &[PartialTrick] {
+        &self.tricks
+    }
+
+    /// Tricks won by NS so far
+    pub fn ns_tricks_won(&self) -> u8 {
+        self.ns_tricks_won
+    }
+
+    /// Tricks won by EW so far
+    pub fn ew_tricks_won(&self) -> u8 {
+        self.tricks.len() as u8 - self.ns_tricks_won
+    }
+
+    /// Seat to play the next card
+    pub fn seat_to_play(&self) -> SeatIndex {
+        self.current_trick.next_to_play().unwrap_or(self.leader)
+    }
+
+    /// Check if all tricks have been played
+    pub fn is_finished(&self) -> bool {
+        self.current_trick.is_empty() && self.hands.all_cards().is_empty()
+    }
+
+    /// Legal plays for the seat to play
+    pub fn legal_cards(&self) -> Cards {
+        get_playable_cards(
+            &self.hands,
+            self.seat_to_play(),
+            self.current_trick.lead_suit(),
+        )
+    }
+
+    /// Play a card for the seat to play, completing the trick after the fourth card
+    pub fn play(&mut self, card: Card) -> Result<(), PlayError> {
+        self.play_index(card.index())
+    }
+
+    /// Play the card with this index (0-51) for the seat to play
+    pub(crate) fn play_index(&mut self, card: usize) -> Result<(), PlayError> {
+        if self.is_finished() {
+            return Err(PlayError::Finished);
+        }
+        let seat = self.seat_to_play();
+        if !self.hands[seat].have_index(card) {
+            return Err(PlayError::NotInHand);
+        }
+        if !self.legal_cards().have_index(card) {
+            return Err(PlayError::MustFollowSuit);
+        }
+
+        self.hands[seat].remove_index(card);
+        self.current_trick.add_index(card, seat);
+        if self.current_trick.len() == NUM_SEATS {
+            let winner = self.current_trick.winner_index(self.trump).unwrap().seat;
+            if is_ns(winner) {
+                self.ns_tricks_won += 1;
+            }
+            self.leader = winner;
+            self.tricks.push(std::mem::take(&mut self.current_trick));
+        }
+        Ok(())
+    }
+
+    /// Total NS tricks for the deal with best play from the current position
+    pub fn solve(&self, cutoff_cache: &mut CutoffCache, pattern_cache: &mut PatternCache) -> u8 {
+        let remaining = if self.is_finished() {
+            0
+        } else if self.current_trick.is_empty() {
+            Solver::with_indices(self.hands, self.trump, self.leader)
+                .solve_with_caches(cutoff_cache, pattern_cache)
+        } else {
+            Solver::mid_trick_with_indices(self.hands, self.trump, &self.current_trick)
+                .unwrap()
+                .solve_mid_trick(cutoff_cache, pattern_cache, &self.current_trick)
+        };
+        self.ns_tricks_won + remaining
+    }
+
+    /// Analyse every legal play, giving total NS tricks for the deal after each card
+    pub fn analyze(
+        &self,
+        cutoff_cache: &mut CutoffCache,
+        pattern_cache: &mut PatternCache,
+    ) -> Vec<CardAnalysis> {
+        self.analyze_with_limit(cutoff_cache, pattern_cache, &SolveLimit::unlimited())
+            .unwrap()
+    }
+
+    /// Like [`PlaySession::analyze`], giving up when `limit` is reached by any one solve
+    pub fn analyze_with_limit(
+        &self,
+        cutoff_cache: &mut CutoffCache,
+        pattern_cache: &mut PatternCache,
+        limit: &SolveLimit,
+    ) -> Option<Vec<CardAnalysis>> {
+        if self.is_finished() {
+            return Some(Vec::new());
+        }
+        let mut results = match analyze_plays_with_limit(
+            self.hands,
+            self.trump,
+            self.leader,
+            &self.current_trick,
+            cutoff_cache,
+            pattern_cache,
+            limit,
+        ) {
+            Ok(results) => results,
+            Err(AnalysisError::LimitReached) => return None,
+            // play() completes the trick on its fourth card
+            Err(AnalysisError::InvalidPosition) => unreachable!(),
+        };
+        for result in &mut results {
+            result.ns_tricks += self.ns_tricks_won;
+        }
+        Some(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_play_out_deal() {
+        // N: SA,HA  E: SK,HK  S: S2,H2  W: S3,H3
+        let hands = Hands::from_pbn("N:A.A.. K.K.. 2.2.. 3.3..").unwrap();
+        let mut session = PlaySession::with_indices(hands, NOTRUMP, WEST).unwrap();
+        let mut cutoff = CutoffCache::new(16);
+        let mut pattern = PatternCache::new(16);
+        assert_eq!(session.solve(&mut cutoff, &mut pattern), 2);
+
+        assert_eq!(session.play_index(card_of(HEART, THREE)), Ok(()));
+        assert_eq!(session.seat_to_play(), NORTH);
+        // North must follow hearts
+        assert_eq!(
+            session.play_index(card_of(SPADE, ACE)),
+            Err(PlayError::MustFollowSuit)
+        );
+        assert_eq!(
+            session.play_index(card_of(SPADE, KING)),
+            Err(PlayError::NotInHand)
+        );
+        session.play_index(card_of(HEART, ACE)).unwrap();
+        session.play_index(card_of(HEART, KING)).unwrap();
+        session.play_index(card_of(HEART, TWO)).unwrap();
+
+        assert_eq!(session.ns_tricks_won(), 1);
+        assert_eq!(session.seat_to_play(), NORTH);
+        assert_eq!(session.tricks().len(), 1);
+
+        let analysis = session.analyze(&mut cutoff, &mut pattern);
+        assert_eq!(analysis.len(), 1);
+        assert_eq!(analysis[0].ns_tricks, 2);
+
+        for card in [
+            card_of(SPADE, ACE),
+            card_of(SPADE, KING),
+            card_of(SPADE, TWO),
+            card_of(SPADE, THREE),
+        ] {
+            session.play_index(card).unwrap();
+        }
+        assert!(session.is_finished());
+        assert_eq!(session.ns_tricks_won(), 2);
+        assert_eq!(
+            session.play_index(card_of(CLUB, ACE)),
+            Err(PlayError::Finished)
+        );
+    }
+
+    #[test]
+    fn test_new_rejects_uneven_hands() {
+        let hands = Hands::from_pbn("N:A.A.. K.K.. 2.2.. 3...").unwrap();
+        assert!(PlaySession::with_indices(hands, NOTRUMP, WEST).is_none());
+    }
+}
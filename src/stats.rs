@@ -0,0 +1,362 @@
+//! Search and cache instrumentation
+//!
+//! Counters are kept by the search and by each cache as it runs; a
+//! [`SolveReport`] gathers them for one solve (see `Solver::solve_with_report`).
+//! The report prints as a table, or converts to JSON with the `json` feature;
+//! with the `serde` feature it also serializes field by field.
+
+use super::types::*;
+use std::fmt;
+use std::time::Duration;
+
+/// Search counters for one solve
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchStats {
+    /// Nodes searched (positions whose cards were tried)
+    pub nodes: u64,
+    /// Nodes searched per trick, the first trick solved at index 0
+    pub nodes_by_trick: [u64; TOTAL_TRICKS],
+    /// MTD(f) null-window searches
+    pub iterations: u64,
+    /// Positions cut off at trick start because the tricks already won (or
+    /// lost) decide the bound
+    pub quick_bounds: u64,
+    /// Last tricks resolved without searching
+    pub last_tricks: u64,
+    /// Endings resolved by the endgame table
+    pub endgame_hits: u64,
+    /// Positions cut off by a pattern cache hit
+    pub pattern_cutoffs: u64,
+    /// Positions cut off by fast tricks of the side to play
+    pub fast_tricks: u64,
+    /// Positions cut off by slow tricks of the other side
+    pub slow_tricks: u64,
+    /// Cards skipped as below the ranks that matter
+    pub rank_skips: u64,
+    /// Cards skipped as equivalent to one already tried
+    pub equivalent_skips: u64,
+    /// Nodes cut off by a card reaching beta
+    pub beta_cutoffs: u64,
+    /// Beta cutoffs by the first card tried
+    pub first_card_cutoffs: u64,
+}
+
+impl SearchStats {
+    /// Add the counts of `other`
+    pub fn add(&mut self, other: &SearchStats) {
+        self.nodes += other.nodes;
+        for (total, nodes) in self.nodes_by_trick.iter_mut().zip(other.nodes_by_trick) {
+            *total += nodes;
+        }
+        self.iterations += other.iterations;
+        self.quick_bounds += other.quick_bounds;
+        self.last_tricks += other.last_tricks;
+        self.endgame_hits += other.endgame_hits;
+        self.pattern_cutoffs += other.pattern_cutoffs;
+        self.fast_tricks += other.fast_tricks;
+        self.slow_tricks += other.slow_tricks;
+        self.rank_skips += other.rank_skips;
+        self.equivalent_skips += other.equivalent_skips;
+        self.beta_cutoffs += other.beta_cutoffs;
+        self.first_card_cutoffs += other.first_card_cutoffs;
+    }
+}
+
+/// Cutoff cache counters, since the cache was created or its stats reset
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CutoffCacheStats {
+    pub lookups: u64,
+    /// Lookups that found a cutoff card for the seat
+    pub hits: u64,
+    /// Slots examined by all lookups
+    pub probes: u64,
+    /// Longest probe sequence of a lookup
+    pub max_probe: u64,
+    pub stores: u64,
+    /// Times the table doubled in size
+    pub resizes: u64,
+    /// Times the table was emptied on reaching its memory limit
+    pub clears: u64,
+}
+
+impl CutoffCacheStats {
+    pub fn misses(&self) -> u64 {
+        self.lookups - self.hits
+    }
+}
+
+/// Pattern cache counters, since the cache was created or its stats reset
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PatternCacheStats {
+    pub lookups: u64,
+    /// Lookups that found an entry for the shape
+    pub shape_hits: u64,
+    /// Lookups that found a pattern causing a cutoff
+    pub cutoffs: u64,
+    pub stores: u64,
+    /// Entries replaced by another shape of their bucket
+    pub collisions: u64,
+    /// Entries evicted to stay within the memory limit
+    pub memory_evictions: u64,
+}
+
+/// Sizes of the pattern trees held by a pattern cache
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PatternTreeStats {
+    /// Entries holding a tree
+    pub trees: u64,
+    /// Patterns in all trees
+    pub patterns: u64,
+    /// Patterns in the largest tree
+    pub largest: u64,
+    /// Deepest pattern, counting patterns under the root as depth 1
+    pub max_depth: u64,
+    /// Sum of the depths of all patterns, for the mean depth
+    pub total_depth: u64,
+}
+
+impl PatternTreeStats {
+    pub fn mean_depth(&self) -> f64 {
+        ratio(self.total_depth, self.patterns)
+    }
+}
+
+/// Everything measured during one solve
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolveReport {
+    /// Wall time of the solve (None where there is no clock)
+    pub elapsed: Option<Duration>,
+    pub search: SearchStats,
+    pub cutoff_cache: CutoffCacheStats,
+    pub pattern_cache: PatternCacheStats,
+    /// Pattern trees at the end of the solve
+    pub pattern_trees: PatternTreeStats,
+    /// Memory of the caches at the end of the solve, in bytes
+    pub cutoff_cache_bytes: usize,
+    pub pattern_cache_bytes: usize,
+}
+
+impl SolveReport {
+    /// The report as a JSON object with the same fields, times in seconds
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> serde_json::Value {
+        let search = &self.search;
+        let cutoff = &self.cutoff_cache;
+        let pattern = &self.pattern_cache;
+        let trees = &self.pattern_trees;
+        serde_json::json!({
+            "elapsed": self.elapsed.map(|elapsed| elapsed.as_secs_f64()),
+            "search": {
+                "nodes": search.nodes,
+                "nodes_by_trick": search.nodes_by_trick,
+                "iterations": search.iterations,
+                "quick_bounds": search.quick_bounds,
+                "last_tricks": search.last_tricks,
+                "endgame_hits": search.endgame_hits,
+                "pattern_cutoffs": search.pattern_cutoffs,
+                "fast_tricks": search.fast_tricks,
+                "slow_tricks": search.slow_tricks,
+                "rank_skips": search.rank_skips,
+                "equivalent_skips": search.equivalent_skips,
+                "beta_cutoffs": search.beta_cutoffs,
+                "first_card_cutoffs": search.first_card_cutoffs,
+            },
+            "cutoff_cache": {
+                "lookups": cutoff.lookups,
+                "hits": cutoff.hits,
+                "misses": cutoff.misses(),
+                "probes": cutoff.probes,
+                "max_probe": cutoff.max_probe,
+                "stores": cutoff.stores,
+                "resizes": cutoff.resizes,
+                "clears": cutoff.clears,
+                "bytes": self.cutoff_cache_bytes,
+            },
+            "pattern_cache": {
+                "lookups": pattern.lookups,
+                "shape_hits": pattern.shape_hits,
+                "cutoffs": pattern.cutoffs,
+                "stores": pattern.stores,
+                "collisions": pattern.collisions,
+                "memory_evictions": pattern.memory_evictions,
+                "bytes": self.pattern_cache_bytes,
+                "trees": {
+                    "trees": trees.trees,
+                    "patterns": trees.patterns,
+                    "largest": trees.largest,
+                    "max_depth": trees.max_depth,
+                    "mean_depth": trees.mean_depth(),
+                },
+            },
+        })
+    }
+}
+
+impl fmt::Display for SolveReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let search = &self.search;
+        let cutoff = &self.cutoff_cache;
+        let pattern = &self.pattern_cache;
+        let trees = &self.pattern_trees;
+        let percent = |part, whole| format!("{:.1}%", 100.0 * ratio(part, whole));
+
+        writeln!(f, "Search")?;
+        if let Some(elapsed) = self.elapsed {
+            let ns_per_node = elapsed.as_nanos() as f64 / search.nodes.max(1) as f64;
+            row(f, "time", format!("{:.3} s", elapsed.as_secs_f64()), "")?;
+            row(
+                f,
+                "nodes",
+                search.nodes,
+                format!("{:.1} ns/node", ns_per_node),
+            )?;
+        } else {
+            row(f, "nodes", search.nodes, "")?;
+        }
+        row(f, "mtd(f) iterations", search.iterations, "")?;
+        let last_trick = search.nodes_by_trick.iter().rposition(|&nodes| nodes > 0);
+        for (trick, &nodes) in search.nodes_by_trick[..last_trick.map_or(0, |t| t + 1)]
+            .iter()
+            .enumerate()
+        {
+            let label = format!("  trick {}", trick + 1);
+            row(f, &label, nodes, percent(nodes, search.nodes))?;
+        }
+
+        writeln!(f, "Pruning")?;
+        row(f, "quick bounds", search.quick_bounds, "")?;
+        row(f, "last tricks", search.last_tricks, "")?;
+        row(f, "endgame table", search.endgame_hits, "")?;
+        row(f, "pattern cutoffs", search.pattern_cutoffs, "")?;
+        row(f, "fast tricks", search.fast_tricks, "")?;
+        row(f, "slow tricks", search.slow_tricks, "")?;
+        row(f, "rank skips", search.rank_skips, "")?;
+        row(f, "equivalent skips", search.equivalent_skips, "")?;
+        let first = percent(search.first_card_cutoffs, search.beta_cutoffs);
+        row(
+            f,
+            "beta cutoffs",
+            search.beta_cutoffs,
+            format!("{} on first card", first),
+        )?;
+
+        writeln!(f, "Cutoff cache")?;
+        row(f, "lookups", cutoff.lookups, "")?;
+        row(f, "hits", cutoff.hits, percent(cutoff.hits, cutoff.lookups))?;
+        row(f, "misses", cutoff.misses(), "")?;
+        let mean_probe = format!("{:.2} mean", ratio(cutoff.probes, cutoff.lookups));
+        row(f, "max probe", cutoff.max_probe, mean_probe)?;
+        row(f, "stores", cutoff.stores, "")?;
+        row(f, "resizes", cutoff.resizes, "")?;
+        row(f, "clears", cutoff.clears, "")?;
+        row(f, "memory", kilobytes(self.cutoff_cache_bytes), "")?;
+
+        writeln!(f, "Pattern cache")?;
+        row(f, "lookups", pattern.lookups, "")?;
+        let hits = percent(pattern.shape_hits, pattern.lookups);
+        row(f, "shape hits", pattern.shape_hits, hits)?;
+        row(
+            f,
+            "cutoffs",
+            pattern.cutoffs,
+            percent(pattern.cutoffs, pattern.lookups),
+        )?;
+        row(f, "stores", pattern.stores, "")?;
+        row(f, "collisions", pattern.collisions, "")?;
+        row(f, "memory evictions", pattern.memory_evictions, "")?;
+        row(f, "trees", trees.trees, "")?;
+        let mean = format!("{:.1} per tree", ratio(trees.patterns, trees.trees));
+        row(f, "patterns", trees.patterns, mean)?;
+        row(f, "largest tree", trees.largest, "")?;
+        let mean = format!("{:.2} mean", trees.mean_depth());
+        row(f, "max depth", trees.max_depth, mean)?;
+        row(f, "memory", kilobytes(self.pattern_cache_bytes), "")
+    }
+}
+
+/// One line of the table: label, value, optional note
+fn row(
+    f: &mut fmt::Formatter,
+    label: &str,
+    value: impl fmt::Display,
+    note: impl fmt::Display,
+) -> fmt::Result {
+    let line = format!("  {:<20}{:>12}  {}", label, value.to_string(), note);
+    writeln!(f, "{}", line.trim_end())
+}
+
+fn kilobytes(bytes: usize) -> String {
+    format!("{} KB", bytes.div_ceil(1024))
+}
+
+fn ratio(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{NOTRUMP, WEST};
+    use crate::{get_node_count, new_caches, Hands, Solver};
+
+    const DEAL: &str = "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72";
+
+    #[test]
+    fn test_solve_report() {
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
+        let (mut cutoff, mut pattern) = new_caches();
+        let (tricks, report) = solver.solve_with_report(&mut cutoff, &mut pattern);
+        assert_eq!(tricks, solver.solve());
+
+        let search = &report.search;
+        assert!(search.iterations > 0);
+        assert_eq!(search.nodes, search.nodes_by_trick.iter().sum::<u64>());
+        assert!(search.nodes_by_trick[0] > 0);
+        assert!(search.pattern_cutoffs > 0);
+        assert!(search.first_card_cutoffs <= search.beta_cutoffs);
+
+        assert!(report.cutoff_cache.hits <= report.cutoff_cache.lookups);
+        assert!(report.cutoff_cache.probes >= report.cutoff_cache.lookups);
+        let patterns = &report.pattern_cache;
+        assert!(patterns.cutoffs <= patterns.shape_hits && patterns.shape_hits <= patterns.lookups);
+        assert!(patterns.stores > 0);
+        let trees = &report.pattern_trees;
+        assert!(trees.trees > 0 && trees.patterns >= trees.largest);
+        assert!(trees.mean_depth() >= 1.0 && trees.mean_depth() <= trees.max_depth as f64);
+        assert_eq!(report.pattern_cache_bytes, pattern.memory_used());
+
+        // A second solve reports only its own work
+        let (_, again) = solver.solve_with_report(&mut cutoff, &mut pattern);
+        assert_eq!(again.search.nodes, get_node_count());
+        assert!(again.search.nodes < search.nodes);
+        assert!(again.pattern_cache.lookups < patterns.lookups);
+
+        let table = report.to_string();
+        assert!(table.contains("Cutoff cache") && table.contains("trick 1"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_report_json() {
+        let hands = Hands::from_pbn(DEAL).unwrap();
+        let (mut cutoff, mut pattern) = new_caches();
+        let (_, report) =
+            Solver::with_indices(hands, NOTRUMP, WEST).solve_with_report(&mut cutoff, &mut pattern);
+        let json = report.to_json();
+        assert_eq!(json["search"]["nodes"], report.search.nodes);
+        assert_eq!(json["cutoff_cache"]["misses"], report.cutoff_cache.misses());
+        assert_eq!(
+            json["pattern_cache"]["trees"]["patterns"],
+            report.pattern_trees.patterns
+        );
+    }
+}
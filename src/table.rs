@@ -0,0 +1,239 @@
+//! Double-dummy table: tricks for every declarer and strain
+//!
+//! Caches are shared across the four declarers of a strain, matching how the
+//! C++ solver (and the `bridge-solver` binary) solve a full table.
+
+use super::bridge_solver::{new_caches, SolveLimit, Solver};
+use super::canonical::ResultCache;
+use super::hands::Hands;
+use super::pattern::PatternCache;
+use super::pbn::{decode_ddt, encode_ddt};
+use super::scoring::{Contract, Doubled, Vulnerability};
+use super::search::CutoffCache;
+use super::typed::{Seat, Strain};
+use super::types::*;
+
+/// Declarer tricks for each declarer seat and strain
+///
+/// Indexed by solver seat (WEST..SOUTH) and strain (SPADE..CLUB, NOTRUMP).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DdTable {
+    tricks: [[u8; NUM_STRAINS]; NUM_SEATS],
+}
+
+impl DdTable {
+    /// Create a table from raw tricks, indexed `[declarer][strain]`
+    pub fn from_tricks(tricks: [[u8; NUM_STRAINS]; NUM_SEATS]) -> Self {
+        DdTable { tricks }
+    }
+
+    /// Tricks taken by `declarer` playing in `strain`
+    #[inline]
+    pub fn get(&self, declarer: Seat, strain: impl Into<Strain>) -> u8 {
+        self.get_index(declarer.index(), strain.into().index())
+    }
+
+    /// Set the tricks taken by `declarer` playing in `strain`
+    #[inline]
+    pub fn set(&mut self, declarer: Seat, strain: impl Into<Strain>, tricks: u8) {
+        self.set_index(declarer.index(), strain.into().index(), tricks);
+    }
+
+    /// Tricks by seat and strain index
+    #[inline]
+    pub(crate) fn get_index(&self, declarer: SeatIndex, strain: usize) -> u8 {
+        self.tricks[declarer][strain]
+    }
+
+    /// Set the tricks by seat and strain index
+    #[inline]
+    pub(crate) fn set_index(&mut self, declarer: SeatIndex, strain: usize, tricks: u8) {
+        self.tricks[declarer][strain] = tricks;
+    }
+
+    /// Raw tricks, indexed `[declarer][strain]`
+    pub fn tricks(&self) -> &[[u8; NUM_STRAINS]; NUM_SEATS] {
+        &self.tricks
+    }
+
+    /// Encode as a PBN DoubleDummyTricks tag value, e.g. "32691326914a74a4a74a"
+    pub fn to_dd_tricks(&self) -> String {
+        encode_ddt(self)
+    }
+
+    /// Decode a PBN DoubleDummyTricks tag value, None if malformed
+    pub fn from_dd_tricks(s: &str) -> Option<Self> {
+        decode_ddt(s)
+    }
+
+    /// Score for declarer's side when `contract` takes its double-dummy tricks
+    pub fn score(&self, contract: &Contract, vulnerability: Vulnerability) -> i32 {
+        contract.score(
+            vulnerability,
+            self.get_index(contract.declarer, contract.strain),
+        )
+    }
+
+    /// Score of the best undoubled contract for `declarer` in `strain`
+    ///
+    /// That is the highest contract the double-dummy tricks make, or a one-level
+    /// contract going down when none does.
+    pub fn best_score(
+        &self,
+        declarer: SeatIndex,
+        strain: usize,
+        vulnerability: Vulnerability,
+    ) -> i32 {
+        (1..=7)
+            .map(|level| Contract::new(level, strain, Doubled::Undoubled, declarer))
+            .map(|contract| self.score(&contract, vulnerability))
+            .max()
+            .unwrap()
+    }
+
+    /// Solve all four declarers in one strain, sharing the given caches
+    pub fn solve_strain(
+        &mut self,
+        hands: &Hands,
+        strain: usize,
+        cutoff_cache: &mut CutoffCache,
+        pattern_cache: &mut PatternCache,
+    ) {
+        self.solve_strain_with_limit(
+            hands,
+            strain,
+            cutoff_cache,
+            pattern_cache,
+            &SolveLimit::unlimited(),
+        )
+        .unwrap()
+    }
+
+    /// Like [`DdTable::solve_strain`], giving up when `limit` is reached by any one solve
+    ///
+    /// Returns None if a solve was abandoned, leaving the strain partly filled in.
+    pub fn solve_strain_with_limit(
+        &mut self,
+        hands: &Hands,
+        strain: usize,
+        cutoff_cache: &mut CutoffCache,
+        pattern_cache: &mut PatternCache,
+        limit: &SolveLimit,
+    ) -> Option<()> {
+        let num_tricks = hands.num_tricks() as u8;
+        for declarer in 0..NUM_SEATS {
+            // The opening lead is made by declarer's left-hand opponent
+            let solver = Solver::with_indices(*hands, strain, left_hand_opp(declarer));
+            let ns_tricks = solver.solve_with_limit(cutoff_cache, pattern_cache, limit)?;
+            let tricks = if is_ns(declarer) {
+                ns_tricks
+            } else {
+                num_tricks - ns_tricks
+            };
+            self.set_index(declarer, strain, tricks);
+        }
+        Some(())
+    }
+}
+
+/// Solve the full double-dummy table (20 solves) with fresh caches per strain
+pub fn solve_dd_table(hands: &Hands) -> DdTable {
+    let mut table = DdTable::default();
+    for strain in 0..NUM_STRAINS {
+        let (mut cutoff_cache, mut pattern_cache) = new_caches();
+        table.solve_strain(hands, strain, &mut cutoff_cache, &mut pattern_cache);
+    }
+    table
+}
+
+/// Like [`solve_dd_table`], answering from `cache` every position equivalent
+/// to one already solved and recording the others
+pub fn solve_dd_table_cached(hands: &Hands, cache: &mut ResultCache) -> DdTable {
+    let num_tricks = hands.num_tricks() as u8;
+    let mut table = DdTable::default();
+    for strain in 0..NUM_STRAINS {
+        // Only allocate caches for a strain that needs solving
+        let mut caches = None;
+        for declarer in 0..NUM_SEATS {
+            let leader = left_hand_opp(declarer);
+            let ns_tricks = match cache.get(hands, strain, leader) {
+                Some(ns_tricks) => ns_tricks,
+                None => {
+                    let (cutoff_cache, pattern_cache) = caches.get_or_insert_with(new_caches);
+                    let solver = Solver::with_indices(*hands, strain, leader);
+                    let ns_tricks = solver.solve_with_caches(cutoff_cache, pattern_cache);
+                    cache.insert(hands, strain, leader, ns_tricks);
+                    ns_tricks
+                }
+            };
+            let tricks = if is_ns(declarer) {
+                ns_tricks
+            } else {
+                num_tricks - ns_tricks
+            };
+            table.set_index(declarer, strain, tricks);
+        }
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_table() {
+        // NS hold the top two cards of both suits and take every trick in every strain
+        // N: SA,HA  E: SQ,HQ  S: SK,HK  W: SJ,HJ
+        let hands = Hands::from_pbn("N:A.A.. Q.Q.. K.K.. J.J..").unwrap();
+        let table = solve_dd_table(&hands);
+        for strain in 0..NUM_STRAINS {
+            assert_eq!(table.get_index(NORTH, strain), 2);
+            assert_eq!(table.get_index(SOUTH, strain), 2);
+            assert_eq!(table.get_index(EAST, strain), 0);
+            assert_eq!(table.get_index(WEST, strain), 0);
+        }
+    }
+
+    #[test]
+    fn test_scores() {
+        let mut table = DdTable::default();
+        table.set_index(NORTH, SPADE, 10);
+        table.set_index(EAST, HEART, 6);
+        table.set_index(SOUTH, NOTRUMP, 12);
+
+        let contract = Contract::new(4, SPADE, Doubled::Doubled, NORTH);
+        assert_eq!(table.score(&contract, Vulnerability::None), 590);
+        assert_eq!(table.best_score(NORTH, SPADE, Vulnerability::NS), 620);
+        assert_eq!(table.best_score(EAST, HEART, Vulnerability::None), -50);
+        assert_eq!(table.best_score(SOUTH, NOTRUMP, Vulnerability::None), 990);
+    }
+
+    #[test]
+    fn test_cached_table() {
+        let hands = Hands::from_pbn(
+            "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72",
+        )
+        .unwrap();
+        let mut cache = ResultCache::new();
+        let table = solve_dd_table_cached(&hands, &mut cache);
+        assert_eq!(table, solve_dd_table(&hands));
+
+        // The same deal turned a seat: every solve is answered from the cache
+        let mut rotated = Hands::new();
+        for seat in 0..NUM_SEATS {
+            rotated[left_hand_opp(seat)] = hands[seat];
+        }
+        let misses = cache.misses();
+        let rotated_table = solve_dd_table_cached(&rotated, &mut cache);
+        assert_eq!(cache.misses(), misses);
+        for seat in 0..NUM_SEATS {
+            for strain in 0..NUM_STRAINS {
+                assert_eq!(
+                    rotated_table.get_index(left_hand_opp(seat), strain),
+                    table.get_index(seat, strain)
+                );
+            }
+        }
+    }
+}
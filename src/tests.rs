@@ -1,5 +1,6 @@
 //! Test suite matching C++ solver test cases
 
+use super::types::{SeatIndex, CLUB, DIAMOND, EAST, HEART, NORTH, NOTRUMP, SOUTH, SPADE, WEST};
 use super::*;
 
 /// Test case structure matching test_cases.txt format
@@ -7,7 +8,7 @@ struct TestCase {
     name: &'static str,
     pbn: &'static str,
     trump: usize,
-    leader: Seat,
+    leader: SeatIndex,
     expected_ns_tricks: u8,
 }
 
@@ -973,7 +974,7 @@ fn test_all_cases() {
         let hands = Hands::from_pbn(case.pbn)
             .unwrap_or_else(|| panic!("Failed to parse PBN for {}", case.name));
 
-        let solver = Solver::new(hands, case.trump, case.leader);
+        let solver = Solver::with_indices(hands, case.trump, case.leader);
         let ns_tricks = solver.solve();
 
         assert_eq!(
@@ -991,7 +992,7 @@ fn test_upstream_fixed_deals() {
         let hands = Hands::from_pbn(case.pbn)
             .unwrap_or_else(|| panic!("Failed to parse PBN for {}", case.name));
 
-        let solver = Solver::new(hands, case.trump, case.leader);
+        let solver = Solver::with_indices(hands, case.trump, case.leader);
         let ns_tricks = solver.solve();
 
         assert_eq!(
@@ -1007,11 +1008,11 @@ fn test_cards_basic_operations() {
     let mut cards = Cards::new();
     assert!(cards.is_empty());
 
-    cards.add(cards::card_of(SPADE, types::ACE));
+    cards.add_index(cards::card_of(SPADE, types::ACE));
     assert_eq!(cards.size(), 1);
-    assert!(cards.have(cards::card_of(SPADE, types::ACE)));
+    assert!(cards.have_index(cards::card_of(SPADE, types::ACE)));
 
-    cards.add(cards::card_of(HEART, types::KING));
+    cards.add_index(cards::card_of(HEART, types::KING));
     assert_eq!(cards.size(), 2);
 
     let spades = cards.suit(SPADE);
@@ -0,0 +1,600 @@
+//! Search tracing hooks
+//!
+//! A [`SearchObserver`] is told what the search does as it does it: nodes
+//! entered and left, pattern cache (TT) lookups and stores, cutoff cache hits,
+//! fast/slow trick pruning and the cards tried. Every callback does nothing by
+//! default, and a search without an observer uses [`NoObserver`], whose empty
+//! callbacks compile away.
+//!
+//! Two tracers are built in: [`XrayTracer`] writes the text lines of the C++
+//! solver's xray output (what `set_xray_limit` and `solver-diag -X` print), and
+//! `JsonTracer` (with the `json` feature) writes one JSON object per event.
+//! Tracers ignore write errors, so tracing never interrupts a search.
+
+use super::cards::{name_of, Cards};
+use super::hands::Hands;
+use super::types::*;
+use std::io::Write;
+
+/// Where the search is when an event happens
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchNode {
+    /// Cards played since the start of the deal (the first trick of a
+    /// mid-trick search starts with the cards already played to it)
+    pub depth: usize,
+    /// Seat to play
+    pub seat: SeatIndex,
+    /// The search tests whether NS can take `beta` tricks
+    pub beta: i8,
+    /// Tricks NS have won since the start of the search
+    pub ns_tricks_won: u8,
+}
+
+/// Callbacks for search events
+///
+/// Pattern cache bounds are relative to `ns_tricks_won`: they bound the tricks
+/// NS take from the node on. Cards are card indices (see the `cards` module).
+#[allow(unused_variables)]
+pub trait SearchObserver {
+    /// An MTD(f) iteration starts a search of `hands`
+    fn begin_search(&mut self, hands: &Hands, trump: usize, beta: i8) {}
+
+    /// The search enters a node, holding `hands`
+    fn enter_node(&mut self, node: &SearchNode, hands: &Hands) {}
+
+    /// The search leaves a node, with NS taking `ns_tricks` (a bound when
+    /// the node was cut off)
+    fn exit_node(&mut self, node: &SearchNode, ns_tricks: u8) {}
+
+    /// The pattern cache was searched for the relative `hands` at a trick
+    /// start, finding a pattern with `bounds` (lower, upper) or nothing
+    fn tt_lookup(
+        &mut self,
+        node: &SearchNode,
+        shape: u64,
+        hands: &Hands,
+        bounds: Option<(i8, i8)>,
+    ) {
+    }
+
+    /// A pattern of relative `hands` was stored with `bounds` (lower, upper);
+    /// `rank_winners` are the cards whose ranks decided the result
+    fn tt_store(
+        &mut self,
+        node: &SearchNode,
+        shape: u64,
+        hands: &Hands,
+        bounds: (i8, i8),
+        rank_winners: Cards,
+    ) {
+    }
+
+    /// The cutoff cache holds a playable `card` that caused a cutoff before,
+    /// so it is tried first
+    fn cutoff_card(&mut self, node: &SearchNode, card: usize) {}
+
+    /// The side to play has `tricks` fast tricks; `pruned` if they decide the node
+    fn fast_tricks(&mut self, node: &SearchNode, tricks: usize, pruned: bool) {}
+
+    /// The other side has `tricks` slow tricks; `pruned` if they decide the node
+    fn slow_tricks(&mut self, node: &SearchNode, tricks: usize, pruned: bool) {}
+
+    /// `card` is played at the node
+    fn play_card(&mut self, node: &SearchNode, card: usize) {}
+
+    /// Playing `card` gave NS `ns_tricks`, the best so far for the side to
+    /// play being `best`
+    fn card_result(
+        &mut self,
+        node: &SearchNode,
+        card: usize,
+        ns_tricks: u8,
+        best: u8,
+        rank_winners: Cards,
+    ) {
+    }
+
+    /// Playing `card` reached beta: the other cards are not searched
+    fn beta_cutoff(&mut self, node: &SearchNode, card: usize, ns_tricks: u8) {}
+}
+
+/// The observer of a search that is not traced
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoObserver;
+
+impl SearchObserver for NoObserver {}
+
+impl<T: SearchObserver + ?Sized> SearchObserver for &mut T {
+    fn begin_search(&mut self, hands: &Hands, trump: usize, beta: i8) {
+        (**self).begin_search(hands, trump, beta)
+    }
+    fn enter_node(&mut self, node: &SearchNode, hands: &Hands) {
+        (**self).enter_node(node, hands)
+    }
+    fn exit_node(&mut self, node: &SearchNode, ns_tricks: u8) {
+        (**self).exit_node(node, ns_tricks)
+    }
+    fn tt_lookup(
+        &mut self,
+        node: &SearchNode,
+        shape: u64,
+        hands: &Hands,
+        bounds: Option<(i8, i8)>,
+    ) {
+        (**self).tt_lookup(node, shape, hands, bounds)
+    }
+    fn tt_store(
+        &mut self,
+        node: &SearchNode,
+        shape: u64,
+        hands: &Hands,
+        bounds: (i8, i8),
+        rank_winners: Cards,
+    ) {
+        (**self).tt_store(node, shape, hands, bounds, rank_winners)
+    }
+    fn cutoff_card(&mut self, node: &SearchNode, card: usize) {
+        (**self).cutoff_card(node, card)
+    }
+    fn fast_tricks(&mut self, node: &SearchNode, tricks: usize, pruned: bool) {
+        (**self).fast_tricks(node, tricks, pruned)
+    }
+    fn slow_tricks(&mut self, node: &SearchNode, tricks: usize, pruned: bool) {
+        (**self).slow_tricks(node, tricks, pruned)
+    }
+    fn play_card(&mut self, node: &SearchNode, card: usize) {
+        (**self).play_card(node, card)
+    }
+    fn card_result(
+        &mut self,
+        node: &SearchNode,
+        card: usize,
+        ns_tricks: u8,
+        best: u8,
+        rank_winners: Cards,
+    ) {
+        (**self).card_result(node, card, ns_tricks, best, rank_winners)
+    }
+    fn beta_cutoff(&mut self, node: &SearchNode, card: usize, ns_tricks: u8) {
+        (**self).beta_cutoff(node, card, ns_tricks)
+    }
+}
+
+/// Writes the C++ solver's xray trace lines for the first `limit` nodes
+///
+/// Nodes are counted as they are entered; trick starts print `XRAY`, `HANDS`
+/// and `PLAY` lines, followed by the pattern cache and pruning lines of the
+/// node, and every card tried prints a `SCORE` line (and `CUTOFF` if it
+/// reaches beta).
+pub struct XrayTracer<W: Write> {
+    out: W,
+    limit: usize,
+    count: usize,
+    trump: usize,
+    /// Cards played, by depth
+    plays: [usize; TOTAL_CARDS],
+}
+
+impl<W: Write> XrayTracer<W> {
+    pub fn new(out: W, limit: usize) -> Self {
+        XrayTracer {
+            out,
+            limit,
+            count: 0,
+            trump: NOTRUMP,
+            plays: [0; TOTAL_CARDS],
+        }
+    }
+
+    /// Nodes entered so far
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn logging(&self) -> bool {
+        self.count <= self.limit
+    }
+
+    /// The cards played before `depth`, with `|` between tricks
+    fn play_sequence(&self, depth: usize) -> String {
+        let mut sequence = String::new();
+        for (d, &card) in self.plays[..depth].iter().enumerate() {
+            if d > 0 {
+                sequence.push_str(if d & 3 == 0 { " | " } else { " " });
+            }
+            sequence.push_str(&name_of(card));
+        }
+        sequence
+    }
+}
+
+impl<W: Write> SearchObserver for XrayTracer<W> {
+    fn begin_search(&mut self, _hands: &Hands, trump: usize, _beta: i8) {
+        self.trump = trump;
+    }
+
+    fn enter_node(&mut self, node: &SearchNode, hands: &Hands) {
+        self.count += 1;
+        if self.count == self.limit + 1 {
+            let _ = writeln!(self.out, "XRAY_LIMIT_REACHED: {} iterations", self.limit);
+        }
+        if !self.logging() || node.depth & 3 != 0 {
+            return;
+        }
+        let _ = writeln!(
+            self.out,
+            "XRAY {}: depth={} seat={} beta={} ns_tricks_won={}",
+            self.count,
+            node.depth,
+            seat_name(node.seat),
+            node.beta,
+            node.ns_tricks_won
+        );
+        let _ = writeln!(
+            self.out,
+            "HANDS: W={:x} N={:x} E={:x} S={:x}",
+            hands[WEST].value(),
+            hands[NORTH].value(),
+            hands[EAST].value(),
+            hands[SOUTH].value()
+        );
+        if node.depth > 0 {
+            let _ = writeln!(self.out, "PLAY: {}", self.play_sequence(node.depth));
+        }
+    }
+
+    fn tt_lookup(
+        &mut self,
+        node: &SearchNode,
+        shape: u64,
+        hands: &Hands,
+        bounds: Option<(i8, i8)>,
+    ) {
+        let Some((lower, upper)) = bounds.filter(|_| self.logging()) else {
+            return;
+        };
+        let won = node.ns_tricks_won as i8;
+        if lower + won >= node.beta {
+            let _ = writeln!(
+                self.out,
+                "PATTERN_HIT: depth={} seat={} beta={} ns_tricks_won={} bounds=[{},{}] adj_lower={} LOWER_CUT shape={:x} hands=[{:x},{:x},{:x},{:x}]",
+                node.depth, node.seat, node.beta, won, lower, upper, lower + won, shape,
+                hands[WEST].value(), hands[NORTH].value(), hands[EAST].value(), hands[SOUTH].value()
+            );
+        } else if upper + won < node.beta {
+            let _ = writeln!(
+                self.out,
+                "PATTERN_HIT: depth={} seat={} beta={} ns_tricks_won={} bounds=[{},{}] adj_upper={} UPPER_CUT",
+                node.depth, node.seat, node.beta, won, lower, upper, upper + won
+            );
+        }
+    }
+
+    fn tt_store(
+        &mut self,
+        node: &SearchNode,
+        shape: u64,
+        hands: &Hands,
+        (lower, upper): (i8, i8),
+        rank_winners: Cards,
+    ) {
+        if !self.logging() {
+            return;
+        }
+        // A search failing low stores an upper bound, one failing high a lower bound
+        let won = node.ns_tricks_won as i8;
+        let result = if upper + won < node.beta {
+            upper
+        } else {
+            lower
+        } + won;
+        let _ = writeln!(
+            self.out,
+            "PATTERN_STORE: depth={} seat={} beta={} ns_tricks_won={} result={} bounds=[{},{}] shape={:x} hands=[{:x},{:x},{:x},{:x}] rank_winners={:x}",
+            node.depth, node.seat, node.beta, won, result, lower, upper, shape,
+            hands[WEST].value(), hands[NORTH].value(), hands[EAST].value(), hands[SOUTH].value(),
+            rank_winners.value()
+        );
+    }
+
+    fn fast_tricks(&mut self, node: &SearchNode, tricks: usize, _pruned: bool) {
+        if self.logging() {
+            let _ = writeln!(
+                self.out,
+                "FAST_TRICKS: depth={} seat={} fast={} trump={}",
+                node.depth, node.seat, tricks, self.trump
+            );
+        }
+    }
+
+    fn slow_tricks(&mut self, node: &SearchNode, tricks: usize, _pruned: bool) {
+        if self.logging() {
+            let _ = writeln!(
+                self.out,
+                "SLOW_TRICKS: depth={} seat={} slow={} trump={}",
+                node.depth, node.seat, tricks, self.trump
+            );
+        }
+    }
+
+    fn play_card(&mut self, node: &SearchNode, card: usize) {
+        self.plays[node.depth] = card;
+    }
+
+    fn card_result(
+        &mut self,
+        node: &SearchNode,
+        card: usize,
+        ns_tricks: u8,
+        best: u8,
+        rank_winners: Cards,
+    ) {
+        if self.logging() {
+            let _ = writeln!(
+                self.out,
+                "SCORE: depth={} card={} score={} best={} beta={} maximizing={} rank_winners={:x} play=[{}]",
+                node.depth, name_of(card), ns_tricks, best, node.beta, is_ns(node.seat),
+                rank_winners.value(), self.play_sequence(node.depth)
+            );
+        }
+    }
+
+    fn beta_cutoff(&mut self, node: &SearchNode, card: usize, ns_tricks: u8) {
+        if self.logging() {
+            let _ =
+                writeln!(
+                self.out,
+                "CUTOFF: depth={} seat={} card={} score={} best={} beta={} maximizing={} play=[{}]",
+                node.depth, node.seat, name_of(card), ns_tricks, ns_tricks, node.beta,
+                is_ns(node.seat), self.play_sequence(node.depth)
+            );
+        }
+    }
+}
+
+/// Writes every event as one line of JSON
+///
+/// Each object has an `event` name and the node's `depth`, `seat` (a letter),
+/// `beta` and `ns_tricks_won`; cards are names such as `"SA"` and hands PBN
+/// deal strings. `begin_search` gives the `deal`, `trump` and `beta`.
+#[cfg(feature = "json")]
+pub struct JsonTracer<W: Write> {
+    out: W,
+}
+
+#[cfg(feature = "json")]
+impl<W: Write> JsonTracer<W> {
+    pub fn new(out: W) -> Self {
+        JsonTracer { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn write(&mut self, event: &str, node: &SearchNode, fields: serde_json::Value) {
+        let mut line = serde_json::json!({
+            "event": event,
+            "depth": node.depth,
+            "seat": seat_letter(node.seat).to_string(),
+            "beta": node.beta,
+            "ns_tricks_won": node.ns_tricks_won,
+        });
+        if let (Some(line), serde_json::Value::Object(fields)) = (line.as_object_mut(), fields) {
+            line.extend(fields);
+        }
+        let _ = writeln!(self.out, "{}", line);
+    }
+}
+
+#[cfg(feature = "json")]
+fn card_names(cards: Cards) -> Vec<String> {
+    cards.iter().map(name_of).collect()
+}
+
+#[cfg(feature = "json")]
+impl<W: Write> SearchObserver for JsonTracer<W> {
+    fn begin_search(&mut self, hands: &Hands, trump: usize, beta: i8) {
+        let line = serde_json::json!({
+            "event": "search",
+            "deal": hands.to_pbn(),
+            "trump": strain_name(trump),
+            "beta": beta,
+        });
+        let _ = writeln!(self.out, "{}", line);
+    }
+
+    fn enter_node(&mut self, node: &SearchNode, _hands: &Hands) {
+        self.write("enter", node, serde_json::json!({}));
+    }
+
+    fn exit_node(&mut self, node: &SearchNode, ns_tricks: u8) {
+        self.write("exit", node, serde_json::json!({ "ns_tricks": ns_tricks }));
+    }
+
+    fn tt_lookup(
+        &mut self,
+        node: &SearchNode,
+        shape: u64,
+        hands: &Hands,
+        bounds: Option<(i8, i8)>,
+    ) {
+        let fields = serde_json::json!({
+            "shape": format!("{:x}", shape),
+            "hands": hands.to_pbn(),
+            "hit": bounds.is_some(),
+            "bounds": bounds.map(|(lower, upper)| [lower, upper]),
+        });
+        self.write("tt_lookup", node, fields);
+    }
+
+    fn tt_store(
+        &mut self,
+        node: &SearchNode,
+        shape: u64,
+        hands: &Hands,
+        (lower, upper): (i8, i8),
+        rank_winners: Cards,
+    ) {
+        let fields = serde_json::json!({
+            "shape": format!("{:x}", shape),
+            "hands": hands.to_pbn(),
+            "bounds": [lower, upper],
+            "rank_winners": card_names(rank_winners),
+        });
+        self.write("tt_store", node, fields);
+    }
+
+    fn cutoff_card(&mut self, node: &SearchNode, card: usize) {
+        self.write(
+            "cutoff_card",
+            node,
+            serde_json::json!({ "card": name_of(card) }),
+        );
+    }
+
+    fn fast_tricks(&mut self, node: &SearchNode, tricks: usize, pruned: bool) {
+        let fields = serde_json::json!({ "tricks": tricks, "pruned": pruned });
+        self.write("fast_tricks", node, fields);
+    }
+
+    fn slow_tricks(&mut self, node: &SearchNode, tricks: usize, pruned: bool) {
+        let fields = serde_json::json!({ "tricks": tricks, "pruned": pruned });
+        self.write("slow_tricks", node, fields);
+    }
+
+    fn play_card(&mut self, node: &SearchNode, card: usize) {
+        self.write("play", node, serde_json::json!({ "card": name_of(card) }));
+    }
+
+    fn card_result(
+        &mut self,
+        node: &SearchNode,
+        card: usize,
+        ns_tricks: u8,
+        best: u8,
+        rank_winners: Cards,
+    ) {
+        let fields = serde_json::json!({
+            "card": name_of(card),
+            "ns_tricks": ns_tricks,
+            "best": best,
+            "rank_winners": card_names(rank_winners),
+        });
+        self.write("card_result", node, fields);
+    }
+
+    fn beta_cutoff(&mut self, node: &SearchNode, card: usize, ns_tricks: u8) {
+        let fields = serde_json::json!({ "card": name_of(card), "ns_tricks": ns_tricks });
+        self.write("beta_cutoff", node, fields);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_caches, Solver};
+
+    const ENDING: &str = "N:AKQ.J6.. 65.A.T4. J7..J.A8 98..96.Q";
+
+    /// Checks that events nest and counts them
+    #[derive(Default)]
+    struct Checker {
+        searches: usize,
+        depths: Vec<usize>,
+        entered: usize,
+        tt_stores: usize,
+        plays: usize,
+        results: usize,
+    }
+
+    impl SearchObserver for Checker {
+        fn begin_search(&mut self, _hands: &Hands, _trump: usize, _beta: i8) {
+            assert!(self.depths.is_empty());
+            self.searches += 1;
+        }
+        fn enter_node(&mut self, node: &SearchNode, hands: &Hands) {
+            if let Some(&depth) = self.depths.last() {
+                assert_eq!(node.depth, depth + 1);
+            }
+            assert_eq!(hands.all_cards().size() + node.depth, 20);
+            self.depths.push(node.depth);
+            self.entered += 1;
+        }
+        fn exit_node(&mut self, node: &SearchNode, _ns_tricks: u8) {
+            assert_eq!(self.depths.pop(), Some(node.depth));
+        }
+        fn tt_store(&mut self, node: &SearchNode, _: u64, _: &Hands, bounds: (i8, i8), _: Cards) {
+            assert_eq!(node.depth & 3, 0);
+            assert!(bounds.0 <= bounds.1);
+            self.tt_stores += 1;
+        }
+        fn play_card(&mut self, node: &SearchNode, _card: usize) {
+            assert_eq!(self.depths.last(), Some(&node.depth));
+            self.plays += 1;
+        }
+        fn card_result(&mut self, _: &SearchNode, _: usize, _: u8, _: u8, _: Cards) {
+            self.results += 1;
+        }
+    }
+
+    #[test]
+    fn test_observer_events() {
+        let hands = Hands::from_pbn(ENDING).unwrap();
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
+        let (mut cutoff, mut pattern) = new_caches();
+        let mut checker = Checker::default();
+        let tricks = solver.solve_with_observer(&mut cutoff, &mut pattern, &mut checker);
+        assert_eq!(tricks, solver.solve());
+        assert!(checker.searches > 0 && checker.depths.is_empty());
+        assert!(checker.tt_stores > 0);
+        assert_eq!(checker.plays, checker.results);
+        // Every node but the first of each search follows a card played
+        assert_eq!(checker.entered, checker.plays + checker.searches);
+    }
+
+    #[test]
+    fn test_xray_tracer() {
+        let hands = Hands::from_pbn(ENDING).unwrap();
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
+        let (mut cutoff, mut pattern) = new_caches();
+        let mut tracer = XrayTracer::new(Vec::new(), 20);
+        solver.solve_with_observer(&mut cutoff, &mut pattern, &mut tracer);
+        assert!(tracer.count() > 20);
+        let text = String::from_utf8(tracer.into_inner()).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(
+            lines.next(),
+            Some("XRAY 1: depth=0 seat=West beta=5 ns_tricks_won=0")
+        );
+        assert!(lines.next().unwrap().starts_with("HANDS: W="));
+        assert!(text.contains("\nSCORE: depth=") && text.contains("\nPLAY: "));
+        assert!(text.ends_with("XRAY_LIMIT_REACHED: 20 iterations\n"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_tracer() {
+        let hands = Hands::from_pbn(ENDING).unwrap();
+        let solver = Solver::with_indices(hands, NOTRUMP, WEST);
+        let (mut cutoff, mut pattern) = new_caches();
+        let mut tracer = JsonTracer::new(Vec::new());
+        solver.solve_with_observer(&mut cutoff, &mut pattern, &mut tracer);
+        let text = String::from_utf8(tracer.into_inner()).unwrap();
+        let events: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events[0]["event"], "search");
+        assert_eq!(events[0]["deal"], hands.to_pbn());
+        assert_eq!(events[1]["event"], "enter");
+        assert_eq!(events[1]["seat"], "W");
+        let count = |name: &str| events.iter().filter(|e| e["event"] == name).count();
+        assert_eq!(count("enter"), count("exit"));
+        assert!(count("tt_store") > 0 && count("play") > 0);
+    }
+}
@@ -0,0 +1,383 @@
+//! Search tree export
+//!
+//! [`SearchTree`] is a [`SearchObserver`] recording the alpha-beta tree a
+//! solve explores: the card leading to each node, beta, the result, pattern
+//! cache (TT) hits and why a node was cut off. Each MTD(f) iteration adds a
+//! root. Recording stops at a node limit and, optionally, a depth limit, so it
+//! is meant for endings and small positions. The tree is written as Graphviz
+//! DOT, or as JSON with the `json` feature.
+
+use super::cards::name_of;
+use super::hands::Hands;
+use super::trace::{SearchNode, SearchObserver};
+use super::types::*;
+use std::fmt::Write;
+
+/// Why a node was decided without searching all its cards
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeCutoff {
+    /// The pattern cache held bounds deciding the node
+    Pattern,
+    /// Fast tricks of the side to play decided the node
+    FastTricks,
+    /// Slow tricks of the other side decided the node
+    SlowTricks,
+    /// Playing `card` reached beta
+    Beta { card: usize },
+}
+
+impl NodeCutoff {
+    fn name(&self) -> String {
+        match self {
+            NodeCutoff::Pattern => "pattern".to_string(),
+            NodeCutoff::FastTricks => "fast tricks".to_string(),
+            NodeCutoff::SlowTricks => "slow tricks".to_string(),
+            NodeCutoff::Beta { card } => format!("beta by {}", name_of(*card)),
+        }
+    }
+}
+
+/// One node of a recorded search tree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeNode {
+    /// The search's position (depth, seat to play, beta, tricks won)
+    pub node: SearchNode,
+    /// The card played to reach the node (None at a root)
+    pub card: Option<usize>,
+    /// NS tricks the node returned (None if the search was abandoned)
+    pub ns_tricks: Option<u8>,
+    /// Bounds (lower, upper) found in the pattern cache, relative to the
+    /// tricks won
+    pub tt_bounds: Option<(i8, i8)>,
+    /// Card tried first because the cutoff cache held it
+    pub cutoff_card: Option<usize>,
+    pub cutoff: Option<NodeCutoff>,
+    /// Nodes reached by the cards played here
+    pub children: Vec<usize>,
+    /// Some children were searched but not recorded (limits reached)
+    pub truncated: bool,
+}
+
+/// Records the tree explored by a solve (see the module documentation)
+#[derive(Clone, Debug)]
+pub struct SearchTree {
+    nodes: Vec<TreeNode>,
+    roots: Vec<usize>,
+    trump: usize,
+    max_nodes: usize,
+    max_depth: usize,
+    /// Recorded node of each node entered and not yet left
+    stack: Vec<Option<usize>>,
+    root_depth: usize,
+    card: Option<usize>,
+}
+
+impl SearchTree {
+    /// Record at most `max_nodes` nodes
+    pub fn new(max_nodes: usize) -> Self {
+        SearchTree {
+            nodes: Vec::new(),
+            roots: Vec::new(),
+            trump: NOTRUMP,
+            max_nodes,
+            max_depth: usize::MAX,
+            stack: Vec::new(),
+            root_depth: 0,
+            card: None,
+        }
+    }
+
+    /// Record nodes at most `max_depth` cards below the root
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn nodes(&self) -> &[TreeNode] {
+        &self.nodes
+    }
+
+    /// The root of each MTD(f) iteration, in order
+    pub fn roots(&self) -> &[usize] {
+        &self.roots
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Check if some searched nodes were not recorded
+    pub fn is_truncated(&self) -> bool {
+        self.nodes.iter().any(|node| node.truncated)
+    }
+
+    /// The tree as a Graphviz DOT digraph
+    ///
+    /// NS nodes are boxes and EW nodes ellipses; nodes cut off are filled
+    /// grey and truncated subtrees end in a dashed `...` node.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        let trump = strain_name(self.trump);
+        dot.push_str("digraph search {\n");
+        let _ = writeln!(dot, "  label=\"{} search tree\";", trump);
+        dot.push_str("  node [fontname=\"monospace\"];\n");
+        for (id, tree_node) in self.nodes.iter().enumerate() {
+            let node = &tree_node.node;
+            let mut label = format!(
+                "{} to play, beta {}\\nwon {}, result {}",
+                seat_letter(node.seat),
+                node.beta,
+                node.ns_tricks_won,
+                tree_node
+                    .ns_tricks
+                    .map_or("?".to_string(), |tricks| tricks.to_string())
+            );
+            if let Some(iteration) = self.roots.iter().position(|&root| root == id) {
+                label = format!("search {}\\n{}", iteration + 1, label);
+            }
+            if let Some((lower, upper)) = tree_node.tt_bounds {
+                let _ = write!(label, "\\ntt [{},{}]", lower, upper);
+            }
+            if let Some(card) = tree_node.cutoff_card {
+                let _ = write!(label, "\\ncutoff card {}", name_of(card));
+            }
+            if let Some(cutoff) = tree_node.cutoff {
+                let _ = write!(label, "\\ncut: {}", cutoff.name());
+            }
+            let shape = if is_ns(node.seat) { "box" } else { "ellipse" };
+            let style = if tree_node.cutoff.is_some() {
+                ", style=filled, fillcolor=lightgrey"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                dot,
+                "  n{} [shape={}{}, label=\"{}\"];",
+                id, shape, style, label
+            );
+            for &child in &tree_node.children {
+                let card = self.nodes[child].card.map_or(String::new(), name_of);
+                let _ = writeln!(dot, "  n{} -> n{} [label=\"{}\"];", id, child, card);
+            }
+            if tree_node.truncated {
+                let _ = writeln!(dot, "  t{} [shape=plaintext, label=\"...\"];", id);
+                let _ = writeln!(dot, "  n{} -> t{} [style=dashed];", id, id);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// The tree as JSON: the trump, the roots and a list of nodes whose
+    /// children are indices into it
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> serde_json::Value {
+        let nodes: Vec<serde_json::Value> = self
+            .nodes
+            .iter()
+            .map(|tree_node| {
+                let node = &tree_node.node;
+                serde_json::json!({
+                    "depth": node.depth,
+                    "seat": seat_letter(node.seat).to_string(),
+                    "beta": node.beta,
+                    "ns_tricks_won": node.ns_tricks_won,
+                    "card": tree_node.card.map(name_of),
+                    "ns_tricks": tree_node.ns_tricks,
+                    "tt_bounds": tree_node.tt_bounds.map(|(lower, upper)| [lower, upper]),
+                    "cutoff_card": tree_node.cutoff_card.map(name_of),
+                    "cutoff": tree_node.cutoff.map(|cutoff| cutoff.name()),
+                    "children": tree_node.children,
+                    "truncated": tree_node.truncated,
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "trump": strain_name(self.trump),
+            "roots": self.roots,
+            "nodes": nodes,
+        })
+    }
+
+    /// The recorded node being searched, if any
+    fn current(&mut self) -> Option<&mut TreeNode> {
+        let id = (*self.stack.last()?)?;
+        Some(&mut self.nodes[id])
+    }
+}
+
+impl SearchObserver for SearchTree {
+    fn begin_search(&mut self, _hands: &Hands, trump: usize, _beta: i8) {
+        self.trump = trump;
+        self.stack.clear();
+        self.card = None;
+    }
+
+    fn enter_node(&mut self, node: &SearchNode, _hands: &Hands) {
+        let parent = match self.stack.last() {
+            None => {
+                self.root_depth = node.depth;
+                None
+            }
+            Some(None) => {
+                // Below a node that was not recorded
+                self.stack.push(None);
+                return;
+            }
+            Some(&Some(parent)) => Some(parent),
+        };
+        let card = self.card.take();
+        if self.nodes.len() >= self.max_nodes || node.depth - self.root_depth > self.max_depth {
+            if let Some(parent) = parent {
+                self.nodes[parent].truncated = true;
+            }
+            self.stack.push(None);
+            return;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(TreeNode {
+            node: *node,
+            card,
+            ns_tricks: None,
+            tt_bounds: None,
+            cutoff_card: None,
+            cutoff: None,
+            children: Vec::new(),
+            truncated: false,
+        });
+        match parent {
+            Some(parent) => self.nodes[parent].children.push(id),
+            None => self.roots.push(id),
+        }
+        self.stack.push(Some(id));
+    }
+
+    fn exit_node(&mut self, _node: &SearchNode, ns_tricks: u8) {
+        if let Some(tree_node) = self.current() {
+            tree_node.ns_tricks = Some(ns_tricks);
+        }
+        self.stack.pop();
+    }
+
+    fn tt_lookup(&mut self, node: &SearchNode, _: u64, _: &Hands, bounds: Option<(i8, i8)>) {
+        let Some(tree_node) = self.current() else {
+            return;
+        };
+        tree_node.tt_bounds = bounds;
+        if let Some((lower, upper)) = bounds {
+            let won = node.ns_tricks_won as i8;
+            if lower + won >= node.beta || upper + won < node.beta {
+                tree_node.cutoff = Some(NodeCutoff::Pattern);
+            }
+        }
+    }
+
+    fn cutoff_card(&mut self, _node: &SearchNode, card: usize) {
+        if let Some(tree_node) = self.current() {
+            tree_node.cutoff_card = Some(card);
+        }
+    }
+
+    fn fast_tricks(&mut self, _node: &SearchNode, _tricks: usize, pruned: bool) {
+        if let (true, Some(tree_node)) = (pruned, self.current()) {
+            tree_node.cutoff = Some(NodeCutoff::FastTricks);
+        }
+    }
+
+    fn slow_tricks(&mut self, _node: &SearchNode, _tricks: usize, pruned: bool) {
+        if let (true, Some(tree_node)) = (pruned, self.current()) {
+            tree_node.cutoff = Some(NodeCutoff::SlowTricks);
+        }
+    }
+
+    fn play_card(&mut self, _node: &SearchNode, card: usize) {
+        self.card = Some(card);
+    }
+
+    fn beta_cutoff(&mut self, _node: &SearchNode, card: usize, _ns_tricks: u8) {
+        if let Some(tree_node) = self.current() {
+            tree_node.cutoff = Some(NodeCutoff::Beta { card });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{new_caches, Solver};
+
+    const ENDING: &str = "N:AKQ.J6.. 65.A.T4. J7..J.A8 98..96.Q";
+
+    fn record(tree: &mut SearchTree) -> u8 {
+        let hands = Hands::from_pbn(ENDING).unwrap();
+        let (mut cutoff, mut pattern) = new_caches();
+        Solver::with_indices(hands, NOTRUMP, WEST).solve_with_observer(
+            &mut cutoff,
+            &mut pattern,
+            tree,
+        )
+    }
+
+    #[test]
+    fn test_record_tree() {
+        let mut tree = SearchTree::new(usize::MAX);
+        let tricks = record(&mut tree);
+        assert!(!tree.is_empty() && !tree.is_truncated());
+
+        // Each root is a whole search, ending at the solution
+        let last_root = &tree.nodes()[*tree.roots().last().unwrap()];
+        assert_eq!(last_root.node.depth, 0);
+        assert!(last_root.card.is_none());
+        let children: usize = tree.nodes().iter().map(|node| node.children.len()).sum();
+        assert_eq!(children + tree.roots().len(), tree.len());
+
+        for node in tree.nodes() {
+            assert!(node.ns_tricks.is_some());
+            for &child in &node.children {
+                assert_eq!(tree.nodes()[child].node.depth, node.node.depth + 1);
+                assert!(tree.nodes()[child].card.is_some());
+            }
+            if let Some(NodeCutoff::Beta { card }) = node.cutoff {
+                let last = *node.children.last().unwrap();
+                assert_eq!(tree.nodes()[last].card, Some(card));
+            }
+        }
+        assert!(tree.nodes().iter().any(|node| node.cutoff.is_some()));
+        assert!(tricks <= 5);
+    }
+
+    #[test]
+    fn test_limits_and_dot() {
+        let mut tree = SearchTree::new(10);
+        record(&mut tree);
+        assert_eq!(tree.len(), 10);
+        assert!(tree.is_truncated());
+
+        let mut shallow = SearchTree::new(usize::MAX).with_max_depth(1);
+        record(&mut shallow);
+        assert!(shallow.nodes().iter().all(|node| node.node.depth <= 1));
+
+        let dot = tree.to_dot();
+        assert!(dot.starts_with("digraph search {\n") && dot.ends_with("}\n"));
+        assert!(dot.contains("n0 [shape=ellipse") && dot.contains("search 1\\nW to play"));
+        let edges = dot.lines().filter(|line| line.contains(" -> n")).count();
+        assert_eq!(edges, tree.len() - tree.roots().len());
+        assert!(dot.contains("[style=dashed]"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_tree_json() {
+        let mut tree = SearchTree::new(usize::MAX);
+        record(&mut tree);
+        let json = tree.to_json();
+        assert_eq!(json["trump"], "NT");
+        assert_eq!(json["nodes"].as_array().unwrap().len(), tree.len());
+        assert_eq!(json["nodes"][0]["seat"], "W");
+        assert!(json["nodes"][0]["card"].is_null());
+    }
+}
@@ -0,0 +1,359 @@
+//! Type-safe seats, suits, strains, ranks and cards
+//!
+//! The solver works on plain indices (see `types`) for speed. These types
+//! wrap the same values for the public API, so that a seat cannot be passed
+//! where a strain is expected. Each converts to its index for free with
+//! `index()`, and back with `from_index()`.
+//!
+//! Entry points such as `Solver::new` and `Cards::has_card` take only the
+//! typed values. Code written against the index constants converts on
+//! purpose: `Seat::from_index(seat)`, or the deprecated `Solver::from_indices`
+//! and `PlaySession::from_indices`. The index forms `Cards::have`, `add` and
+//! `remove`, and the `types::Seat`, `Suit` and `Rank` aliases, stay for a
+//! release as deprecated.
+
+use super::cards::{card_of, name_of, parse_card, rank_of, suit_of};
+use super::types::{
+    self, char_to_rank, char_to_seat, char_to_suit, NUM_RANKS, NUM_SEATS, NUM_STRAINS, NUM_SUITS,
+    TOTAL_CARDS,
+};
+use std::fmt;
+
+/// Common methods of the index-backed enums
+macro_rules! indexed_enum {
+    ($name:ident, $count:expr, [$($variant:ident),+]) => {
+        impl $name {
+            /// Every value, in index order
+            pub const ALL: [$name; $count] = [$($name::$variant),+];
+
+            /// The solver's index of this value
+            #[inline]
+            pub fn index(self) -> usize {
+                self as usize
+            }
+
+            /// The value with this index, if there is one
+            #[inline]
+            pub fn from_index(index: usize) -> Option<Self> {
+                Self::ALL.get(index).copied()
+            }
+
+            /// Iterate over every value, in index order
+            pub fn iter() -> impl Iterator<Item = Self> {
+                Self::ALL.into_iter()
+            }
+        }
+
+        impl From<$name> for usize {
+            fn from(value: $name) -> usize {
+                value.index()
+            }
+        }
+    };
+}
+
+/// A seat at the table
+///
+/// Seats are numbered clockwise from West, as in the solver: West=0,
+/// North=1, East=2, South=3. This differs from `bridge_types::Direction`,
+/// which starts at North; convert between the two with `From`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Seat {
+    West = 0,
+    North = 1,
+    East = 2,
+    South = 3,
+}
+
+indexed_enum!(Seat, NUM_SEATS, [West, North, East, South]);
+
+impl Seat {
+    /// Seat from its letter (W, N, E or S, either case)
+    pub fn from_char(c: char) -> Option<Self> {
+        char_to_seat(c).and_then(Self::from_index)
+    }
+
+    /// First letter of the seat's name
+    pub fn letter(self) -> char {
+        types::seat_letter(self.index())
+    }
+
+    /// Full name, e.g. "North"
+    pub fn name(self) -> &'static str {
+        types::seat_name(self.index())
+    }
+
+    /// Whether the seat is North or South
+    pub fn is_ns(self) -> bool {
+        types::is_ns(self.index())
+    }
+
+    /// Partner's seat
+    pub fn partner(self) -> Self {
+        Self::ALL[types::partner(self.index())]
+    }
+
+    /// Next seat clockwise, which plays after this one
+    pub fn left_hand_opp(self) -> Self {
+        Self::ALL[types::left_hand_opp(self.index())]
+    }
+
+    /// Previous seat clockwise
+    pub fn right_hand_opp(self) -> Self {
+        Self::ALL[types::right_hand_opp(self.index())]
+    }
+}
+
+impl fmt::Display for Seat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.letter())
+    }
+}
+
+/// A suit, in the solver's order: Spades=0, Hearts=1, Diamonds=2, Clubs=3
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Suit {
+    Spades = 0,
+    Hearts = 1,
+    Diamonds = 2,
+    Clubs = 3,
+}
+
+indexed_enum!(Suit, NUM_SUITS, [Spades, Hearts, Diamonds, Clubs]);
+
+impl Suit {
+    /// Suit from its letter (S, H, D or C, either case)
+    pub fn from_char(c: char) -> Option<Self> {
+        char_to_suit(c).and_then(Self::from_index)
+    }
+
+    /// The suit's letter
+    pub fn letter(self) -> char {
+        types::strain_name(self.index()).chars().next().unwrap()
+    }
+}
+
+impl fmt::Display for Suit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.letter())
+    }
+}
+
+/// A contract strain: one of the suits as trumps, or notrump
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Strain {
+    Spades = 0,
+    Hearts = 1,
+    Diamonds = 2,
+    Clubs = 3,
+    NoTrump = 4,
+}
+
+indexed_enum!(
+    Strain,
+    NUM_STRAINS,
+    [Spades, Hearts, Diamonds, Clubs, NoTrump]
+);
+
+impl Strain {
+    /// Strain from its letter (S, H, D, C or N, either case)
+    pub fn from_char(c: char) -> Option<Self> {
+        char_to_suit(c).and_then(Self::from_index)
+    }
+
+    /// The trump suit, or None in notrump
+    pub fn suit(self) -> Option<Suit> {
+        Suit::from_index(self.index())
+    }
+
+    /// Symbol as in contracts: "S", "H", "D", "C" or "NT"
+    pub fn symbol(self) -> &'static str {
+        types::strain_name(self.index())
+    }
+}
+
+impl From<Suit> for Strain {
+    fn from(suit: Suit) -> Self {
+        Strain::ALL[suit.index()]
+    }
+}
+
+impl fmt::Display for Strain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+/// A card rank, Two=0 up to Ace=12
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Rank {
+    Two = 0,
+    Three = 1,
+    Four = 2,
+    Five = 3,
+    Six = 4,
+    Seven = 5,
+    Eight = 6,
+    Nine = 7,
+    Ten = 8,
+    Jack = 9,
+    Queen = 10,
+    King = 11,
+    Ace = 12,
+}
+
+indexed_enum!(
+    Rank,
+    NUM_RANKS,
+    [Two, Three, Four, Five, Six, Seven, Eight, Nine, Ten, Jack, Queen, King, Ace]
+);
+
+impl Rank {
+    /// Rank from its character (2-9, T, J, Q, K or A)
+    pub fn from_char(c: char) -> Option<Self> {
+        char_to_rank(c).and_then(Self::from_index)
+    }
+
+    /// The rank's character
+    pub fn letter(self) -> char {
+        types::rank_name(self.index())
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.letter())
+    }
+}
+
+/// A playing card, held as the solver's card index (0 is the ace of spades)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Card(u8);
+
+impl Card {
+    /// The card of a suit and rank
+    pub fn new(suit: Suit, rank: Rank) -> Self {
+        Card(card_of(suit.index(), rank.index()) as u8)
+    }
+
+    /// The card with this index, if it is below 52
+    pub fn from_index(index: usize) -> Option<Self> {
+        (index < TOTAL_CARDS).then_some(Card(index as u8))
+    }
+
+    /// Parse a card name such as "SA" or "h9"
+    pub fn from_name(name: &str) -> Option<Self> {
+        parse_card(name).and_then(Self::from_index)
+    }
+
+    /// The solver's index of this card
+    #[inline]
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// The card's suit
+    pub fn suit(self) -> Suit {
+        Suit::ALL[suit_of(self.index())]
+    }
+
+    /// The card's rank
+    pub fn rank(self) -> Rank {
+        Rank::ALL[rank_of(self.index())]
+    }
+
+    /// Iterate over all 52 cards, in index order
+    pub fn iter() -> impl Iterator<Item = Self> {
+        (0..TOTAL_CARDS as u8).map(Card)
+    }
+}
+
+impl From<Card> for usize {
+    fn from(card: Card) -> usize {
+        card.index()
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", name_of(self.index()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ACE, EAST, HEART, NORTH, NOTRUMP, SPADE, WEST};
+    use crate::{Hands, Solver};
+
+    #[test]
+    fn test_indices_match_constants() {
+        assert_eq!(Seat::West.index(), WEST);
+        assert_eq!(Seat::East.index(), EAST);
+        assert_eq!(Suit::Hearts.index(), HEART);
+        assert_eq!(Strain::NoTrump.index(), NOTRUMP);
+        assert_eq!(Rank::Ace.index(), ACE);
+        assert_eq!(
+            Card::new(Suit::Spades, Rank::Ace).index(),
+            card_of(SPADE, ACE)
+        );
+        assert_eq!(Seat::from_index(4), None);
+        assert_eq!(Card::from_index(52), None);
+    }
+
+    #[test]
+    fn test_iteration() {
+        let seats: Vec<Seat> = Seat::iter().collect();
+        assert_eq!(seats, [Seat::West, Seat::North, Seat::East, Seat::South]);
+        assert_eq!(Strain::iter().count(), 5);
+        assert_eq!(Rank::iter().last(), Some(Rank::Ace));
+        for (index, card) in Card::iter().enumerate() {
+            assert_eq!(card.index(), index);
+            assert_eq!(Card::new(card.suit(), card.rank()), card);
+        }
+    }
+
+    #[test]
+    fn test_display_and_parse() {
+        assert_eq!(Seat::North.to_string(), "N");
+        assert_eq!(Seat::North.name(), "North");
+        assert_eq!(Strain::NoTrump.to_string(), "NT");
+        assert_eq!(Strain::from(Suit::Clubs), Strain::Clubs);
+        assert_eq!(Strain::NoTrump.suit(), None);
+        assert_eq!(Suit::from_char('n'), None);
+        assert_eq!(Strain::from_char('n'), Some(Strain::NoTrump));
+        let card = Card::from_name("hT").unwrap();
+        assert_eq!((card.suit(), card.rank()), (Suit::Hearts, Rank::Ten));
+        assert_eq!(card.to_string(), "HT");
+    }
+
+    #[test]
+    fn test_seat_relations() {
+        assert_eq!(Seat::West.partner(), Seat::East);
+        assert_eq!(Seat::South.left_hand_opp(), Seat::West);
+        assert_eq!(Seat::West.right_hand_opp(), Seat::South);
+        assert!(Seat::North.is_ns());
+    }
+
+    #[test]
+    fn test_typed_arguments() {
+        let hands = Hands::from_pbn(
+            "N:AKQT3.J6.KJ42.95 652.AK42.AQ87.T4 J74.QT95.T.AK863 98.873.9653.QJ72",
+        )
+        .unwrap();
+        let typed = Solver::new(hands, Strain::NoTrump, Seat::West).solve();
+        #[allow(deprecated)]
+        let indexed = Solver::from_indices(hands, NOTRUMP, WEST).solve();
+        assert_eq!(typed, indexed);
+        assert_eq!(
+            Solver::new(hands, Suit::Spades, Seat::West).solve(),
+            Solver::new(hands, Strain::Spades, Seat::West).solve()
+        );
+        assert!(hands[NORTH].has_card(Card::new(Suit::Spades, Rank::Ace)));
+        assert_eq!(hands[NORTH].cards().next(), Card::from_name("SA"));
+    }
+}
@@ -1,37 +1,45 @@
 //! Core type definitions matching the C++ solver
 
 /// Suits: SPADE=0, HEART=1, DIAMOND=2, CLUB=3
-pub type Suit = usize;
-pub const SPADE: Suit = 0;
-pub const HEART: Suit = 1;
-pub const DIAMOND: Suit = 2;
-pub const CLUB: Suit = 3;
+pub type SuitIndex = usize;
+#[deprecated(note = "use `SuitIndex`, or the typed `crate::Suit`")]
+pub type Suit = SuitIndex;
+pub const SPADE: SuitIndex = 0;
+pub const HEART: SuitIndex = 1;
+pub const DIAMOND: SuitIndex = 2;
+pub const CLUB: SuitIndex = 3;
 pub const NUM_SUITS: usize = 4;
 pub const NOTRUMP: usize = NUM_SUITS;
+/// Strains: the four suits plus NOTRUMP
+pub const NUM_STRAINS: usize = NUM_SUITS + 1;
 
 /// Ranks: TWO=0, ..., TEN=8, JACK=9, QUEEN=10, KING=11, ACE=12
-pub type Rank = usize;
-pub const TWO: Rank = 0;
-pub const THREE: Rank = 1;
-pub const FOUR: Rank = 2;
-pub const FIVE: Rank = 3;
-pub const SIX: Rank = 4;
-pub const SEVEN: Rank = 5;
-pub const EIGHT: Rank = 6;
-pub const NINE: Rank = 7;
-pub const TEN: Rank = 8;
-pub const JACK: Rank = 9;
-pub const QUEEN: Rank = 10;
-pub const KING: Rank = 11;
-pub const ACE: Rank = 12;
+pub type RankIndex = usize;
+#[deprecated(note = "use `RankIndex`, or the typed `crate::Rank`")]
+pub type Rank = RankIndex;
+pub const TWO: RankIndex = 0;
+pub const THREE: RankIndex = 1;
+pub const FOUR: RankIndex = 2;
+pub const FIVE: RankIndex = 3;
+pub const SIX: RankIndex = 4;
+pub const SEVEN: RankIndex = 5;
+pub const EIGHT: RankIndex = 6;
+pub const NINE: RankIndex = 7;
+pub const TEN: RankIndex = 8;
+pub const JACK: RankIndex = 9;
+pub const QUEEN: RankIndex = 10;
+pub const KING: RankIndex = 11;
+pub const ACE: RankIndex = 12;
 pub const NUM_RANKS: usize = 13;
 
 /// Seats: WEST=0, NORTH=1, EAST=2, SOUTH=3
-pub type Seat = usize;
-pub const WEST: Seat = 0;
-pub const NORTH: Seat = 1;
-pub const EAST: Seat = 2;
-pub const SOUTH: Seat = 3;
+pub type SeatIndex = usize;
+#[deprecated(note = "use `SeatIndex`, or the typed `crate::Seat`")]
+pub type Seat = SeatIndex;
+pub const WEST: SeatIndex = 0;
+pub const NORTH: SeatIndex = 1;
+pub const EAST: SeatIndex = 2;
+pub const SOUTH: SeatIndex = 3;
 pub const NUM_SEATS: usize = 4;
 
 pub const TOTAL_TRICKS: usize = NUM_RANKS;
@@ -39,53 +47,59 @@ pub const TOTAL_CARDS: usize = NUM_RANKS * NUM_SUITS;
 
 /// Check if seat is NS (North or South)
 #[inline]
-pub fn is_ns(seat: Seat) -> bool {
+pub fn is_ns(seat: SeatIndex) -> bool {
     seat & 1 != 0
 }
 
 /// Get partner seat
 #[inline]
-pub fn partner(seat: Seat) -> Seat {
+pub fn partner(seat: SeatIndex) -> SeatIndex {
     (seat + 2) % NUM_SEATS
 }
 
 /// Get left-hand opponent
 #[inline]
-pub fn left_hand_opp(seat: Seat) -> Seat {
+pub fn left_hand_opp(seat: SeatIndex) -> SeatIndex {
     (seat + 1) % NUM_SEATS
 }
 
 /// Get right-hand opponent
 #[inline]
-pub fn right_hand_opp(seat: Seat) -> Seat {
+pub fn right_hand_opp(seat: SeatIndex) -> SeatIndex {
     (seat + 3) % NUM_SEATS
 }
 
 /// Get next seat (clockwise)
 #[inline]
-pub fn next_seat(seat: Seat) -> Seat {
+pub fn next_seat(seat: SeatIndex) -> SeatIndex {
     (seat + 1) % NUM_SEATS
 }
 
 /// Get seat name
-pub fn seat_name(seat: Seat) -> &'static str {
+pub fn seat_name(seat: SeatIndex) -> &'static str {
     const NAMES: [&str; 4] = ["West", "North", "East", "South"];
     NAMES[seat]
 }
 
 /// Get seat letter
-pub fn seat_letter(seat: Seat) -> char {
+pub fn seat_letter(seat: SeatIndex) -> char {
     seat_name(seat).chars().next().unwrap()
 }
 
 /// Get suit name
-pub fn suit_name(suit: Suit) -> &'static str {
+pub fn suit_name(suit: SuitIndex) -> &'static str {
     const NAMES: [&str; 5] = ["Spade", "Heart", "Diamond", "Club", "NoTrump"];
     NAMES[suit]
 }
 
+/// Get strain symbol ("S", "H", "D", "C" or "NT")
+pub fn strain_name(strain: usize) -> &'static str {
+    const NAMES: [&str; NUM_STRAINS] = ["S", "H", "D", "C", "NT"];
+    NAMES[strain]
+}
+
 /// Get rank name (character)
-pub fn rank_name(rank: Rank) -> char {
+pub fn rank_name(rank: RankIndex) -> char {
     const NAMES: [char; 13] = [
         '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K', 'A',
     ];
@@ -93,7 +107,7 @@ pub fn rank_name(rank: Rank) -> char {
 }
 
 /// Convert character to suit
-pub fn char_to_suit(c: char) -> Option<Suit> {
+pub fn char_to_suit(c: char) -> Option<SuitIndex> {
     match c.to_ascii_uppercase() {
         'S' => Some(SPADE),
         'H' => Some(HEART),
@@ -105,7 +119,7 @@ pub fn char_to_suit(c: char) -> Option<Suit> {
 }
 
 /// Convert character to rank
-pub fn char_to_rank(c: char) -> Option<Rank> {
+pub fn char_to_rank(c: char) -> Option<RankIndex> {
     match c.to_ascii_uppercase() {
         '2' => Some(TWO),
         '3' => Some(THREE),
@@ -125,7 +139,7 @@ pub fn char_to_rank(c: char) -> Option<Rank> {
 }
 
 /// Convert character to seat
-pub fn char_to_seat(c: char) -> Option<Seat> {
+pub fn char_to_seat(c: char) -> Option<SeatIndex> {
     match c.to_ascii_uppercase() {
         'W' => Some(WEST),
         'N' => Some(NORTH),
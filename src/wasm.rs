@@ -0,0 +1,127 @@
+//! WebAssembly bindings (requires the `wasm` feature)
+//!
+//! Build for the browser with e.g. `wasm-pack build --target web --features wasm`.
+//! Every function takes PBN/JSON strings and returns a JSON string holding
+//! either `{"result": ...}` or `{"error": {"code": ..., "message": ...}}`,
+//! with the same schema as the `--serve` protocol (see the `json` module):
+//!
+//! ```text
+//! solve("N:AKQT3.J6.KJ42.95 ...", "NT", "W")        {"result": {"ns_tricks": 9, "ew_tricks": 4}}
+//! solve(deal, "S", "W", ["HK", "HA"])               mid-trick
+//! ddTable(deal)                                     {"result": {"table": {"N": {"NT": 9, ...}, ...}}}
+//! analyze(deal, "H", "E")                           {"result": {"seat": "E", "cards": [...]}}
+//! handle('{"method": "par", "deal": "..."}')        any request of the protocol
+//! ```
+//!
+//! Caches are kept between calls, so analysing a deal card by card is cheap.
+//! Solves cannot be interrupted in the browser (there is no clock), so call
+//! these from a Web Worker to keep the page responsive.
+
+use super::json::Service;
+use serde_json::Value;
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    static SERVICE: RefCell<Service> = RefCell::new(Service::new());
+}
+
+/// NS and EW tricks for `deal` with `trump` and `leader`, after the cards in
+/// `played` (if any) have been played to the first trick
+#[wasm_bindgen]
+pub fn solve(deal: &str, trump: &str, leader: &str, played: Option<Vec<String>>) -> String {
+    request(serde_json::json!({
+        "method": "solve",
+        "deal": deal,
+        "trump": trump,
+        "leader": leader,
+        "played": played.unwrap_or_default(),
+    }))
+}
+
+/// Double-dummy table of `deal`
+#[wasm_bindgen(js_name = ddTable)]
+pub fn dd_table(deal: &str) -> String {
+    request(serde_json::json!({ "method": "table", "deal": deal }))
+}
+
+/// NS tricks after each legal play of the seat to move
+#[wasm_bindgen]
+pub fn analyze(deal: &str, trump: &str, leader: &str, played: Option<Vec<String>>) -> String {
+    request(serde_json::json!({
+        "method": "analyze",
+        "deal": deal,
+        "trump": trump,
+        "leader": leader,
+        "played": played.unwrap_or_default(),
+    }))
+}
+
+/// Handle one request of the `--serve` protocol, given and answered as JSON
+#[wasm_bindgen]
+pub fn handle(request: &str) -> String {
+    SERVICE.with(|service| service.borrow_mut().handle_line(request))
+}
+
+/// Set the cache size as 2^bits entries per cache (default 16)
+///
+/// Smaller caches use less memory but make long solves slower. Caches already
+/// created keep their size, so call this before solving.
+#[wasm_bindgen(js_name = setCacheBits)]
+pub fn set_cache_bits(bits: usize) {
+    super::bridge_solver::set_cache_bits(bits);
+}
+
+/// Handle a request built by one of the functions above, dropping the `id`
+fn request(request: Value) -> String {
+    let mut response = SERVICE.with(|service| service.borrow_mut().handle(&request));
+    if let Value::Object(fields) = &mut response {
+        fields.remove("id");
+    }
+    response.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEAL: &str = "N:A.A.. K.K.. 2.2.. 3.3..";
+
+    fn parse(response: String) -> Value {
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn test_solve_and_analyze() {
+        let response = parse(solve(DEAL, "NT", "W", None));
+        assert_eq!(response["result"]["ns_tricks"], 2);
+        assert!(response.get("id").is_none());
+
+        let response = parse(solve(DEAL, "NT", "W", Some(vec!["S3".to_string()])));
+        assert_eq!(response["result"]["ns_tricks"], 2);
+
+        let response = parse(analyze(DEAL, "S", "E", None));
+        assert_eq!(response["result"]["seat"], "E");
+        assert_eq!(response["result"]["cards"].as_array().unwrap().len(), 2);
+
+        let response = parse(dd_table(DEAL));
+        assert_eq!(response["result"]["table"]["N"]["NT"], 2);
+
+        let response = parse(handle(&format!(
+            r#"{{"id": 7, "method": "par", "deal": "{}"}}"#,
+            DEAL
+        )));
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["result"]["ns_score"], 0);
+    }
+
+    #[test]
+    fn test_errors() {
+        let response = parse(solve("N:A.A..", "NT", "W", None));
+        assert_eq!(response["error"]["code"], "invalid_deal");
+        let response = parse(solve(DEAL, "X", "W", None));
+        assert_eq!(response["error"]["code"], "invalid_request");
+        let response = parse(handle("not json"));
+        assert_eq!(response["error"]["code"], "parse_error");
+    }
+}